@@ -0,0 +1,83 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+//! A contract that stands in for the bridge's callees in its end-to-end tests.
+//!
+//! It subscribes to the bridge's header notifications and records those it is sent. Types it
+//! shares with the bridge are redeclared here with the same SCALE encoding, as any other callee
+//! would, so that the bridge's tests can depend on it without a dependency cycle.
+
+pub use self::bridge_mock::{BridgeMock, BridgeMockRef, HeaderNotification};
+
+#[ink::contract]
+mod bridge_mock {
+	use ink::{
+		env::call::{build_call, ExecutionInput, Selector},
+		prelude::vec::Vec,
+	};
+
+	/// Encoded as the bridge's `ChainId`.
+	pub type ChainId = u32;
+
+	/// Encoded as the bridge's `HashValue`.
+	pub type HashValue = [u8; 32];
+
+	/// Encoded as the bridge's `HeaderNotification`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub enum HeaderNotification {
+		/// A new canon tip has been accepted
+		NewTip { chain_id: ChainId, block_hash: HashValue, block_height: u64 },
+		/// Canon blocks were retracted by a heavier fork
+		Reorg { chain_id: ChainId, common_ancestor: HashValue, new_tip: HashValue, depth: u64 },
+	}
+
+	#[ink(storage)]
+	#[derive(Default)]
+	pub struct BridgeMock {
+		/// The header notifications received, oldest first
+		notifications: Vec<HeaderNotification>,
+		/// Whether header notification callbacks should fail
+		failing: bool,
+	}
+
+	impl BridgeMock {
+		#[ink(constructor)]
+		pub fn new() -> Self {
+			Self::default()
+		}
+
+		/// Ask the bridge to notify this contract of header changes, paying the transferred value
+		/// as the subscription deposit. Returns whether the bridge accepted the request.
+		#[ink(message, payable)]
+		pub fn subscribe(&mut self, bridge: AccountId) -> bool {
+			let call = build_call::<Environment>()
+				.call(bridge)
+				.transferred_value(self.env().transferred_value())
+				.exec_input(
+					ExecutionInput::new(Selector::new(ink::selector_bytes!("subscribe_headers")))
+						.push_arg(ink::selector_bytes!("on_header")),
+				)
+				.returns::<Result<(), ()>>();
+			matches!(call.try_invoke(), Ok(Ok(Ok(()))))
+		}
+
+		/// The bridge's header notification callback.
+		#[ink(message)]
+		pub fn on_header(&mut self, notification: HeaderNotification) {
+			assert!(!self.failing, "header notifications are failing");
+			self.notifications.push(notification);
+		}
+
+		/// Make header notification callbacks fail, or succeed again.
+		#[ink(message)]
+		pub fn set_failing(&mut self, failing: bool) {
+			self.failing = failing;
+		}
+
+		/// The header notifications received, oldest first.
+		#[ink(message)]
+		pub fn notifications(&self) -> Vec<HeaderNotification> {
+			self.notifications.clone()
+		}
+	}
+}
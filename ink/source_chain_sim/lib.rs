@@ -0,0 +1,339 @@
+//! A simulated source chain for exercising the bridge without a real one.
+//!
+//! `SourceChain` mines headers that meet a fixed PoW target, builds forks off any known block,
+//! and commits each block to a merkle tree of its transactions, so that it can hand out the
+//! headers a relayer would submit and the proofs a verifier would present:
+//!
+//! ```
+//! use source_chain_sim::SourceChain;
+//! use spv_bridge::MerkleProof;
+//!
+//! let mut chain = SourceChain::new(100, 0, [0x0f; 32], 600_000);
+//! let block = chain.extend_tip(vec![[1; 32], [2; 32]]);
+//! let root = chain.fields(block).unwrap().transactions_root;
+//! let proof = chain.transaction_proof(block, [2; 32]).unwrap();
+//! assert!(MerkleProof::check_merkle_proof([2; 32], proof, root));
+//! ```
+//!
+//! Headers and proofs are built through their SCALE encodings, as any off-chain tool would, so
+//! they hash exactly as the contract hashes them, see `spv_bridge::codec`.
+
+use scale::Encode;
+use spv_bridge::{codec, HashValue, Header, MerkleProof};
+use std::collections::BTreeMap;
+
+/// The fields of a `Header`, laid out and encoded in the same order.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Encode)]
+pub struct HeaderFields {
+	/// The height of this block in the chain
+	pub height: u64,
+	/// The hash of this block's parent
+	pub parent: HashValue,
+	/// The merkle tree root of the storage
+	pub storage_root: HashValue,
+	/// The merkle tree root of the transactions included in the block
+	pub transactions_root: HashValue,
+	/// The merkle tree root of the events emitted by the block's transactions
+	pub receipts_root: HashValue,
+	/// The PoW target that this block's hash must be below
+	pub target: HashValue,
+	/// The nonce that allows the block's hash to satisfy the proof of work
+	pub pow_nonce: u64,
+	/// The time at which this block was authored, in milliseconds since the Unix epoch
+	pub timestamp: u64,
+}
+
+impl HeaderFields {
+	/// The header with these fields.
+	pub fn header(&self) -> Header {
+		codec::decode_header(&self.encode()).expect("the fields encode as a header; qed")
+	}
+
+	/// The hash of the header with these fields, as `SpvBridge::hash_header` computes it.
+	pub fn hash(&self) -> HashValue {
+		codec::header_hash(&self.header())
+	}
+
+	/// Whether the header's hash meets its own PoW target.
+	pub fn meets_target(&self) -> bool {
+		self.hash() < self.target
+	}
+}
+
+/// Increment the header's nonce until its hash meets its own PoW target.
+pub fn mine(mut fields: HeaderFields) -> HeaderFields {
+	while !fields.meets_target() {
+		fields.pow_nonce += 1;
+	}
+	fields
+}
+
+/// A merkle inclusion proof with the given siblings and direction bits, see `MerkleProof`.
+pub fn merkle_proof(siblings: Vec<HashValue>, directions: u64) -> MerkleProof {
+	scale::Decode::decode(&mut &(siblings, directions).encode()[..])
+		.expect("the fields encode as a proof; qed")
+}
+
+/// Build a merkle tree over the given leaves, duplicating the last node of any level with an
+/// odd number of nodes. Returns the root and an inclusion proof for every leaf.
+///
+/// The root of no leaves is all zeros, which no proof can reach.
+pub fn merkle_tree(leaves: &[HashValue]) -> (HashValue, Vec<MerkleProof>) {
+	if leaves.is_empty() {
+		return ([0; 32], Vec::new())
+	}
+	let mut paths: Vec<(Vec<HashValue>, u64)> = leaves.iter().map(|_| (Vec::new(), 0)).collect();
+	let mut level: Vec<_> = leaves.iter().map(MerkleProof::hash_leaf).collect();
+	let mut depth = 0;
+	while level.len() > 1 {
+		if level.len() % 2 == 1 {
+			level.push(*level.last().expect("the level is not empty; qed"));
+		}
+		for (leaf, (siblings, directions)) in paths.iter_mut().enumerate() {
+			let index = leaf >> depth;
+			siblings.push(level[index ^ 1]);
+			*directions |= ((index & 1) as u64) << depth;
+		}
+		level = level.chunks(2).map(|pair| MerkleProof::hash_pair(&pair[0], &pair[1])).collect();
+		depth += 1;
+	}
+	let proofs = paths
+		.into_iter()
+		.map(|(siblings, directions)| merkle_proof(siblings, directions))
+		.collect();
+	(level[0], proofs)
+}
+
+/// A mined block and the transactions its header commits to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+	/// The block's header
+	pub fields: HeaderFields,
+	/// The hashes of the block's transactions, in merkle tree order
+	pub transactions: Vec<HashValue>,
+}
+
+/// A tree of mined blocks growing from a genesis block, whose tip is the highest block.
+#[derive(Debug, Clone)]
+pub struct SourceChain {
+	/// Every known block by hash
+	blocks: BTreeMap<HashValue, Block>,
+	/// The hash of the first block
+	genesis: HashValue,
+	/// The hash of the highest block; the first one mined at its height wins ties
+	tip: HashValue,
+	/// The PoW target of every mined header
+	target: HashValue,
+	/// The milliseconds between a block's timestamp and its children's
+	block_time: u64,
+}
+
+impl SourceChain {
+	/// A chain with a single, unmined genesis block at the given height and timestamp, as the
+	/// bridge's constructor expects it.
+	pub fn new(height: u64, timestamp: u64, target: HashValue, block_time: u64) -> Self {
+		// The bridge does not check the genesis seal, but a non-zero nonce keeps the header from
+		// being the all-zero default header.
+		let fields = HeaderFields { height, target, pow_nonce: 1, timestamp, ..Default::default() };
+		let genesis = fields.hash();
+		let blocks = BTreeMap::from([(genesis, Block { fields, transactions: Vec::new() })]);
+		Self { blocks, genesis, tip: genesis, target, block_time }
+	}
+
+	/// The hash of the genesis block.
+	pub fn genesis(&self) -> HashValue {
+		self.genesis
+	}
+
+	/// The hash of the highest block.
+	pub fn tip(&self) -> HashValue {
+		self.tip
+	}
+
+	/// The PoW target headers are mined to.
+	pub fn target(&self) -> HashValue {
+		self.target
+	}
+
+	/// The block with the given hash, if it is known.
+	pub fn block(&self, hash: HashValue) -> Option<&Block> {
+		self.blocks.get(&hash)
+	}
+
+	/// The fields of the header with the given hash, if it is known.
+	pub fn fields(&self, hash: HashValue) -> Option<HeaderFields> {
+		self.block(hash).map(|block| block.fields)
+	}
+
+	/// The header with the given hash, if it is known.
+	pub fn header(&self, hash: HashValue) -> Option<Header> {
+		self.block(hash).map(|block| block.fields.header())
+	}
+
+	/// Add a block, returning its hash. The block's header must already be mined.
+	///
+	/// Panics if the block's parent is unknown.
+	pub fn push(&mut self, block: Block) -> HashValue {
+		let parent = self.fields(block.fields.parent).expect("the parent block is known");
+		assert_eq!(block.fields.height, parent.height + 1, "the block is its parent's child");
+		let hash = block.fields.hash();
+		if block.fields.height > self.blocks[&self.tip].fields.height {
+			self.tip = hash;
+		}
+		self.blocks.insert(hash, block);
+		hash
+	}
+
+	/// Mine a child of the given block that includes the given transactions, returning its hash.
+	///
+	/// Panics if the parent is unknown.
+	pub fn extend(&mut self, parent: HashValue, transactions: Vec<HashValue>) -> HashValue {
+		self.extend_with_storage_root(parent, transactions, [0; 32])
+	}
+
+	/// Mine a child of the given block that includes the given transactions and commits to the
+	/// given storage root, returning its hash.
+	///
+	/// Panics if the parent is unknown.
+	pub fn extend_with_storage_root(
+		&mut self,
+		parent: HashValue,
+		transactions: Vec<HashValue>,
+		storage_root: HashValue,
+	) -> HashValue {
+		let parent_fields = self.fields(parent).expect("the parent block is known");
+		let (transactions_root, _) = merkle_tree(&transactions);
+		let fields = mine(HeaderFields {
+			height: parent_fields.height + 1,
+			parent,
+			storage_root,
+			transactions_root,
+			receipts_root: [0; 32],
+			target: self.target,
+			pow_nonce: 1,
+			timestamp: parent_fields.timestamp + self.block_time,
+		});
+		self.push(Block { fields, transactions })
+	}
+
+	/// Mine a child of the tip that includes the given transactions, returning its hash.
+	pub fn extend_tip(&mut self, transactions: Vec<HashValue>) -> HashValue {
+		self.extend(self.tip, transactions)
+	}
+
+	/// Mine `length` empty blocks on top of the given block, returning their hashes in order.
+	///
+	/// Each fork gets its own timestamps, so that two forks of the same length from the same
+	/// block have different hashes.
+	///
+	/// Panics if the fork point is unknown.
+	pub fn fork(&mut self, from: HashValue, length: usize) -> Vec<HashValue> {
+		let siblings = self.blocks.values().filter(|block| block.fields.parent == from).count();
+		let mut parent = from;
+		(0..length)
+			.map(|i| {
+				let fields = self.fields(parent).expect("the fork point is known");
+				let mut child = HeaderFields {
+					height: fields.height + 1,
+					parent,
+					target: self.target,
+					pow_nonce: 1,
+					timestamp: fields.timestamp + self.block_time,
+					..Default::default()
+				};
+				// Nudge only the first block, the rest differ through their parents.
+				if i == 0 {
+					child.timestamp += siblings as u64;
+				}
+				parent = self.push(Block { fields: mine(child), transactions: Vec::new() });
+				parent
+			})
+			.collect()
+	}
+
+	/// The headers from just above the `from` block up to and including the `to` block, in
+	/// submission order, or `None` if `from` is not an ancestor of `to`.
+	pub fn headers_between(&self, from: HashValue, to: HashValue) -> Option<Vec<Header>> {
+		let mut headers = Vec::new();
+		let mut hash = to;
+		while hash != from {
+			let fields = self.fields(hash)?;
+			headers.push(fields.header());
+			if hash == self.genesis {
+				return None
+			}
+			hash = fields.parent;
+		}
+		headers.reverse();
+		Some(headers)
+	}
+
+	/// The headers from just above genesis up to and including the tip, in submission order.
+	pub fn canon_headers(&self) -> Vec<Header> {
+		self.headers_between(self.genesis, self.tip)
+			.expect("the tip descends from genesis; qed")
+	}
+
+	/// An inclusion proof of the transaction in the block's transactions root, or `None` if the
+	/// block is unknown or does not include the transaction.
+	pub fn transaction_proof(
+		&self,
+		block: HashValue,
+		transaction: HashValue,
+	) -> Option<MerkleProof> {
+		let block = self.block(block)?;
+		let index = block.transactions.iter().position(|tx| *tx == transaction)?;
+		let (_, mut proofs) = merkle_tree(&block.transactions);
+		Some(proofs.swap_remove(index))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TARGET: HashValue = [0x0f; 32];
+
+	#[test]
+	fn mined_headers_meet_the_target_and_link_up() {
+		let mut chain = SourceChain::new(100, 0, TARGET, 600_000);
+		let genesis = chain.genesis();
+		chain.extend_tip(Vec::new());
+		let tip = chain.extend_tip(Vec::new());
+
+		let headers = chain.canon_headers();
+		assert_eq!(headers.len(), 2);
+		assert_eq!(chain.fields(tip).unwrap().height, 102);
+		assert_eq!(codec::header_hash(&headers[1]), tip);
+		assert!(chain.fields(tip).unwrap().meets_target());
+		assert_eq!(chain.headers_between(tip, genesis), None);
+	}
+
+	#[test]
+	fn forks_move_the_tip_only_when_higher() {
+		let mut chain = SourceChain::new(100, 0, TARGET, 600_000);
+		let genesis = chain.genesis();
+		let main = chain.fork(genesis, 2);
+		let short = chain.fork(genesis, 2);
+		assert_ne!(main, short);
+		assert_eq!(chain.tip(), main[1]);
+
+		let long = chain.fork(short[0], 2);
+		assert_eq!(chain.tip(), long[1]);
+		assert_eq!(chain.headers_between(genesis, long[1]).unwrap().len(), 3);
+	}
+
+	#[test]
+	fn proofs_check_against_the_transactions_root() {
+		let mut chain = SourceChain::new(100, 0, TARGET, 600_000);
+		let transactions: Vec<HashValue> = (1..=5).map(|i| [i; 32]).collect();
+		let block = chain.extend_tip(transactions.clone());
+		let root = chain.fields(block).unwrap().transactions_root;
+
+		for tx in transactions {
+			let proof = chain.transaction_proof(block, tx).unwrap();
+			assert!(MerkleProof::check_merkle_proof(tx, proof, root));
+		}
+		assert_eq!(chain.transaction_proof(block, [9; 32]), None);
+	}
+}
@@ -0,0 +1,549 @@
+//! The canonical byte encodings of source chain data structures.
+//!
+//! Every component that hashes source chain data (the contract, relayer tooling, test chain
+//! generators) should go through these functions, so that they all hash identical bytes.
+//! All encodings are SCALE.
+
+use crate::{
+	crypto,
+	finality::{
+		BeaconBlockHeader, BeefyCommitment, DigestItem, GrandpaConsensusLog, Precommit,
+		SubstrateHeader, GRANDPA_ENGINE_ID,
+	},
+	spv_bridge::{
+		BitcoinHeader, ChainId, HashAlgorithm, HashValue, Header, SourceTransaction, StateClaim,
+	},
+};
+use ink::{
+	env::hash::{Blake2x256, HashOutput, Sha2x256},
+	prelude::vec::Vec,
+};
+use scale::{Decode, Encode};
+
+/// The length of an encoded `Header`. Every field has a fixed size, so all headers encode to
+/// the same number of bytes.
+pub const HEADER_ENCODED_LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 8;
+
+/// The length of an encoded `StateClaim`.
+pub const STATE_CLAIM_ENCODED_LEN: usize = 8 + 8;
+
+/// The length of an encoded `SourceTransaction`.
+pub const TRANSACTION_ENCODED_LEN: usize = 32 + 32 + 16 + 16 + 8;
+
+/// The length of a serialized `BitcoinHeader`.
+pub const BITCOIN_HEADER_LEN: usize = 4 + 32 + 32 + 4 + 4 + 4;
+
+/// Encode a header into the bytes that its hash commits to.
+pub fn encode_header(header: &Header) -> Vec<u8> {
+	header.encode()
+}
+
+/// Decode a header from its canonical encoding, rejecting trailing bytes.
+pub fn decode_header(bytes: &[u8]) -> Result<Header, scale::Error> {
+	decode_exact(bytes)
+}
+
+/// Encode a state claim into the bytes that its storage leaf commits to.
+pub fn encode_state_claim(claim: &StateClaim) -> Vec<u8> {
+	claim.encode()
+}
+
+/// Decode a state claim from its canonical encoding, rejecting trailing bytes.
+pub fn decode_state_claim(bytes: &[u8]) -> Result<StateClaim, scale::Error> {
+	decode_exact(bytes)
+}
+
+/// Encode a source transaction into the bytes that its transaction hash commits to.
+pub fn encode_transaction(transaction: &SourceTransaction) -> Vec<u8> {
+	transaction.encode()
+}
+
+/// Decode a source transaction from its canonical encoding, rejecting trailing bytes.
+pub fn decode_transaction(bytes: &[u8]) -> Result<SourceTransaction, scale::Error> {
+	decode_exact(bytes)
+}
+
+/// Serialize a Bitcoin header into the 80 bytes that its hash commits to.
+pub fn encode_bitcoin_header(header: &BitcoinHeader) -> Vec<u8> {
+	header.encode()
+}
+
+/// Deserialize a Bitcoin header from its 80 byte serialization, rejecting trailing bytes.
+pub fn decode_bitcoin_header(bytes: &[u8]) -> Result<BitcoinHeader, scale::Error> {
+	decode_exact(bytes)
+}
+
+/// The hash identifying a header.
+pub fn header_hash(header: &Header) -> HashValue {
+	header_hash_with(HashAlgorithm::Sha2x256, header)
+}
+
+/// The hash identifying a header on a deployment using the given hash algorithm.
+pub fn header_hash_with(algorithm: HashAlgorithm, header: &Header) -> HashValue {
+	hash(algorithm, &encode_header(header))
+}
+
+/// The hash identifying a `HeaderV1` with the given fields, which commits to the encoded header
+/// followed by the extension bytes.
+pub fn header_v1_hash(algorithm: HashAlgorithm, header: &Header, extension: &[u8]) -> HashValue {
+	let mut bytes = encode_header(header);
+	bytes.extend_from_slice(extension);
+	hash(algorithm, &bytes)
+}
+
+/// The hash identifying a Bitcoin header: the double SHA-256 of its serialization.
+///
+/// The hash is returned in display byte order, the reverse of Bitcoin's internal order, so that
+/// it compares against targets as a big-endian number.
+pub fn bitcoin_header_hash(header: &BitcoinHeader) -> HashValue {
+	let mut hash = crypto::double_sha256(&encode_bitcoin_header(header));
+	hash.reverse();
+	hash
+}
+
+/// The leaf that a state claim occupies in a block's storage merkle tree.
+pub fn state_claim_leaf(claim: &StateClaim) -> HashValue {
+	state_claim_leaf_with(HashAlgorithm::Sha2x256, claim)
+}
+
+/// The leaf that a state claim occupies on a deployment using the given hash algorithm.
+pub fn state_claim_leaf_with(algorithm: HashAlgorithm, claim: &StateClaim) -> HashValue {
+	hash(algorithm, &encode_state_claim(claim))
+}
+
+/// The hash identifying a source transaction, which is its leaf in a block's transactions
+/// merkle tree.
+pub fn transaction_hash(transaction: &SourceTransaction) -> HashValue {
+	sha2_256(&encode_transaction(transaction))
+}
+
+/// The identifier that a bridge deployment gives to a claim verified against a block.
+///
+/// Mixing in the deployment's salt means identifiers issued by different deployments bridging
+/// the same source chain never collide.
+pub fn claim_id(salt: &HashValue, leaf: &HashValue, block_hash: &HashValue) -> HashValue {
+	let mut bytes = [0; 96];
+	bytes[..32].copy_from_slice(salt);
+	bytes[32..64].copy_from_slice(leaf);
+	bytes[64..].copy_from_slice(block_hash);
+	sha2_256(&bytes)
+}
+
+/// The byte that precedes a claim hash when it is hashed into a merkle tree leaf.
+pub const MERKLE_LEAF_PREFIX: u8 = 0;
+
+/// The byte that precedes the children of a merkle tree node when they are hashed.
+pub const MERKLE_NODE_PREFIX: u8 = 1;
+
+/// The merkle tree leaf that commits to the given claim hash.
+///
+/// Leaves and interior nodes hash distinct prefixes, so that no interior node is also the leaf of
+/// some claim, and a proof can't pass off an interior node as a claim.
+pub fn merkle_leaf(claim: &HashValue) -> HashValue {
+	let mut bytes = [0; 33];
+	bytes[0] = MERKLE_LEAF_PREFIX;
+	bytes[1..].copy_from_slice(claim);
+	sha2_256(&bytes)
+}
+
+/// The merkle tree node whose children are `left` and `right`.
+pub fn merkle_node(left: &HashValue, right: &HashValue) -> HashValue {
+	let mut bytes = [0; 65];
+	bytes[0] = MERKLE_NODE_PREFIX;
+	bytes[1..33].copy_from_slice(left);
+	bytes[33..].copy_from_slice(right);
+	sha2_256(&bytes)
+}
+
+/// The marker that precedes the merged mining commitment in a parent chain coinbase
+/// transaction.
+pub const MERGED_MINING_MAGIC: [u8; 4] = [0xfa, 0xbe, b'm', b'm'];
+
+/// The hash identifying a serialized Bitcoin transaction, in Bitcoin's internal byte order.
+pub fn bitcoin_transaction_hash(transaction: &[u8]) -> HashValue {
+	crypto::double_sha256(transaction)
+}
+
+/// The node of a Bitcoin style merkle tree whose children are `left` and `right`, hashed with
+/// double SHA-256.
+pub fn bitcoin_merkle_node(left: &HashValue, right: &HashValue) -> HashValue {
+	let mut bytes = [0; 64];
+	bytes[..32].copy_from_slice(left);
+	bytes[32..].copy_from_slice(right);
+	bitcoin_transaction_hash(&bytes)
+}
+
+/// The hash identifying a Substrate header: the BLAKE2b-256 of its SCALE encoding.
+pub fn substrate_header_hash(header: &SubstrateHeader) -> HashValue {
+	blake2_256(&header.encode())
+}
+
+/// The message that a GRANDPA authority signs to precommit to a block in the given round of the
+/// given authority set.
+pub fn grandpa_precommit_payload(precommit: &Precommit, round: u64, set_id: u64) -> Vec<u8> {
+	// The message is `Message::Precommit`, whose variant index is 1.
+	(1u8, precommit, round, set_id).encode()
+}
+
+/// The GRANDPA consensus logs in a Substrate header's digest.
+pub fn grandpa_consensus_logs(
+	header: &SubstrateHeader,
+) -> Result<Vec<GrandpaConsensusLog>, scale::Error> {
+	header
+		.digest
+		.iter()
+		.filter_map(|item| match item {
+			DigestItem::Consensus(engine, log) if *engine == GRANDPA_ENGINE_ID => Some(log),
+			_ => None,
+		})
+		.map(|log| decode_exact(log))
+		.collect()
+}
+
+/// The parachain call that a verified transaction is forwarded as, see `crate::xcm`: the call's
+/// pallet and call index, then the SCALE-encoded chain id, transaction hash, block hash, claim id
+/// and the depth the transaction was verified at.
+pub fn xcm_claim_call(
+	call_index: [u8; 2],
+	chain_id: ChainId,
+	tx_hash: &HashValue,
+	block_hash: &HashValue,
+	claim_id: &HashValue,
+	depth: u64,
+) -> Vec<u8> {
+	(call_index, chain_id, tx_hash, block_hash, claim_id, depth).encode()
+}
+
+/// The domain type that Ethereum sync committees sign under.
+pub const DOMAIN_SYNC_COMMITTEE: [u8; 4] = [7, 0, 0, 0];
+
+/// The SSZ merkle node whose children are `left` and `right`.
+pub fn ssz_node(left: &HashValue, right: &HashValue) -> HashValue {
+	let mut bytes = [0; 64];
+	bytes[..32].copy_from_slice(left);
+	bytes[32..].copy_from_slice(right);
+	sha2_256(&bytes)
+}
+
+/// The SSZ hash tree root of a BLS public key, a `Bytes48` padded to two chunks.
+pub fn ssz_public_key_root(public_key: &[u8; 48]) -> HashValue {
+	let mut bytes = [0; 64];
+	bytes[..48].copy_from_slice(public_key);
+	sha2_256(&bytes)
+}
+
+/// The SSZ merkle root of the given chunks, whose number must be a power of two.
+pub fn ssz_merkleize(chunks: &[HashValue]) -> HashValue {
+	let mut layer = chunks.to_vec();
+	while layer.len() > 1 {
+		layer = layer.chunks(2).map(|pair| ssz_node(&pair[0], &pair[1])).collect();
+	}
+	layer.first().copied().unwrap_or_default()
+}
+
+/// The root that an SSZ merkle branch proves `leaf` to be under, at `index` among the leaves of a
+/// tree as deep as the branch is long. The branch lists siblings from the leaf up.
+pub fn ssz_branch_root(leaf: &HashValue, branch: &[HashValue], index: u64) -> HashValue {
+	branch.iter().enumerate().fold(*leaf, |node, (depth, sibling)| {
+		if (index >> depth) & 1 == 1 {
+			ssz_node(sibling, &node)
+		} else {
+			ssz_node(&node, sibling)
+		}
+	})
+}
+
+/// The hash identifying a beacon block header: its SSZ hash tree root, which is the beacon
+/// block root.
+pub fn beacon_header_root(header: &BeaconBlockHeader) -> HashValue {
+	let uint64 = |value: u64| {
+		let mut chunk = [0; 32];
+		chunk[..8].copy_from_slice(&value.to_le_bytes());
+		chunk
+	};
+	ssz_merkleize(&[
+		uint64(header.slot),
+		uint64(header.proposer_index),
+		header.parent_root,
+		header.state_root,
+		header.body_root,
+		[0; 32],
+		[0; 32],
+		[0; 32],
+	])
+}
+
+/// The root a sync committee signs for a beacon block: the SSZ `SigningData` of the block root
+/// under the sync committee domain of the given fork.
+pub fn sync_committee_signing_root(
+	block_root: &HashValue,
+	fork_version: [u8; 4],
+	genesis_validators_root: &HashValue,
+) -> HashValue {
+	let mut version = [0; 32];
+	version[..4].copy_from_slice(&fork_version);
+	let fork_data_root = ssz_node(&version, genesis_validators_root);
+	let mut domain = [0; 32];
+	domain[..4].copy_from_slice(&DOMAIN_SYNC_COMMITTEE);
+	domain[4..].copy_from_slice(&fork_data_root[..28]);
+	ssz_node(block_root, &domain)
+}
+
+/// The claim hash of an Ethereum style account, see `SpvBridge::verify_account`.
+pub fn account_claim_hash(address: &[u8; 20], account: &[u8]) -> HashValue {
+	keccak_256(&[&address[..], account].concat())
+}
+
+/// The claim hash of the value of an Ethereum style storage slot, see
+/// `SpvBridge::verify_storage`.
+pub fn storage_claim_hash(address: &[u8; 20], slot: &HashValue, value: &HashValue) -> HashValue {
+	keccak_256(&[&address[..], slot, value].concat())
+}
+
+/// The claim hash of a Substrate storage read: the Blake2-256 of the SCALE encoding of the key
+/// and the optional value, see `SpvBridge::verify_substrate_storage`.
+pub fn substrate_storage_claim_hash(key: &[u8], value: &Option<Vec<u8>>) -> HashValue {
+	blake2_256(&(key, value).encode())
+}
+
+/// The hash BEEFY authorities sign: the Keccak-256 of the commitment's SCALE encoding.
+pub fn beefy_commitment_hash(commitment: &BeefyCommitment) -> HashValue {
+	keccak_256(&commitment.encode())
+}
+
+/// The MMR node of the leaf committing to a header.
+pub fn mmr_leaf_hash(header: &Header) -> HashValue {
+	keccak_256(&encode_header(header))
+}
+
+/// The MMR node whose children are `left` and `right`. Peaks are bagged with the same function,
+/// the right peak first.
+pub fn mmr_node(left: &HashValue, right: &HashValue) -> HashValue {
+	let mut bytes = [0; 64];
+	bytes[..32].copy_from_slice(left);
+	bytes[32..].copy_from_slice(right);
+	keccak_256(&bytes)
+}
+
+/// The Keccak-256 hash of some bytes, which Ethereum's tries and accounts are keyed by.
+pub fn keccak_256(bytes: &[u8]) -> HashValue {
+	crypto::keccak_256(bytes)
+}
+
+/// The Blake2-256 hash of some bytes, which Substrate's trie nodes are referenced by.
+pub fn blake2_256(bytes: &[u8]) -> HashValue {
+	let mut hash = <Blake2x256 as HashOutput>::Type::default();
+	ink::env::hash_bytes::<Blake2x256>(bytes, &mut hash);
+	hash
+}
+
+/// The hash of some bytes under the given algorithm.
+pub fn hash(algorithm: HashAlgorithm, bytes: &[u8]) -> HashValue {
+	match algorithm {
+		HashAlgorithm::Sha2x256 => sha2_256(bytes),
+		HashAlgorithm::DoubleSha256 => crypto::double_sha256(bytes),
+		HashAlgorithm::Keccak256 => keccak_256(bytes),
+		HashAlgorithm::Blake2x256 => blake2_256(bytes),
+	}
+}
+
+fn sha2_256(bytes: &[u8]) -> HashValue {
+	let mut hash = <Sha2x256 as HashOutput>::Type::default();
+	ink::env::hash_bytes::<Sha2x256>(bytes, &mut hash);
+	hash
+}
+
+fn decode_exact<T: Decode>(mut bytes: &[u8]) -> Result<T, scale::Error> {
+	let value = T::decode(&mut bytes)?;
+	if !bytes.is_empty() {
+		return Err("trailing bytes after encoded value".into())
+	}
+	Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn header() -> Header {
+		decode_header(&[7; HEADER_ENCODED_LEN]).unwrap()
+	}
+
+	#[test]
+	fn header_round_trips() {
+		let header = header();
+		let bytes = encode_header(&header);
+		assert_eq!(bytes.len(), HEADER_ENCODED_LEN);
+		assert_eq!(decode_header(&bytes), Ok(header));
+	}
+
+	#[test]
+	fn state_claim_round_trips() {
+		let bytes = [3; STATE_CLAIM_ENCODED_LEN];
+		let claim = decode_state_claim(&bytes).unwrap();
+		assert_eq!(encode_state_claim(&claim), bytes.to_vec());
+	}
+
+	#[test]
+	fn transaction_round_trips() {
+		let bytes = [5; TRANSACTION_ENCODED_LEN];
+		let transaction = decode_transaction(&bytes).unwrap();
+		assert_eq!(encode_transaction(&transaction), bytes.to_vec());
+		assert!(decode_transaction(&bytes[1..]).is_err());
+	}
+
+	#[test]
+	fn decoding_rejects_wrong_lengths() {
+		assert!(decode_header(&[7; HEADER_ENCODED_LEN - 1]).is_err());
+		assert!(decode_header(&[7; HEADER_ENCODED_LEN + 1]).is_err());
+		assert!(decode_state_claim(&[3; STATE_CLAIM_ENCODED_LEN + 1]).is_err());
+	}
+
+	/// The Bitcoin mainnet genesis block header.
+	const BITCOIN_GENESIS: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+	fn hex(text: &str) -> Vec<u8> {
+		(0..text.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+			.collect()
+	}
+
+	#[test]
+	fn bitcoin_header_round_trips() {
+		crypto::register_unsupported_extension();
+		let bytes = hex(BITCOIN_GENESIS);
+		assert_eq!(bytes.len(), BITCOIN_HEADER_LEN);
+		let header = decode_bitcoin_header(&bytes).unwrap();
+		assert_eq!(encode_bitcoin_header(&header), bytes);
+		assert_eq!(
+			bitcoin_header_hash(&header).to_vec(),
+			hex("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+		);
+		assert!(decode_bitcoin_header(&bytes[1..]).is_err());
+	}
+
+	#[test]
+	fn substrate_headers_hash_with_blake2() {
+		// The Polkadot genesis header.
+		let header = SubstrateHeader {
+			parent_hash: [0; 32],
+			number: 0,
+			state_root: hex("29d0d972cd27cbc511e9589fcb7a4506d5eb6a9e8df205f00472e5ab354a4e17")
+				.try_into()
+				.unwrap(),
+			extrinsics_root: hex(
+				"03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314",
+			)
+			.try_into()
+			.unwrap(),
+			digest: Vec::new(),
+		};
+		assert_eq!(
+			substrate_header_hash(&header).to_vec(),
+			hex("91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3")
+		);
+	}
+
+	#[test]
+	fn grandpa_logs_are_read_from_the_digest() {
+		use crate::finality::ScheduledChange;
+
+		let change = GrandpaConsensusLog::ScheduledChange(ScheduledChange {
+			next_authorities: Vec::from([([1; 32], 1)]),
+			delay: 0,
+		});
+		let mut header = SubstrateHeader {
+			parent_hash: [0; 32],
+			number: 1,
+			state_root: [0; 32],
+			extrinsics_root: [0; 32],
+			digest: Vec::from([
+				DigestItem::PreRuntime(*b"BABE", Vec::from([1, 2])),
+				DigestItem::Consensus(*b"BABE", Vec::from([3])),
+				DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode()),
+			]),
+		};
+		assert_eq!(grandpa_consensus_logs(&header), Ok(Vec::from([change])));
+
+		header.digest.push(DigestItem::Consensus(GRANDPA_ENGINE_ID, Vec::from([9])));
+		assert!(grandpa_consensus_logs(&header).is_err());
+	}
+
+	#[test]
+	fn grandpa_precommit_payload_layout() {
+		let precommit = Precommit { target_hash: [2; 32], target_number: 3 };
+		let payload = grandpa_precommit_payload(&precommit, 4, 5);
+		assert_eq!(payload.len(), 1 + 32 + 4 + 8 + 8);
+		assert_eq!(payload[0], 1);
+		assert_eq!(payload[1..33], [2; 32]);
+		assert_eq!(payload[33..37], 3u32.to_le_bytes());
+		assert_eq!(payload[37..45], 4u64.to_le_bytes());
+		assert_eq!(payload[45..], 5u64.to_le_bytes());
+	}
+
+	#[test]
+	fn ssz_branches_prove_merkleized_leaves() {
+		let leaves: Vec<HashValue> = (0..8).map(|i| [i; 32]).collect();
+		let root = ssz_merkleize(&leaves);
+		let branch = [leaves[4], ssz_node(&leaves[6], &leaves[7]), ssz_merkleize(&leaves[..4])];
+		assert_eq!(ssz_branch_root(&leaves[5], &branch, 5), root);
+		assert_ne!(ssz_branch_root(&leaves[5], &branch, 4), root);
+	}
+
+	#[test]
+	fn beacon_headers_hash_to_their_block_root() {
+		// Slot 128 of a Snowbridge Ethereum light client test fixture, whose root is the parent
+		// root of the header at slot 129.
+		let header = BeaconBlockHeader {
+			slot: 128,
+			proposer_index: 1,
+			parent_root: hex("82cd835b86cdbb52c4884d6e6c6e0459ad7fbe453b1f17232ff8669f94d7847c")
+				.try_into()
+				.unwrap(),
+			state_root: hex("548d726be27fe0419d2b591e3f46b6118f9a8f6bc4631b2f44562bdba12c3c5d")
+				.try_into()
+				.unwrap(),
+			body_root: hex("24a18b40c0fa55c3b6dc164d46aaa9e2187f29b0097cf676d82211de3e9295a1")
+				.try_into()
+				.unwrap(),
+		};
+		assert_eq!(
+			beacon_header_root(&header).to_vec(),
+			hex("bdf2dd55a235731bdd69198123053670502c79b50ec67360758f10b78b556f0a")
+		);
+	}
+
+	#[test]
+	fn hashes_match_the_contract() {
+		use crate::spv_bridge::{MerkleProof, SpvBridge};
+
+		let header = header();
+		assert_eq!(header_hash(&header), SpvBridge::hash_header(header));
+
+		let claim = decode_state_claim(&[3; STATE_CLAIM_ENCODED_LEN]).unwrap();
+		assert_eq!(state_claim_leaf(&claim), SpvBridge::hash_claim(claim));
+
+		assert_eq!(merkle_node(&[1; 32], &[2; 32]), MerkleProof::hash_pair(&[1; 32], &[2; 32]));
+		assert_eq!(merkle_leaf(&[1; 32]), MerkleProof::hash_leaf(&[1; 32]));
+	}
+
+	#[test]
+	fn hash_algorithms_differ() {
+		crypto::register_unsupported_extension();
+		let bytes = encode_header(&header());
+		let hashes = [
+			HashAlgorithm::Sha2x256,
+			HashAlgorithm::DoubleSha256,
+			HashAlgorithm::Keccak256,
+			HashAlgorithm::Blake2x256,
+		]
+		.map(|algorithm| header_hash_with(algorithm, &header()));
+		assert_eq!(
+			hashes,
+			[sha2_256(&bytes), sha2_256(&sha2_256(&bytes)), keccak_256(&bytes), blake2_256(&bytes)]
+		);
+		assert!(hashes.iter().enumerate().all(|(i, hash)| !hashes[i + 1..].contains(hash)));
+		assert_eq!(header_hash(&header()), hashes[0]);
+	}
+}
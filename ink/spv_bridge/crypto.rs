@@ -0,0 +1,327 @@
+//! Cryptography that source chain consensus and proofs are checked with.
+//!
+//! Everything here is implemented in the contract. With the `crypto-extension` feature, the
+//! functions first ask the runtime's crypto chain extension, whose function ids are below, to do
+//! the work natively, which costs far less gas. An extension that does not provide a function
+//! says so with a non-zero status code, and the work is done in the contract instead. A contract
+//! built with the feature must only be deployed on a runtime that has the extension, because
+//! calling a missing chain extension traps.
+
+use crate::spv_bridge::HashValue;
+use bls12_381::{
+	hash_to_curve::{ExpandMsgXmd, HashToCurve},
+	multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt,
+};
+use ed25519_zebra::{Signature, VerificationKey};
+use ink::{
+	env::{
+		chain_extension::{ChainExtensionMethod, FromStatusCode},
+		hash::{CryptoHash, HashOutput, Keccak256, Sha2x256},
+	},
+	prelude::vec::Vec,
+};
+
+/// Double SHA-256, taking the bytes to hash and returning the hash.
+pub const DOUBLE_SHA256: u32 = 0x5350_0000;
+
+/// Keccak-256, taking the bytes to hash and returning the hash.
+pub const KECCAK_256: u32 = 0x5350_0001;
+
+/// BLS12-381 signature verification, taking a public key, a message and a signature and
+/// returning whether the signature is valid.
+pub const BLS_VERIFY: u32 = 0x5350_0002;
+
+/// BLS12-381 public key aggregation, taking public keys and returning their sum, or `None` if
+/// one of them is not a valid key.
+pub const BLS_AGGREGATE: u32 = 0x5350_0003;
+
+/// Ed25519 signature verification, taking a public key, a message and a signature and returning
+/// whether the signature is valid.
+pub const ED25519_VERIFY: u32 = 0x5350_0004;
+
+/// How many public keys are sent to the extension at once, so that a call's input fits the
+/// contract's 16 KiB buffer.
+pub const BLS_AGGREGATE_CHUNK: usize = 128;
+
+/// Whether the contract was built to call the crypto chain extension.
+pub const OFFLOADED: bool = cfg!(feature = "crypto-extension");
+
+/// The domain separation tag of Ethereum's BLS signatures, which use proofs of possession.
+const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The SHA-256 of the SHA-256 of some bytes, as Bitcoin hashes headers and transactions.
+pub fn double_sha256(bytes: &[u8]) -> HashValue {
+	offloaded(DOUBLE_SHA256, &bytes)
+		.unwrap_or_else(|| host_hash::<Sha2x256>(&host_hash::<Sha2x256>(bytes)))
+}
+
+/// The Keccak-256 hash of some bytes.
+pub fn keccak_256(bytes: &[u8]) -> HashValue {
+	offloaded(KECCAK_256, &bytes).unwrap_or_else(|| host_hash::<Keccak256>(bytes))
+}
+
+/// Whether `signature` is a valid Ed25519 signature of `message` by `public_key`.
+///
+/// Signatures are checked under the ZIP 215 rules, as Substrate and Tendermint check them, so the
+/// contract accepts exactly the signatures that the source chain's own nodes accept.
+pub fn ed25519_verify(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64]) -> bool {
+	if let Some(valid) = offloaded(ED25519_VERIFY, &(public_key, message, signature)) {
+		return valid
+	}
+	VerificationKey::try_from(*public_key)
+		.and_then(|key| key.verify(&Signature::from(*signature), message))
+		.is_ok()
+}
+
+/// Whether `signature` is a valid BLS12-381 aggregate signature of `message` by all of
+/// `public_keys`, as Ethereum's `FastAggregateVerify` checks it.
+///
+/// Public keys are compressed G1 points and the signature a compressed G2 point. Points that are
+/// malformed or outside their subgroup fail the check, as does an empty set of keys.
+pub fn bls_fast_aggregate_verify(
+	public_keys: &[[u8; 48]],
+	message: &[u8],
+	signature: &[u8; 96],
+) -> bool {
+	if public_keys.is_empty() {
+		return false
+	}
+	if let Some(valid) = offloaded_bls_verify(public_keys, message, signature) {
+		return valid
+	}
+	let mut aggregate = G1Projective::identity();
+	for public_key in public_keys {
+		let Some(public_key) = Option::<G1Affine>::from(G1Affine::from_compressed(public_key))
+		else {
+			return false
+		};
+		aggregate += public_key;
+	}
+	let Some(signature) = Option::<G2Affine>::from(G2Affine::from_compressed(signature)) else {
+		return false
+	};
+	let message =
+		<G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, BLS_DST);
+	// e(aggregate, H(message)) == e(generator, signature)
+	multi_miller_loop(&[
+		(&G1Affine::from(aggregate), &G2Prepared::from(G2Affine::from(message))),
+		(&-G1Affine::generator(), &G2Prepared::from(signature)),
+	])
+	.final_exponentiation() ==
+		Gt::identity()
+}
+
+/// Whether the crypto chain extension finds `signature` a valid signature of `message` by the
+/// aggregate of `public_keys`, or `None` if it does not provide BLS. The keys are aggregated in
+/// chunks, and the chunks' aggregates aggregated in turn.
+fn offloaded_bls_verify(
+	public_keys: &[[u8; 48]],
+	message: &[u8],
+	signature: &[u8; 96],
+) -> Option<bool> {
+	let aggregate = |keys: &[[u8; 48]]| offloaded::<_, Option<[u8; 48]>>(BLS_AGGREGATE, &keys);
+	let mut partials = Vec::new();
+	for chunk in public_keys.chunks(BLS_AGGREGATE_CHUNK) {
+		let Some(partial) = aggregate(chunk)? else { return Some(false) };
+		partials.push(partial);
+	}
+	let public_key = match partials.as_slice() {
+		[public_key] => *public_key,
+		partials => match aggregate(partials)? {
+			Some(public_key) => public_key,
+			None => return Some(false),
+		},
+	};
+	offloaded(BLS_VERIFY, &(public_key, message, signature))
+}
+
+/// A non-zero status code from the crypto chain extension: it does not provide the function.
+struct Unsupported;
+
+impl FromStatusCode for Unsupported {
+	fn from_status_code(status_code: u32) -> Result<(), Self> {
+		match status_code {
+			0 => Ok(()),
+			_ => Err(Self),
+		}
+	}
+}
+
+/// The output of the crypto chain extension's function `func_id`, or `None` if the contract was
+/// built without the extension or the extension does not provide the function.
+fn offloaded<I: scale::Encode, O: scale::Decode>(func_id: u32, input: &I) -> Option<O> {
+	if !OFFLOADED {
+		return None
+	}
+	ChainExtensionMethod::build(func_id)
+		.input::<I>()
+		.output::<O, false>()
+		.handle_error_code::<Unsupported>()
+		.call(input)
+		.ok()
+}
+
+fn host_hash<H: CryptoHash + HashOutput<Type = HashValue>>(bytes: &[u8]) -> HashValue {
+	let mut hash = HashValue::default();
+	ink::env::hash_bytes::<H>(bytes, &mut hash);
+	hash
+}
+
+/// Stand in for a crypto chain extension that provides no functions, so that everything is
+/// computed in the contract. The off-chain environment traps on unregistered chain extension
+/// functions like a runtime does.
+#[cfg(test)]
+pub fn register_unsupported_extension() {
+	#[cfg(feature = "crypto-extension")]
+	for func_id in [DOUBLE_SHA256, KECCAK_256, BLS_VERIFY, BLS_AGGREGATE, ED25519_VERIFY] {
+		ink::env::test::register_chain_extension(UnsupportedExtension(func_id));
+	}
+}
+
+/// A mock chain extension function that the runtime does not provide.
+#[cfg(all(test, feature = "crypto-extension"))]
+struct UnsupportedExtension(u32);
+
+#[cfg(all(test, feature = "crypto-extension"))]
+impl ink::env::test::ChainExtension for UnsupportedExtension {
+	fn func_id(&self) -> u32 {
+		self.0
+	}
+
+	fn call(&mut self, _input: &[u8], _output: &mut Vec<u8>) -> u32 {
+		1
+	}
+}
+
+/// The compressed BLS public key of the given secret key.
+#[cfg(test)]
+pub(crate) fn bls_public_key(secret_key: bls12_381::Scalar) -> [u8; 48] {
+	G1Affine::from(G1Affine::generator() * secret_key).to_compressed()
+}
+
+/// The compressed BLS signature of `message` by the given secret key. Signatures by several keys
+/// aggregate to the signature by the sum of the keys.
+#[cfg(test)]
+pub(crate) fn bls_sign(secret_key: bls12_381::Scalar, message: &[u8]) -> [u8; 96] {
+	let point =
+		<G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, BLS_DST);
+	G2Affine::from(point * secret_key).to_compressed()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn hex<const N: usize>(text: &str) -> [u8; N] {
+		core::array::from_fn(|i| u8::from_str_radix(&text[2 * i..2 * i + 2], 16).unwrap())
+	}
+
+	#[test]
+	fn ed25519_signatures_verify() {
+		register_unsupported_extension();
+		// Test 1 of RFC 8032, which signs the empty message.
+		let public_key = hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+		let signature = hex(concat!(
+			"e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555",
+			"fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+		));
+		assert!(ed25519_verify(&public_key, b"", &signature));
+		assert!(!ed25519_verify(&public_key, b"forged", &signature));
+		let mut other_key = public_key;
+		other_key[0] ^= 1;
+		assert!(!ed25519_verify(&other_key, b"", &signature));
+	}
+
+	#[test]
+	fn bls_aggregate_signatures_verify() {
+		use bls12_381::Scalar;
+
+		register_unsupported_extension();
+		let secret_keys = [Scalar::from(3), Scalar::from(5)];
+		let public_keys = secret_keys.map(bls_public_key);
+		let signature = bls_sign(secret_keys[0] + secret_keys[1], b"message");
+
+		assert!(bls_fast_aggregate_verify(&public_keys, b"message", &signature));
+		assert!(!bls_fast_aggregate_verify(&public_keys, b"forged", &signature));
+		assert!(!bls_fast_aggregate_verify(&public_keys[..1], b"message", &signature));
+		assert!(!bls_fast_aggregate_verify(&[], b"message", &signature));
+		assert!(!bls_fast_aggregate_verify(&[[0xff; 48]], b"message", &signature));
+		let signature = bls_sign(secret_keys[0], b"message");
+		assert!(bls_fast_aggregate_verify(&public_keys[..1], b"message", &signature));
+	}
+
+	#[test]
+	fn hashes_match_host_functions() {
+		register_unsupported_extension();
+		assert_eq!(
+			double_sha256(b""),
+			hex("5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456")
+		);
+		assert_eq!(
+			keccak_256(b""),
+			hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "crypto-extension")]
+	fn calls_the_chain_extension() {
+		use scale::{Decode, Encode};
+
+		/// Stands in for the runtime, hashing to a fixed value, accepting signatures of the
+		/// message `signed` and aggregating keys by XOR.
+		struct MockExtension(u32);
+
+		impl ink::env::test::ChainExtension for MockExtension {
+			fn func_id(&self) -> u32 {
+				self.0
+			}
+
+			fn call(&mut self, input: &[u8], output: &mut Vec<u8>) -> u32 {
+				let input = Vec::<u8>::decode(&mut &input[..]).unwrap();
+				match self.0 {
+					BLS_AGGREGATE => {
+						let keys = Vec::<[u8; 48]>::decode(&mut &input[..]).unwrap();
+						let xor = keys.iter().fold([0; 48], |mut sum, key| {
+							sum.iter_mut().zip(key).for_each(|(sum, byte)| *sum ^= byte);
+							sum
+						});
+						Some(xor).encode_to(output)
+					},
+					BLS_VERIFY => {
+						let (public_key, message, _) =
+							<([u8; 48], Vec<u8>, [u8; 96])>::decode(&mut &input[..]).unwrap();
+						(message == b"signed" && public_key != [0; 48]).encode_to(output)
+					},
+					ED25519_VERIFY => {
+						let (_, message, _) =
+							<([u8; 32], Vec<u8>, [u8; 64])>::decode(&mut &input[..]).unwrap();
+						(message == b"signed").encode_to(output)
+					},
+					func_id => [func_id as u8; 32].encode_to(output),
+				}
+				0
+			}
+		}
+
+		for func_id in [DOUBLE_SHA256, KECCAK_256, BLS_VERIFY, BLS_AGGREGATE, ED25519_VERIFY] {
+			ink::env::test::register_chain_extension(MockExtension(func_id));
+		}
+		assert_eq!(double_sha256(b"abc"), [0; 32]);
+		assert_eq!(keccak_256(b"abc"), [1; 32]);
+		assert!(ed25519_verify(&[0; 32], b"signed", &[0; 64]));
+		assert!(!ed25519_verify(&[0; 32], b"forged", &[0; 64]));
+		assert!(bls_fast_aggregate_verify(&[[1; 48]], b"signed", &[0; 96]));
+		assert!(!bls_fast_aggregate_verify(&[[1; 48]], b"forged", &[0; 96]));
+		// Keys beyond a chunk are aggregated in parts, and the parts aggregated in turn.
+		let keys: Vec<_> = (1..=BLS_AGGREGATE_CHUNK as u8 + 1).map(|i| [i; 48]).collect();
+		assert!(bls_fast_aggregate_verify(&keys, b"signed", &[0; 96]));
+		assert!(!bls_fast_aggregate_verify(&[[1; 48], [1; 48]], b"signed", &[0; 96]));
+
+		// Functions the extension does not provide are computed in the contract.
+		register_unsupported_extension();
+		assert_eq!(double_sha256(b""), host_hash::<Sha2x256>(&host_hash::<Sha2x256>(b"")));
+		assert_eq!(keccak_256(b""), host_hash::<Keccak256>(b""));
+		assert!(!bls_fast_aggregate_verify(&[[1; 48]], b"signed", &[0; 96]));
+	}
+}
@@ -0,0 +1,153 @@
+//! The fees relayers and verifiers pay: how the relay fee follows the rate of headers, how verify
+//! fees are discounted, sponsored, escrowed and shared out.
+//!
+//! The balances and credits these fees move are held in `SpvBridge`'s storage; this module only
+//! has the types and the arithmetic that doesn't need it.
+
+use crate::spv_bridge::{ChainId, HashValue};
+use ink::{env::DefaultEnvironment, primitives::AccountId};
+
+type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+type BlockNumber = <DefaultEnvironment as ink::env::Environment>::BlockNumber;
+type Timestamp = <DefaultEnvironment as ink::env::Environment>::Timestamp;
+
+/// The most steps by which the relay fee decays, however long no header has been accepted.
+/// Enough for any fee to decay to its floor at a step of 10%.
+pub const MAX_FEE_DECAY_STEPS: u64 = 512;
+
+/// The number of target chain blocks in each period of fee exempt verification quotas.
+pub const FEE_EXEMPTION_PERIOD: BlockNumber = 14_400;
+
+/// The discount, in percent of the verify fee, on each claim after the first one that is
+/// verified against the same block in one call.
+pub const BATCH_VERIFY_DISCOUNT_PERCENT: Balance = 50;
+
+/// A sponsor's authorization to pay verify fees for a user, see `SpvBridge::sponsor`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct Sponsorship {
+	/// The account whose credits pay the user's fees
+	pub sponsor: AccountId,
+	/// How much more of the user's fees the sponsor will pay
+	pub allowance: Balance,
+}
+
+/// How verify fees are shared out, in basis points of each fee, see
+/// `SpvBridge::set_fee_split`. The relayer of the verified block gets what is left.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct FeeSplit {
+	/// The share added to the treasury
+	pub treasury_bps: u16,
+	/// The share burned, which stays in the contract without being credited to anyone
+	pub burn_bps: u16,
+}
+
+/// How the default chain's relay fee follows the rate at which headers are accepted, see
+/// `SpvBridge::current_relay_fee`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct FeeController {
+	/// The interval between headers, in milliseconds, below which the fee rises. Usually the
+	/// source chain's target block time.
+	pub target_block_time: Timestamp,
+	/// By how many percent the fee rises or decays at each step
+	pub step_percent: u32,
+}
+
+/// A verify fee held by the bridge until the verified block is sufficiently confirmed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct FeeEscrow {
+	/// The account that paid the fee, who is refunded if the block leaves the canon chain
+	pub(crate) verifier: AccountId,
+	/// The source chain of the block that was verified against
+	pub(crate) chain_id: ChainId,
+	/// The block that was verified against
+	pub(crate) block_hash: HashValue,
+	/// The height of the block that was verified against
+	pub(crate) block_height: u64,
+	/// The relayer of the block, who is paid the fee once it is released
+	pub(crate) relayer: AccountId,
+	/// The best height at which the fee may be released to the block's relayer
+	pub(crate) release_height: u64,
+	/// The escrowed amount
+	pub(crate) amount: Balance,
+}
+
+impl FeeSplit {
+	/// The treasury's and the burned share of a verify fee. The relayer's share is the rest.
+	pub fn shares(&self, verify_fee: Balance) -> (Balance, Balance) {
+		let share = |bps: u16| verify_fee.saturating_mul(Balance::from(bps)) / 10_000;
+		(share(self.treasury_bps), share(self.burn_bps))
+	}
+}
+
+impl FeeController {
+	/// The surge fee decayed by a step for every target block time elapsed since the last
+	/// header, at most `MAX_FEE_DECAY_STEPS` times, and never below the base fee.
+	pub fn decayed_fee(&self, surge_fee: Balance, base: Balance, elapsed: Timestamp) -> Balance {
+		let periods = elapsed / self.target_block_time.max(1);
+		let mut fee = surge_fee;
+		for _ in 0..periods.min(MAX_FEE_DECAY_STEPS) {
+			if fee <= base {
+				break
+			}
+			fee = fee.saturating_mul(100 - Balance::from(self.step_percent.min(100))) / 100;
+		}
+		fee.max(base)
+	}
+
+	/// The fee raised by a step if a header arrived sooner than the target block time after
+	/// the previous one, or the fee unchanged otherwise.
+	pub fn surged_fee(&self, fee: Balance, interval: Timestamp) -> Balance {
+		if interval < self.target_block_time {
+			fee.saturating_add(fee.saturating_mul(Balance::from(self.step_percent)) / 100)
+		} else {
+			fee
+		}
+	}
+}
+
+/// The combined verify fee for a number of claims against the same block, see
+/// `SpvBridge::batch_verify_fee`.
+pub fn discounted_fee(verify_fee: Balance, claims: u32) -> Balance {
+	let discounted = verify_fee.saturating_mul(100 - BATCH_VERIFY_DISCOUNT_PERCENT) / 100;
+	let additional = Balance::from(claims.saturating_sub(1));
+	verify_fee.saturating_add(discounted.saturating_mul(additional))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn later_claims_are_discounted() {
+		assert_eq!(discounted_fee(100, 0), 100);
+		assert_eq!(discounted_fee(100, 1), 100);
+		assert_eq!(discounted_fee(100, 3), 200);
+		assert_eq!(discounted_fee(Balance::MAX, 2), Balance::MAX);
+	}
+
+	#[test]
+	fn fee_split_shares_are_basis_points() {
+		let split = FeeSplit { treasury_bps: 2_500, burn_bps: 1_000 };
+		assert_eq!(split.shares(1_000), (250, 100));
+		assert_eq!(FeeSplit::default().shares(1_000), (0, 0));
+	}
+
+	#[test]
+	fn relay_fee_surges_and_decays() {
+		let controller = FeeController { target_block_time: 1_000, step_percent: 10 };
+		assert_eq!(controller.surged_fee(1_000, 999), 1_100);
+		assert_eq!(controller.surged_fee(1_000, 1_000), 1_000);
+
+		assert_eq!(controller.decayed_fee(1_210, 1_000, 999), 1_210);
+		assert_eq!(controller.decayed_fee(1_210, 1_000, 1_000), 1_089);
+		// The fee never decays below the base fee
+		assert_eq!(controller.decayed_fee(1_210, 1_000, 5_000), 1_000);
+		// A zero target block time counts every millisecond as a period
+		let controller = FeeController { target_block_time: 0, step_percent: 100 };
+		assert_eq!(controller.decayed_fee(1_000, 10, 1), 10);
+	}
+}
@@ -0,0 +1,550 @@
+//! The light clients of source chains whose headers are proven final by their consensus
+//! rather than by work: GRANDPA, BEEFY, Tendermint and the Ethereum sync committee.
+//!
+//! These are the types that their headers, proofs and light client states are made of, and the
+//! checks on them that don't need the bridge's storage. The messages that register such chains
+//! and submit their headers are `SpvBridge`'s, see `ConsensusBackend`.
+
+use crate::{
+	codec, crypto,
+	spv_bridge::{Error, HashValue, Result},
+	tendermint,
+};
+use ink::prelude::vec::Vec;
+
+/// The id that GRANDPA's messages in Substrate header digests and its justifications are
+/// tagged with.
+pub const GRANDPA_ENGINE_ID: [u8; 4] = *b"FRNK";
+
+/// The payload id of the MMR root in a BEEFY commitment.
+pub const BEEFY_MMR_ROOT_ID: [u8; 2] = *b"mh";
+
+/// The number of members of an Ethereum sync committee.
+pub const SYNC_COMMITTEE_SIZE: u32 = 512;
+
+/// The number of sync committee public keys submitted together, see
+/// `SpvBridge::submit_sync_committee_keys`.
+pub const SYNC_COMMITTEE_KEYS_PER_CHUNK: u32 = 128;
+
+/// The number of slots in a beacon chain epoch.
+pub const SLOTS_PER_EPOCH: u64 = 32;
+
+/// The number of slots, which are the heights of a sync committee chain's headers, that a
+/// sync committee serves for.
+pub const SLOTS_PER_SYNC_COMMITTEE_PERIOD: u64 = 8192;
+
+/// The index of the current sync committee's root among the leaves of a beacon state's SSZ
+/// tree, whose depth depends on the fork, see `BeaconFork`.
+pub const CURRENT_SYNC_COMMITTEE_INDEX: u64 = 22;
+
+/// The index of the next sync committee's root among the leaves of a beacon state's SSZ
+/// tree.
+pub const NEXT_SYNC_COMMITTEE_INDEX: u64 = 23;
+
+/// A Substrate block header.
+///
+/// Its SCALE encoding matches Substrate's `sp_runtime::generic::Header` with `u32` block
+/// numbers, so relayers can submit the header bytes a Substrate node serves, and its hash is
+/// `codec::substrate_header_hash`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SubstrateHeader {
+	/// The hash of the parent block
+	pub parent_hash: HashValue,
+	/// The block number
+	#[codec(compact)]
+	pub number: u32,
+	/// The root of the state trie after the block
+	pub state_root: HashValue,
+	/// The root of the trie of the block's extrinsics
+	pub extrinsics_root: HashValue,
+	/// The logs the runtime and the consensus engines attached to the block
+	pub digest: Vec<DigestItem>,
+}
+
+/// A log in a Substrate header's digest, matching `sp_runtime::generic::DigestItem`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum DigestItem {
+	/// Anything else
+	#[codec(index = 0)]
+	Other(Vec<u8>),
+	/// A message from the runtime to a consensus engine, such as a GRANDPA authority set
+	/// change under `GRANDPA_ENGINE_ID`
+	#[codec(index = 4)]
+	Consensus([u8; 4], Vec<u8>),
+	/// A consensus engine's seal on the block
+	#[codec(index = 5)]
+	Seal([u8; 4], Vec<u8>),
+	/// A message from a consensus engine to the runtime
+	#[codec(index = 6)]
+	PreRuntime([u8; 4], Vec<u8>),
+	/// The runtime's code or heap pages have changed
+	#[codec(index = 8)]
+	RuntimeEnvironmentUpdated,
+}
+
+/// The GRANDPA light client state of a source chain, see `SpvBridge::grandpa_state`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct GrandpaState {
+	/// The id of the current authority set
+	pub set_id: u64,
+	/// The Ed25519 public keys and voting weights of the current authorities
+	pub authorities: Vec<([u8; 32], u64)>,
+	/// The height of the header that enacted the current set, or the checkpoint's
+	pub enacted_at: u64,
+}
+
+/// A GRANDPA justification of a header, see `SpvBridge::submit_grandpa_header`.
+///
+/// Its SCALE encoding matches Substrate's `GrandpaJustification`, the justification a node
+/// stores under `GRANDPA_ENGINE_ID`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct GrandpaJustification {
+	/// The round the precommits were cast in
+	pub round: u64,
+	/// The justified block and the precommits for it
+	pub commit: GrandpaCommit,
+	/// The headers from the justified block up to the blocks that precommits target, for
+	/// precommits to its descendants
+	pub votes_ancestries: Vec<SubstrateHeader>,
+}
+
+/// The precommits that finalize a block in a round of GRANDPA.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct GrandpaCommit {
+	/// The hash of the finalized block
+	pub target_hash: HashValue,
+	/// The number of the finalized block
+	pub target_number: u32,
+	/// The precommits, at most one per authority
+	pub precommits: Vec<SignedPrecommit>,
+}
+
+/// A GRANDPA vote for a block and, implicitly, its ancestors.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct Precommit {
+	/// The hash of the block voted for
+	pub target_hash: HashValue,
+	/// The number of the block voted for
+	pub target_number: u32,
+}
+
+/// A GRANDPA authority's signed precommit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct SignedPrecommit {
+	/// The precommit
+	pub precommit: Precommit,
+	/// The authority's signature of `codec::grandpa_precommit_payload`
+	pub signature: [u8; 64],
+	/// The authority's Ed25519 public key
+	pub id: [u8; 32],
+}
+
+/// A message from the runtime to GRANDPA in a header's digest, matching
+/// `sp_consensus_grandpa::ConsensusLog` with `u32` block numbers.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum GrandpaConsensusLog {
+	/// The authority set changes once the header is finalized and `delay` more blocks are
+	#[codec(index = 1)]
+	ScheduledChange(ScheduledChange),
+	/// The authority set changes at the given number plus the change's delay, whether or not
+	/// it is finalized
+	#[codec(index = 2)]
+	ForcedChange(u32, ScheduledChange),
+	/// The authority at the given position in the set is disabled
+	#[codec(index = 3)]
+	OnDisabled(u64),
+	/// GRANDPA pauses after the given delay
+	#[codec(index = 4)]
+	Pause(u32),
+	/// GRANDPA resumes after the given delay
+	#[codec(index = 5)]
+	Resume(u32),
+}
+
+/// A change to the next GRANDPA authority set, see `GrandpaConsensusLog`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct ScheduledChange {
+	/// The Ed25519 public keys and voting weights of the next authorities
+	pub next_authorities: Vec<([u8; 32], u64)>,
+	/// The number of blocks after the scheduling one at which the change is enacted
+	pub delay: u32,
+}
+
+/// A BEEFY commitment, which the source chain's authorities sign to finalize a block.
+///
+/// Its SCALE encoding matches Substrate's `sp_consensus_beefy::Commitment`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct BeefyCommitment {
+	/// The signed data, keyed by two byte ids. The MMR root is under `BEEFY_MMR_ROOT_ID`.
+	pub payload: Vec<([u8; 2], Vec<u8>)>,
+	/// The number of the finalized block
+	pub block_number: u32,
+	/// The id of the authority set that signs the commitment
+	pub validator_set_id: u64,
+}
+
+/// The BEEFY light client state of a source chain, see `SpvBridge::beefy_state`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct BeefyState {
+	/// The id of the current authority set
+	pub validator_set_id: u64,
+	/// The compressed ECDSA public keys of the current authorities
+	pub authorities: Vec<[u8; 33]>,
+	/// The MMR root of the latest accepted commitment
+	pub mmr_root: HashValue,
+	/// The block number of the latest accepted commitment
+	pub block_number: u32,
+}
+
+// `StorageLayout` is not implemented for arrays longer than 32, so it can't be derived.
+#[cfg(feature = "std")]
+impl ink::storage::traits::StorageLayout for BeefyState {
+	fn layout(key: &ink::primitives::Key) -> ink::metadata::layout::Layout {
+		use ink::metadata::layout::{Layout, LayoutKey, LeafLayout};
+		Layout::Leaf(LeafLayout::from_key::<Self>(LayoutKey::from(key)))
+	}
+}
+
+/// A proof that a header is a leaf of a source chain's MMR, see `mmr::root_from_proof`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct MmrProof {
+	/// The position of the header's leaf
+	pub leaf_index: u64,
+	/// The number of leaves in the MMR the proof is against
+	pub leaf_count: u64,
+	/// The peaks and siblings needed to recompute the root
+	pub items: Vec<HashValue>,
+}
+
+/// A header of a Tendermint chain, with the fields of Tendermint's `Header`, see
+/// `tendermint::header_hash`.
+///
+/// Hashes that a header may leave out, such as the data hash of an empty block, are empty
+/// when absent.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct TendermintHeader {
+	/// The versions of the block protocol and of the application
+	pub version: TendermintVersion,
+	/// The chain's Tendermint chain id
+	pub chain_id: Vec<u8>,
+	/// The block height
+	pub height: u64,
+	/// The block time
+	pub time: TendermintTime,
+	/// The id of the parent block
+	pub last_block_id: BlockId,
+	/// The merkle root of the parent block's commit
+	pub last_commit_hash: Vec<u8>,
+	/// The merkle root of the block's transactions
+	pub data_hash: Vec<u8>,
+	/// The `tendermint::validators_hash` of the validators that commit the block
+	pub validators_hash: HashValue,
+	/// The `tendermint::validators_hash` of the validators that commit the next block
+	pub next_validators_hash: HashValue,
+	/// The hash of the consensus parameters
+	pub consensus_hash: HashValue,
+	/// The application's state root after the parent block
+	pub app_hash: Vec<u8>,
+	/// The merkle root of the parent block's transaction results
+	pub last_results_hash: Vec<u8>,
+	/// The merkle root of the evidence of misbehaviour in the block
+	pub evidence_hash: Vec<u8>,
+	/// The address of the validator that proposed the block
+	pub proposer_address: Vec<u8>,
+}
+
+/// The version of a Tendermint header.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct TendermintVersion {
+	/// The block protocol version
+	pub block: u64,
+	/// The application version
+	pub app: u64,
+}
+
+/// A point in time, as Tendermint's protobuf `Timestamp`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct TendermintTime {
+	/// Seconds since the Unix epoch
+	pub seconds: i64,
+	/// Nanoseconds into the second
+	pub nanos: i32,
+}
+
+/// The id of a Tendermint block: its header hash and the header of its part set.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct BlockId {
+	/// The block's header hash, empty for the parent of the first block
+	pub hash: Vec<u8>,
+	/// The header of the parts the block was gossiped in
+	pub part_set_header: PartSetHeader,
+}
+
+/// The header of the set of parts a Tendermint block is gossiped in.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct PartSetHeader {
+	/// The number of parts
+	pub total: u32,
+	/// The merkle root of the parts
+	pub hash: Vec<u8>,
+}
+
+/// A Tendermint commit of a header: the precommits to it from one round of voting.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct TendermintCommit {
+	/// The round the precommits were cast in
+	pub round: u32,
+	/// The part set header of the committed block's id
+	pub part_set_header: PartSetHeader,
+	/// One entry per validator of the set that signed, in the set's order. Validators that
+	/// did not precommit to the block have none.
+	pub signatures: Vec<Option<CommitSig>>,
+}
+
+/// A validator's signed precommit in a Tendermint commit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct CommitSig {
+	/// The validator's vote time
+	pub timestamp: TendermintTime,
+	/// The validator's signature of `tendermint::vote_sign_bytes`
+	pub signature: [u8; 64],
+}
+
+/// The Tendermint light client state of a source chain, see `SpvBridge::tendermint_state`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct TendermintState {
+	/// The chain's Tendermint chain id, which its validators sign votes under
+	pub network: Vec<u8>,
+	/// The Ed25519 public keys and voting powers of the validator set trusted to commit the
+	/// blocks after the trusted height
+	pub validators: Vec<([u8; 32], u64)>,
+	/// The height of the latest accepted header, or the checkpoint's
+	pub trusted_height: u64,
+}
+
+/// A beacon chain block header, see `codec::beacon_header_root`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct BeaconBlockHeader {
+	/// The slot the block was proposed in
+	pub slot: u64,
+	/// The index of the validator that proposed the block
+	pub proposer_index: u64,
+	/// The root of the parent block
+	pub parent_root: HashValue,
+	/// The SSZ hash tree root of the beacon state after the block
+	pub state_root: HashValue,
+	/// The SSZ hash tree root of the block body
+	pub body_root: HashValue,
+}
+
+/// A beacon chain fork: the version sync committees sign under from its first epoch on, and
+/// the depth of the beacon state's SSZ tree, which proves sync committees.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct BeaconFork {
+	/// The first epoch of the fork
+	pub epoch: u64,
+	/// The fork version
+	pub version: [u8; 4],
+	/// The depth of the beacon state's SSZ tree: 5 from Altair, 6 from Electra
+	pub state_depth: u8,
+}
+
+/// The Ethereum sync committee light client state of a source chain, see
+/// `SpvBridge::sync_committee_state`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct SyncCommitteeState {
+	/// The root of the beacon chain's genesis validators, which fixes the signing domain
+	pub genesis_validators_root: HashValue,
+	/// The chain's forks from the checkpoint's on, by first epoch
+	pub forks: Vec<BeaconFork>,
+	/// The sync committee period of the current committee
+	pub period: u64,
+	/// The SSZ hash tree root of the current committee
+	pub committee_root: HashValue,
+	/// The SSZ hash tree root of the next period's committee, once a header has proven it
+	pub next_committee_root: Option<HashValue>,
+}
+
+impl SyncCommitteeState {
+	/// The fork in effect at the given slot.
+	pub(crate) fn fork_at(&self, slot: u64) -> BeaconFork {
+		let epoch = slot / SLOTS_PER_EPOCH;
+		let mut forks = self.forks.iter().rev();
+		*forks.find(|fork| fork.epoch <= epoch).unwrap_or(&self.forks[0])
+	}
+
+	/// Whether `branch` proves the sync committee with the given root to be the one at
+	/// `index` among the leaves of the state tree of `header`.
+	pub(crate) fn proves_committee(
+		&self,
+		header: &BeaconBlockHeader,
+		root: &HashValue,
+		branch: &[HashValue],
+		index: u64,
+	) -> bool {
+		branch.len() == usize::from(self.fork_at(header.slot).state_depth) &&
+			codec::ssz_branch_root(root, branch, index) == header.state_root
+	}
+}
+
+/// The next period's sync committee, proven against the state root of a signed header.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub struct NextSyncCommittee {
+	/// The SSZ hash tree root of the committee
+	pub root: HashValue,
+	/// The SSZ merkle branch from the root to the header's state root, from the leaf up
+	pub branch: Vec<HashValue>,
+}
+/// The positions in the current authority set of the authorities whose precommits in a
+/// justification are valid. Fails if a precommit is by an authority outside the set,
+/// repeated, badly signed, or for a block that the ancestry headers don't link to the
+/// justified one.
+pub fn grandpa_signers(
+	state: &GrandpaState,
+	justification: &GrandpaJustification,
+) -> Result<Vec<usize>> {
+	let ancestry: Vec<_> = justification
+		.votes_ancestries
+		.iter()
+		.map(|header| (codec::substrate_header_hash(header), header.parent_hash))
+		.collect();
+	let target = justification.commit.target_hash;
+
+	let mut signers = Vec::new();
+	for signed in &justification.commit.precommits {
+		let signer = state
+			.authorities
+			.iter()
+			.position(|(public_key, _)| *public_key == signed.id)
+			.ok_or(Error::InvalidSignature)?;
+		if signers.contains(&signer) {
+			return Err(Error::DuplicateVote)
+		}
+		// A precommit for a block counts for its ancestors too.
+		let mut block = signed.precommit.target_hash;
+		for _ in 0..ancestry.len() {
+			match ancestry.iter().find(|(hash, _)| *hash == block) {
+				Some((_, parent)) if block != target => block = *parent,
+				_ => break,
+			}
+		}
+		if block != target {
+			return Err(Error::InvalidJustification)
+		}
+		let payload =
+			codec::grandpa_precommit_payload(&signed.precommit, justification.round, state.set_id);
+		if !crypto::ed25519_verify(&signed.id, &payload, &signed.signature) {
+			return Err(Error::InvalidSignature)
+		}
+		signers.push(signer);
+	}
+	Ok(signers)
+}
+
+/// The change to the next GRANDPA authority set, if the given header enacts one.
+pub fn grandpa_authority_change(header: &SubstrateHeader) -> Result<Option<ScheduledChange>> {
+	let logs = codec::grandpa_consensus_logs(header).map_err(|_| Error::MalformedHeader)?;
+	let mut enacted = None;
+	for log in logs {
+		// The bridge can't tell which header enacts a delayed or forced change.
+		let supported = match &log {
+			GrandpaConsensusLog::ScheduledChange(change) =>
+				change.delay == 0 && change.next_authorities.iter().any(|(_, weight)| *weight != 0),
+			GrandpaConsensusLog::ForcedChange(..) => false,
+			_ => continue,
+		};
+		if !supported {
+			return Err(Error::UnsupportedAuthorityChange)
+		}
+		if let GrandpaConsensusLog::ScheduledChange(change) = log {
+			enacted = Some(change);
+		}
+	}
+	Ok(enacted)
+}
+
+/// The total voting power of the given validators.
+pub fn voting_power<'a>(validators: impl IntoIterator<Item = &'a ([u8; 32], u64)>) -> u128 {
+	validators.into_iter().map(|(_, power)| u128::from(*power)).sum()
+}
+
+/// The number of BEEFY authorities that signed a commitment. `signatures` has one entry per
+/// authority, in the set's order. Fails if a signature is not its authority's.
+pub fn beefy_signers(
+	authorities: &[[u8; 33]],
+	commitment: &BeefyCommitment,
+	signatures: &[Option<[u8; 65]>],
+) -> Result<usize> {
+	if signatures.len() != authorities.len() {
+		return Err(Error::InsufficientSignatures)
+	}
+	let message = codec::beefy_commitment_hash(commitment);
+	let mut signed = 0;
+	for (authority, signature) in authorities.iter().zip(signatures) {
+		let Some(signature) = signature else { continue };
+		let mut signer = [0; 33];
+		if ink::env::ecdsa_recover(signature, &message, &mut signer).is_err() ||
+			signer != *authority
+		{
+			return Err(Error::InvalidSignature)
+		}
+		signed += 1;
+	}
+	Ok(signed)
+}
+
+/// The validators whose precommits in a Tendermint commit to the header with the given hash
+/// are signed, under the given Tendermint chain id. `commit` has one entry per validator, in
+/// the set's order. Fails if a signature is not the validator's.
+pub fn tendermint_signers<'a>(
+	network: &[u8],
+	header: &TendermintHeader,
+	header_hash: &HashValue,
+	commit: &TendermintCommit,
+	validators: &'a [([u8; 32], u64)],
+) -> Result<Vec<&'a ([u8; 32], u64)>> {
+	if commit.signatures.len() != validators.len() {
+		return Err(Error::InvalidJustification)
+	}
+	let mut signers = Vec::new();
+	for (validator, vote) in validators.iter().zip(&commit.signatures) {
+		let Some(vote) = vote else { continue };
+		let sign_bytes = tendermint::vote_sign_bytes(
+			network,
+			header.height,
+			commit.round,
+			header_hash,
+			&commit.part_set_header,
+			&vote.timestamp,
+		);
+		if !crypto::ed25519_verify(&validator.0, &sign_bytes, &vote.signature) {
+			return Err(Error::InvalidSignature)
+		}
+		signers.push(validator);
+	}
+	Ok(signers)
+}
@@ -0,0 +1,23 @@
+//! Decode arbitrary bytes as the source chain structures the bridge accepts, and check that
+//! whatever decodes encodes back to the same bytes.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use spv_bridge::codec;
+
+fuzz_target!(|data: &[u8]| {
+	if let Ok(header) = codec::decode_header(data) {
+		assert_eq!(codec::encode_header(&header), data);
+		codec::header_hash(&header);
+	}
+	if let Ok(claim) = codec::decode_state_claim(data) {
+		assert_eq!(codec::encode_state_claim(&claim), data);
+	}
+	if let Ok(transaction) = codec::decode_transaction(data) {
+		assert_eq!(codec::encode_transaction(&transaction), data);
+	}
+	if let Ok(header) = codec::decode_bitcoin_header(data) {
+		assert_eq!(codec::encode_bitcoin_header(&header), data);
+	}
+});
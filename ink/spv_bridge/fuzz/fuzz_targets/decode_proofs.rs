@@ -0,0 +1,25 @@
+//! Decode arbitrary bytes as each proof format, and check the decoded proof against a claim and
+//! root taken from the input. Checking must reject, not panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use scale::Decode;
+use spv_bridge::{mpt, substrate_trie, HashValue, MerkleMultiproof, MerkleProof};
+
+fuzz_target!(|data: &[u8]| {
+	let Ok((claim, root)) = <(HashValue, HashValue)>::decode(&mut &data[..]) else { return };
+	let proof = &data[64..];
+
+	if let Ok(p) = MerkleProof::decode(&mut &proof[..]) {
+		p.leaf_index();
+		MerkleProof::check_merkle_proof(claim, p, root);
+	}
+	if let Ok((leaves, p)) = <(Vec<HashValue>, MerkleMultiproof)>::decode(&mut &proof[..]) {
+		MerkleMultiproof::check_merkle_multiproof(&leaves, &p, root);
+	}
+	if let Ok(nodes) = Vec::<Vec<u8>>::decode(&mut &proof[..]) {
+		let _ = mpt::read(&root, &claim, &nodes);
+		let _ = substrate_trie::read(&root, &claim, &nodes);
+	}
+});
@@ -0,0 +1,36 @@
+//! Submit arbitrary bytes as a header to a freshly deployed bridge. Submission must reject bad
+//! headers with an error, not panic.
+//!
+//! Random bytes are unlikely to name a known parent, so each input is also submitted with its
+//! parent field pointed at the bridge's tip, which reaches the checks past the parent lookup.
+
+#![no_main]
+
+use ink::env::{test, DefaultEnvironment};
+use libfuzzer_sys::fuzz_target;
+use spv_bridge::{codec, SpvBridge};
+
+/// An easy PoW threshold, so that mutated headers often pass the seal check.
+const THRESHOLD: [u8; 32] = [0x7f; 32];
+
+/// The offset of the parent hash in an encoded header, after the height.
+const PARENT_OFFSET: usize = 8;
+
+fuzz_target!(|data: &[u8]| {
+	let Ok(header) = codec::decode_header(data) else { return };
+
+	test::run_test::<DefaultEnvironment, _>(|accounts| {
+		let genesis = codec::decode_header(&[1; codec::HEADER_ENCODED_LEN]).unwrap();
+		test::set_callee::<DefaultEnvironment>([0xff; 32].into());
+		test::set_caller::<DefaultEnvironment>(accounts.alice);
+		test::set_block_timestamp::<DefaultEnvironment>(u64::MAX / 2);
+		let mut bridge = SpvBridge::new(genesis, THRESHOLD, 0, 0);
+		let _ = bridge.submit_new_header(header);
+
+		let mut reparented = data.to_vec();
+		reparented[PARENT_OFFSET..PARENT_OFFSET + 32].copy_from_slice(&bridge.best_header_hash());
+		let _ = bridge.submit_new_header(codec::decode_header(&reparented).unwrap());
+		Ok(())
+	})
+	.unwrap();
+});
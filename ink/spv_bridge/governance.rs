@@ -0,0 +1,173 @@
+//! The bridge's governance: the roles its messages require, the critical actions that signers
+//! approve, and the parameter changes that the timelock queues.
+//!
+//! The messages themselves are `SpvBridge`'s, see `SpvBridge::grant_role`,
+//! `SpvBridge::set_signers` and `SpvBridge::set_parameter_timelock`.
+
+use crate::{
+	fees::{FeeController, FeeSplit},
+	spv_bridge::{
+		ChainId, Error, HashValue, Header, RateLimit, Result, WrappedAsset, XcmDestination,
+	},
+};
+use ink::{
+	env::DefaultEnvironment,
+	prelude::vec::Vec,
+	primitives::{AccountId, Hash},
+};
+
+type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+type BlockNumber = <DefaultEnvironment as ink::env::Environment>::BlockNumber;
+
+/// The maximum number of signers that approve critical actions.
+pub const MAX_SIGNERS: usize = 16;
+
+/// The maximum number of parameter changes that can be queued at once.
+pub const MAX_PENDING_CHANGES: usize = 16;
+
+/// A duty that governance messages require of their caller. Admins hold every role.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum Role {
+	/// May grant and revoke roles, and call every governance message
+	Admin,
+	/// May change the relay and verify fees and how they are charged and shared
+	FeeManager,
+	/// May pause and unpause the bridge
+	Pauser,
+	/// May re-anchor the bridge at a new checkpoint and resolve halts
+	CheckpointManager,
+}
+
+/// An action that, once signers are configured, only executes with their approval, see
+/// `SpvBridge::set_signers`.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub enum CriticalAction {
+	/// Replace the contract's code, as `SpvBridge::upgrade` does
+	Upgrade(Hash),
+	/// Schedule a checkpoint reset, as `SpvBridge::reset_checkpoint` does
+	ResetCheckpoint(Header),
+	/// Pause the bridge, as `SpvBridge::pause` does
+	Pause,
+	/// Unpause the bridge, as `SpvBridge::unpause` does
+	Unpause,
+	/// Lift a halt, as `SpvBridge::resolve_halt` does
+	ResolveHalt(bool),
+	/// Replace the signer set and threshold, as `SpvBridge::set_signers` does
+	SetSigners(Vec<AccountId>, u32),
+}
+
+/// A critical action collecting signer approvals.
+#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct Proposal {
+	/// The action to execute once enough signers approve
+	pub action: CriticalAction,
+	/// The signers that have approved, the proposer first
+	pub approvals: Vec<AccountId>,
+	/// The signer set the approvals were given under
+	pub signer_epoch: u32,
+}
+
+/// A governance-controlled bridge parameter, together with its new value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub enum BridgeParameter {
+	/// The fee a relayer must pay to submit a header
+	RelayFee(Balance),
+	/// The fee a verifier must pay to verify a claim
+	VerifyFee(Balance),
+	/// The easiest PoW target a header may declare
+	DifficultyThreshold(HashValue),
+	/// The maximum number of siblings a merkle proof may have
+	MaxProofLength(u32),
+	/// The maximum number of headers that may be submitted in one batch
+	MaxBatchSize(u32),
+	/// How far behind the tip headers may be pruned, or `None` to disable pruning
+	PruneDepth(Option<u64>),
+	/// How far the finalized height trails the tip, or `None` to only finalize the checkpoint
+	FinalityDepth(Option<u64>),
+	/// How many verifications against each header are subsidized for verifiers who don't pay
+	FreeVerifications(u32),
+	/// How many target chain blocks apart best height samples are taken, or `None` to stop
+	SampleInterval(Option<BlockNumber>),
+	/// Whether headers may prove their work by merged mining
+	MergedMining(bool),
+	/// For how many target chain blocks optimistically submitted headers may be challenged,
+	/// or `None` to disable optimistic submission
+	ChallengePeriod(Option<BlockNumber>),
+	/// The bond a relayer must lock with each optimistically submitted header
+	HeaderBond(Balance),
+	/// The stake a relayer must have bonded to submit headers, or `None` to let anyone submit
+	MinRelayerStake(Option<Balance>),
+	/// Whether only relayers on the allowlist may submit headers
+	RelayerAllowlist(bool),
+	/// How many headers may be accepted per target chain block, or `None` for no limit
+	RateLimit(Option<RateLimit>),
+	/// How verify fees are shared between relayers, the treasury and burning
+	FeeSplit(FeeSplit),
+	/// How many canon blocks a reorg may retract before it halts the bridge, or `None` for no
+	/// limit
+	MaxReorgDepth(Option<u64>),
+	/// The depth every verification requires at least
+	MinConfirmations(u64),
+	/// For how many target chain blocks without a new header verifications are still
+	/// accepted, or `None` to accept them however long relaying has stopped
+	StalenessWindow(Option<BlockNumber>),
+	/// How the relay fee follows the rate of header submissions, or `None` for a fixed fee
+	FeeController(Option<FeeController>),
+	/// The parachain verified transactions are forwarded to, or `None` to stop forwarding
+	XcmDestination(Option<XcmDestination>),
+	/// The token minted for proven deposits, or `None` to stop minting
+	WrappedAsset(Option<WrappedAsset>),
+	/// Whether the bridge is paused
+	Paused(bool),
+	/// For how many target chain blocks parameter changes are queued before they can be
+	/// enacted
+	ParameterTimelock(BlockNumber),
+	/// `PruneDepth` for a source chain other than the default one
+	ChainPruneDepth(ChainId, Option<u64>),
+	/// `FinalityDepth` for a source chain other than the default one
+	ChainFinalityDepth(ChainId, Option<u64>),
+}
+
+/// A parameter change the owner has queued, see `SpvBridge::set_parameter_timelock`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct PendingChange {
+	/// The id to enact or cancel the change by
+	pub id: u32,
+	/// The parameter and its new value
+	pub parameter: BridgeParameter,
+	/// The first target chain block in which the change may be enacted
+	pub enactable_at: BlockNumber,
+}
+
+/// Check a signer set and threshold before they replace the current ones: the signers must be
+/// distinct and no more than `MAX_SIGNERS`, and the threshold no more than their number.
+pub fn check_signer_set(signers: &[AccountId], threshold: u32) -> Result<()> {
+	let distinct = signers.iter().enumerate().all(|(i, signer)| !signers[..i].contains(signer));
+	if threshold as usize > signers.len() || signers.len() > MAX_SIGNERS || !distinct {
+		return Err(Error::InvalidSignerSet)
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn signer_sets_are_checked() {
+		let signers: Vec<AccountId> = (0..3).map(|i| AccountId::from([i; 32])).collect();
+		assert_eq!(check_signer_set(&signers, 3), Ok(()));
+		assert_eq!(check_signer_set(&[], 0), Ok(()));
+		assert_eq!(check_signer_set(&signers, 4), Err(Error::InvalidSignerSet));
+		let repeated = [signers[0], signers[1], signers[0]];
+		assert_eq!(check_signer_set(&repeated, 2), Err(Error::InvalidSignerSet));
+		let too_many: Vec<AccountId> =
+			(0..=MAX_SIGNERS as u8).map(|i| AccountId::from([i; 32])).collect();
+		assert_eq!(check_signer_set(&too_many, 1), Err(Error::InvalidSignerSet));
+	}
+}
@@ -1,16 +1,84 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
+pub mod codec;
+pub mod crypto;
+pub mod fees;
+pub mod finality;
+pub mod governance;
+pub mod mmr;
+pub mod mpt;
+pub mod psp22;
+pub mod rlp;
+pub mod substrate_trie;
+pub mod tendermint;
+pub mod u256;
+pub mod verifier;
+pub mod xcm;
+
+pub use self::{
+	spv_bridge::{
+		HashValue, Header, MerkleMultiproof, MerkleProof, SourceTransaction, SpvBridge, StateClaim,
+		VerificationError, VerificationResult,
+	},
+	verifier::SpvVerifier,
+};
+
 #[ink::contract]
 mod spv_bridge {
+	use crate::{
+		codec, crypto,
+		fees::{self, FeeController, FeeEscrow, FeeSplit, Sponsorship, FEE_EXEMPTION_PERIOD},
+		finality::{
+			self, BeaconBlockHeader, BeaconFork, BeefyCommitment, BeefyState, GrandpaJustification,
+			GrandpaState, MmrProof, NextSyncCommittee, SubstrateHeader, SyncCommitteeState,
+			TendermintCommit, TendermintHeader, TendermintState, BEEFY_MMR_ROOT_ID,
+			CURRENT_SYNC_COMMITTEE_INDEX, NEXT_SYNC_COMMITTEE_INDEX, SLOTS_PER_EPOCH,
+			SLOTS_PER_SYNC_COMMITTEE_PERIOD, SYNC_COMMITTEE_KEYS_PER_CHUNK, SYNC_COMMITTEE_SIZE,
+		},
+		governance::{
+			self, BridgeParameter, CriticalAction, PendingChange, Proposal, Role,
+			MAX_PENDING_CHANGES,
+		},
+		mmr, mpt, psp22, rlp, substrate_trie, tendermint,
+		u256::U256,
+		verifier::SpvVerifier,
+		xcm,
+	};
 	use ink::{
-		env::hash::{HashOutput, Sha2x256},
+		env::{
+			call::{build_call, ExecutionInput, Selector},
+			hash::{Blake2x256, HashOutput},
+		},
+		prelude::vec::Vec,
 		storage::Mapping,
 	};
 
 	pub type HashValue = [u8; 32];
 
+	/// Identifies an accepted header submission.
+	pub type SubmissionId = u64;
+
+	/// Identifies a source chain bridged by this contract.
+	pub type ChainId = u32;
+
+	/// A block height on a given source chain.
+	pub type ChainHeight = (ChainId, u64);
+
+	/// A chunk of the public keys of the sync committee with the given root, see
+	/// `SpvBridge::submit_sync_committee_keys`.
+	pub type CommitteeChunk = (HashValue, u32);
+
+	/// A block hash on a given source chain.
+	pub type ChainBlock = (ChainId, HashValue);
+
+	/// A claim hash against a block hash on a given source chain.
+	pub type ChainClaim = (ChainId, HashValue, HashValue);
+
 	/// A block header from the source chain.
-	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	///
+	/// The all-zero `Header::default()` is never a valid header, so that it can't be confused
+	/// with a missing value.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
 	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
 	pub struct Header {
 		/// The height of this block in the chain
@@ -18,36 +86,173 @@ mod spv_bridge {
 		/// The hash of this block's parent
 		parent: HashValue,
 		/// The merkle tree root of the storage
-		storage_root: u64,
+		storage_root: HashValue,
 		/// The merkle tree root of the transactions included in the block
-		transactions_root: u64,
+		transactions_root: HashValue,
+		/// The merkle tree root of the events emitted by the block's transactions
+		receipts_root: HashValue,
+		/// The PoW target that this block's hash must be below
+		target: HashValue,
 		/// The nonce that allows the block's hash to satisfy the proof of work
 		pow_nonce: u64,
+		/// The time at which this block was authored, in milliseconds since the Unix epoch
+		timestamp: u64,
 	}
 
-	/// A STUB of a Merkle Proof
+	/// A header in a versioned envelope, see `SpvBridge::submit_versioned_header`.
 	///
-	/// Rather than actually reassembling a partial tree of siblings etc, we just have a simple
-	/// field indicating whether the proof should be treated as valid or not.
+	/// Each version fixes the bytes its hash commits to, so headers relayed under one version
+	/// keep their hashes when a later version adds fields.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum VersionedHeader {
+		/// A `Header` with extension bytes, hashed with `codec::header_v1_hash`
+		V1(HeaderV1),
+	}
+
+	/// The first version of the header envelope.
 	///
-	/// We stub the proof system because students already built exactly this in assignment 1.
-	/// For this assignment, there is enough to focus on in the smart contract and blockchain
-	/// aspects.
-	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	/// Its hash commits to the encoded header followed by the extension bytes. Without extension
+	/// bytes that is the hash of the bare `Header`, as submitted by `submit_new_header`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct HeaderV1 {
+		/// The header fields that the bridge interprets
+		pub header: Header,
+		/// Fields the source chain has added that the bridge does not interpret yet
+		pub extension: Vec<u8>,
+	}
+
+	/// A Merkle inclusion proof for a single leaf.
+	///
+	/// The proof carries the sibling of each node on the path from the leaf up to the root.
+	/// Leaves are the Sha2x256 hash of `codec::MERKLE_LEAF_PREFIX` followed by the claim hash,
+	/// and interior nodes that of `codec::MERKLE_NODE_PREFIX` followed by their left and right
+	/// children.
+	#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
 	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
 	pub struct MerkleProof {
-		verifies: bool,
+		/// The sibling hashes, ordered from the leaf level upward
+		siblings: Vec<HashValue>,
+		/// Bit `i` is set when the path node at level `i` is a right child, i.e. its sibling
+		/// is on the left. Equivalently, the index of the leaf in the bottom level of the tree.
+		directions: u64,
 	}
 
 	impl MerkleProof {
+		/// Recompute the root from the claim's leaf and the proof's sibling path, and check that
+		/// it matches the given merkle root. Every direction bit must be used by the path.
 		pub fn check_merkle_proof(
-			_claim: HashValue,
+			claim: HashValue,
 			proof: MerkleProof,
-			_merkle_root: u64,
+			merkle_root: HashValue,
+		) -> bool {
+			// The direction bits can't describe a path longer than this.
+			if proof.siblings.len() > u64::BITS as usize || proof.leaf_index().is_none() {
+				return false
+			}
+
+			let mut node = Self::hash_leaf(&claim);
+			for (level, sibling) in proof.siblings.iter().enumerate() {
+				node = if proof.directions >> level & 1 == 0 {
+					Self::hash_pair(&node, sibling)
+				} else {
+					Self::hash_pair(sibling, &node)
+				};
+			}
+
+			node == merkle_root
+		}
+
+		/// The index of the proven leaf in the bottom level of the tree, or `None` if the direction
+		/// bits describe a longer path than the proof has siblings for.
+		pub fn leaf_index(&self) -> Option<u64> {
+			let unused = self.directions.checked_shr(self.siblings.len() as u32).unwrap_or(0);
+			(unused == 0).then_some(self.directions)
+		}
+
+		/// Hash a claim into its leaf node.
+		pub fn hash_leaf(claim: &HashValue) -> HashValue {
+			codec::merkle_leaf(claim)
+		}
+
+		/// Hash two child nodes into their parent node.
+		pub fn hash_pair(left: &HashValue, right: &HashValue) -> HashValue {
+			codec::merkle_node(left, right)
+		}
+	}
+
+	/// A proof of several leaves of the same merkle tree, which carries each interior node that
+	/// is needed to recompute the root only once.
+	///
+	/// `indices[i]` is the index in the bottom level of the tree of the i-th proven leaf, and the
+	/// tree has `2^depth` leaves. `hashes` are the sibling nodes that can't be computed from the
+	/// leaves, ordered level by level from the bottom up, and left to right within a level.
+	#[derive(Debug, Clone, Default, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct MerkleMultiproof {
+		/// The index of each proven leaf, strictly increasing
+		indices: Vec<u64>,
+		/// The sibling nodes that the leaves don't determine
+		hashes: Vec<HashValue>,
+		/// The number of levels below the root
+		depth: u32,
+	}
+
+	impl MerkleMultiproof {
+		/// Recompute the root from the claims' leaves, in the order of the proof's indices, and
+		/// check that it matches the given merkle root. Every hash of the proof must be used.
+		pub fn check_merkle_multiproof(
+			leaves: &[HashValue],
+			proof: &MerkleMultiproof,
+			merkle_root: HashValue,
 		) -> bool {
-			// This is where the actual merkle proof checking logic _would_ go
-			// if we weren't stubbing the proofs. Instead this stub is given.
-			proof.verifies
+			if leaves.is_empty() ||
+				leaves.len() != proof.indices.len() ||
+				proof.depth > u64::BITS ||
+				proof.indices.windows(2).any(|pair| pair[0] >= pair[1]) ||
+				proof
+					.indices
+					.iter()
+					.any(|index| index.checked_shr(proof.depth).unwrap_or(0) != 0)
+			{
+				return false
+			}
+
+			let mut hashes = proof.hashes.iter();
+			let mut level: Vec<(u64, HashValue)> = proof
+				.indices
+				.iter()
+				.copied()
+				.zip(leaves.iter().map(MerkleProof::hash_leaf))
+				.collect();
+			for _ in 0..proof.depth {
+				let mut parents = Vec::with_capacity(level.len());
+				let mut nodes = level.into_iter().peekable();
+				while let Some((index, node)) = nodes.next() {
+					// A left node's sibling may be the next proven node, otherwise it is in the
+					// proof.
+					let sibling = match nodes.peek() {
+						Some(&(next, sibling)) if index & 1 == 0 && next == index + 1 => {
+							nodes.next();
+							sibling
+						},
+						_ => match hashes.next() {
+							Some(sibling) => *sibling,
+							None => return false,
+						},
+					};
+					let parent = if index & 1 == 0 {
+						MerkleProof::hash_pair(&node, &sibling)
+					} else {
+						MerkleProof::hash_pair(&sibling, &node)
+					};
+					parents.push((index >> 1, parent));
+				}
+				level = parents;
+			}
+
+			hashes.next().is_none() && level == [(0, merkle_root)]
 		}
 	}
 
@@ -57,8 +262,8 @@ mod spv_bridge {
 	/// We model the source chain storage as a key value mapping, like most blockchains.
 	/// An instance of this struct would claim that a particular key holds a particular value.
 	///
-	/// For assignment purposes, the storage model doesn't matter so much because we stub the
-	/// proofs. Nonetheless, we give a somewhat realistic model.
+	/// The storage merkle tree commits to the hash of the encoded claim, through its leaf, see
+	/// `MerkleProof`.
 	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
 	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
 	pub struct StateClaim {
@@ -66,504 +271,8089 @@ mod spv_bridge {
 		value: u64,
 	}
 
-	#[ink(storage)]
-	pub struct SpvBridge {
-		/// The main source chain header database.
-		/// Maps header hashes to complete headers.
-		headers: Mapping<HashValue, Header>,
-
-		/// A representation of the canonical source chain.
-		/// Maps block heights to the canonical source block hash at that height.
-		/// Updates when a re-org happens
-		canon_chain: Mapping<u64, HashValue>,
-
-		/// The user who submitted each block hash.
-		/// Fees paid by verifiers will go to this address.
-		fee_recipient: Mapping<HashValue, AccountId>,
+	/// A value transfer on the source chain, in the transaction encoding that the bridge
+	/// understands.
+	///
+	/// The transaction's hash, which is its leaf in a block's transactions merkle tree, is the
+	/// hash of the encoded transaction.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct SourceTransaction {
+		/// The account sending the value
+		sender: HashValue,
+		/// The account receiving the value
+		recipient: HashValue,
+		/// The value transferred
+		amount: u128,
+		/// The fee paid to the source chain's block author
+		fee: u128,
+		/// The sender's transaction counter
+		nonce: u64,
+	}
 
-		/// The height of the current best known source chain
-		best_height: u64,
+	/// A Bitcoin block header, whose 80 byte serialization is its encoding.
+	///
+	/// The hashes are in the byte order they are serialized in, which is the reverse of the order
+	/// they are usually displayed in. A Bitcoin header does not commit to its height, so the
+	/// bridge takes it from the header's parent.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct BitcoinHeader {
+		/// The block version, which signals soft fork readiness
+		version: i32,
+		/// The hash of this block's parent
+		prev_block_hash: HashValue,
+		/// The merkle tree root of the transactions included in the block
+		merkle_root: HashValue,
+		/// The time at which this block was authored, in seconds since the Unix epoch
+		time: u32,
+		/// The compact encoding of the PoW target that this block's hash must be below
+		bits: u32,
+		/// The nonce that allows the block's hash to satisfy the proof of work
+		nonce: u32,
+	}
 
-		/// The difficulty threshold for the PoW
-		difficulty_threshold: HashValue,
+	/// A proof that a header's work was done by a block of a parent chain that merge mines the
+	/// source chain, in the style of Namecoin and Dogecoin.
+	///
+	/// The parent block's coinbase transaction commits to the root of a merkle tree of the
+	/// merge mined chains' block hashes, so the parent block's PoW also counts for the header.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct AuxPow {
+		/// The parent block's serialized coinbase transaction. It must contain
+		/// `codec::MERGED_MINING_MAGIC` exactly once, followed by the merged mining root, the
+		/// number of leaves in the merged mining tree as a little-endian `u32`, and a nonce.
+		coinbase_transaction: Vec<u8>,
+		/// The siblings linking the coinbase transaction to the parent block's merkle root. The
+		/// coinbase is the first transaction, so it is always the left child.
+		coinbase_branch: Vec<HashValue>,
+		/// The siblings linking the header's hash to the merged mining root
+		chain_branch: Vec<HashValue>,
+		/// The header's position in the merged mining tree. Bit `i` is set if the `i`-th node is
+		/// a right child.
+		chain_index: u32,
+		/// The parent block's header
+		parent_header: BitcoinHeader,
+	}
 
-		/// The fee the relayer must pay in order to relay a block on top
-		/// of any protocol level gas fees
-		relay_fee: Balance,
+	/// How a source chain's headers are serialized and hashed.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub enum HeaderFormat {
+		/// `Header`, hashed with the deployment's `HashAlgorithm`, see `codec::header_hash_with`
+		#[default]
+		Native,
+		/// `BitcoinHeader`, hashed with `codec::bitcoin_header_hash`
+		Bitcoin,
+	}
 
-		/// The fee the verifier must pay in order to verify that their
-		/// transaction or state claim is canonical on the source chain.
-		verify_fee: Balance,
+	/// The hash function a deployment identifies native headers and state claims by, see
+	/// `SpvBridge::new_with_hash_algorithm`.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub enum HashAlgorithm {
+		/// A single SHA-256
+		#[default]
+		Sha2x256,
+		/// SHA-256 applied twice, as Bitcoin does
+		DoubleSha256,
+		/// Keccak-256, as Ethereum does
+		Keccak256,
+		/// Blake2b-256, as Substrate does
+		Blake2x256,
 	}
 
-	/// Errors that can occur upon calling this contract.
-	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+	/// How a deployment is configured, so tooling can configure itself against it. See
+	/// `SpvBridge::bridge_config`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
 	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-	pub enum Error {
-		/// Insufficient fee provided when attempting to relay a block
-		InsufficientRelayFee,
-		/// Insufficient fee provided when attempting to verify a proof
-		InsufficientVerifyFee,
-		/// Header cannot be submitted because it was previously submitted
-		HeaderAlreadySubmitted,
-		/// Parent is not in the DB
-		UnknownParent,
-		/// Header height is invalid
-		IncorrectHeight,
-		/// PoW threshold has not been met
-		PoWThresholdNotMet,
-		/// Attempted reward payment to a relayer failed,
-		PaymentFailed,
+	pub struct BridgeConfig {
+		/// How the default chain's headers are shown to be part of it
+		pub consensus: ConsensusBackend,
+		/// How the default chain's headers are serialized and hashed
+		pub header_format: HeaderFormat,
+		/// The hash function native headers and state claims are identified by
+		pub hash_algorithm: HashAlgorithm,
+		/// When set, header targets must follow the difficulty retargeting algorithm
+		pub retarget: Option<RetargetParams>,
+		/// The easiest PoW target a header may declare
+		pub difficulty_threshold: HashValue,
+		/// The fee a relayer must pay to submit a header
+		pub relay_fee: Balance,
+		/// The fee a verifier must pay to verify a claim
+		pub verify_fee: Balance,
+		/// How far behind the tip headers may be pruned, if pruning is enabled
+		pub prune_depth: Option<u64>,
+		/// The header the default chain is anchored at
+		pub checkpoint: Header,
+		/// The `STORAGE_VERSION` of the running code
+		pub code_version: u32,
 	}
 
-	/// Type alias for the contract's `Result` type.
-	pub type Result<T> = core::result::Result<T, Error>;
-
-	/// Someone has successfully submitted a source chain header.
-	#[ink(event)]
-	pub struct HeaderSubmitted {
-		block_hash: HashValue,
-		block_height: u64,
-		#[ink(topic)]
-		submitter: AccountId,
+	/// The parameters the bridge is deployed with, see `SpvBridge::new_with_config`. Every
+	/// constructor describes its deployment with one of these.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct DeployConfig {
+		/// The header the default chain is anchored at
+		pub checkpoint: Header,
+		/// Whether the checkpoint's proof of work is checked before anchoring at it
+		pub check_checkpoint_pow: bool,
+		/// The hash function native headers and state claims are identified by
+		pub hash_algorithm: HashAlgorithm,
+		/// When set, header targets must follow the difficulty retargeting algorithm
+		pub retarget: Option<RetargetParams>,
+		/// The easiest PoW target a header may declare
+		pub difficulty_threshold: HashValue,
+		/// The fee a relayer must pay to submit a header
+		pub relay_fee: Balance,
+		/// The fee a verifier must pay to verify a claim
+		pub verify_fee: Balance,
+		/// How far behind the tip headers may be pruned, or `None` to disable pruning
+		pub prune_depth: Option<u64>,
+		/// How far the finalized height trails the tip, or `None` to only finalize the checkpoint
+		pub finality_depth: Option<u64>,
+		/// When set, verify fees are escrowed until the verified block has this many
+		/// confirmations, see `SpvBridge::settle_escrow`
+		pub payout_depth: Option<u64>,
+		/// When set, verify fees are escrowed until the verified block has this many
+		/// confirmations beyond the depth that was verified
+		pub escrow_safety_margin: Option<u64>,
+		/// The deployment salt, or `None` to derive it from the contract's account, see
+		/// `SpvBridge::claim_id`
+		pub salt: Option<HashValue>,
 	}
 
-	/// An on-chain light client (or SPV client) for a foreign source chain.
-	///
-	/// This contract, inspired by btc-relay, allows users to submit new block headers
-	/// from a foreign PoW blockchain for validation. It then allows (potentially different)
-	/// users to verify claims about what transactions and state exist on the source chain.
-	impl SpvBridge {
-		/// Initialize the on-chain light client with a "checkpoint" header.
-		///
-		/// In many cases, the source chain is older than the target chain, and may have a long
-		/// history. We allow starting from a recent point in the source chain and verifying
-		/// thereafter.
-		///
-		/// This constructor allows the contract deployer to specify the recent block from which to
-		/// start
-		#[ink(constructor)]
+	impl DeployConfig {
+		/// A native proof of work chain anchored at `checkpoint`, with every optional feature
+		/// disabled.
 		pub fn new(
-			source_genesis_header: Header,
-			difficulty: HashValue,
-			init_relay_fee: Balance,
-			init_verify_fee: Balance,
+			checkpoint: Header,
+			difficulty_threshold: HashValue,
+			relay_fee: Balance,
+			verify_fee: Balance,
 		) -> Self {
-			let caller = Self::env().caller();
-
-			let mut headers = Mapping::default();
-			let mut canon_chain = Mapping::default();
-			let mut fee_recipient = Mapping::default();
-
-			let difficulty_threshold = difficulty;
-			let relay_fee = init_relay_fee;
-			let verify_fee = init_verify_fee;
-
-			// Calculate header hash and put header in storage
-			let h = Self::hash_header(source_genesis_header);
-			headers.insert(h, &source_genesis_header);
-
-			// Update other storages
-			let best_height = source_genesis_header.height;
-			canon_chain.insert(best_height, &h);
-
-			// Record the deployer as the fee recipient for the checkpoint block
-			fee_recipient.insert(h, &caller);
-
 			Self {
-				headers,
-				canon_chain,
-				fee_recipient,
-				best_height,
+				checkpoint,
+				check_checkpoint_pow: false,
+				hash_algorithm: HashAlgorithm::default(),
+				retarget: None,
 				difficulty_threshold,
 				relay_fee,
 				verify_fee,
+				prune_depth: None,
+				finality_depth: None,
+				payout_depth: None,
+				escrow_safety_margin: None,
+				salt: None,
 			}
 		}
 
-		/// Submit a new source chain block header to the bridge for verification.
-		/// In order for the new header to be valid, these conditions must be met:
-		/// 0. The relayer must pay the relay fee (which will be locked forever).
-		/// 1. The header must not already be in the db
-		/// 2. The header's parent must already be in the db
-		/// 3. The header's height must be one more than it's parent
-		/// 4. The header's hash must satisfy the PoW threshold
-		///
-		/// Once the block is validated you must determine whether this causes
-		/// a re-org or not, and update storage accordingly.
-		///
-		/// The relay fee does not go to anyone. It is locked up forever; effectively burnt.
-		#[ink(message, payable)]
-		pub fn submit_new_header(&mut self, header: Header) -> Result<()> {
-			todo!()
+		/// A native proof of work chain with an easy difficulty and small fees, anchored at a
+		/// checkpoint of height 100, for tests to override field by field.
+		#[cfg(feature = "std")]
+		pub fn default_for_tests() -> Self {
+			let mut difficulty_threshold = [0; 32];
+			difficulty_threshold[0] = 63;
+			// The checkpoint's PoW is not checked. The non-zero nonce keeps it from being the
+			// default header. Its only transaction is `[0; 32]`.
+			let checkpoint = Header {
+				height: 100,
+				transactions_root: codec::merkle_leaf(&[0; 32]),
+				target: difficulty_threshold,
+				pow_nonce: 1,
+				..Header::default()
+			};
+			Self::new(checkpoint, difficulty_threshold, 1_000, 100)
 		}
+	}
 
-		/// Verify that some transaction has occurred on the source chain.
-		///
-		/// In order for a verification to be successful (to return true), these conditions must be
-		/// met:
-		/// 0. The verifier must pay the verification fee (which will go to the relayer).
-		/// 1. The block is in the db
-		/// 2. The block is in the best chain
-		/// 3. The block's height in the best chain is at least `min_depth` before the tip of the
-		///    chain. A min_depth of 0 just means that the header is canon at all. A min_depth of 1
-		///    means there is at least one block confirmation afterward.
-		/// 4. The merkle proof must be valid
-		#[ink(message, payable)]
-		pub fn verify_transaction(
-			&mut self,
-			tx_hash: HashValue,
-			header_hash: HashValue,
-			min_depth: u64,
-			p: MerkleProof,
-		) -> Result<bool> {
-			todo!()
-		}
+	/// The parameters a source chain is registered with, see `SpvBridge::register_chain`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct ChainParams {
+		/// The easiest PoW target a header may declare
+		pub difficulty_threshold: HashValue,
+		/// The fee a relayer must pay to submit a header
+		pub relay_fee: Balance,
+		/// The fee a verifier must pay to verify a claim
+		pub verify_fee: Balance,
+		/// When set, header targets must follow the difficulty retargeting algorithm
+		pub retarget: Option<RetargetParams>,
+		/// How far behind the tip headers may be pruned, or `None` to disable pruning
+		pub prune_depth: Option<u64>,
+		/// How far the finalized height trails the tip, or `None` to only finalize the checkpoint
+		pub finality_depth: Option<u64>,
+	}
 
-		/// Verify that some state exists on the source chain.
-		///
-		/// The checks performed are the same as when verifying a transaction.
-		/// However, in this chase, you pass the hash of the state claim
-		#[ink(message, payable)]
-		pub fn verify_state(
-			&mut self,
-			claim: StateClaim,
-			block_hash: HashValue,
-			min_depth: u64,
-			p: MerkleProof,
-		) -> Result<bool> {
-			let mut claim_hash = <Sha2x256 as HashOutput>::Type::default();
-			ink::env::hash_encoded::<Sha2x256, _>(&claim, &mut claim_hash);
+	/// The state of a source chain bridged by this contract, apart from its headers.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct SourceChain {
+		/// The height of the current best known chain
+		pub best_height: u64,
 
-			todo!()
-		}
+		/// The difficulty threshold for the PoW.
+		/// This is the easiest target that a header may declare.
+		pub difficulty_threshold: HashValue,
 
-		/// Helper function to hash a block header.
-		/// It would be pretty reasonable to just put this inline.
-		/// But we provide it to help avoid bit-level errors from hashing differently.
-		pub fn hash_header(header: Header) -> HashValue {
-			let mut hash_value = <Sha2x256 as HashOutput>::Type::default();
-			ink::env::hash_encoded::<Sha2x256, _>(&header, &mut hash_value);
-			hash_value
-		}
+		/// When set, header targets must follow the difficulty retargeting algorithm.
+		/// Otherwise any target no easier than the difficulty threshold is accepted.
+		pub retarget: Option<RetargetParams>,
 
-		/// A helper function to detect whether a header exists in the storage
-		pub fn header_is_known(&self, header_hash: HashValue) -> bool {
-			todo!()
-		}
+		/// The fee the relayer must pay in order to relay a block on top
+		/// of any protocol level gas fees
+		pub relay_fee: Balance,
 
-		/// A helper function to determine whether a header is in the canon chain
-		pub fn header_is_canon(&self, header_hash: HashValue) -> bool {
-			todo!()
-		}
+		/// The fee the verifier must pay in order to verify that their
+		/// transaction or state claim is canonical on the source chain.
+		pub verify_fee: Balance,
 
-		/// This function is not graded. It is just for collecting feedback.
-		/// On a scale from 0 - 100, with zero being extremely easy and 100 being extremely hard,
-		/// how hard did you find the exercises in this section?
-		pub fn how_hard_was_this_section() -> u8 {
-			todo!()
+		/// Exponential moving average of the interval between consecutive blocks, in
+		/// milliseconds. Observed from the timestamps of headers that become the new tip.
+		/// `None` until the first such header has been accepted.
+		pub block_time_ema: Option<u64>,
+
+		/// The target chain timestamp at which the canon tip last changed.
+		pub last_tip_update: Timestamp,
+
+		/// The target chain block in which a header of the chain was last accepted, or the chain
+		/// was registered or reset to a new checkpoint.
+		pub last_header_block: BlockNumber,
+
+		/// Headers below this height have been pruned. Their canon chain entries are kept.
+		pub pruned_height: u64,
+
+		/// The height of the highest finalized canon block. The canon chain can never be
+		/// reorganized at or below this height. Initially the checkpoint height.
+		pub finalized_height: u64,
+
+		/// When set, the finalized height trails the tip by this many blocks.
+		/// Otherwise only the checkpoint is finalized.
+		pub finality_depth: Option<u64>,
+
+		/// When set, headers more than this many blocks behind the tip may be pruned.
+		pub prune_depth: Option<u64>,
+
+		/// The height of the checkpoint header the chain was registered with.
+		pub checkpoint_height: u64,
+
+		/// The number of headers currently stored, canon or not.
+		pub header_count: u64,
+
+		/// The number of stored fork headers at or below the finalized height.
+		pub orphan_count: u64,
+
+		/// How the chain's headers are serialized and hashed.
+		pub header_format: HeaderFormat,
+
+		/// Whether headers may prove their work with an `AuxPow` instead of meeting their target
+		/// themselves.
+		pub merged_mining: bool,
+
+		/// How the chain's headers are shown to be part of it.
+		pub consensus: ConsensusBackend,
+	}
+
+	impl SourceChain {
+		/// Fold a newly observed block interval into the block time moving average.
+		fn observe_block_interval(&mut self, interval: u64) {
+			self.block_time_ema = Some(match self.block_time_ema {
+				Some(ema) => ema - ema / BLOCK_TIME_EMA_WEIGHT + interval / BLOCK_TIME_EMA_WEIGHT,
+				None => interval,
+			});
 		}
 
-		/// This function is not graded. It is just for collecting feedback.
-		/// About how much time (in minutes) did you spend on the exercises in this section?
-		pub fn how_many_minutes_did_you_spend_on_this_section() -> u32 {
-			todo!()
+		/// Whether no new tip has been accepted for `STALENESS_BLOCK_TIMES` estimated block
+		/// times, as of the given target chain timestamp.
+		fn is_stale(&self, now: Timestamp) -> bool {
+			self.block_time_ema.is_some_and(|block_time| {
+				now.saturating_sub(self.last_tip_update) >
+					block_time.saturating_mul(STALENESS_BLOCK_TIMES)
+			})
 		}
 	}
 
-	#[cfg(test)]
-	mod tests {
-		// The threshold is set so that we have roughly 1 in 4 chance of finding a valid block.
-		const THRESHOLD: [u8; 32] = [
-			63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-			0, 0, 0,
-		];
-		const RELAY_FEE: u128 = 1_000;
-		const VERIFY_FEE: u128 = 100;
+	#[ink(storage)]
+	pub struct SpvBridge {
+		/// The account allowed to change the bridge's parameters.
+		/// Initially the deployer.
+		owner: AccountId,
 
-		use super::*;
+		/// The account the owner has offered ownership to, until it accepts.
+		pending_owner: Option<AccountId>,
 
-		fn default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
-			ink::env::test::default_accounts::<Environment>()
-		}
+		/// The roles granted to each account.
+		roles: Mapping<(Role, AccountId), ()>,
 
-		fn set_next_caller(caller: AccountId) {
-			ink::env::test::set_caller::<Environment>(caller);
-		}
+		/// The number of accounts holding the admin role, which is never allowed to drop to zero.
+		admin_count: u32,
 
-		fn make_child(parent: Header) -> Header {
-			make_child_with_transactions_root(parent, 0)
-		}
+		/// The accounts that approve critical actions, see `set_signers`.
+		signers: Vec<AccountId>,
 
-		fn make_child_with_transactions_root(parent: Header, tx_root: u64) -> Header {
-			let mut child = Header {
-				height: parent.height + 1,
-				parent: SpvBridge::hash_header(parent),
-				storage_root: 0,
-				transactions_root: tx_root,
-				pow_nonce: 1,
-			};
+		/// How many signers must approve a critical action, or zero if critical actions don't
+		/// need approval.
+		signer_threshold: u32,
 
-			while SpvBridge::hash_header(child) >= THRESHOLD {
-				child.pow_nonce = child.pow_nonce + 1;
-			}
+		/// Bumped whenever the signer set changes, so approvals given under an older set lapse.
+		signer_epoch: u32,
 
-			child
-		}
+		/// The critical actions waiting for approval, by id.
+		proposals: Mapping<u32, Proposal>,
 
-		pub fn deploy_bridge(deployer: AccountId) -> (SpvBridge, Header) {
-			set_next_caller(deployer);
+		/// The id of the next proposal.
+		next_proposal_id: u32,
 
-			let source_genesis_header = Header {
-				height: 100,
-				parent: [0; 32],
-				storage_root: 0,
-				transactions_root: 0,
-				// The initial block is not checked; not even its pow seal;
-				// We put a non-zero nonce here to make sure this block
-				// isn't the default block.
-				pow_nonce: 1,
-			};
-			let spv_bridge =
-				SpvBridge::new(source_genesis_header, THRESHOLD, RELAY_FEE, VERIFY_FEE);
+		/// Whether the owner has paused the bridge. While paused, headers cannot be submitted and
+		/// claims cannot be verified.
+		paused: bool,
 
-			(spv_bridge, source_genesis_header)
-		}
+		/// The version of the storage layout, see `STORAGE_VERSION`.
+		/// Lags behind `STORAGE_VERSION` after an upgrade, until `migrate_storage` has been
+		/// called.
+		storage_version: u32,
 
-		#[ink::test]
-		fn test_constructor_works() {
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
+		/// The source chains bridged by this contract. The constructor registers the default
+		/// chain, and the owner may register more.
+		chains: Mapping<ChainId, SourceChain>,
 
-			let (spv_bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			assert_eq!(spv_bridge.fee_recipient.get(genesis_hash), Some(default_accounts.alice));
-		}
+		/// The main source chain header database.
+		/// Maps each chain's header hashes to complete headers.
+		headers: Mapping<(ChainId, HashValue), Header>,
 
-		#[ink::test]
-		fn test_submit_extend_longest_chain() {
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
+		/// A representation of each canonical source chain.
+		/// Maps block heights to the canonical source block hash at that height.
+		/// Updates when a re-org happens
+		canon_chain: Mapping<ChainHeight, HashValue>,
 
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let child_header = make_child(genesis_header);
-			let child_hash = SpvBridge::hash_header(child_header);
+		/// The user who submitted each block hash.
+		/// Fees paid by verifiers are credited to this address's pending rewards.
+		fee_recipient: Mapping<(ChainId, HashValue), AccountId>,
 
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(child_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
+		/// The cumulative proof of work of each header and all of its ancestors back to the
+		/// checkpoint. The canon chain is the one whose tip has the most work.
+		chain_work: Mapping<(ChainId, HashValue), U256>,
 
-			// Validate Storage
-			assert_eq!(bridge.canon_chain.get(100), Some(genesis_hash));
-			assert_eq!(bridge.canon_chain.get(101), Some(child_hash));
+		/// Signatures relayers have chosen to attach to the headers they submitted.
+		header_provenance: Mapping<(ChainId, HashValue), HeaderProvenance>,
 
-			assert_eq!(bridge.fee_recipient.get(genesis_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(child_hash), Some(default_accounts.alice));
-		}
+		/// The hash of the parent chain block whose PoW each merge mined header relies on.
+		aux_pow_parents: Mapping<(ChainId, HashValue), HashValue>,
 
-		#[ink::test]
-		fn test_submit_side_chain() {
-			// We start by creating a linear source chain that looks like this
-			// G---A---B
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
-
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
-			let a_hash = SpvBridge::hash_header(a_header);
-			let b_header = make_child(a_header);
-			let b_hash = SpvBridge::hash_header(b_header);
-
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			// Now we create a fork in the source chain.
-			// The fork is not long enough to cause a re-org.
-			// We should be able to submit the header successfully, but it should not cause a re-org
-			// G---A---B
-			//  \
-			//   --C
-
-			let c_header = make_child_with_transactions_root(genesis_header, 1);
-			let c_hash = SpvBridge::hash_header(c_header);
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			// Validate Storage
-			assert_eq!(bridge.canon_chain.get(100), Some(genesis_hash));
-			assert_eq!(bridge.canon_chain.get(101), Some(a_hash));
-			assert_eq!(bridge.canon_chain.get(102), Some(b_hash));
-
-			assert_eq!(bridge.fee_recipient.get(genesis_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(a_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(b_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(c_hash), Some(default_accounts.alice));
-		}
-
-		#[ink::test]
-		fn test_submit_reorg_chain() {
-			// We start by creating a linear source chain that looks like this
-			// G---A
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
-
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
-			let a_hash = SpvBridge::hash_header(a_header);
-
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			// Now we create a fork in the source chain.
-			// The fork is long enough to cause a re-org.
-			// G---A
-			//  \
-			//   --C---D
-
-			let c_header = make_child_with_transactions_root(genesis_header, 1);
-			let c_hash = SpvBridge::hash_header(c_header);
-			let d_header = make_child_with_transactions_root(c_header, 1);
-			let d_hash = SpvBridge::hash_header(d_header);
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			// Validate Storage
-			assert_eq!(bridge.canon_chain.get(100), Some(genesis_hash));
-			assert_eq!(bridge.canon_chain.get(101), Some(c_hash));
-			assert_eq!(bridge.canon_chain.get(102), Some(d_hash));
-
-			assert_eq!(bridge.fee_recipient.get(genesis_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(a_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(c_hash), Some(default_accounts.alice));
-			assert_eq!(bridge.fee_recipient.get(d_hash), Some(default_accounts.alice));
-		}
-
-		#[ink::test]
-		fn test_tx_verification_success() {
-			// We start by creating a linear source chain that looks like this
-			// G---A
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
-
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
-
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			debug_assert_eq!(
-				ink::env::pay_with_call!(
-					bridge.verify_transaction(
-						[0u8; 32],
-						genesis_hash,
-						0,
-						MerkleProof { verifies: true }
-					),
-					VERIFY_FEE
-				),
-				Ok(true)
-			);
-		}
+		/// The light client state of each source chain that uses `ConsensusBackend::Grandpa`.
+		grandpa_states: Mapping<ChainId, GrandpaState>,
 
-		#[ink::test]
-		fn test_tx_verification_failure() {
-			// We start by creating a linear source chain that looks like this
-			// G---A
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
-
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
-
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
-
-			assert_eq!(
-				ink::env::pay_with_call!(
-					bridge.verify_transaction(
-						[0u8; 32],
-						genesis_hash,
-						0,
-						MerkleProof { verifies: false }
-					),
-					VERIFY_FEE
-				),
-				Ok(false)
-			);
-		}
+		/// Skip list links of each header accepted by proof of work: entry `i` is the header's
+		/// ancestor `2^i` blocks below it, for as far as its ancestors reach back. Checkpoints
+		/// have no links.
+		ancestor_links: Mapping<ChainBlock, Vec<HashValue>>,
 
-		//TODO There are many more ways that a transaction or state verification can fail,
-		// that we have not yet tested for.
-		// You would be wise to add some tests of your own to ensure your code is working as
-		// expected.
+		/// The stored headers of each source chain that have no stored children: the canon tip and
+		/// the tip of every fork the bridge knows about.
+		fork_tips: Mapping<ChainId, Vec<HashValue>>,
 
-		#[ink::test]
-		fn test_state_verification_success() {
-			// We start by creating a linear source chain that looks like this
-			// G---A
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
+		/// The hashes of the stored children of each header, in the order they were accepted.
+		children: Mapping<ChainBlock, Vec<HashValue>>,
 
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
+		/// The extension bytes of headers submitted with any, which their hashes commit to.
+		header_extensions: Mapping<ChainBlock, Vec<u8>>,
 
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
+		/// The light client state of each source chain that uses `ConsensusBackend::Beefy`.
+		beefy_states: Mapping<ChainId, BeefyState>,
 
-			let claim = StateClaim { key: 0, value: 0 };
+		/// The light client state of each source chain that uses `ConsensusBackend::Tendermint`.
+		tendermint_states: Mapping<ChainId, TendermintState>,
 
-			assert_eq!(
-				ink::env::pay_with_call!(
-					bridge.verify_state(claim, genesis_hash, 0, MerkleProof { verifies: true }),
-					VERIFY_FEE
-				),
-				Ok(true)
-			);
-		}
+		/// The light client state of each source chain that uses
+		/// `ConsensusBackend::SyncCommittee`.
+		sync_committee_states: Mapping<ChainId, SyncCommitteeState>,
 
-		#[ink::test]
-		fn test_state_verification_failure() {
-			// We start by creating a linear source chain that looks like this
-			// G---A
-			let default_accounts = default_accounts();
-			set_next_caller(default_accounts.alice);
+		/// The public keys of sync committees, by committee root and chunk, see
+		/// `submit_sync_committee_keys`.
+		sync_committee_keys: Mapping<CommitteeChunk, Vec<[u8; 48]>>,
 
-			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
-			let genesis_hash = SpvBridge::hash_header(genesis_header);
-			let a_header = make_child(genesis_header);
+		/// The hashes of all stored headers at each height, canon or not.
+		/// Lets `prune` find the headers to remove.
+		headers_at_height: Mapping<ChainHeight, Vec<HashValue>>,
 
-			let relay_response =
-				ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
-			assert_eq!(relay_response, Ok(()));
+		/// The maximum number of siblings a merkle proof may have.
+		max_proof_length: u32,
 
-			let claim = StateClaim { key: 0, value: 0 };
+		/// The maximum number of headers that may be submitted in one batch.
+		max_batch_size: u32,
 
-			assert_eq!(
-				ink::env::pay_with_call!(
-					bridge.verify_state(claim, genesis_hash, 0, MerkleProof { verifies: false }),
-					VERIFY_FEE
-				),
-				Ok(false)
+		/// Accounts that have registered to be named as topics on bridge health alerts.
+		/// Bounded by `MAX_ALERT_SUBSCRIBERS`.
+		alert_subscribers: Vec<AccountId>,
+
+		/// The deposit each alert subscriber has locked, refunded when they unsubscribe.
+		alert_deposits: Mapping<AccountId, Balance>,
+
+		/// When set, verify fees are held in escrow until the verified block has
+		/// `min_depth + escrow_safety_margin` confirmations, rather than paid out immediately.
+		escrow_safety_margin: Option<u64>,
+
+		/// When set, verify fees are only paid to a block's relayer once the block is canon with
+		/// at least this many confirmations. Until then the fee is held in escrow.
+		payout_depth: Option<u64>,
+
+		/// Receipts for recently accepted submissions, keyed by relayer and header hash.
+		submission_receipts: Mapping<(AccountId, HashValue), SubmissionReceipt>,
+
+		/// The id that will be given to the next accepted submission.
+		next_submission_id: SubmissionId,
+
+		/// The number of submission receipts that have not been removed.
+		receipt_count: u64,
+
+		/// Verify fees currently held in escrow, keyed by escrow id.
+		fee_escrows: Mapping<u64, FeeEscrow>,
+
+		/// The id that will be given to the next fee escrow.
+		next_escrow_id: u64,
+
+		/// Mixed into the identifiers this deployment issues for verified claims, so they can't be
+		/// confused with those of another deployment. See `claim_id`.
+		deployment_salt: HashValue,
+
+		/// Verify fees that relayers have earned but not yet withdrawn.
+		pending_rewards: Mapping<AccountId, Balance>,
+
+		/// How many verifications against each header are paid for from the subsidy pool when
+		/// the verifier does not pay the verify fee.
+		free_verifications: u32,
+
+		/// The number of subsidized verifications made against each header.
+		free_verifications_used: Mapping<(ChainId, HashValue), u32>,
+
+		/// Funds set aside to pay the verify fee of subsidized verifications.
+		subsidy_pool: Balance,
+
+		/// The greatest depth at which a claim against each canon block has been successfully
+		/// verified. Later verifications against the block at no greater depth skip the canon and
+		/// depth checks. Cleared when the block is retracted from the canon chain.
+		verified_depths: Mapping<(ChainId, HashValue), u64>,
+
+		/// The source chain best height when each transaction was first successfully verified
+		/// against each block, keyed by chain, transaction hash and block hash.
+		verified_transactions: Mapping<ChainClaim, u64>,
+
+		/// The last `MAX_ATTESTATIONS_PER_CLAIM` successful verifications of each state claim
+		/// hash, oldest first.
+		attestations: Mapping<HashValue, Vec<Attestation>>,
+
+		/// When set, the default chain's best height is sampled at most once per this many
+		/// target chain blocks.
+		sample_interval: Option<BlockNumber>,
+
+		/// A ring buffer of the last `MAX_HEIGHT_SAMPLES` best height samples. The n-th sample
+		/// ever taken is stored at index `n % MAX_HEIGHT_SAMPLES`.
+		height_samples: Mapping<u32, HeightSample>,
+
+		/// The number of best height samples ever taken.
+		sample_count: u32,
+
+		/// When set, default chain headers may be submitted optimistically, and are only
+		/// accepted once they have gone unchallenged for this many target chain blocks.
+		challenge_period: Option<BlockNumber>,
+
+		/// When set, verifications against a source chain are refused once none of its headers
+		/// has been accepted for this many target chain blocks.
+		staleness_window: Option<BlockNumber>,
+
+		/// The bond a relayer must lock with each optimistically submitted header.
+		header_bond: Balance,
+
+		/// Optimistically submitted default chain headers that have not yet been accepted or
+		/// rejected, keyed by hash.
+		pending_headers: Mapping<HashValue, PendingHeader>,
+
+		/// When set, relayers must have bonded at least this much stake to submit headers.
+		min_relayer_stake: Option<Balance>,
+
+		/// The depth every verification requires at least, whatever `min_depth` the verifier
+		/// asks for.
+		min_confirmations: u64,
+
+		/// What each relayer has contributed, see `relayer_stats`.
+		relayer_stats: Mapping<AccountId, RelayerStats>,
+
+		/// The first `MAX_RANKED_RELAYERS` relayers to have had a header accepted, in that order.
+		ranked_relayers: Vec<AccountId>,
+
+		/// The stake each relayer has bonded or is unbonding.
+		relayer_stakes: Mapping<AccountId, RelayerStake>,
+
+		/// Whether only relayers on the allowlist may submit headers.
+		allowlist_enabled: bool,
+
+		/// The relayers the owner has allowed to submit headers while the allowlist is enabled.
+		/// Kept while it is disabled.
+		relayer_allowlist: Mapping<AccountId, ()>,
+
+		/// When set, limits how many headers are accepted per target chain block.
+		rate_limit: Option<RateLimit>,
+
+		/// The last target chain block in which each relayer had a header accepted, and how many
+		/// they had accepted in it.
+		relayer_submissions: Mapping<AccountId, (BlockNumber, u32)>,
+
+		/// The last target chain block in which any header was accepted, and how many were
+		/// accepted in it.
+		block_submissions: (BlockNumber, u32),
+
+		/// The checkpoint reset waiting out its timelock, if any.
+		scheduled_checkpoint: Option<ScheduledCheckpoint>,
+
+		/// For how many target chain blocks parameter changes are queued before they can be
+		/// enacted. Changes apply immediately while this is zero.
+		parameter_timelock: BlockNumber,
+
+		/// The parameter changes waiting out their timelock, oldest first.
+		pending_changes: Vec<PendingChange>,
+
+		/// The id of the next parameter change to be queued.
+		next_change_id: u32,
+
+		/// When set, a fork that would retract more canon blocks than this halts the bridge
+		/// instead of becoming canon.
+		max_reorg_depth: Option<u64>,
+
+		/// The fork that halted the bridge, until the owner resolves it.
+		halt: Option<Halt>,
+
+		/// When set, the default chain's relay fee follows the rate of header submissions.
+		fee_controller: Option<FeeController>,
+
+		/// The controlled relay fee as of the last accepted default chain header, before decay.
+		surge_fee: Balance,
+
+		/// The target chain timestamp at which the last default chain header was accepted.
+		last_header_arrival: Timestamp,
+
+		/// Relay fees and slashed stake collected by the bridge, which only the owner can spend.
+		treasury: Balance,
+
+		/// How verify fees are shared between relayers, the treasury and burning.
+		fee_split: FeeSplit,
+
+		/// The total of verify fees burned, see `FeeSplit`.
+		burned_fees: Balance,
+
+		/// The prepaid verification credits of each account, see `deposit_credits`.
+		credits: Mapping<AccountId, Balance>,
+
+		/// The sponsor paying each sponsored user's verify fees, see `sponsor`.
+		sponsorships: Mapping<AccountId, Sponsorship>,
+
+		/// How many fee exempt verifications each exempted account may make per
+		/// `FEE_EXEMPTION_PERIOD`, see `set_fee_exemption`.
+		fee_exemptions: Mapping<AccountId, u32>,
+
+		/// The last period in which each exempted account made fee exempt verifications, and how
+		/// many it made in it.
+		fee_exemption_usage: Mapping<AccountId, (BlockNumber, u32)>,
+
+		/// The contracts notified of canon tip changes, see `subscribe_headers`.
+		header_subscribers: Vec<HeaderSubscriber>,
+
+		/// When set, verified transactions are forwarded to this parachain over XCM.
+		xcm_destination: Option<XcmDestination>,
+
+		/// The contracts waiting for an admin to approve their header subscription.
+		pending_header_subscribers: Mapping<AccountId, HeaderSubscriber>,
+
+		/// The headers that the owner has blacklisted, and their chains, see
+		/// `blacklist_chain_header`.
+		blacklisted_headers: Vec<(ChainId, HashValue)>,
+
+		/// When set, proven deposits to the lock address mint the wrapped token.
+		wrapped_asset: Option<WrappedAsset>,
+
+		/// The source transactions whose deposits have been minted, so none is minted twice.
+		minted_deposits: Mapping<HashValue, ()>,
+
+		/// The nonce given to the next release request, see `burn_for_release`.
+		release_nonce: u64,
+
+		/// How native headers and state claims are hashed. Fixed at deployment.
+		hash_algorithm: HashAlgorithm,
+
+		/// The header the default chain is anchored at, kept even once it has been pruned.
+		checkpoint_header: Header,
+
+		/// Default chain headers submitted before their parent, oldest first.
+		orphan_headers: Vec<OrphanHeader>,
+
+		/// The number of verifications that failed for each reason, per failure epoch.
+		failure_counts: Mapping<(u32, VerificationFailure), u64>,
+
+		/// The failure epoch whose counts are currently being collected. Starting a new epoch
+		/// resets all counts to zero.
+		failure_epoch: u32,
+
+		/// Adjustments to the target chain's clock and caller, for deterministic testing.
+		/// Only the test helper messages modify these, so they have no effect in release builds.
+		test_overrides: TestOverrides,
+	}
+
+	/// Adjustments to the environment that the contract observes, see `SpvBridge::fast_forward`
+	/// and `SpvBridge::impersonate`.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct TestOverrides {
+		/// Added to the target chain's block timestamp
+		clock_offset: Timestamp,
+		/// Added to the target chain's block number
+		block_offset: BlockNumber,
+		/// Used in place of the real caller, if set
+		caller: Option<AccountId>,
+	}
+
+	/// Everything needed to assemble a verification call against a particular block, read
+	/// atomically so that the parts cannot disagree because of a reorg in between reads.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct VerificationContext {
+		/// The canonical block hash at the block's height, which differs from the block's own
+		/// hash if the block is not canon
+		pub canonical_hash: Option<HashValue>,
+		/// The height of the block
+		pub height: u64,
+		/// The number of confirmations the block has, if it is canon
+		pub current_depth: Option<u64>,
+		/// The fee that must be transferred with the verification call
+		pub required_fee: Balance,
+		/// Whether the bridge has stopped receiving new headers, see `is_stale`
+		pub is_stale: bool,
+	}
+
+	/// Approximate counts of the entries the bridge keeps in storage, see
+	/// `SpvBridge::storage_report`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct StorageReport {
+		/// Stored headers that are in the canon chain
+		pub canon_headers: u64,
+		/// Stored headers that are not in the canon chain
+		pub fork_headers: u64,
+		/// Canon chain entries, which are kept even when their headers are pruned
+		pub canon_entries: u64,
+		/// Submission receipts that have not been removed, whether or not they have expired
+		pub receipts: u64,
+		/// Fork headers at or below the finalized height, which can never become canon
+		pub orphans: u64,
+	}
+
+	/// The optional subsystems a deployment has enabled, see `SpvBridge::capabilities`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct Capabilities {
+		/// How the default chain's headers are serialized and hashed
+		pub header_format: HeaderFormat,
+		/// Whether header targets must follow difficulty retargeting
+		pub retargeting: bool,
+		/// Whether headers may prove their work by merged mining
+		pub merged_mining: bool,
+		/// Whether the finalized height trails the tip, rather than staying at the checkpoint
+		pub rolling_finality: bool,
+		/// Whether old headers may be pruned
+		pub pruning: bool,
+		/// Whether verify fees may be held in escrow until the verified block is buried
+		pub fee_escrow: bool,
+		/// Whether some verifications are paid for from the subsidy pool
+		pub subsidized_verifications: bool,
+		/// Whether samples of the best height are recorded
+		pub height_sampling: bool,
+		/// Whether headers may be submitted optimistically, subject to a challenge period
+		pub optimistic_submission: bool,
+		/// Whether relayers must bond a stake to submit headers
+		pub relayer_staking: bool,
+		/// Whether only relayers on the allowlist may submit headers
+		pub relayer_allowlist: bool,
+		/// Whether the number of headers accepted per target chain block is limited
+		pub rate_limiting: bool,
+		/// Whether verifications are refused once relaying has stopped for too long
+		pub staleness_freeze: bool,
+		/// Whether a reorg deeper than the maximum reorg depth halts the bridge
+		pub reorg_circuit_breaker: bool,
+		/// Whether the relay fee rises and decays with the rate of header submissions
+		pub dynamic_relay_fee: bool,
+		/// Whether verified transactions are forwarded to a parachain over XCM
+		pub xcm_forwarding: bool,
+		/// Whether proven deposits on the default chain mint a wrapped asset
+		pub wrapped_asset: bool,
+		/// How native headers and state claims are hashed
+		pub hash_algorithm: HashAlgorithm,
+		/// Whether the contract was built to offload heavy cryptography to the runtime's crypto
+		/// chain extension
+		pub crypto_extension: bool,
+		/// Whether the contract was built with the messages that manipulate its clock and
+		/// caller. This must never be the case in production.
+		pub test_helpers: bool,
+	}
+
+	/// The default chain's best height as of a target chain block, see
+	/// `SpvBridge::height_samples`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct HeightSample {
+		/// The target chain block number at which the sample was taken
+		pub block_number: BlockNumber,
+		/// The best height of the default source chain at that block
+		pub best_height: u64,
+	}
+
+	/// The declared value fields of a source transaction whose inclusion has been verified, see
+	/// `SpvBridge::verify_transaction_fee`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct TransactionFeeReceipt {
+		/// The hash of the verified transaction
+		pub tx_hash: HashValue,
+		/// The block the transaction was verified against
+		pub block_hash: HashValue,
+		/// This deployment's identifier for the transaction in the block
+		pub claim_id: HashValue,
+		/// The value the transaction transferred
+		pub amount: u128,
+		/// The fee the transaction paid on the source chain
+		pub fee: u128,
+	}
+
+	/// Evidence that a relayer's header submission was accepted.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct SubmissionReceipt {
+		/// The id given to the submission
+		id: SubmissionId,
+		/// The target chain block in which the submission was accepted
+		block_number: BlockNumber,
+	}
+
+	/// A record of a state claim that was successfully verified, see `SpvBridge::attestations`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct Attestation {
+		/// The source chain the claim holds on
+		pub chain_id: ChainId,
+		/// The hash of the claim, as in its `StateVerified` event
+		pub claim_hash: HashValue,
+		/// The block the claim was verified against
+		pub block_hash: HashValue,
+		/// The number of blocks built on the block when the claim was verified
+		pub depth: u64,
+		/// The account that verified the claim
+		pub verifier: AccountId,
+		/// The target chain time of the verification
+		pub timestamp: Timestamp,
+	}
+
+	/// The reason a claim verification reported a `false` outcome.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum VerificationFailure {
+		/// The block is not in the db
+		UnknownBlock,
+		/// The block is not in the canon chain
+		NotCanon,
+		/// The block has `have` confirmations, fewer than the `need` that were requested or that
+		/// `min_confirmations` requires
+		InsufficientDepth { have: u64, need: u64 },
+		/// The merkle proof does not match the block's root
+		InvalidProof,
+	}
+
+	impl VerificationFailure {
+		/// The reason for the failure without its details, as failures are counted by.
+		fn reason(self) -> Self {
+			match self {
+				Self::InsufficientDepth { .. } => Self::InsufficientDepth { have: 0, need: 0 },
+				failure => failure,
+			}
+		}
+	}
+
+	/// Parameters of Bitcoin-style difficulty retargeting.
+	///
+	/// The target may only change at heights that are a multiple of `interval`. There it is
+	/// scaled by the time taken by the previous epoch relative to `target_timespan`, and otherwise
+	/// it must equal the parent's target.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct RetargetParams {
+		/// The number of blocks in each difficulty epoch
+		interval: u64,
+		/// The expected duration of an epoch, in milliseconds
+		target_timespan: u64,
+	}
+
+	/// Evidence that a relayer asserted a header, see `SpvBridge::submit_signed_header`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct HeaderProvenance {
+		/// The account that submitted the header
+		pub relayer: AccountId,
+		/// The compressed ECDSA public key of the relayer's account
+		pub signer: [u8; 33],
+		/// The relayer's recoverable ECDSA signature over the header hash
+		pub signature: [u8; 65],
+	}
+
+	// `StorageLayout` is not implemented for arrays longer than 32, so it can't be derived.
+	#[cfg(feature = "std")]
+	impl ink::storage::traits::StorageLayout for HeaderProvenance {
+		fn layout(key: &ink::primitives::Key) -> ink::metadata::layout::Layout {
+			use ink::metadata::layout::{Layout, LayoutKey, LeafLayout};
+			Layout::Leaf(LeafLayout::from_key::<Self>(LayoutKey::from(key)))
+		}
+	}
+
+	/// How headers of a source chain are shown to be part of it.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub enum ConsensusBackend {
+		/// Headers are relayed one by one and the chain with the most work is canon
+		#[default]
+		ProofOfWork,
+		/// Headers are accepted with a GRANDPA justification by the chain's authorities, and
+		/// are final once justified. See `SpvBridge::register_grandpa_chain`.
+		Grandpa,
+		/// Headers are proven against an MMR root signed by the chain's BEEFY authorities, and
+		/// are final once proven. See `SpvBridge::register_beefy_chain`.
+		Beefy,
+		/// Headers are accepted with a commit signed by the chain's Tendermint validators, and
+		/// are final once committed. See `SpvBridge::register_tendermint_chain`.
+		Tendermint,
+		/// Headers are signed by the chain's Ethereum sync committee, and are final once signed.
+		/// See `SpvBridge::register_sync_committee_chain`.
+		SyncCommittee,
+	}
+
+	impl SubstrateHeader {
+		/// The header fields that the bridge interprets: the state root is the storage root, and
+		/// the extrinsics root the transactions root.
+		fn to_header(&self) -> Header {
+			Header {
+				height: self.number.into(),
+				parent: self.parent_hash,
+				storage_root: self.state_root,
+				transactions_root: self.extrinsics_root,
+				..Header::default()
+			}
+		}
+	}
+
+	impl TendermintHeader {
+		/// The header fields that the bridge interprets: the app hash is the storage root, and
+		/// the data hash the transactions root. Hashes that are not 32 bytes long are left zero.
+		fn to_header(&self) -> Header {
+			let hash = |bytes: &[u8]| HashValue::try_from(bytes).unwrap_or_default();
+			let seconds = u64::try_from(self.time.seconds).unwrap_or_default();
+			let millis = u64::try_from(self.time.nanos / 1_000_000).unwrap_or_default();
+			Header {
+				height: self.height,
+				parent: hash(&self.last_block_id.hash),
+				storage_root: hash(&self.app_hash),
+				transactions_root: hash(&self.data_hash),
+				timestamp: seconds.saturating_mul(1000).saturating_add(millis),
+				..Header::default()
+			}
+		}
+	}
+
+	impl BeaconBlockHeader {
+		/// The header fields that the bridge interprets: the slot is the height, the state root
+		/// the storage root, and the body root the transactions root.
+		fn to_header(self) -> Header {
+			Header {
+				height: self.slot,
+				parent: self.parent_root,
+				storage_root: self.state_root,
+				transactions_root: self.body_root,
+				..Header::default()
+			}
+		}
+	}
+
+	/// The trie nodes proving a storage slot's value, see `SpvBridge::verify_storage`.
+	#[derive(Debug, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub struct StorageProof {
+		/// The state trie nodes on the path to the account, starting with the root
+		pub account_proof: Vec<Vec<u8>>,
+		/// The account's storage trie nodes on the path to the slot, starting with the root
+		pub storage_proof: Vec<Vec<u8>>,
+	}
+
+	/// A header submitted in optimistic mode, waiting out its challenge period, see
+	/// `SpvBridge::submit_optimistic_header`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct PendingHeader {
+		/// The submitted header
+		pub header: Header,
+		/// The account that submitted the header
+		pub relayer: AccountId,
+		/// The bond the relayer locked, forfeited to whoever proves the header fraudulent
+		pub bond: Balance,
+		/// The last target chain block in which the header may be challenged
+		pub challengeable_until: BlockNumber,
+	}
+
+	/// A default chain header that arrived before its parent, held until the parent is accepted
+	/// or it expires. See `SpvBridge::submit_new_header`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct OrphanHeader {
+		/// The header
+		pub header: Header,
+		/// The header's hash
+		pub block_hash: HashValue,
+		/// The relayer that submitted the header, who is paid for it once accepted
+		pub submitter: AccountId,
+		/// The last target chain block in which the header is connected if its parent arrives
+		pub expires_at: BlockNumber,
+		/// The relayer's signature over the header, if it was submitted with one
+		pub provenance: Option<HeaderProvenance>,
+	}
+
+	/// A newer checkpoint the owner has scheduled the bridge to be re-anchored at, see
+	/// `SpvBridge::reset_checkpoint`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct ScheduledCheckpoint {
+		/// The trusted header to re-anchor at
+		pub header: Header,
+		/// The first target chain block in which the reset may be enacted
+		pub enactable_at: BlockNumber,
+	}
+
+	/// A fork that would have reorganized a canon chain deeper than the maximum reorg depth, and
+	/// so halted the bridge, see `SpvBridge::set_max_reorg_depth`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct Halt {
+		/// The source chain the fork is on
+		pub chain_id: ChainId,
+		/// The tip of the fork, which has more work than the canon tip
+		pub block_hash: HashValue,
+		/// The number of canon blocks the fork would retract
+		pub depth: u64,
+	}
+
+	/// How many headers may be accepted per target chain block, see `SpvBridge::set_rate_limit`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct RateLimit {
+		/// The maximum number of headers one relayer may have accepted in a target chain block
+		pub per_relayer: u32,
+		/// The maximum number of headers all relayers together may have accepted in a target
+		/// chain block
+		pub per_block: u32,
+	}
+
+	/// The stake a relayer has bonded, see `SpvBridge::register_relayer`.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct RelayerStake {
+		/// The stake that counts towards the minimum relayer stake
+		pub bonded: Balance,
+		/// The stake that is being unbonded. It can still be slashed.
+		pub unbonding: Balance,
+		/// The target chain block from which the unbonding stake may be withdrawn
+		pub unlocks_at: BlockNumber,
+	}
+
+	/// What a relayer has contributed to the bridge, see `SpvBridge::relayer_stats`.
+	#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct RelayerStats {
+		/// The number of the relayer's headers that have been accepted, canon or not
+		pub headers_submitted: u64,
+		/// The number of the relayer's headers that are currently canon
+		pub canon_headers: u64,
+		/// The verify fees the relayer has been credited for its headers
+		pub rewards_earned: Balance,
+	}
+
+	/// What a challenger claims is wrong with a pending header, see `SpvBridge::challenge_header`.
+	/// The contract checks the claim against the header and the headers it already knows.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum FraudProof {
+		/// The header's target is easier than the difficulty threshold, or its hash does not
+		/// meet its target
+		InvalidPoW,
+		/// The header's parent is neither stored nor pending, or the header's height is not one
+		/// more than its parent's
+		BadParentLink,
+	}
+
+	/// The parachain that verified transactions are forwarded to, see
+	/// `SpvBridge::set_xcm_destination`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct XcmDestination {
+		/// The id of the parachain
+		pub para_id: u32,
+		/// The pallet and call index of the parachain's call that receives claims, see
+		/// `codec::xcm_claim_call`
+		pub call_index: [u8; 2],
+	}
+
+	/// The token minted for deposits locked on the default source chain, see
+	/// `SpvBridge::mint_deposit`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct WrappedAsset {
+		/// The PSP22 token contract, which must let the bridge mint and burn
+		pub token: AccountId,
+		/// The source chain account that deposits are sent to
+		pub lock_address: HashValue,
+		/// How many confirmations a deposit's block needs before it is minted
+		pub confirmations: u64,
+	}
+
+	/// A contract subscribed to header notifications, see `SpvBridge::subscribe_headers`.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+	pub struct HeaderSubscriber {
+		/// The subscribed contract
+		pub contract: AccountId,
+		/// The selector of the contract's message that takes a `HeaderNotification`
+		pub selector: [u8; 4],
+		/// The deposit the contract locked, refunded on unsubscribing
+		pub deposit: Balance,
+	}
+
+	/// A change to a source chain's canon chain, passed to header subscribers' callbacks.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum HeaderNotification {
+		/// A new canon tip has been accepted
+		NewTip { chain_id: ChainId, block_hash: HashValue, block_height: u64 },
+		/// Canon blocks were retracted by a heavier fork. Sent before the new tip's `NewTip`.
+		Reorg { chain_id: ChainId, common_ancestor: HashValue, new_tip: HashValue, depth: u64 },
+	}
+
+	/// The source chain registered by the constructor. Messages without a `chain_id` parameter
+	/// operate on this chain.
+	pub const DEFAULT_CHAIN_ID: ChainId = 0;
+
+	/// The weight given to each new block interval observation in the block time EMA is
+	/// `1 / BLOCK_TIME_EMA_WEIGHT`.
+	pub const BLOCK_TIME_EMA_WEIGHT: u64 = 8;
+
+	/// The initial maximum number of siblings a merkle proof may have, enough for trees of
+	/// billions of leaves.
+	pub const DEFAULT_MAX_PROOF_LENGTH: u32 = 32;
+
+	/// The initial maximum number of headers that may be submitted in one batch.
+	pub const DEFAULT_MAX_BATCH_SIZE: u32 = 32;
+
+	/// The compact encoding of the easiest target a Bitcoin mainnet header may declare.
+	pub const BITCOIN_POW_LIMIT: u32 = 0x1d00_ffff;
+
+	/// Bitcoin's difficulty retargeting: every 2016 blocks, aiming for two weeks per epoch.
+	pub const BITCOIN_RETARGET: RetargetParams =
+		RetargetParams { interval: 2016, target_timespan: 14 * 24 * 60 * 60 * 1000 };
+
+	/// The maximum depth of a merged mining tree, as in Namecoin.
+	pub const MAX_MERGED_MINING_DEPTH: usize = 30;
+
+	/// A header's timestamp must be later than the median timestamp of this many of its ancestors.
+	pub const MEDIAN_TIME_SPAN: usize = 11;
+
+	/// How far ahead of the target chain's clock a header's timestamp may be, in milliseconds.
+	pub const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60 * 1000;
+
+	/// The bridge is considered stale once no new tip has been accepted for this many estimated
+	/// source block times.
+	pub const STALENESS_BLOCK_TIMES: u64 = 6;
+
+	/// For how many target chain blocks a scheduled checkpoint reset can be cancelled before it
+	/// can be enacted.
+	pub const CHECKPOINT_RESET_DELAY: BlockNumber = 14_400;
+
+	/// The maximum number of headers held while waiting for their parent.
+	pub const MAX_ORPHAN_HEADERS: usize = 16;
+
+	/// The maximum number of headers held for any one relayer while waiting for their parent.
+	pub const MAX_ORPHAN_HEADERS_PER_RELAYER: usize = 4;
+
+	/// For how many target chain blocks a header is held while waiting for its parent.
+	pub const ORPHAN_HEADER_TTL: BlockNumber = 600;
+
+	/// For how many target chain blocks unbonded relayer stake stays slashable before it can be
+	/// withdrawn.
+	pub const UNBONDING_DELAY: BlockNumber = 14_400;
+
+	/// The percentage of a slashed relayer stake that is paid to the reporter. The rest goes to
+	/// the treasury, like relay fees.
+	pub const SLASH_REPORTER_PERCENT: Balance = 50;
+
+	/// For how many target chain blocks a submission receipt can be looked up.
+	pub const SUBMISSION_RECEIPT_TTL: BlockNumber = 14_400;
+
+	/// The maximum number of accounts that may subscribe to bridge health alerts.
+	/// Every alert is emitted once per subscriber, so this bounds the cost of raising one.
+	pub const MAX_ALERT_SUBSCRIBERS: u32 = 16;
+
+	/// The number of best height samples kept. Older samples are overwritten.
+	pub const MAX_HEIGHT_SAMPLES: u32 = 256;
+
+	/// The deposit an account must lock in order to subscribe to bridge health alerts.
+	pub const ALERT_SUBSCRIPTION_DEPOSIT: Balance = 1_000;
+
+	/// The maximum number of contracts that may subscribe to header notifications.
+	/// Every canon tip change calls each subscriber, so this bounds the cost of accepting a header.
+	pub const MAX_HEADER_SUBSCRIBERS: u32 = 8;
+
+	/// The maximum number of relayers that `top_relayers` ranks. Relayers whose first header is
+	/// accepted once this many are ranked still have their stats tracked.
+	pub const MAX_RANKED_RELAYERS: u32 = 64;
+
+	/// The maximum number of default chain headers that may be blacklisted at once.
+	/// Every header submission and verification is checked against each of them.
+	pub const MAX_BLACKLISTED_HEADERS: u32 = 16;
+
+	/// The deposit a contract must lock in order to subscribe to header notifications.
+	pub const HEADER_SUBSCRIPTION_DEPOSIT: Balance = 1_000;
+
+	/// The gas (reference time) each header notification callback may use. The relayer whose
+	/// header changes the canon tip pays for every callback, so this is kept small.
+	pub const HEADER_CALLBACK_GAS_LIMIT: u64 = 500_000_000;
+
+	/// A reorg that retracts at least this many canon blocks raises a `DeepReorg` alert.
+	pub const DEEP_REORG_ALERT_DEPTH: u64 = 6;
+
+	/// The maximum number of canon blocks that `assert_chain_consistency` checks in one call.
+	pub const MAX_CONSISTENCY_RANGE: u64 = 64;
+
+	/// The number of attestations kept per claim hash. Older attestations are dropped.
+	pub const MAX_ATTESTATIONS_PER_CLAIM: usize = 8;
+
+	/// The maximum number of heights that `canon_range` looks up in one call.
+	pub const MAX_CANON_RANGE_PAGE: u32 = 256;
+
+	/// The version of the storage layout that this code expects. Bump this whenever a change
+	/// requires `migrate_storage` to transform the storage left behind by the previous code.
+	///
+	/// - 1: the initial layout.
+	/// - 2: a single owner became role-based access control, see `Role`.
+	pub const STORAGE_VERSION: u32 = 2;
+
+	/// Errors that can occur upon calling this contract.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum Error {
+		/// Insufficient fee provided when attempting to relay a block
+		InsufficientRelayFee,
+		/// Insufficient fee provided when attempting to verify a proof
+		InsufficientVerifyFee,
+		/// Header cannot be submitted because it was previously submitted
+		HeaderAlreadySubmitted,
+		/// Parent is not in the DB
+		UnknownParent,
+		/// Header height is invalid
+		IncorrectHeight,
+		/// PoW threshold has not been met
+		PoWThresholdNotMet,
+		/// The header's timestamp is not later than the median time of its recent ancestors
+		TimestampTooOld,
+		/// The header's timestamp is too far ahead of the target chain's clock
+		TimestampTooFarInFuture,
+		/// The header's PoW target is easier than the difficulty threshold, or does not follow
+		/// the difficulty retargeting algorithm
+		InvalidDifficultyTarget,
+		/// Attempted reward payment to a relayer failed,
+		PaymentFailed,
+		/// The caller lacks the role the message requires
+		MissingRole,
+		/// The last admin can't give up the admin role
+		LastAdmin,
+		/// Signers are configured, so the action must be proposed and approved
+		MultisigRequired,
+		/// The caller is not one of the signers
+		NotSigner,
+		/// The threshold is higher than the number of signers, signers are repeated, or there are
+		/// more than `MAX_SIGNERS`
+		InvalidSignerSet,
+		/// No proposal with the given id is waiting for approval
+		NoProposal,
+		/// The signer set has changed since the proposal was made
+		StaleProposal,
+		/// The signer has already approved the proposal
+		AlreadyApproved,
+		/// The caller is not the account ownership has been offered to
+		NotPendingOwner,
+		/// Insufficient deposit provided when subscribing to alerts
+		InsufficientAlertDeposit,
+		/// The caller is already subscribed to alerts
+		AlreadySubscribed,
+		/// The caller is not subscribed to alerts
+		NotSubscribed,
+		/// The alert subscriber registry is full
+		AlertRegistryFull,
+		/// A header in a batch is not the child of the header before it
+		NonContiguousBatch,
+		/// The batch has more headers than the maximum batch size
+		BatchTooLarge,
+		/// The batch has nothing in it
+		EmptyBatch,
+		/// No header of the source chain has been accepted within the staleness window
+		BridgeStale,
+		/// The merkle proof has more siblings than the maximum proof length
+		ProofTooLarge,
+		/// The requested depth can never be reached by the block being verified
+		DepthUnreachable,
+		/// No fee escrow exists with the given id
+		UnknownEscrow,
+		/// The escrowed block has not yet reached its release height
+		EscrowNotMature,
+		/// No submission receipt exists for the given relayer and header
+		UnknownReceipt,
+		/// The submission receipt has not yet expired
+		ReceiptNotExpired,
+		/// The bridge is paused
+		BridgePaused,
+		/// Replacing the contract's code failed
+		UpgradeFailed,
+		/// The storage was written by a newer version of the code than this one
+		UnsupportedStorageVersion,
+		/// The header is the all-zero default header
+		DefaultHeader,
+		/// The all-zero hash was given as, or is the hash of, a block
+		ZeroHash,
+		/// The caller has no rewards to withdraw
+		NoPendingRewards,
+		/// The height range is empty or not entirely within the canon chain
+		InvalidRange,
+		/// The height range spans more than `MAX_CONSISTENCY_RANGE` blocks
+		RangeTooLarge,
+		/// The stored canon chain is not linked, or its cumulative work does not add up
+		InconsistentChain,
+		/// The header's branch would become canon, but its fork from the canon chain was pruned
+		ForkPruned,
+		/// The prune depth is too shallow to keep the ancestors needed to validate new headers
+		InvalidPruneDepth,
+		/// The fee split's shares add up to more than the whole fee
+		InvalidFeeSplit,
+		/// The header or block is on a fork from the canon chain below the finalized height
+		ConflictsWithFinalized,
+		/// The header is more than the prune depth behind the tip, so its ancestors may be pruned
+		/// and it can never become canon
+		BelowPruneHorizon,
+		/// As many headers as `MAX_ORPHAN_HEADERS` are already waiting for their parent, or as
+		/// many as `MAX_ORPHAN_HEADERS_PER_RELAYER` of the caller's
+		OrphanPoolFull,
+		/// The bytes are not a transaction in the encoding the bridge understands
+		MalformedTransaction,
+		/// The signature was not made over the header hash by the key of the caller's account
+		InvalidSignature,
+		/// No source chain is registered with the given id
+		UnknownChain,
+		/// A source chain is already registered with the given id
+		ChainAlreadyRegistered,
+		/// The checkpoint header does not start a difficulty epoch
+		InvalidCheckpoint,
+		/// The header is not in the format of the source chain it was submitted to
+		WrongHeaderFormat,
+		/// The bytes are not a header in the format of the source chain
+		MalformedHeader,
+		/// The source chain does not accept merge mined headers
+		MergedMiningDisabled,
+		/// The merged mining proof does not commit to the header
+		InvalidAuxPow,
+		/// The source chain does not accept headers by this consensus mechanism
+		WrongConsensus,
+		/// The header or commitment is not signed for by the current authority set
+		UnknownValidatorSet,
+		/// The authorities that signed hold too little of their set's voting weight
+		InsufficientSignatures,
+		/// The same authority's vote is counted more than once
+		DuplicateVote,
+		/// The justification is not for the header, or one of its precommits is for a block
+		/// that is not shown to descend from the header
+		InvalidJustification,
+		/// The header schedules an authority set change that the bridge can't follow: a delayed
+		/// or forced change, or one to a set without voting weight
+		UnsupportedAuthorityChange,
+		/// The commitment is not newer than the latest accepted one
+		StaleCommitment,
+		/// The commitment's payload has no MMR root
+		MissingMmrRoot,
+		/// The MMR proof does not match the latest MMR root
+		InvalidMmrProof,
+		/// The given validator set is not the one the header commits to
+		ValidatorSetMismatch,
+		/// The validators that signed hold too little of the trusted set's voting power to
+		/// trust a new set
+		UntrustedValidatorSet,
+		/// The sync committee or its public keys are not proven by the given branch
+		InvalidCommitteeProof,
+		/// Not all public keys of the signing sync committee have been submitted
+		MissingCommitteeKeys,
+		/// The beacon chain forks are not in order of epoch, or none is in effect at the
+		/// checkpoint
+		InvalidForkSchedule,
+		/// Headers cannot be submitted optimistically, because no challenge period is set
+		OptimisticModeDisabled,
+		/// Insufficient bond provided when optimistically submitting a header
+		InsufficientBond,
+		/// No optimistically submitted header is pending with the given hash
+		UnknownPendingHeader,
+		/// The pending header's challenge period is over
+		ChallengePeriodOver,
+		/// The pending header's challenge period is not over yet
+		ChallengePeriodNotOver,
+		/// The fraud proof does not hold for the pending header
+		FraudNotProven,
+		/// The relayer has not bonded the minimum relayer stake
+		InsufficientStake,
+		/// The caller has no bonded stake to unbond
+		NoBondedStake,
+		/// The caller has no unbonding stake, or it is still locked
+		NothingToWithdraw,
+		/// The header has no relayer with stake to slash
+		NothingToSlash,
+		/// The caller has no verification credits to withdraw
+		NoCredits,
+		/// The treasury holds less than the amount to spend
+		InsufficientTreasury,
+		/// The relayer allowlist is enabled and the relayer is not on it
+		RelayerNotAllowed,
+		/// The relayer, or all relayers together, have had as many headers accepted in this
+		/// target chain block as the rate limit allows
+		RateLimited,
+		/// A new checkpoint must be higher than the current best height
+		CheckpointNotNewer,
+		/// No checkpoint reset is scheduled
+		NoCheckpointReset,
+		/// A fork deeper than the maximum reorg depth has halted the bridge
+		BridgeHalted,
+		/// The bridge is not halted
+		NotHalted,
+		/// The scheduled checkpoint reset's timelock has not passed yet
+		CheckpointResetLocked,
+		/// No parameter change with the given id is queued
+		NoPendingChange,
+		/// The queued parameter change's timelock has not passed yet
+		ParameterChangeLocked,
+		/// As many parameter changes as `MAX_PENDING_CHANGES` are already queued
+		TooManyPendingChanges,
+		/// Insufficient deposit provided when subscribing to header notifications
+		InsufficientSubscriptionDeposit,
+		/// The header subscriber registry is full
+		HeaderRegistryFull,
+		/// The contract was built without the XCM chain extension
+		XcmUnavailable,
+		/// The user is already sponsored by another account
+		AlreadySponsored,
+		/// The header is blacklisted or descends from a blacklisted header
+		BlacklistedHeader,
+		/// The header blacklist is full
+		BlacklistFull,
+		/// The header is not blacklisted
+		NotBlacklisted,
+		/// No wrapped asset is configured
+		WrappedAssetDisabled,
+		/// The transaction does not transfer to the wrapped asset's lock address
+		NotADeposit,
+		/// The deposit has already been minted
+		DepositAlreadyMinted,
+		/// The deposit's inclusion in a sufficiently confirmed canon block could not be verified
+		DepositNotProven,
+		/// The wrapped asset's token contract did not mint
+		MintFailed,
+		/// The wrapped asset's token contract did not burn
+		BurnFailed,
+	}
+
+	/// Type alias for the contract's `Result` type.
+	pub type Result<T> = core::result::Result<T, Error>;
+
+	/// Why a transaction or state claim was not verified.
+	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum VerificationError {
+		/// The call was rejected before the claim could be checked
+		Rejected(Error),
+		/// The claim was checked and does not hold
+		Failed(VerificationFailure),
+	}
+
+	impl From<Error> for VerificationError {
+		fn from(error: Error) -> Self {
+			Self::Rejected(error)
+		}
+	}
+
+	impl From<VerificationFailure> for VerificationError {
+		fn from(failure: VerificationFailure) -> Self {
+			Self::Failed(failure)
+		}
+	}
+
+	/// The outcome of verifying a transaction or state claim.
+	pub type VerificationResult = core::result::Result<(), VerificationError>;
+
+	/// Someone has successfully submitted a source chain header.
+	#[ink(event)]
+	pub struct HeaderSubmitted {
+		submission_id: SubmissionId,
+		chain_id: ChainId,
+		#[ink(topic)]
+		block_hash: HashValue,
+		#[ink(topic)]
+		block_height: u64,
+		#[ink(topic)]
+		submitter: AccountId,
+	}
+
+	/// A header has been submitted before its parent, and is held until the parent is accepted.
+	#[ink(event)]
+	pub struct HeaderPooled {
+		#[ink(topic)]
+		block_hash: HashValue,
+		#[ink(topic)]
+		parent: HashValue,
+		#[ink(topic)]
+		submitter: AccountId,
+	}
+
+	/// A verify fee has been placed in escrow.
+	#[ink(event)]
+	pub struct FeeEscrowed {
+		#[ink(topic)]
+		escrow_id: u64,
+		#[ink(topic)]
+		verifier: AccountId,
+		block_hash: HashValue,
+		release_height: u64,
+		amount: Balance,
+	}
+
+	/// An escrowed verify fee has been paid out, either to the relayer's pending rewards or back
+	/// to the verifier.
+	#[ink(event)]
+	pub struct EscrowSettled {
+		#[ink(topic)]
+		escrow_id: u64,
+		#[ink(topic)]
+		recipient: AccountId,
+		refunded: bool,
+	}
+
+	/// A header has been submitted optimistically, and may be challenged until the given target
+	/// chain block.
+	#[ink(event)]
+	pub struct HeaderPending {
+		#[ink(topic)]
+		block_hash: HashValue,
+		#[ink(topic)]
+		relayer: AccountId,
+		challengeable_until: BlockNumber,
+	}
+
+	/// A pending header has been proven fraudulent and rejected. Its relayer's bond has been
+	/// credited to the challenger's pending rewards.
+	#[ink(event)]
+	pub struct HeaderChallenged {
+		#[ink(topic)]
+		block_hash: HashValue,
+		#[ink(topic)]
+		relayer: AccountId,
+		#[ink(topic)]
+		challenger: AccountId,
+		fraud: FraudProof,
+		bond: Balance,
+	}
+
+	/// The owner has added a relayer to, or removed one from, the relayer allowlist.
+	#[ink(event)]
+	pub struct RelayerSetChanged {
+		#[ink(topic)]
+		relayer: AccountId,
+		allowed: bool,
+	}
+
+	/// The owner has spent from the treasury. `to` is `None` when the funds were moved to the
+	/// subsidy pool.
+	#[ink(event)]
+	pub struct TreasurySpent {
+		#[ink(topic)]
+		to: Option<AccountId>,
+		amount: Balance,
+	}
+
+	/// A header notification was sent to a subscribed contract. `delivered` is false if the
+	/// callback failed or ran out of gas.
+	#[ink(event)]
+	pub struct HeaderCallback {
+		#[ink(topic)]
+		subscriber: AccountId,
+		notification: HeaderNotification,
+		delivered: bool,
+	}
+
+	/// A verified transaction has been forwarded to a parachain, see
+	/// `SpvBridge::set_xcm_destination`.
+	#[ink(event)]
+	pub struct ClaimForwarded {
+		#[ink(topic)]
+		claim_id: HashValue,
+		para_id: u32,
+		/// Whether the runtime sent the message
+		sent: bool,
+	}
+
+	/// A proven source chain deposit has been minted as the wrapped asset.
+	#[ink(event)]
+	pub struct DepositMinted {
+		#[ink(topic)]
+		tx_hash: HashValue,
+		#[ink(topic)]
+		beneficiary: AccountId,
+		amount: Balance,
+	}
+
+	/// Wrapped tokens have been burned to release the locked deposit on the default source chain.
+	///
+	/// Relayers release `amount` from the lock address to `source_address`, and the source chain
+	/// should release each `nonce` at most once.
+	#[ink(event)]
+	pub struct ReleaseRequested {
+		#[ink(topic)]
+		nonce: u64,
+		#[ink(topic)]
+		account: AccountId,
+		source_address: HashValue,
+		amount: Balance,
+	}
+
+	/// An account has deposited prepaid verification credits.
+	#[ink(event)]
+	pub struct CreditsDeposited {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	/// A sponsor has set how much of a user's verify fees it will pay.
+	#[ink(event)]
+	pub struct SponsorshipSet {
+		#[ink(topic)]
+		sponsor: AccountId,
+		#[ink(topic)]
+		user: AccountId,
+		allowance: Balance,
+	}
+
+	/// The owner has exempted an account from the verify fee, or revoked its exemption.
+	#[ink(event)]
+	pub struct FeeExemptionSet {
+		#[ink(topic)]
+		account: AccountId,
+		/// The number of fee exempt verifications per `FEE_EXEMPTION_PERIOD`, or `None` if the
+		/// exemption was revoked
+		quota: Option<u32>,
+	}
+
+	/// An account has withdrawn its unused verification credits.
+	#[ink(event)]
+	pub struct CreditsWithdrawn {
+		#[ink(topic)]
+		account: AccountId,
+		amount: Balance,
+	}
+
+	/// A relayer has bonded stake. `stake` is their total bonded stake.
+	#[ink(event)]
+	pub struct RelayerRegistered {
+		#[ink(topic)]
+		relayer: AccountId,
+		stake: Balance,
+	}
+
+	/// A relayer has started unbonding their stake.
+	#[ink(event)]
+	pub struct RelayerUnbonding {
+		#[ink(topic)]
+		relayer: AccountId,
+		amount: Balance,
+		unlocks_at: BlockNumber,
+	}
+
+	/// A relayer has withdrawn their unbonded stake.
+	#[ink(event)]
+	pub struct StakeWithdrawn {
+		#[ink(topic)]
+		relayer: AccountId,
+		amount: Balance,
+	}
+
+	/// A relayer's stake has been slashed for an invalid header. `reward` of the slashed amount
+	/// has been credited to the reporter's pending rewards.
+	#[ink(event)]
+	pub struct RelayerSlashed {
+		#[ink(topic)]
+		relayer: AccountId,
+		#[ink(topic)]
+		reporter: AccountId,
+		block_hash: HashValue,
+		amount: Balance,
+		reward: Balance,
+	}
+
+	/// Someone has checked that a range of the stored canon chain is consistent.
+	#[ink(event)]
+	pub struct ChainConsistencyAttested {
+		#[ink(topic)]
+		auditor: AccountId,
+		from_height: u64,
+		to_height: u64,
+		/// The canon block at `to_height`
+		to_hash: HashValue,
+		/// The cumulative work of the canon block at `to_height`
+		work: U256,
+	}
+
+	/// Old headers have been removed from the db.
+	#[ink(event)]
+	pub struct HeadersPruned {
+		chain_id: ChainId,
+		/// The number of headers removed
+		count: u32,
+		/// Headers below this height have now been pruned
+		pruned_height: u64,
+	}
+
+	/// A relayer has withdrawn their pending rewards.
+	#[ink(event)]
+	pub struct RewardsWithdrawn {
+		#[ink(topic)]
+		relayer: AccountId,
+		amount: Balance,
+	}
+
+	/// The subsidy pool has paid the verify fee of a verification.
+	#[ink(event)]
+	pub struct VerificationSubsidized {
+		#[ink(topic)]
+		verifier: AccountId,
+		block_hash: HashValue,
+		amount: Balance,
+	}
+
+	/// The canon chain has switched to a different fork.
+	///
+	/// Every canon block above the common ancestor, up to the retracted tip, is no longer canon.
+	#[ink(event)]
+	pub struct ReorgOccurred {
+		#[ink(topic)]
+		chain_id: ChainId,
+		/// The most recent block that is canon on both the old and the new chain
+		common_ancestor: HashValue,
+		/// The tip of the chain that was canon before the reorg
+		#[ink(topic)]
+		retracted_tip: HashValue,
+		/// The tip of the chain that is canon after the reorg
+		#[ink(topic)]
+		new_tip: HashValue,
+		/// The number of previously canon blocks that were retracted
+		depth: u64,
+	}
+
+	/// The owner has registered a new source chain.
+	#[ink(event)]
+	pub struct ChainRegistered {
+		#[ink(topic)]
+		chain_id: ChainId,
+		checkpoint_hash: HashValue,
+		checkpoint_height: u64,
+	}
+
+	/// A header of a GRANDPA chain has enacted the next authority set.
+	#[ink(event)]
+	pub struct GrandpaAuthoritiesChanged {
+		#[ink(topic)]
+		chain_id: ChainId,
+		set_id: u64,
+		enacted_at: u64,
+	}
+
+	/// A BEEFY commitment has been accepted, and headers can be proven against its MMR root.
+	#[ink(event)]
+	pub struct BeefyCommitmentAccepted {
+		#[ink(topic)]
+		chain_id: ChainId,
+		block_number: u32,
+		mmr_root: HashValue,
+	}
+
+	/// A Tendermint chain has moved on to a new trusted validator set.
+	#[ink(event)]
+	pub struct TendermintValidatorsChanged {
+		#[ink(topic)]
+		chain_id: ChainId,
+		trusted_height: u64,
+	}
+
+	/// A sync committee chain has moved on to the committee of a new period.
+	#[ink(event)]
+	pub struct SyncCommitteeRotated {
+		#[ink(topic)]
+		chain_id: ChainId,
+		period: u64,
+		committee_root: HashValue,
+	}
+
+	/// The owner has changed one of the bridge's parameters.
+	#[ink(event)]
+	pub struct ParameterChanged {
+		parameter: BridgeParameter,
+	}
+
+	/// The owner has queued a parameter change behind the parameter timelock.
+	#[ink(event)]
+	pub struct ParameterChangeScheduled {
+		#[ink(topic)]
+		id: u32,
+		parameter: BridgeParameter,
+		/// The first target chain block in which the change may be enacted
+		enactable_at: BlockNumber,
+	}
+
+	/// The owner has cancelled a queued parameter change.
+	#[ink(event)]
+	pub struct ParameterChangeCancelled {
+		#[ink(topic)]
+		id: u32,
+		parameter: BridgeParameter,
+	}
+
+	/// The owner has scheduled the bridge to be re-anchored at a newer checkpoint.
+	#[ink(event)]
+	pub struct CheckpointResetScheduled {
+		#[ink(topic)]
+		checkpoint_hash: HashValue,
+		checkpoint_height: u64,
+		/// The first target chain block in which the reset may be enacted
+		enactable_at: BlockNumber,
+	}
+
+	/// The owner has cancelled a scheduled checkpoint reset.
+	#[ink(event)]
+	pub struct CheckpointResetCancelled {
+		#[ink(topic)]
+		checkpoint_hash: HashValue,
+	}
+
+	/// The bridge has been re-anchored at a new checkpoint. Blocks of the old chain remain
+	/// stored, but can no longer become canon.
+	#[ink(event)]
+	pub struct CheckpointReset {
+		#[ink(topic)]
+		checkpoint_hash: HashValue,
+		checkpoint_height: u64,
+		/// The canon tip before the reset
+		previous_tip: HashValue,
+	}
+
+	/// A fork with more work than the canon chain would have retracted more canon blocks than
+	/// the maximum reorg depth. It has been stored but not made canon, and the bridge is halted.
+	#[ink(event)]
+	pub struct BridgeHalted {
+		#[ink(topic)]
+		chain_id: ChainId,
+		/// The tip of the fork
+		#[ink(topic)]
+		block_hash: HashValue,
+		/// The number of canon blocks the fork would retract
+		depth: u64,
+	}
+
+	/// The owner has resolved a halt, either adopting the fork that caused it or keeping the
+	/// canon chain.
+	#[ink(event)]
+	pub struct HaltResolved {
+		#[ink(topic)]
+		block_hash: HashValue,
+		adopted: bool,
+	}
+
+	/// The owner has blacklisted a source chain header and its descendants.
+	#[ink(event)]
+	pub struct HeaderBlacklisted {
+		chain_id: ChainId,
+		#[ink(topic)]
+		block_hash: HashValue,
+	}
+
+	/// The owner has removed a source chain header from the blacklist.
+	#[ink(event)]
+	pub struct HeaderUnblacklisted {
+		chain_id: ChainId,
+		#[ink(topic)]
+		block_hash: HashValue,
+	}
+
+	/// The owner has paused the bridge.
+	#[ink(event)]
+	pub struct Paused {
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	/// The owner has unpaused the bridge.
+	#[ink(event)]
+	pub struct Unpaused {
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	/// The owner has offered ownership of the bridge to another account.
+	#[ink(event)]
+	pub struct OwnershipTransferStarted {
+		#[ink(topic)]
+		previous_owner: AccountId,
+		#[ink(topic)]
+		new_owner: AccountId,
+	}
+
+	/// An account has accepted ownership of the bridge.
+	#[ink(event)]
+	pub struct OwnershipTransferred {
+		#[ink(topic)]
+		previous_owner: AccountId,
+		#[ink(topic)]
+		new_owner: AccountId,
+	}
+
+	/// An admin has granted a role to an account.
+	#[ink(event)]
+	pub struct RoleGranted {
+		#[ink(topic)]
+		role: Role,
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	/// An admin has revoked a role from an account.
+	#[ink(event)]
+	pub struct RoleRevoked {
+		#[ink(topic)]
+		role: Role,
+		#[ink(topic)]
+		account: AccountId,
+	}
+
+	/// A signer has proposed a critical action.
+	#[ink(event)]
+	pub struct ProposalCreated {
+		#[ink(topic)]
+		id: u32,
+		#[ink(topic)]
+		proposer: AccountId,
+		action: CriticalAction,
+	}
+
+	/// A signer has approved a proposal.
+	#[ink(event)]
+	pub struct ProposalApproved {
+		#[ink(topic)]
+		id: u32,
+		#[ink(topic)]
+		signer: AccountId,
+		approvals: u32,
+	}
+
+	/// A proposal has reached the signer threshold and its action has been executed.
+	#[ink(event)]
+	pub struct ProposalExecuted {
+		#[ink(topic)]
+		id: u32,
+	}
+
+	/// The signers that approve critical actions, or their threshold, have changed.
+	#[ink(event)]
+	pub struct SignersChanged {
+		signers: Vec<AccountId>,
+		threshold: u32,
+	}
+
+	/// The owner has replaced the contract's code.
+	#[ink(event)]
+	pub struct Upgraded {
+		code_hash: Hash,
+	}
+
+	/// The storage has been migrated to the layout expected by the current code.
+	#[ink(event)]
+	pub struct Migrated {
+		from_version: u32,
+		to_version: u32,
+	}
+
+	/// A block has stopped being canon because of a reorg.
+	#[ink(event)]
+	pub struct CanonBlockRetracted {
+		chain_id: ChainId,
+		block_hash: HashValue,
+		block_height: u64,
+	}
+
+	/// A block has become canon, either by extending the canon chain or because of a reorg.
+	#[ink(event)]
+	pub struct CanonBlockAdded {
+		chain_id: ChainId,
+		block_hash: HashValue,
+		block_height: u64,
+	}
+
+	/// A verify fee has been added to the pending rewards of the relayer of the verified block.
+	#[ink(event)]
+	pub struct RelayerPaid {
+		#[ink(topic)]
+		relayer: AccountId,
+		block_hash: HashValue,
+		amount: Balance,
+	}
+
+	/// Someone has checked whether a transaction is included in a source chain block.
+	#[ink(event)]
+	pub struct TransactionVerified {
+		chain_id: ChainId,
+		#[ink(topic)]
+		tx_hash: HashValue,
+		#[ink(topic)]
+		block_hash: HashValue,
+		/// This deployment's identifier for the transaction in the block, see `claim_id`
+		claim_id: HashValue,
+		/// The minimum number of confirmations that was required
+		depth: u64,
+		#[ink(topic)]
+		verifier: AccountId,
+		/// Whether the verification succeeded
+		outcome: bool,
+		/// Why the verification failed, if it did
+		failure: Option<VerificationFailure>,
+	}
+
+	/// Someone has proven that an event was emitted in a source chain block.
+	#[ink(event)]
+	pub struct EventVerified {
+		chain_id: ChainId,
+		#[ink(topic)]
+		event_hash: HashValue,
+		#[ink(topic)]
+		block_hash: HashValue,
+		/// This deployment's identifier for the event in the block, see `claim_id`
+		claim_id: HashValue,
+		/// The minimum number of confirmations that was required
+		depth: u64,
+		#[ink(topic)]
+		verifier: AccountId,
+	}
+
+	/// Someone has proven that a state claim holds in a source chain block.
+	#[ink(event)]
+	pub struct StateVerified {
+		chain_id: ChainId,
+		#[ink(topic)]
+		claim_hash: HashValue,
+		#[ink(topic)]
+		block_hash: HashValue,
+		/// This deployment's identifier for the claim in the block, see `claim_id`
+		claim_id: HashValue,
+		#[ink(topic)]
+		verifier: AccountId,
+		/// Whether the verification succeeded
+		outcome: bool,
+		/// Why the verification failed, if it did
+		failure: Option<VerificationFailure>,
+	}
+
+	/// The kinds of critical bridge health events that alert subscribers are notified of.
+	#[derive(Debug, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+	#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+	pub enum AlertKind {
+		/// The bridge has been paused
+		Paused,
+		/// No new headers have been relayed for a long time
+		Stalled,
+		/// A reorg retracted at least `DEEP_REORG_ALERT_DEPTH` canon blocks
+		DeepReorg,
+		/// The bridge has been re-anchored at a new checkpoint header
+		CheckpointReset,
+		/// A fork deeper than the maximum reorg depth has halted the bridge
+		Halted,
+	}
+
+	/// A critical bridge health event, emitted once for each alert subscriber.
+	#[ink(event)]
+	pub struct BridgeAlert {
+		#[ink(topic)]
+		subscriber: AccountId,
+		#[ink(topic)]
+		kind: AlertKind,
+	}
+
+	/// An account has subscribed to bridge health alerts.
+	#[ink(event)]
+	pub struct AlertSubscribed {
+		#[ink(topic)]
+		subscriber: AccountId,
+	}
+
+	/// An account has unsubscribed from bridge health alerts.
+	#[ink(event)]
+	pub struct AlertUnsubscribed {
+		#[ink(topic)]
+		subscriber: AccountId,
+	}
+
+	/// A contract has asked to subscribe to header notifications, pending an admin's approval.
+	#[ink(event)]
+	pub struct HeaderSubscriptionRequested {
+		#[ink(topic)]
+		subscriber: AccountId,
+		selector: [u8; 4],
+	}
+
+	/// An admin has approved a contract's subscription to header notifications.
+	#[ink(event)]
+	pub struct HeaderSubscribed {
+		#[ink(topic)]
+		subscriber: AccountId,
+	}
+
+	/// A contract has been unsubscribed from header notifications, or its request withdrawn.
+	#[ink(event)]
+	pub struct HeaderUnsubscribed {
+		#[ink(topic)]
+		subscriber: AccountId,
+	}
+
+	/// An on-chain light client (or SPV client) for a foreign source chain.
+	///
+	/// This contract, inspired by btc-relay, allows users to submit new block headers
+	/// from a foreign PoW blockchain for validation. It then allows (potentially different)
+	/// users to verify claims about what transactions and state exist on the source chain.
+	///
+	/// # Event ordering
+	///
+	/// Indexers may rely on the order of events emitted within a single call:
+	/// - Accepting a header emits `HeaderSubmitted` first. If the header becomes the new tip, that
+	///   is followed by a `CanonBlockRetracted` for each evicted block from the old tip down, then
+	///   a `CanonBlockAdded` for each newly canon block from the common ancestor up, then
+	///   `ReorgOccurred` if any block was evicted, then any resulting `BridgeAlert`s.
+	/// - Verifying a claim emits the payment event (`RelayerPaid` or `FeeEscrowed`) before any
+	///   event describing the verification result.
+	impl SpvBridge {
+		/// Initialize the on-chain light client with a "checkpoint" header.
+		///
+		/// In many cases, the source chain is older than the target chain, and may have a long
+		/// history. We allow starting from a recent point in the source chain and verifying
+		/// thereafter.
+		///
+		/// This constructor allows the contract deployer to specify the recent block from which to
+		/// start
+		#[ink(constructor)]
+		pub fn new(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+		) -> Self {
+			Self::new_with_config(DeployConfig::new(
+				source_genesis_header,
+				difficulty,
+				init_relay_fee,
+				init_verify_fee,
+			))
+		}
+
+		/// Initialize the bridge like `new`, but hold verify fees in escrow.
+		///
+		/// A verify fee is only released to the relayer once the verified block is canon and has
+		/// - at least `payout_depth` confirmations, if set, and
+		/// - at least `min_depth + safety_margin` confirmations, if a safety margin is set.
+		///
+		/// If the block leaves the canon chain first, the fee is returned to the verifier instead.
+		/// See `settle_escrow`.
+		#[ink(constructor)]
+		pub fn new_with_fee_escrow(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+			payout_depth: Option<u64>,
+			safety_margin: Option<u64>,
+		) -> Self {
+			Self::new_with_config(DeployConfig {
+				payout_depth,
+				escrow_safety_margin: safety_margin,
+				..DeployConfig::new(
+					source_genesis_header,
+					difficulty,
+					init_relay_fee,
+					init_verify_fee,
+				)
+			})
+		}
+
+		/// Initialize the bridge like `new`, and if `check_pow` is set, first check the checkpoint
+		/// header's proof of work: its target must be no easier than the difficulty threshold,
+		/// and its hash must meet its target.
+		///
+		/// This rejects obviously invalid checkpoints, but not a valid header of the wrong chain.
+		#[ink(constructor)]
+		pub fn new_with_checkpoint_check(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+			check_pow: bool,
+		) -> Self {
+			Self::new_with_config(DeployConfig {
+				check_checkpoint_pow: check_pow,
+				..DeployConfig::new(
+					source_genesis_header,
+					difficulty,
+					init_relay_fee,
+					init_verify_fee,
+				)
+			})
+		}
+
+		/// Initialize the bridge like `new`, but with the given deployment salt rather than one
+		/// derived from the contract's account. See `claim_id`.
+		#[ink(constructor)]
+		pub fn new_with_salt(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+			salt: HashValue,
+		) -> Self {
+			Self::new_with_config(DeployConfig {
+				salt: Some(salt),
+				..DeployConfig::new(
+					source_genesis_header,
+					difficulty,
+					init_relay_fee,
+					init_verify_fee,
+				)
+			})
+		}
+
+		/// Initialize the bridge like `new`, but identify native headers and state claims by the
+		/// given hash function, for source chains that do not use SHA-256.
+		///
+		/// Merkle tree nodes are hashed with SHA-256 whatever the algorithm.
+		#[ink(constructor)]
+		pub fn new_with_hash_algorithm(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+			algorithm: HashAlgorithm,
+		) -> Self {
+			Self::new_with_config(DeployConfig {
+				hash_algorithm: algorithm,
+				..DeployConfig::new(
+					source_genesis_header,
+					difficulty,
+					init_relay_fee,
+					init_verify_fee,
+				)
+			})
+		}
+
+		/// Initialize the bridge like `new`, but validate header targets with difficulty
+		/// retargeting. The `difficulty` is the easiest target any header may declare.
+		///
+		/// The checkpoint header must be the first block of a difficulty epoch.
+		#[ink(constructor)]
+		pub fn new_with_retargeting(
+			source_genesis_header: Header,
+			difficulty: HashValue,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+			retarget: RetargetParams,
+		) -> Self {
+			Self::new_with_config(DeployConfig {
+				retarget: Some(retarget),
+				..DeployConfig::new(
+					source_genesis_header,
+					difficulty,
+					init_relay_fee,
+					init_verify_fee,
+				)
+			})
+		}
+
+		/// Initialize the bridge to track Bitcoin mainnet from the given checkpoint, which is a
+		/// Bitcoin header in its 80 byte serialization, at the given height.
+		///
+		/// Headers must be submitted with `submit_bitcoin_header`. Their hashes and targets are
+		/// checked by Bitcoin's rules, including its difficulty retargeting, so the checkpoint must
+		/// be the first block of a difficulty epoch. Claims are still checked against the merkle
+		/// root with `MerkleProof`, not with Bitcoin's transaction merkle tree.
+		#[ink(constructor)]
+		pub fn new_bitcoin(
+			checkpoint: Vec<u8>,
+			checkpoint_height: u64,
+			init_relay_fee: Balance,
+			init_verify_fee: Balance,
+		) -> Self {
+			let checkpoint = codec::decode_bitcoin_header(&checkpoint)
+				.expect("checkpoint must be a serialized Bitcoin header");
+			let config = DeployConfig {
+				retarget: Some(BITCOIN_RETARGET),
+				..DeployConfig::new(
+					Self::from_bitcoin_header(checkpoint, checkpoint_height)
+						.expect("checkpoint must declare a valid target"),
+					U256::from_compact(BITCOIN_POW_LIMIT)
+						.expect("the PoW limit is a valid target; qed")
+						.to_be_bytes(),
+					init_relay_fee,
+					init_verify_fee,
+				)
+			};
+			Self::deploy(config, codec::bitcoin_header_hash(&checkpoint), HeaderFormat::Bitcoin)
+		}
+
+		/// Initialize the bridge for a native proof of work chain, with each parameter named in
+		/// `config`. The other constructors are shorthands for this one.
+		///
+		/// With retargeting, the checkpoint must be the first block of a difficulty epoch, and a
+		/// prune depth must be one `set_prune_depth` would accept. Bitcoin bridges are deployed
+		/// with `new_bitcoin`.
+		#[ink(constructor)]
+		pub fn new_with_config(config: DeployConfig) -> Self {
+			let checkpoint_hash =
+				codec::header_hash_with(config.hash_algorithm, &config.checkpoint);
+			Self::deploy(config, checkpoint_hash, HeaderFormat::Native)
+		}
+
+		/// Check a deployment's config and build the bridge from it.
+		fn deploy(config: DeployConfig, checkpoint_hash: HashValue, format: HeaderFormat) -> Self {
+			let checkpoint = config.checkpoint;
+			if config.check_checkpoint_pow {
+				let target = U256::from_be_bytes(checkpoint.target);
+				assert!(
+					target <= U256::from_be_bytes(config.difficulty_threshold),
+					"checkpoint target must be no easier than the difficulty"
+				);
+				assert!(
+					Self::meets_target(checkpoint_hash, checkpoint.target),
+					"checkpoint must meet its PoW target"
+				);
+			}
+			if let Some(retarget) = config.retarget {
+				assert!(
+					retarget.interval > 0 && checkpoint.height.is_multiple_of(retarget.interval),
+					"checkpoint must start a difficulty epoch"
+				);
+			}
+			assert!(
+				config
+					.prune_depth
+					.is_none_or(|depth| depth >= Self::min_prune_depth(config.retarget)),
+				"prune depth must keep the ancestors needed to validate new headers"
+			);
+
+			let mut bridge = Self::init(
+				checkpoint,
+				checkpoint_hash,
+				ChainParams {
+					difficulty_threshold: config.difficulty_threshold,
+					relay_fee: config.relay_fee,
+					verify_fee: config.verify_fee,
+					retarget: config.retarget,
+					prune_depth: config.prune_depth,
+					finality_depth: config.finality_depth,
+				},
+				format,
+			);
+			bridge.hash_algorithm = config.hash_algorithm;
+			bridge.payout_depth = config.payout_depth;
+			bridge.escrow_safety_margin = config.escrow_safety_margin;
+			if let Some(salt) = config.salt {
+				bridge.deployment_salt = salt;
+			}
+			bridge
+		}
+
+		/// Build the bridge with the given checkpoint and parameters for the default chain.
+		fn init(
+			source_genesis_header: Header,
+			checkpoint_hash: HashValue,
+			params: ChainParams,
+			header_format: HeaderFormat,
+		) -> Self {
+			assert!(
+				source_genesis_header != Header::default() && checkpoint_hash != [0; 32],
+				"checkpoint must be a real header"
+			);
+
+			let mut bridge = Self {
+				owner: Self::env().caller(),
+				pending_owner: None,
+				roles: Mapping::default(),
+				admin_count: 0,
+				signers: Vec::new(),
+				signer_threshold: 0,
+				signer_epoch: 0,
+				proposals: Mapping::default(),
+				next_proposal_id: 0,
+				paused: false,
+				storage_version: STORAGE_VERSION,
+				chains: Mapping::default(),
+				headers: Mapping::default(),
+				canon_chain: Mapping::default(),
+				fee_recipient: Mapping::default(),
+				chain_work: Mapping::default(),
+				header_provenance: Mapping::default(),
+				aux_pow_parents: Mapping::default(),
+				grandpa_states: Mapping::default(),
+				ancestor_links: Mapping::default(),
+				fork_tips: Mapping::default(),
+				children: Mapping::default(),
+				header_extensions: Mapping::default(),
+				beefy_states: Mapping::default(),
+				tendermint_states: Mapping::default(),
+				sync_committee_states: Mapping::default(),
+				sync_committee_keys: Mapping::default(),
+				headers_at_height: Mapping::default(),
+				max_proof_length: DEFAULT_MAX_PROOF_LENGTH,
+				max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+				alert_subscribers: Vec::new(),
+				alert_deposits: Mapping::default(),
+				submission_receipts: Mapping::default(),
+				next_submission_id: 0,
+				receipt_count: 0,
+				escrow_safety_margin: None,
+				payout_depth: None,
+				fee_escrows: Mapping::default(),
+				next_escrow_id: 0,
+				deployment_salt: *Self::env().account_id().as_ref(),
+				pending_rewards: Mapping::default(),
+				free_verifications: 0,
+				free_verifications_used: Mapping::default(),
+				verified_depths: Mapping::default(),
+				verified_transactions: Mapping::default(),
+				attestations: Mapping::default(),
+				subsidy_pool: 0,
+				sample_interval: None,
+				height_samples: Mapping::default(),
+				sample_count: 0,
+				challenge_period: None,
+				header_bond: 0,
+				pending_headers: Mapping::default(),
+				min_relayer_stake: None,
+				min_confirmations: 0,
+				staleness_window: None,
+				relayer_stats: Mapping::default(),
+				ranked_relayers: Vec::new(),
+				relayer_stakes: Mapping::default(),
+				allowlist_enabled: false,
+				relayer_allowlist: Mapping::default(),
+				rate_limit: None,
+				relayer_submissions: Mapping::default(),
+				block_submissions: (0, 0),
+				scheduled_checkpoint: None,
+				parameter_timelock: 0,
+				pending_changes: Vec::new(),
+				next_change_id: 0,
+				max_reorg_depth: None,
+				halt: None,
+				fee_controller: None,
+				surge_fee: 0,
+				last_header_arrival: 0,
+				treasury: 0,
+				fee_split: FeeSplit::default(),
+				burned_fees: 0,
+				credits: Mapping::default(),
+				sponsorships: Mapping::default(),
+				fee_exemptions: Mapping::default(),
+				fee_exemption_usage: Mapping::default(),
+				header_subscribers: Vec::new(),
+				xcm_destination: None,
+				pending_header_subscribers: Mapping::default(),
+				blacklisted_headers: Vec::new(),
+				wrapped_asset: None,
+				minted_deposits: Mapping::default(),
+				release_nonce: 0,
+				hash_algorithm: HashAlgorithm::default(),
+				checkpoint_header: source_genesis_header,
+				orphan_headers: Vec::new(),
+				failure_counts: Mapping::default(),
+				failure_epoch: 0,
+				test_overrides: TestOverrides::default(),
+			};
+			bridge.grant(Role::Admin, bridge.owner);
+			bridge.init_chain(
+				DEFAULT_CHAIN_ID,
+				source_genesis_header,
+				checkpoint_hash,
+				params,
+				header_format,
+			);
+			bridge
+		}
+
+		/// Start bridging another PoW source chain from the given checkpoint header. Only
+		/// admins may call this.
+		///
+		/// The new chain has its own headers, canon chain, fees and difficulty, and is
+		/// addressed by `chain_id` in the `submit_chain_headers`, `verify_chain_transaction` and
+		/// `verify_chain_state` messages. Its parameters are fixed at registration, apart from
+		/// its prune and finality depths, see `set_chain_prune_depth` and
+		/// `set_chain_finality_depth`. The prune depth must be one `set_prune_depth` would accept.
+		#[ink(message)]
+		pub fn register_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: Header,
+			params: ChainParams,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.register_chain_as(chain_id, checkpoint, self.header_hash(checkpoint), params)
+		}
+
+		/// Like `register_chain`, for a checkpoint identified by the given hash.
+		fn register_chain_as(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: Header,
+			checkpoint_hash: HashValue,
+			params: ChainParams,
+		) -> Result<()> {
+			if self.chains.contains(chain_id) {
+				return Err(Error::ChainAlreadyRegistered)
+			}
+			if checkpoint == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			if checkpoint_hash == [0; 32] {
+				return Err(Error::ZeroHash)
+			}
+			if params.retarget.is_some_and(|retarget| {
+				retarget.interval == 0 || !checkpoint.height.is_multiple_of(retarget.interval)
+			}) {
+				return Err(Error::InvalidCheckpoint)
+			}
+			if params
+				.prune_depth
+				.is_some_and(|depth| depth < Self::min_prune_depth(params.retarget))
+			{
+				return Err(Error::InvalidPruneDepth)
+			}
+
+			self.init_chain(chain_id, checkpoint, checkpoint_hash, params, HeaderFormat::Native);
+			self.env().emit_event(ChainRegistered {
+				chain_id,
+				checkpoint_hash,
+				checkpoint_height: checkpoint.height,
+			});
+
+			Ok(())
+		}
+
+		/// Start bridging a Substrate chain finalized by GRANDPA from the given checkpoint header.
+		/// Only admins may call this.
+		///
+		/// Instead of being relayed one by one, the chain's headers are accepted with a
+		/// justification by the authorities of set `set_id`, see `submit_grandpa_header`, and are
+		/// final straight away. The set must be the one that finalizes the blocks after the
+		/// checkpoint. Later sets are enacted by the headers that schedule them. Headers are
+		/// stored under their Substrate hash, and the checkpoint's difficulty parameters and
+		/// prune depth are unused.
+		#[ink(message)]
+		pub fn register_grandpa_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: SubstrateHeader,
+			params: ChainParams,
+			set_id: u64,
+			authorities: Vec<([u8; 32], u64)>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if authorities.iter().all(|(_, weight)| *weight == 0) {
+				return Err(Error::InsufficientSignatures)
+			}
+			let header = checkpoint.to_header();
+			self.register_chain_as(
+				chain_id,
+				header,
+				codec::substrate_header_hash(&checkpoint),
+				ChainParams { retarget: None, prune_depth: None, ..params },
+			)?;
+
+			let mut chain = self.load_chain(chain_id)?;
+			chain.consensus = ConsensusBackend::Grandpa;
+			self.chains.insert(chain_id, &chain);
+			let state = GrandpaState { set_id, authorities, enacted_at: header.height };
+			self.grandpa_states.insert(chain_id, &state);
+
+			Ok(())
+		}
+
+		/// Submit a header of a GRANDPA chain, in its Substrate encoding, with a justification by
+		/// the chain's current authorities. The relayer must pay the relay fee.
+		///
+		/// The justification's commit must be for the header. Each precommit must be signed by
+		/// an authority of the current set, over `codec::grandpa_precommit_payload` in the
+		/// justification's round, and be for the header or a descendant of it among the
+		/// justification's ancestry headers. The authorities that signed must hold more than two
+		/// thirds of the set's weight.
+		///
+		/// Authority set changes are read from the header's digest, as in Substrate's bridge
+		/// pallet: a header whose GRANDPA log schedules a change without delay enacts the next
+		/// set, whose id is one more, and which must justify the headers after it. Headers with
+		/// delayed or forced changes are rejected, since the bridge could not tell which header
+		/// enacts them. The header's parent need not be known: a justified header is final, so it
+		/// becomes canon and finalized at its height straight away.
+		#[ink(message, payable)]
+		pub fn submit_grandpa_header(
+			&mut self,
+			chain_id: ChainId,
+			header: SubstrateHeader,
+			justification: GrandpaJustification,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let mut chain = self.load_chain(chain_id)?;
+			let mut state = self.grandpa_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			self.charge_relay_fee(chain.relay_fee)?;
+			let header_hash = codec::substrate_header_hash(&header);
+			if self.headers.contains((chain_id, header_hash)) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			// Headers up to the current set's handoff were justified by an earlier set.
+			if u64::from(header.number) <= state.enacted_at {
+				return Err(Error::UnknownValidatorSet)
+			}
+
+			let commit = &justification.commit;
+			if commit.target_hash != header_hash || commit.target_number != header.number {
+				return Err(Error::InvalidJustification)
+			}
+			if commit.precommits.len() > state.authorities.len() ||
+				justification.votes_ancestries.len() > self.max_proof_length as usize
+			{
+				return Err(Error::ProofTooLarge)
+			}
+			let signers = finality::grandpa_signers(&state, &justification)?;
+			let weight = |(_, weight): &([u8; 32], u64)| u128::from(*weight);
+			let signed: u128 =
+				signers.iter().map(|signer| weight(&state.authorities[*signer])).sum();
+			let total: u128 = state.authorities.iter().map(weight).sum();
+			// GRANDPA's threshold: more than two thirds of the weight.
+			if 3 * signed <= 2 * total {
+				return Err(Error::InsufficientSignatures)
+			}
+			let change = finality::grandpa_authority_change(&header)?;
+
+			self.store_final_header(chain_id, &mut chain, header.to_header(), header_hash)?;
+			if let Some(change) = change {
+				state.set_id += 1;
+				state.authorities = change.next_authorities;
+				state.enacted_at = header.number.into();
+				self.grandpa_states.insert(chain_id, &state);
+				self.env().emit_event(GrandpaAuthoritiesChanged {
+					chain_id,
+					set_id: state.set_id,
+					enacted_at: state.enacted_at,
+				});
+			}
+			Ok(())
+		}
+
+		/// The GRANDPA light client state of the given source chain, if it uses GRANDPA.
+		#[ink(message)]
+		pub fn grandpa_state(&self, chain_id: ChainId) -> Option<GrandpaState> {
+			self.grandpa_states.get(chain_id)
+		}
+
+		/// Store a header that a finality backend has proven final, making it canon and finalized
+		/// at its height. Its parent need not be known; if it is, the header is linked to it like a
+		/// relayed header. The caller is its relayer.
+		fn store_final_header(
+			&mut self,
+			chain_id: ChainId,
+			chain: &mut SourceChain,
+			header: Header,
+			header_hash: HashValue,
+		) -> Result<()> {
+			if header.height <= chain.checkpoint_height ||
+				self.canon_chain.contains((chain_id, header.height))
+			{
+				return Err(Error::ConflictsWithFinalized)
+			}
+			if self.is_blacklisted(chain_id, header_hash) ||
+				self.is_blacklisted(chain_id, header.parent)
+			{
+				return Err(Error::BlacklistedHeader)
+			}
+
+			let submitter = self.caller();
+			self.headers.insert((chain_id, header_hash), &header);
+			self.fee_recipient.insert((chain_id, header_hash), &submitter);
+			self.link_header(chain_id, &header, header_hash);
+			self.canon_chain.insert((chain_id, header.height), &header_hash);
+			self.headers_at_height
+				.insert((chain_id, header.height), &Vec::from([header_hash]));
+			chain.header_count += 1;
+			chain.last_header_block = self.current_block();
+			if header.height > chain.best_height {
+				// Final headers don't fork, so the best one is the only tip.
+				self.fork_tips.insert(chain_id, &Vec::from([header_hash]));
+				chain.best_height = header.height;
+				chain.finalized_height = header.height;
+				chain.last_tip_update = self.now();
+			}
+			self.chains.insert(chain_id, chain);
+
+			let submission_id = self.next_submission_id;
+			self.next_submission_id += 1;
+			let receipt =
+				SubmissionReceipt { id: submission_id, block_number: self.current_block() };
+			self.submission_receipts.insert((submitter, header_hash), &receipt);
+			self.receipt_count += 1;
+			self.env().emit_event(HeaderSubmitted {
+				submission_id,
+				chain_id,
+				block_hash: header_hash,
+				block_height: header.height,
+				submitter,
+			});
+			self.env().emit_event(CanonBlockAdded {
+				chain_id,
+				block_hash: header_hash,
+				block_height: header.height,
+			});
+			self.record_relayed_header(submitter);
+			self.update_relayer_stats(submitter, |stats| stats.canon_headers += 1);
+
+			Ok(())
+		}
+
+		/// Start bridging a finalized Substrate chain with BEEFY from the given checkpoint header.
+		/// Only admins may call this.
+		///
+		/// Instead of being relayed one by one, the chain's headers are proven against the MMR
+		/// root of the latest commitment signed by its authorities, see `submit_beefy_commitment`
+		/// and `submit_mmr_header`. The checkpoint's difficulty parameters and prune depth are
+		/// unused.
+		#[ink(message)]
+		pub fn register_beefy_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: Header,
+			params: ChainParams,
+			validator_set_id: u64,
+			authorities: Vec<[u8; 33]>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if authorities.is_empty() {
+				return Err(Error::InsufficientSignatures)
+			}
+			self.register_chain(
+				chain_id,
+				checkpoint,
+				ChainParams { retarget: None, prune_depth: None, ..params },
+			)?;
+
+			let mut chain = self.load_chain(chain_id)?;
+			chain.consensus = ConsensusBackend::Beefy;
+			self.chains.insert(chain_id, &chain);
+			let state =
+				BeefyState { validator_set_id, authorities, mmr_root: [0; 32], block_number: 0 };
+			self.beefy_states.insert(chain_id, &state);
+
+			Ok(())
+		}
+
+		/// Replace the authority set of a BEEFY chain, on a handoff to the next set. Only
+		/// admins may call this.
+		///
+		/// The new set's id must be greater than the current one. Commitments signed by the
+		/// previous set are no longer accepted.
+		#[ink(message)]
+		pub fn set_beefy_authorities(
+			&mut self,
+			chain_id: ChainId,
+			validator_set_id: u64,
+			authorities: Vec<[u8; 33]>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let mut state = self.beefy_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			if validator_set_id <= state.validator_set_id {
+				return Err(Error::UnknownValidatorSet)
+			}
+			if authorities.is_empty() {
+				return Err(Error::InsufficientSignatures)
+			}
+			state.validator_set_id = validator_set_id;
+			state.authorities = authorities;
+			self.beefy_states.insert(chain_id, &state);
+			Ok(())
+		}
+
+		/// Submit a BEEFY commitment signed by the chain's current authorities, making its MMR
+		/// root the one that headers are proven against.
+		///
+		/// `signatures` has one entry per authority, in the order of the authority set. More
+		/// than two thirds of the authorities must have signed, and every given signature must
+		/// be valid. The commitment must be for a later block than the latest accepted one.
+		#[ink(message)]
+		pub fn submit_beefy_commitment(
+			&mut self,
+			chain_id: ChainId,
+			commitment: BeefyCommitment,
+			signatures: Vec<Option<[u8; 65]>>,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let mut state = self.beefy_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			if commitment.validator_set_id != state.validator_set_id {
+				return Err(Error::UnknownValidatorSet)
+			}
+			if commitment.block_number <= state.block_number {
+				return Err(Error::StaleCommitment)
+			}
+			let mmr_root = commitment
+				.payload
+				.iter()
+				.find(|(id, _)| *id == BEEFY_MMR_ROOT_ID)
+				.and_then(|(_, root)| HashValue::try_from(root.as_slice()).ok())
+				.ok_or(Error::MissingMmrRoot)?;
+
+			let signed = finality::beefy_signers(&state.authorities, &commitment, &signatures)?;
+			// BEEFY's threshold: more than two thirds of the authorities.
+			let authorities = state.authorities.len();
+			if signed < authorities - (authorities - 1) / 3 {
+				return Err(Error::InsufficientSignatures)
+			}
+
+			state.mmr_root = mmr_root;
+			state.block_number = commitment.block_number;
+			self.beefy_states.insert(chain_id, &state);
+			self.env().emit_event(BeefyCommitmentAccepted {
+				chain_id,
+				block_number: commitment.block_number,
+				mmr_root,
+			});
+
+			Ok(())
+		}
+
+		/// Submit a header of a BEEFY chain with a proof that it is a leaf of the MMR whose root
+		/// was last signed, see `submit_beefy_commitment`. The relayer must pay the relay fee.
+		///
+		/// The header's leaf is `codec::mmr_leaf_hash`. Its parent need not be known: a proven
+		/// header is final, so it becomes canon and finalized at its height straight away.
+		#[ink(message, payable)]
+		pub fn submit_mmr_header(
+			&mut self,
+			chain_id: ChainId,
+			header: Header,
+			proof: MmrProof,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let mut chain = self.load_chain(chain_id)?;
+			let state = self.beefy_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			self.charge_relay_fee(chain.relay_fee)?;
+			if header == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			let header_hash = self.header_hash(header);
+			if self.headers.contains((chain_id, header_hash)) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			if proof.items.len() > self.max_proof_length as usize {
+				return Err(Error::ProofTooLarge)
+			}
+			let root = mmr::root_from_proof(
+				codec::mmr_leaf_hash(&header),
+				proof.leaf_index,
+				proof.leaf_count,
+				&proof.items,
+			);
+			if root != Some(state.mmr_root) {
+				return Err(Error::InvalidMmrProof)
+			}
+
+			self.store_final_header(chain_id, &mut chain, header, header_hash)
+		}
+
+		/// The BEEFY light client state of the given source chain, if it uses BEEFY.
+		#[ink(message)]
+		pub fn beefy_state(&self, chain_id: ChainId) -> Option<BeefyState> {
+			self.beefy_states.get(chain_id)
+		}
+
+		/// Start bridging a Cosmos SDK chain finalized by Tendermint from the given checkpoint
+		/// header. Only admins may call this.
+		///
+		/// `validators` must be the set that the checkpoint's next validators hash commits to.
+		/// Headers are accepted with a commit signed by the trusted validators, see
+		/// `submit_tendermint_header`, and are final straight away. Headers are stored under
+		/// their Tendermint hash, and the checkpoint's difficulty parameters and prune depth are
+		/// unused.
+		#[ink(message)]
+		pub fn register_tendermint_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: TendermintHeader,
+			params: ChainParams,
+			validators: Vec<([u8; 32], u64)>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if validators.iter().all(|(_, power)| *power == 0) {
+				return Err(Error::InsufficientSignatures)
+			}
+			if tendermint::validators_hash(&validators) != checkpoint.next_validators_hash {
+				return Err(Error::ValidatorSetMismatch)
+			}
+			let header = checkpoint.to_header();
+			self.register_chain_as(
+				chain_id,
+				header,
+				tendermint::header_hash(&checkpoint),
+				ChainParams { retarget: None, prune_depth: None, ..params },
+			)?;
+
+			let mut chain = self.load_chain(chain_id)?;
+			chain.consensus = ConsensusBackend::Tendermint;
+			self.chains.insert(chain_id, &chain);
+			let state = TendermintState {
+				network: checkpoint.chain_id,
+				validators,
+				trusted_height: header.height,
+			};
+			self.tendermint_states.insert(chain_id, &state);
+
+			Ok(())
+		}
+
+		/// Submit a header of a Tendermint chain with a commit to it. The relayer must pay the
+		/// relay fee.
+		///
+		/// `validators` is the set that signed the commit, which the header's validators hash
+		/// must commit to. Each signature must be the validator's signature of
+		/// `tendermint::vote_sign_bytes` for the header's block id, at its height, in the commit's
+		/// round and at the vote's time. The validators that signed must hold more than two thirds
+		/// of the set's voting power.
+		///
+		/// When the set is not the trusted one, as in Tendermint's skipping verification, the
+		/// validators that signed must also hold more than a third of the trusted set's power.
+		/// The header's next validators then become the trusted set: `next_validators`, which
+		/// must match the header's next validators hash, or otherwise its own validators if they
+		/// are the same set. The header's parent need not be known: a committed header is final,
+		/// so it becomes canon and finalized at its height straight away.
+		#[ink(message, payable)]
+		pub fn submit_tendermint_header(
+			&mut self,
+			chain_id: ChainId,
+			header: TendermintHeader,
+			commit: TendermintCommit,
+			validators: Vec<([u8; 32], u64)>,
+			next_validators: Option<Vec<([u8; 32], u64)>>,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let mut chain = self.load_chain(chain_id)?;
+			let mut state = self.tendermint_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			self.charge_relay_fee(chain.relay_fee)?;
+			if header.chain_id != state.network {
+				return Err(Error::MalformedHeader)
+			}
+			let header_hash = tendermint::header_hash(&header);
+			if self.headers.contains((chain_id, header_hash)) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			// Headers up to the trusted one may have been committed by another set.
+			if header.height <= state.trusted_height {
+				return Err(Error::UnknownValidatorSet)
+			}
+			if tendermint::validators_hash(&validators) != header.validators_hash {
+				return Err(Error::ValidatorSetMismatch)
+			}
+			let next_validators = match next_validators {
+				Some(next) if tendermint::validators_hash(&next) == header.next_validators_hash =>
+					next,
+				None if header.next_validators_hash == header.validators_hash => validators.clone(),
+				_ => return Err(Error::ValidatorSetMismatch),
+			};
+
+			let signers = finality::tendermint_signers(
+				&state.network,
+				&header,
+				&header_hash,
+				&commit,
+				&validators,
+			)?;
+			// Tendermint's threshold: more than two thirds of the voting power.
+			if 3 * finality::voting_power(signers.iter().copied()) <=
+				2 * finality::voting_power(&validators)
+			{
+				return Err(Error::InsufficientSignatures)
+			}
+			if validators != state.validators {
+				let trusted_signers = state.validators.iter().filter(|(public_key, _)| {
+					signers.iter().any(|(signer, _)| signer == public_key)
+				});
+				if 3 * finality::voting_power(trusted_signers) <=
+					finality::voting_power(&state.validators)
+				{
+					return Err(Error::UntrustedValidatorSet)
+				}
+			}
+
+			self.store_final_header(chain_id, &mut chain, header.to_header(), header_hash)?;
+			let changed = next_validators != state.validators;
+			state.validators = next_validators;
+			state.trusted_height = header.height;
+			self.tendermint_states.insert(chain_id, &state);
+			if changed {
+				self.env().emit_event(TendermintValidatorsChanged {
+					chain_id,
+					trusted_height: header.height,
+				});
+			}
+			Ok(())
+		}
+
+		/// The Tendermint light client state of the given source chain, if it uses Tendermint.
+		#[ink(message)]
+		pub fn tendermint_state(&self, chain_id: ChainId) -> Option<TendermintState> {
+			self.tendermint_states.get(chain_id)
+		}
+
+		/// Start bridging the Ethereum beacon chain with a sync committee light client, from the
+		/// given checkpoint header. Only admins may call this.
+		///
+		/// A header's height is its slot, and it is stored under its beacon block root. Headers
+		/// are accepted once signed by at least two thirds of the sync committee of the signature
+		/// slot's period, see `submit_sync_committee_header`, and are final straight away.
+		/// `committee_branch` proves `committee_root` to be the checkpoint state's current sync
+		/// committee, which serves the checkpoint's period; each later one is proven by a header
+		/// of the period before it. `forks` is the chain's fork schedule, in order of epoch, whose
+		/// first fork must be in effect at the checkpoint; later forks can be added with
+		/// `add_beacon_fork`. The checkpoint's difficulty parameters and prune depth are unused.
+		#[ink(message)]
+		#[allow(clippy::too_many_arguments)]
+		pub fn register_sync_committee_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: BeaconBlockHeader,
+			params: ChainParams,
+			genesis_validators_root: HashValue,
+			forks: Vec<BeaconFork>,
+			committee_root: HashValue,
+			committee_branch: Vec<HashValue>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if forks.first().is_none_or(|fork| fork.epoch > checkpoint.slot / SLOTS_PER_EPOCH) ||
+				forks.windows(2).any(|pair| pair[0].epoch >= pair[1].epoch)
+			{
+				return Err(Error::InvalidForkSchedule)
+			}
+			let state = SyncCommitteeState {
+				genesis_validators_root,
+				forks,
+				period: checkpoint.slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD,
+				committee_root,
+				next_committee_root: None,
+			};
+			if !state.proves_committee(
+				&checkpoint,
+				&committee_root,
+				&committee_branch,
+				CURRENT_SYNC_COMMITTEE_INDEX,
+			) {
+				return Err(Error::InvalidCommitteeProof)
+			}
+			self.register_chain_as(
+				chain_id,
+				checkpoint.to_header(),
+				codec::beacon_header_root(&checkpoint),
+				ChainParams { retarget: None, prune_depth: None, ..params },
+			)?;
+
+			let mut chain = self.load_chain(chain_id)?;
+			chain.consensus = ConsensusBackend::SyncCommittee;
+			self.chains.insert(chain_id, &chain);
+			self.sync_committee_states.insert(chain_id, &state);
+
+			Ok(())
+		}
+
+		/// Schedule a fork of a sync committee chain, after all its known forks. Only admins may
+		/// call this.
+		#[ink(message)]
+		pub fn add_beacon_fork(&mut self, chain_id: ChainId, fork: BeaconFork) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let mut state =
+				self.sync_committee_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			if state.forks.last().is_some_and(|last| fork.epoch <= last.epoch) {
+				return Err(Error::InvalidForkSchedule)
+			}
+			state.forks.push(fork);
+			self.sync_committee_states.insert(chain_id, &state);
+			Ok(())
+		}
+
+		/// Submit one chunk of the public keys of a sync committee chain's current or next
+		/// committee, which must all be submitted before headers can be checked against the
+		/// committee's signatures.
+		///
+		/// `keys` are the `SYNC_COMMITTEE_KEYS_PER_CHUNK` members starting at position
+		/// `chunk * SYNC_COMMITTEE_KEYS_PER_CHUNK`. `branch` is the SSZ merkle branch from the
+		/// chunk's root to the committee root, from the leaf up, ending with the root of the
+		/// committee's aggregate public key. Anyone may submit keys, since they are proven.
+		#[ink(message)]
+		pub fn submit_sync_committee_keys(
+			&mut self,
+			chain_id: ChainId,
+			committee_root: HashValue,
+			chunk: u32,
+			keys: Vec<[u8; 48]>,
+			branch: Vec<HashValue>,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let state = self.sync_committee_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			if committee_root != state.committee_root &&
+				Some(committee_root) != state.next_committee_root
+			{
+				return Err(Error::UnknownValidatorSet)
+			}
+			// The chunks are the subtrees of the committee's public keys vector, which is the left
+			// child of the committee root.
+			let chunks = SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_KEYS_PER_CHUNK;
+			if chunk >= chunks ||
+				keys.len() != SYNC_COMMITTEE_KEYS_PER_CHUNK as usize ||
+				branch.len() != chunks.ilog2() as usize + 1
+			{
+				return Err(Error::InvalidCommitteeProof)
+			}
+			let leaves: Vec<_> = keys.iter().map(codec::ssz_public_key_root).collect();
+			let chunk_root = codec::ssz_merkleize(&leaves);
+			if codec::ssz_branch_root(&chunk_root, &branch, chunk.into()) != committee_root {
+				return Err(Error::InvalidCommitteeProof)
+			}
+
+			self.sync_committee_keys.insert((committee_root, chunk), &keys);
+			Ok(())
+		}
+
+		/// Submit a header of a sync committee chain signed by its committee. The relayer must pay
+		/// the relay fee.
+		///
+		/// `participation` is the SSZ bitvector of the committee members who signed, and
+		/// `signature` their aggregate BLS signature of `codec::sync_committee_signing_root`,
+		/// made at `signature_slot`, which must be later than the header's slot. The signature is
+		/// checked under the version of the fork in effect at the slot before `signature_slot`,
+		/// as in the consensus specs. At least two thirds of the committee must have signed. As
+		/// in the Altair light client, the signing committee is the one of `signature_slot`'s
+		/// period: a signature from the period after the current committee's is made by the next
+		/// committee, which then becomes current. With `next_committee`, a header of the signing
+		/// committee's period also proves the root of the committee of the period after, against
+		/// its state root. The header's parent need not be known: a signed header is final, so it
+		/// becomes canon and finalized at its height straight away.
+		#[ink(message, payable)]
+		pub fn submit_sync_committee_header(
+			&mut self,
+			chain_id: ChainId,
+			header: BeaconBlockHeader,
+			signature_slot: u64,
+			participation: Vec<u8>,
+			signature: [u8; 96],
+			next_committee: Option<NextSyncCommittee>,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let mut chain = self.load_chain(chain_id)?;
+			let mut state =
+				self.sync_committee_states.get(chain_id).ok_or(Error::WrongConsensus)?;
+			self.charge_relay_fee(chain.relay_fee)?;
+			let header_hash = codec::beacon_header_root(&header);
+			if self.headers.contains((chain_id, header_hash)) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			if signature_slot <= header.slot {
+				return Err(Error::IncorrectHeight)
+			}
+
+			let retired_committee_root = state.committee_root;
+			let period = signature_slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+			let rotated = period == state.period + 1;
+			if rotated {
+				state.committee_root =
+					state.next_committee_root.ok_or(Error::UnknownValidatorSet)?;
+				state.period = period;
+				state.next_committee_root = None;
+			} else if period != state.period {
+				return Err(Error::UnknownValidatorSet)
+			}
+
+			if participation.len() != SYNC_COMMITTEE_SIZE as usize / 8 {
+				return Err(Error::InsufficientSignatures)
+			}
+			let participants = self.sync_committee_participants(&state, &participation)?;
+			if 3 * participants.len() < 2 * SYNC_COMMITTEE_SIZE as usize {
+				return Err(Error::InsufficientSignatures)
+			}
+			let fork = state.fork_at(signature_slot - 1);
+			let signing_root = codec::sync_committee_signing_root(
+				&header_hash,
+				fork.version,
+				&state.genesis_validators_root,
+			);
+			if !crypto::bls_fast_aggregate_verify(&participants, &signing_root, &signature) {
+				return Err(Error::InvalidSignature)
+			}
+
+			if let Some(next_committee) = next_committee {
+				// A header of the previous period would prove the signing committee itself.
+				if header.slot / SLOTS_PER_SYNC_COMMITTEE_PERIOD != period ||
+					!state.proves_committee(
+						&header,
+						&next_committee.root,
+						&next_committee.branch,
+						NEXT_SYNC_COMMITTEE_INDEX,
+					) {
+					return Err(Error::InvalidCommitteeProof)
+				}
+				state.next_committee_root = Some(next_committee.root);
+			}
+
+			self.store_final_header(chain_id, &mut chain, header.to_header(), header_hash)?;
+			self.sync_committee_states.insert(chain_id, &state);
+			if rotated {
+				for chunk in 0..SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_KEYS_PER_CHUNK {
+					self.sync_committee_keys.remove((retired_committee_root, chunk));
+				}
+				self.env().emit_event(SyncCommitteeRotated {
+					chain_id,
+					period,
+					committee_root: state.committee_root,
+				});
+			}
+			Ok(())
+		}
+
+		/// The sync committee light client state of the given source chain, if it follows a
+		/// sync committee.
+		#[ink(message)]
+		pub fn sync_committee_state(&self, chain_id: ChainId) -> Option<SyncCommitteeState> {
+			self.sync_committee_states.get(chain_id)
+		}
+
+		/// The public keys of the current sync committee's members whose bits are set in the
+		/// participation bitvector.
+		fn sync_committee_participants(
+			&self,
+			state: &SyncCommitteeState,
+			participation: &[u8],
+		) -> Result<Vec<[u8; 48]>> {
+			let mut participants = Vec::new();
+			for chunk in 0..SYNC_COMMITTEE_SIZE / SYNC_COMMITTEE_KEYS_PER_CHUNK {
+				let keys = self
+					.sync_committee_keys
+					.get((state.committee_root, chunk))
+					.ok_or(Error::MissingCommitteeKeys)?;
+				let first = (chunk * SYNC_COMMITTEE_KEYS_PER_CHUNK) as usize;
+				participants.extend(keys.into_iter().enumerate().filter_map(|(i, key)| {
+					let member = first + i;
+					(participation[member / 8] >> (member % 8) & 1 == 1).then_some(key)
+				}));
+			}
+			Ok(participants)
+		}
+
+		/// Store a source chain's checkpoint header and initial state.
+		fn init_chain(
+			&mut self,
+			chain_id: ChainId,
+			checkpoint: Header,
+			h: HashValue,
+			params: ChainParams,
+			header_format: HeaderFormat,
+		) {
+			// Put the header in storage under its hash
+			self.headers.insert((chain_id, h), &checkpoint);
+
+			// Update other storages
+			let best_height = checkpoint.height;
+			self.canon_chain.insert((chain_id, best_height), &h);
+			self.headers_at_height.insert((chain_id, best_height), &Vec::from([h]));
+			self.fork_tips.insert(chain_id, &Vec::from([h]));
+
+			// Record the caller as the fee recipient for the checkpoint block
+			self.fee_recipient.insert((chain_id, h), &self.caller());
+
+			// Work is only accumulated from the checkpoint onward
+			self.chain_work
+				.insert((chain_id, h), &Self::work_from_target(checkpoint.target));
+
+			let chain = SourceChain {
+				best_height,
+				difficulty_threshold: params.difficulty_threshold,
+				retarget: params.retarget,
+				relay_fee: params.relay_fee,
+				verify_fee: params.verify_fee,
+				block_time_ema: None,
+				last_tip_update: self.now(),
+				last_header_block: self.current_block(),
+				pruned_height: best_height,
+				finalized_height: best_height,
+				finality_depth: params.finality_depth,
+				prune_depth: params.prune_depth,
+				checkpoint_height: best_height,
+				header_count: 1,
+				orphan_count: 0,
+				header_format,
+				merged_mining: false,
+				consensus: ConsensusBackend::ProofOfWork,
+			};
+			self.chains.insert(chain_id, &chain);
+		}
+
+		/// Submit a new source chain block header to the bridge for verification.
+		/// In order for the new header to be valid, these conditions must be met:
+		/// 0. The relayer must pay the relay fee (which goes to the treasury).
+		/// 1. The header must not already be in the db
+		/// 2. The header's parent must already be in the db
+		/// 3. The header's height must be one more than it's parent
+		/// 4. The header's timestamp must be later than the median timestamp of its last
+		///    `MEDIAN_TIME_SPAN` ancestors, and no more than `MAX_FUTURE_BLOCK_TIME` ahead of the
+		///    target chain's block timestamp
+		/// 5. The header's PoW target must be no easier than the difficulty threshold, and follow
+		///    the retargeting algorithm if one is configured
+		/// 6. The header's hash must satisfy its PoW target
+		/// 7. If pruning is enabled, the header must be no more than `prune_depth` blocks behind
+		///    the tip
+		///
+		/// A header whose parent is not known yet is held for up to `ORPHAN_HEADER_TTL` target
+		/// chain blocks instead, as long as it meets its PoW target and is no more than
+		/// `MAX_ORPHAN_HEADERS` blocks ahead of the tip's child. Each relayer may have at most
+		/// `MAX_ORPHAN_HEADERS_PER_RELAYER` headers held, and a held header counts towards their
+		/// rate limit. It is validated and accepted, on behalf of its relayer, as soon as its
+		/// parent is. Headers whose parent could never become canon still fail with
+		/// `UnknownParent`.
+		///
+		/// Once the block is validated you must determine whether this causes
+		/// a re-org or not, and update storage accordingly. The canon chain is the one with the
+		/// most cumulative work, which is not necessarily the longest one.
+		///
+		/// The relay fee does not go to anyone. It is added to the treasury, which only the owner
+		/// can spend, see `spend_treasury`. Any value transferred beyond the fee is refunded, here
+		/// and in the other submission and verification messages.
+		#[ink(message, payable)]
+		pub fn submit_new_header(&mut self, header: Header) -> Result<()> {
+			self.ensure_not_paused()?;
+			self.charge_relay_fee(self.current_relay_fee())?;
+
+			self.accept_or_pool(header, None)?;
+			Ok(())
+		}
+
+		/// The default chain headers waiting for their parent, oldest first. Expired headers may
+		/// be listed until the pool is next changed.
+		#[ink(message)]
+		pub fn orphan_headers(&self) -> Vec<OrphanHeader> {
+			self.orphan_headers.clone()
+		}
+
+		/// Submit a new source chain block header in a versioned envelope, like
+		/// `submit_new_header`.
+		///
+		/// The header is stored under the hash of its version's encoding, and so is found by that
+		/// hash for verifications and as its children's parent. Extension bytes are stored with
+		/// it, until it is pruned.
+		#[ink(message, payable)]
+		pub fn submit_versioned_header(&mut self, header: VersionedHeader) -> Result<()> {
+			self.ensure_not_paused()?;
+			self.charge_relay_fee(self.current_relay_fee())?;
+
+			let VersionedHeader::V1(HeaderV1 { header, extension }) = header;
+			if header == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			let header_hash = codec::header_v1_hash(self.hash_algorithm, &header, &extension);
+			self.accept_header_as(
+				DEFAULT_CHAIN_ID,
+				header,
+				header_hash,
+				HeaderFormat::Native,
+				None,
+				self.caller(),
+			)?;
+			if !extension.is_empty() {
+				self.header_extensions.insert((DEFAULT_CHAIN_ID, header_hash), &extension);
+			}
+
+			Ok(())
+		}
+
+		/// Submit a new source chain block header, like `submit_new_header`, along with the
+		/// relayer's signature over its hash.
+		///
+		/// The signature is a recoverable ECDSA signature, and the recovered key must be the one
+		/// the caller's account is derived from. It is stored as non-repudiable evidence of who
+		/// asserted the header, for slashing, insurance and off-chain arbitration, until the header
+		/// is pruned. A header that is pooled until its parent arrives has its provenance stored
+		/// once it is accepted. See `header_provenance`.
+		#[ink(message, payable)]
+		pub fn submit_signed_header(&mut self, header: Header, signature: [u8; 65]) -> Result<()> {
+			self.ensure_not_paused()?;
+			self.charge_relay_fee(self.current_relay_fee())?;
+			let relayer = self.caller();
+			let signer = self.recover_signer(self.header_hash(header), &signature, relayer)?;
+
+			let provenance = HeaderProvenance { relayer, signer, signature };
+			if let Some(header_hash) = self.accept_or_pool(header, Some(provenance))? {
+				self.header_provenance.insert((DEFAULT_CHAIN_ID, header_hash), &provenance);
+			}
+
+			Ok(())
+		}
+
+		/// Submit a new header of a merge mined source chain, like `submit_new_header`, with a
+		/// proof that its work was done by a parent chain block.
+		///
+		/// The header's own hash need not meet its target. Instead the parent block's hash must,
+		/// and the parent block's coinbase transaction must commit to the header's hash. The
+		/// owner must have enabled `BridgeParameter::MergedMining`.
+		#[ink(message, payable)]
+		pub fn submit_aux_pow_header(&mut self, header: Header, aux_pow: AuxPow) -> Result<()> {
+			self.ensure_not_paused()?;
+			self.charge_relay_fee(self.current_relay_fee())?;
+
+			self.accept_header_as(
+				DEFAULT_CHAIN_ID,
+				header,
+				self.header_hash(header),
+				HeaderFormat::Native,
+				Some(&aux_pow),
+				self.caller(),
+			)?;
+
+			Ok(())
+		}
+
+		/// Submit a new Bitcoin header, in its 80 byte serialization, to a bridge deployed with
+		/// `new_bitcoin`.
+		///
+		/// The header is validated as in `submit_new_header`. Its height is one more than its
+		/// parent's, and its hash and target are computed by Bitcoin's rules.
+		#[ink(message, payable)]
+		pub fn submit_bitcoin_header(&mut self, header: Vec<u8>) -> Result<()> {
+			self.ensure_not_paused()?;
+			self.charge_relay_fee(self.current_relay_fee())?;
+			let header =
+				codec::decode_bitcoin_header(&header).map_err(|_| Error::MalformedHeader)?;
+			let header_hash = codec::bitcoin_header_hash(&header);
+			let mut parent_hash = header.prev_block_hash;
+			parent_hash.reverse();
+			let parent =
+				self.headers.get((DEFAULT_CHAIN_ID, parent_hash)).ok_or(Error::UnknownParent)?;
+			let converted = Self::from_bitcoin_header(header, parent.height + 1)
+				.ok_or(Error::InvalidDifficultyTarget)?;
+
+			self.accept_header_as(
+				DEFAULT_CHAIN_ID,
+				converted,
+				header_hash,
+				HeaderFormat::Bitcoin,
+				None,
+				self.caller(),
+			)?;
+
+			Ok(())
+		}
+
+		/// Submit a contiguous segment of source chain headers in a single call.
+		///
+		/// Each header after the first must be the child of the one before it. Every header is
+		/// validated, or pooled until its parent arrives, as in `submit_new_header`, and the relay
+		/// fee is charged once per header.
+		///
+		/// At most `max_batch_size` headers may be submitted at once.
+		/// If any header is invalid the whole batch is rejected. Like any message returning an
+		/// error, all changes made by the call are then reverted.
+		#[ink(message, payable)]
+		pub fn submit_headers(&mut self, headers: Vec<Header>) -> Result<()> {
+			self.submit_chain_headers(DEFAULT_CHAIN_ID, headers)
+		}
+
+		/// Submit a contiguous segment of headers of the given source chain, like
+		/// `submit_headers`. The relay fee is the one the chain was registered with. Only default
+		/// chain headers are pooled, the first header of any other chain must have a known parent.
+		#[ink(message, payable)]
+		pub fn submit_chain_headers(
+			&mut self,
+			chain_id: ChainId,
+			headers: Vec<Header>,
+		) -> Result<()> {
+			self.ensure_not_paused()?;
+			let chain = self.load_chain(chain_id)?;
+			if headers.len() > self.max_batch_size as usize {
+				return Err(Error::BatchTooLarge)
+			}
+			let required_fee =
+				self.relay_fee_of(chain_id, &chain).saturating_mul(headers.len() as Balance);
+			self.charge_relay_fee(required_fee)?;
+
+			if headers.windows(2).any(|pair| pair[1].parent != self.header_hash(pair[0])) {
+				return Err(Error::NonContiguousBatch)
+			}
+			for header in headers {
+				if chain_id == DEFAULT_CHAIN_ID {
+					self.accept_or_pool(header, None)?;
+				} else {
+					self.accept_header(chain_id, header)?;
+				}
+			}
+
+			Ok(())
+		}
+
+		/// Submit a new default chain header optimistically. The owner must have set a challenge
+		/// period.
+		///
+		/// The relayer pays the relay fee and locks the header bond. The header is not validated
+		/// now, but held as pending until its challenge period is over. Meanwhile anyone may
+		/// prove it fraudulent with `challenge_header`, claiming the bond. Once the period is
+		/// over, anyone may call `finalize_pending_header` to validate and accept it as in
+		/// `submit_new_header`, and return the bond to the relayer's pending rewards.
+		///
+		/// A pending header's parent may itself be pending, but the parent must be accepted
+		/// first.
+		#[ink(message, payable)]
+		pub fn submit_optimistic_header(&mut self, header: Header) -> Result<()> {
+			self.ensure_not_paused()?;
+			let challenge_period = self.challenge_period.ok_or(Error::OptimisticModeDisabled)?;
+			let paid = self.env().transferred_value();
+			let relay_fee = self.current_relay_fee();
+			if paid < relay_fee {
+				return Err(Error::InsufficientRelayFee)
+			}
+			let bond = paid - relay_fee;
+			if bond < self.header_bond {
+				return Err(Error::InsufficientBond)
+			}
+			self.treasury = self.treasury.saturating_add(relay_fee);
+			let relayer = self.caller();
+			self.ensure_may_relay(relayer)?;
+			if header == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			let header_hash = self.header_hash(header);
+			if self.headers.contains((DEFAULT_CHAIN_ID, header_hash)) ||
+				self.pending_headers.contains(header_hash)
+			{
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+
+			let challengeable_until = self.current_block().saturating_add(challenge_period);
+			let pending = PendingHeader { header, relayer, bond, challengeable_until };
+			self.pending_headers.insert(header_hash, &pending);
+			self.env().emit_event(HeaderPending {
+				block_hash: header_hash,
+				relayer,
+				challengeable_until,
+			});
+
+			Ok(())
+		}
+
+		/// Prove that a pending header is fraudulent, during its challenge period.
+		///
+		/// The header is rejected, and the relayer's bond is credited to the caller's pending
+		/// rewards. The relayer's stake, if any, is slashed with the caller as reporter. Fails with
+		/// `FraudNotProven` if the header is not wrong in the claimed way.
+		#[ink(message)]
+		pub fn challenge_header(&mut self, block_hash: HashValue, fraud: FraudProof) -> Result<()> {
+			let pending =
+				self.pending_headers.get(block_hash).ok_or(Error::UnknownPendingHeader)?;
+			if self.current_block() > pending.challengeable_until {
+				return Err(Error::ChallengePeriodOver)
+			}
+			let header = pending.header;
+			let proven = match fraud {
+				FraudProof::InvalidPoW =>
+					U256::from_be_bytes(header.target) >
+						U256::from_be_bytes(self.default_chain().difficulty_threshold) ||
+						!Self::meets_target(block_hash, header.target),
+				FraudProof::BadParentLink => self
+					.headers
+					.get((DEFAULT_CHAIN_ID, header.parent))
+					.or_else(|| self.pending_headers.get(header.parent).map(|parent| parent.header))
+					.is_none_or(|parent| header.height != parent.height + 1),
+			};
+			if !proven {
+				return Err(Error::FraudNotProven)
+			}
+
+			let challenger = self.caller();
+			self.pending_headers.remove(block_hash);
+			self.credit_reward(challenger, pending.bond);
+			self.env().emit_event(HeaderChallenged {
+				block_hash,
+				relayer: pending.relayer,
+				challenger,
+				fraud,
+				bond: pending.bond,
+			});
+			self.slash(pending.relayer, challenger, block_hash);
+
+			Ok(())
+		}
+
+		/// Accept a pending header whose challenge period is over, validating it as in
+		/// `submit_new_header`. The relayer's bond is credited to their pending rewards, and they
+		/// are the header's fee recipient. Anyone may call this.
+		#[ink(message)]
+		pub fn finalize_pending_header(&mut self, block_hash: HashValue) -> Result<()> {
+			self.ensure_not_paused()?;
+			let pending =
+				self.pending_headers.get(block_hash).ok_or(Error::UnknownPendingHeader)?;
+			if self.current_block() <= pending.challengeable_until {
+				return Err(Error::ChallengePeriodNotOver)
+			}
+
+			self.accept_header_as(
+				DEFAULT_CHAIN_ID,
+				pending.header,
+				block_hash,
+				HeaderFormat::Native,
+				None,
+				pending.relayer,
+			)?;
+			self.pending_headers.remove(block_hash);
+			self.credit_reward(pending.relayer, pending.bond);
+
+			Ok(())
+		}
+
+		/// Bond the transferred value as the caller's relayer stake, adding to any stake they
+		/// have already bonded.
+		///
+		/// When the owner has set a minimum relayer stake, only relayers with at least that much
+		/// bonded may submit headers. The stake is slashed if one of their headers is proven
+		/// fraudulent, or found invalid by governance, see `slash_relayer`.
+		#[ink(message, payable)]
+		pub fn register_relayer(&mut self) -> Result<()> {
+			let relayer = self.caller();
+			let mut stake = self.relayer_stakes.get(relayer).unwrap_or_default();
+			stake.bonded = stake.bonded.saturating_add(self.env().transferred_value());
+			self.relayer_stakes.insert(relayer, &stake);
+			self.env().emit_event(RelayerRegistered { relayer, stake: stake.bonded });
+
+			Ok(())
+		}
+
+		/// Start unbonding all of the caller's bonded stake. It can be withdrawn with
+		/// `withdraw_unbonded` after `UNBONDING_DELAY` target chain blocks, and stays slashable
+		/// until then. Unbonding more stake restarts the delay.
+		#[ink(message)]
+		pub fn unbond(&mut self) -> Result<()> {
+			let relayer = self.caller();
+			let mut stake = self.relayer_stakes.get(relayer).unwrap_or_default();
+			if stake.bonded == 0 {
+				return Err(Error::NoBondedStake)
+			}
+			let amount = core::mem::take(&mut stake.bonded);
+			stake.unbonding = stake.unbonding.saturating_add(amount);
+			stake.unlocks_at = self.current_block().saturating_add(UNBONDING_DELAY);
+			self.relayer_stakes.insert(relayer, &stake);
+			self.env().emit_event(RelayerUnbonding {
+				relayer,
+				amount,
+				unlocks_at: stake.unlocks_at,
+			});
+
+			Ok(())
+		}
+
+		/// Pay the caller their unbonding stake, once it has unlocked.
+		#[ink(message)]
+		pub fn withdraw_unbonded(&mut self) -> Result<()> {
+			let relayer = self.caller();
+			let mut stake = self.relayer_stakes.get(relayer).unwrap_or_default();
+			if stake.unbonding == 0 || self.current_block() < stake.unlocks_at {
+				return Err(Error::NothingToWithdraw)
+			}
+			let amount = core::mem::take(&mut stake.unbonding);
+			if stake.bonded == 0 {
+				self.relayer_stakes.remove(relayer);
+			} else {
+				self.relayer_stakes.insert(relayer, &stake);
+			}
+			self.env().transfer(relayer, amount).map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(StakeWithdrawn { relayer, amount });
+
+			Ok(())
+		}
+
+		/// Slash the stake of the relayer of a default chain header that governance has found
+		/// invalid, paying `SLASH_REPORTER_PERCENT` of it to the reporter's pending rewards. Only
+		/// admins may call this.
+		#[ink(message)]
+		pub fn slash_relayer(&mut self, block_hash: HashValue, reporter: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let relayer = self
+				.fee_recipient
+				.get((DEFAULT_CHAIN_ID, block_hash))
+				.ok_or(Error::NothingToSlash)?;
+			if !self.slash(relayer, reporter, block_hash) {
+				return Err(Error::NothingToSlash)
+			}
+
+			Ok(())
+		}
+
+		/// What the given relayer has contributed: how many of their headers have been accepted
+		/// and are currently canon, and the verify fees they have been credited, on any source
+		/// chain.
+		#[ink(message)]
+		pub fn relayer_stats(&self, relayer: AccountId) -> RelayerStats {
+			self.relayer_stats.get(relayer).unwrap_or_default()
+		}
+
+		/// Up to `n` relayers with the most canon headers, and then the most rewards earned, with
+		/// their stats. Only the first `MAX_RANKED_RELAYERS` relayers are ranked.
+		#[ink(message)]
+		pub fn top_relayers(&self, n: u32) -> Vec<(AccountId, RelayerStats)> {
+			let mut ranking: Vec<_> = self
+				.ranked_relayers
+				.iter()
+				.map(|relayer| (*relayer, self.relayer_stats(*relayer)))
+				.collect();
+			ranking.sort_by(|(_, a), (_, b)| {
+				(b.canon_headers, b.rewards_earned).cmp(&(a.canon_headers, a.rewards_earned))
+			});
+			ranking.truncate(n as usize);
+			ranking
+		}
+
+		/// The stake the given relayer has bonded and is unbonding.
+		#[ink(message)]
+		pub fn stake_of(&self, relayer: AccountId) -> RelayerStake {
+			self.relayer_stakes.get(relayer).unwrap_or_default()
+		}
+
+		/// Verify that some transaction has occurred on the source chain.
+		///
+		/// In order for a verification to be successful (to return `Ok`), these conditions must be
+		/// met:
+		/// 0. The verifier must pay the verification fee (which will go to the relayer).
+		/// 1. The block is in the db
+		/// 2. The block is in the best chain
+		/// 3. The block's height in the best chain is at least `min_depth` before the tip of the
+		///    chain. A min_depth of 0 just means that the header is canon at all. A min_depth of 1
+		///    means there is at least one block confirmation afterward. The owner may set a greater
+		///    floor with `set_min_confirmations`.
+		/// 4. The merkle proof must be valid
+		///
+		/// Conditions 2 and 3 are not re-checked if a claim against the block has already been
+		/// verified at `min_depth` or deeper, see `verified_depth`.
+		///
+		/// Otherwise a `VerificationError` says which condition was not met. Like any message
+		/// returning an error, the call is then reverted, including the fee payment. Any value
+		/// transferred beyond the fee is refunded.
+		///
+		/// A verifier who transfers less than the fee has it drawn from their prepaid credits
+		/// instead, see `deposit_credits`, or failing that from the subsidy pool. The transferred
+		/// value is then refunded in full.
+		#[ink(message, payable)]
+		pub fn verify_transaction(
+			&mut self,
+			tx_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.verify_chain_transaction(DEFAULT_CHAIN_ID, tx_hash, header_hash, min_depth, p)
+		}
+
+		/// Verify that some transaction has occurred on the given source chain, like
+		/// `verify_transaction`. The verify fee is the one the chain was registered with.
+		#[ink(message, payable)]
+		pub fn verify_chain_transaction(
+			&mut self,
+			chain_id: ChainId,
+			tx_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let paid = self.env().transferred_value();
+			self.verify_tx(chain_id, paid, tx_hash, header_hash, min_depth, p)
+		}
+
+		/// Verify that some transaction has occurred on the source chain like
+		/// `verify_transaction`, but report a failed verification as a `false` outcome rather
+		/// than an error, so that its fee payment, event and failure count are kept, see
+		/// `failure_count`. Calls rejected before the claim is checked still fail with an error.
+		#[ink(message, payable)]
+		pub fn verify_transaction_outcome(
+			&mut self,
+			tx_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> Result<bool> {
+			self.ensure_not_paused()?;
+			let paid = self.env().transferred_value();
+			self.verify_tx_outcome(DEFAULT_CHAIN_ID, paid, tx_hash, header_hash, min_depth, p)
+		}
+
+		/// Verify that some event was emitted on the source chain, like `verify_transaction`.
+		///
+		/// The event hash is its leaf in the block's receipts merkle tree, so a dApp can prove
+		/// that a transaction had a particular effect rather than only that it was included.
+		#[ink(message, payable)]
+		pub fn verify_event(
+			&mut self,
+			event_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.verify_chain_event(DEFAULT_CHAIN_ID, event_hash, header_hash, min_depth, p)
+		}
+
+		/// Verify that some event was emitted on the given source chain, like `verify_event`. The
+		/// verify fee is the one the chain was registered with.
+		#[ink(message, payable)]
+		pub fn verify_chain_event(
+			&mut self,
+			chain_id: ChainId,
+			event_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let paid = self.env().transferred_value();
+			self.verify_claim(chain_id, paid, event_hash, header_hash, min_depth, p, |header| {
+				header.receipts_root
+			})?;
+
+			self.env().emit_event(EventVerified {
+				chain_id,
+				event_hash,
+				block_hash: header_hash,
+				claim_id: self.claim_id(event_hash, header_hash),
+				depth: min_depth,
+				verifier: self.caller(),
+			});
+			Ok(())
+		}
+
+		/// Verify that a source transaction has occurred, like `verify_transaction`, and report the
+		/// amount and fee that it declares.
+		///
+		/// The transaction is given in its canonical encoding, see `SourceTransaction`. Returns
+		/// `None` if the verification does not succeed. This saves contracts that share revenue
+		/// based on source chain fees a second round of proving.
+		#[ink(message, payable)]
+		pub fn verify_transaction_fee(
+			&mut self,
+			transaction: Vec<u8>,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> Result<Option<TransactionFeeReceipt>> {
+			self.ensure_not_paused()?;
+			let decoded =
+				codec::decode_transaction(&transaction).map_err(|_| Error::MalformedTransaction)?;
+			let tx_hash = codec::transaction_hash(&decoded);
+
+			let paid = self.env().transferred_value();
+			let outcome =
+				self.verify_tx_outcome(DEFAULT_CHAIN_ID, paid, tx_hash, header_hash, min_depth, p)?;
+
+			Ok(outcome.then_some(TransactionFeeReceipt {
+				tx_hash,
+				block_hash: header_hash,
+				claim_id: self.claim_id(tx_hash, header_hash),
+				amount: decoded.amount,
+				fee: decoded.fee,
+			}))
+		}
+
+		/// Verify that some state exists on the source chain.
+		///
+		/// The checks performed are the same as when verifying a transaction.
+		/// However, in this chase, you pass the hash of the state claim
+		#[ink(message, payable)]
+		pub fn verify_state(
+			&mut self,
+			claim: StateClaim,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.verify_chain_state(DEFAULT_CHAIN_ID, claim, block_hash, min_depth, p)
+		}
+
+		/// Verify that some state exists on the given source chain, like `verify_state`. The
+		/// verify fee is the one the chain was registered with.
+		#[ink(message, payable)]
+		pub fn verify_chain_state(
+			&mut self,
+			chain_id: ChainId,
+			claim: StateClaim,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let claim_hash = self.claim_leaf(claim);
+
+			let paid = self.env().transferred_value();
+			let result =
+				self.verify_claim(chain_id, paid, claim_hash, block_hash, min_depth, p, |header| {
+					header.storage_root
+				});
+			self.emit_state_verified(chain_id, claim_hash, block_hash, result)
+		}
+
+		/// Verify that some state exists on the source chain like `verify_state`, but report a
+		/// failed verification as a `false` outcome rather than an error, so that its fee
+		/// payment, event and failure count are kept, see `failure_count`. Calls rejected before
+		/// the claim is checked still fail with an error.
+		#[ink(message, payable)]
+		pub fn verify_state_outcome(
+			&mut self,
+			claim: StateClaim,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> Result<bool> {
+			self.ensure_not_paused()?;
+			let claim_hash = self.claim_leaf(claim);
+
+			let paid = self.env().transferred_value();
+			let result = self.verify_claim(
+				DEFAULT_CHAIN_ID,
+				paid,
+				claim_hash,
+				block_hash,
+				min_depth,
+				p,
+				|header| header.storage_root,
+			);
+			if let Err(VerificationError::Failed(failure)) = result {
+				self.emit_state_event(DEFAULT_CHAIN_ID, claim_hash, block_hash, Some(failure));
+			}
+			let result = self.emit_state_verified(DEFAULT_CHAIN_ID, claim_hash, block_hash, result);
+			self.outcome(result)
+		}
+
+		/// Verify that several state claims hold in the same block with a single multiproof, like
+		/// `verify_state`, for a combined fee of `batch_verify_fee`.
+		///
+		/// The i-th claim's leaf is at the multiproof's i-th index. The verification fails with
+		/// `InvalidProof` unless all the claims hold, and otherwise a `StateVerified` event is
+		/// emitted for each claim. At most `max_batch_size` claims may be verified in one call.
+		#[ink(message, payable)]
+		pub fn verify_state_batch(
+			&mut self,
+			claims: Vec<StateClaim>,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof: MerkleMultiproof,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			if claims.is_empty() {
+				return Err(Error::EmptyBatch.into())
+			}
+			if claims.len() > self.max_batch_size as usize {
+				return Err(Error::BatchTooLarge.into())
+			}
+			let leaves: Vec<_> = claims.into_iter().map(|claim| self.claim_leaf(claim)).collect();
+			let paid = self.env().transferred_value();
+			self.verify_batch_in_block(
+				DEFAULT_CHAIN_ID,
+				paid,
+				block_hash,
+				min_depth,
+				proof.depth as usize,
+				leaves.len() as u32,
+				|header| {
+					MerkleMultiproof::check_merkle_multiproof(&leaves, &proof, header.storage_root)
+				},
+			)?;
+
+			for claim_hash in leaves {
+				self.record_attestation(DEFAULT_CHAIN_ID, claim_hash, block_hash);
+				self.emit_state_event(DEFAULT_CHAIN_ID, claim_hash, block_hash, None);
+			}
+			Ok(())
+		}
+
+		/// Verify an account of an Ethereum style source chain, whose block `storage_root` is
+		/// the root of its Merkle-Patricia state trie.
+		///
+		/// `account` is the RLP encoded account, `[nonce, balance, storage_root, code_hash]`, and
+		/// `proof` the trie nodes on the path to `keccak256(address)`, starting with the root.
+		/// Otherwise the verification is like `verify_state`, and its `StateVerified` event has
+		/// `codec::account_claim_hash` as the claim hash.
+		#[ink(message, payable)]
+		pub fn verify_account(
+			&mut self,
+			address: [u8; 20],
+			account: Vec<u8>,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof: Vec<Vec<u8>>,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let claim_hash = codec::account_claim_hash(&address, &account);
+			let paid = self.env().transferred_value();
+			let result = self.verify_in_block(
+				DEFAULT_CHAIN_ID,
+				paid,
+				block_hash,
+				min_depth,
+				proof.len(),
+				|header| {
+					Self::read_account(&header.storage_root, &address, &proof) == Some(account)
+				},
+			);
+			self.emit_state_verified(DEFAULT_CHAIN_ID, claim_hash, block_hash, result)
+		}
+
+		/// Verify the value of a storage slot of an account of an Ethereum style source chain,
+		/// like `verify_account`.
+		///
+		/// `proof` holds the state trie nodes on the path to the account, and the account's
+		/// storage trie nodes on the path to `keccak256(slot)`. A slot that is not in the storage
+		/// trie has the value zero. The `StateVerified` event has `codec::storage_claim_hash` as
+		/// the claim hash.
+		#[ink(message, payable)]
+		pub fn verify_storage(
+			&mut self,
+			address: [u8; 20],
+			slot: HashValue,
+			value: HashValue,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof: StorageProof,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let claim_hash = codec::storage_claim_hash(&address, &slot, &value);
+			let paid = self.env().transferred_value();
+			let proof_length = proof.account_proof.len().max(proof.storage_proof.len());
+			let result = self.verify_in_block(
+				DEFAULT_CHAIN_ID,
+				paid,
+				block_hash,
+				min_depth,
+				proof_length,
+				|header| {
+					let Some(account) =
+						Self::read_account(&header.storage_root, &address, &proof.account_proof)
+					else {
+						return false
+					};
+					let storage_root = rlp::decode(&account)
+						.and_then(|account| account.items())
+						.and_then(|fields| fields.get(2)?.bytes()?.try_into().ok());
+					storage_root.is_some_and(|storage_root| {
+						Self::read_storage(&storage_root, &slot, &proof.storage_proof) ==
+							Some(value)
+					})
+				},
+			);
+			self.emit_state_verified(DEFAULT_CHAIN_ID, claim_hash, block_hash, result)
+		}
+
+		/// Verify that several transactions are included in the same block at the given positions,
+		/// like `verify_transaction`, for a combined fee of `batch_verify_fee`.
+		///
+		/// Each entry is a transaction hash, its index in the block and its merkle proof, whose
+		/// direction bits must be exactly the index. The verification fails with `InvalidProof`
+		/// if any entry does not hold, and otherwise a `TransactionVerified` event is emitted for
+		/// each transaction. At most `max_batch_size` transactions may be verified in one call.
+		#[ink(message, payable)]
+		pub fn verify_transactions(
+			&mut self,
+			transactions: Vec<(HashValue, u64, MerkleProof)>,
+			header_hash: HashValue,
+			min_depth: u64,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			if transactions.is_empty() {
+				return Err(Error::EmptyBatch.into())
+			}
+			if transactions.len() > self.max_batch_size as usize {
+				return Err(Error::BatchTooLarge.into())
+			}
+			let paid = self.env().transferred_value();
+			let proof_length = transactions.iter().map(|(_, _, p)| p.siblings.len()).max();
+			let tx_hashes: Vec<_> = transactions.iter().map(|(tx_hash, _, _)| *tx_hash).collect();
+			self.verify_batch_in_block(
+				DEFAULT_CHAIN_ID,
+				paid,
+				header_hash,
+				min_depth,
+				proof_length.unwrap_or_default(),
+				transactions.len() as u32,
+				|header| {
+					transactions.into_iter().all(|(tx_hash, index, p)| {
+						p.leaf_index() == Some(index) &&
+							MerkleProof::check_merkle_proof(tx_hash, p, header.transactions_root)
+					})
+				},
+			)?;
+
+			for tx_hash in tx_hashes {
+				self.record_verified_transaction(DEFAULT_CHAIN_ID, tx_hash, header_hash);
+				self.emit_tx_verified(DEFAULT_CHAIN_ID, tx_hash, header_hash, min_depth, None);
+			}
+			Ok(())
+		}
+
+		/// Submit a new header and verify a transaction in it within the same call.
+		///
+		/// This saves a round trip for time sensitive flows. Both the relay fee and the verify fee
+		/// must be paid. Nothing can have been built on a header that is only now being submitted,
+		/// so `min_depth` must be zero. A header whose parent is not known yet is pooled as in
+		/// `submit_new_header`, and the verification fails with the verify fee refunded.
+		#[ink(message, payable)]
+		pub fn submit_and_verify(
+			&mut self,
+			header: Header,
+			tx_hash: HashValue,
+			p: MerkleProof,
+			min_depth: u64,
+		) -> Result<bool> {
+			self.ensure_not_paused()?;
+			if min_depth.max(self.min_confirmations) > 0 {
+				return Err(Error::DepthUnreachable)
+			}
+			let chain = self.default_chain();
+			let paid = self.env().transferred_value();
+			let relay_fee = self.current_relay_fee();
+			if paid < relay_fee {
+				return Err(Error::InsufficientRelayFee)
+			}
+			self.treasury = self.treasury.saturating_add(relay_fee);
+			let verify_paid = paid - relay_fee;
+			if verify_paid < chain.verify_fee {
+				return Err(Error::InsufficientVerifyFee)
+			}
+
+			let Some(header_hash) = self.accept_or_pool(header, None)? else {
+				// Nothing can be verified in a header that is waiting for its parent.
+				self.refund_excess(paid, relay_fee)?;
+				return Ok(false)
+			};
+			self.verify_tx_outcome(
+				DEFAULT_CHAIN_ID,
+				verify_paid,
+				tx_hash,
+				header_hash,
+				min_depth,
+				p,
+			)
+		}
+
+		/// The height of the current best known source chain.
+		#[ink(message)]
+		pub fn best_height(&self) -> u64 {
+			self.default_chain().best_height
+		}
+
+		/// The hash of the tip of the canon chain.
+		#[ink(message)]
+		pub fn best_header_hash(&self) -> HashValue {
+			self.tip_hash(DEFAULT_CHAIN_ID, &self.default_chain())
+		}
+
+		/// The hash of the canonical block at the given height, if there is one.
+		#[ink(message)]
+		pub fn canon_hash_at(&self, height: u64) -> Option<HashValue> {
+			self.canon_chain.get((DEFAULT_CHAIN_ID, height))
+		}
+
+		/// The height and hash of the canon blocks from `from_height` up, looking up at most
+		/// `limit` heights, capped at `MAX_CANON_RANGE_PAGE`. Heights without a canon block, such
+		/// as those below the checkpoint, are skipped. Indexers can page through the canon chain
+		/// by starting the next call at the last returned height plus one.
+		#[ink(message)]
+		pub fn canon_range(&self, from_height: u64, limit: u32) -> Vec<(u64, HashValue)> {
+			let best_height = self.default_chain().best_height;
+			let end = from_height.saturating_add(limit.min(MAX_CANON_RANGE_PAGE) as u64);
+			(from_height..end.min(best_height.saturating_add(1)))
+				.filter_map(|height| {
+					Some((height, self.canon_chain.get((DEFAULT_CHAIN_ID, height))?))
+				})
+				.collect()
+		}
+
+		/// The hash and height of every stored default chain header without stored children: the
+		/// canon tip and the tip of each competing fork.
+		#[ink(message)]
+		pub fn get_fork_tips(&self) -> Vec<(HashValue, u64)> {
+			self.fork_tips
+				.get(DEFAULT_CHAIN_ID)
+				.unwrap_or_default()
+				.into_iter()
+				.filter_map(|hash| Some((hash, self.headers.get((DEFAULT_CHAIN_ID, hash))?.height)))
+				.collect()
+		}
+
+		/// The hashes of the stored children of a default chain header, in the order they were
+		/// accepted.
+		#[ink(message)]
+		pub fn children_of(&self, block_hash: HashValue) -> Vec<HashValue> {
+			self.children.get((DEFAULT_CHAIN_ID, block_hash)).unwrap_or_default()
+		}
+
+		/// The ancestor at the given height of the given default chain block, whether or not either
+		/// is canon. `None` if the block is unknown or below the height, or the ancestor has been
+		/// pruned. Follows skip list links, so deep ancestors are found in a few storage reads.
+		#[ink(message)]
+		pub fn ancestor_of(&self, block_hash: HashValue, height: u64) -> Option<HashValue> {
+			self.ancestor_at(DEFAULT_CHAIN_ID, block_hash, height)
+		}
+
+		/// Whether the first default chain block is the second one or one of its ancestors.
+		#[ink(message)]
+		pub fn is_ancestor(&self, ancestor: HashValue, descendant: HashValue) -> bool {
+			self.headers.get((DEFAULT_CHAIN_ID, ancestor)).is_some_and(|header| {
+				self.ancestor_at(DEFAULT_CHAIN_ID, descendant, header.height) == Some(ancestor)
+			})
+		}
+
+		/// The fork point of two default chain blocks: the highest block that is either of them or
+		/// an ancestor of both. `None` if either block is unknown or the fork point has been
+		/// pruned.
+		#[ink(message)]
+		pub fn common_ancestor(&self, a: HashValue, b: HashValue) -> Option<HashValue> {
+			self.fork_point(DEFAULT_CHAIN_ID, a, b)
+		}
+
+		/// The header with the given hash, if it is known.
+		#[ink(message)]
+		pub fn header_by_hash(&self, header_hash: HashValue) -> Option<Header> {
+			self.headers.get((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// The optimistically submitted default chain header with the given hash, if it is still
+		/// pending.
+		#[ink(message)]
+		pub fn pending_header(&self, header_hash: HashValue) -> Option<PendingHeader> {
+			self.pending_headers.get(header_hash)
+		}
+
+		/// The relayer's signature over the given header, if they submitted one and the header
+		/// has not been pruned.
+		#[ink(message)]
+		pub fn header_provenance(&self, header_hash: HashValue) -> Option<HeaderProvenance> {
+			self.header_provenance.get((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// The greatest depth at which a claim against the given canon block has been successfully
+		/// verified, if any. Verifications against the block at no greater depth only check the
+		/// merkle proof.
+		#[ink(message)]
+		pub fn verified_depth(&self, header_hash: HashValue) -> Option<u64> {
+			self.verified_depths.get((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// The default chain best height when the given transaction was first successfully
+		/// verified against the given block, while the block is still canon. Contracts can read
+		/// this for free instead of paying to verify the same transaction again; the depth the
+		/// transaction had then is the returned height less the block's height.
+		#[ink(message)]
+		pub fn was_verified(&self, tx_hash: HashValue, block_hash: HashValue) -> Option<u64> {
+			if !self.is_canon(DEFAULT_CHAIN_ID, block_hash) {
+				return None
+			}
+			self.verified_transactions.get((DEFAULT_CHAIN_ID, tx_hash, block_hash))
+		}
+
+		/// The last successful verifications of a state claim, oldest first, on any source chain
+		/// and whatever kind of state proof was used. The claim hash is the one the verifications'
+		/// `StateVerified` events have. An attestation is a record of the past: whether its block
+		/// is still canon must be checked separately.
+		#[ink(message)]
+		pub fn attestations(&self, claim_hash: HashValue) -> Vec<Attestation> {
+			self.attestations.get(claim_hash).unwrap_or_default()
+		}
+
+		/// The hash of the parent chain block whose PoW the given merge mined header relies on,
+		/// if it was submitted with `submit_aux_pow_header` and has not been pruned.
+		#[ink(message)]
+		pub fn aux_pow_parent(&self, header_hash: HashValue) -> Option<HashValue> {
+			self.aux_pow_parents.get((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// The state of the given source chain, if it is registered.
+		#[ink(message)]
+		pub fn source_chain(&self, chain_id: ChainId) -> Option<SourceChain> {
+			self.chains.get(chain_id)
+		}
+
+		/// The hash of the canonical block of the given source chain at the given height, if
+		/// there is one.
+		#[ink(message)]
+		pub fn chain_canon_hash_at(&self, chain_id: ChainId, height: u64) -> Option<HashValue> {
+			self.canon_chain.get((chain_id, height))
+		}
+
+		/// The header of the given source chain with the given hash, if it is known.
+		#[ink(message)]
+		pub fn chain_header_by_hash(
+			&self,
+			chain_id: ChainId,
+			header_hash: HashValue,
+		) -> Option<Header> {
+			self.headers.get((chain_id, header_hash))
+		}
+
+		/// The fee a relayer must pay to submit a header, when no fee controller is set. Otherwise
+		/// the floor of the controlled fee, see `current_relay_fee`.
+		#[ink(message)]
+		pub fn relay_fee(&self) -> Balance {
+			self.default_chain().relay_fee
+		}
+
+		/// The fee a relayer must pay to submit a default chain header right now.
+		///
+		/// Without a fee controller this is `relay_fee`. With one, the fee rises by the
+		/// controller's step every time a header is accepted sooner than the target block time
+		/// after the previous one. It decays by the same step for every target block time that
+		/// passes, down to `relay_fee`.
+		#[ink(message)]
+		pub fn current_relay_fee(&self) -> Balance {
+			let base = self.default_chain().relay_fee;
+			let Some(controller) = self.fee_controller else { return base };
+			let elapsed = self.now().saturating_sub(self.last_header_arrival);
+			controller.decayed_fee(self.surge_fee, base, elapsed)
+		}
+
+		/// The fee a verifier must pay to verify a transaction or state claim.
+		#[ink(message)]
+		pub fn verify_fee(&self) -> Balance {
+			self.default_chain().verify_fee
+		}
+
+		/// The combined fee for verifying the given number of claims against the same block in
+		/// one call: the verify fee for the first claim, and the verify fee less
+		/// `BATCH_VERIFY_DISCOUNT_PERCENT` for each one after it.
+		#[ink(message)]
+		pub fn batch_verify_fee(&self, claims: u32) -> Balance {
+			fees::discounted_fee(self.default_chain().verify_fee, claims)
+		}
+
+		/// The admin that deployed the bridge, or that ownership was last transferred to.
+		#[ink(message)]
+		pub fn owner(&self) -> AccountId {
+			self.owner
+		}
+
+		/// Whether the account holds the role, or is an admin.
+		#[ink(message)]
+		pub fn has_role(&self, role: Role, account: AccountId) -> bool {
+			self.roles.contains((role, account)) || self.roles.contains((Role::Admin, account))
+		}
+
+		/// Grant a role to an account. Only admins may call this.
+		#[ink(message)]
+		pub fn grant_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.grant(role, account);
+			Ok(())
+		}
+
+		/// Revoke a role from an account. Only admins may call this.
+		///
+		/// Fails with `LastAdmin` rather than leave the bridge without an admin.
+		#[ink(message)]
+		pub fn revoke_role(&mut self, role: Role, account: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.revoke(role, account)
+		}
+
+		/// Require `threshold` of `signers` to approve every critical action, see
+		/// `CriticalAction`. Only admins may call this, and only while no signers are configured;
+		/// afterwards the signer set is changed by proposal. A threshold of zero removes the
+		/// requirement.
+		#[ink(message)]
+		pub fn set_signers(&mut self, signers: Vec<AccountId>, threshold: u32) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.ensure_no_multisig()?;
+			self.replace_signers(signers, threshold)
+		}
+
+		/// The accounts that approve critical actions, and how many of them must approve each.
+		#[ink(message)]
+		pub fn signers(&self) -> (Vec<AccountId>, u32) {
+			(self.signers.clone(), self.signer_threshold)
+		}
+
+		/// The proposal with the given id, if it is waiting for approval.
+		#[ink(message)]
+		pub fn proposal(&self, id: u32) -> Option<Proposal> {
+			self.proposals.get(id)
+		}
+
+		/// Propose a critical action, approving it on the caller's behalf, and return the
+		/// proposal's id. Only signers may call this.
+		///
+		/// The action executes as soon as the threshold is met, which for a threshold of one is
+		/// straight away.
+		#[ink(message)]
+		pub fn propose(&mut self, action: CriticalAction) -> Result<u32> {
+			let proposer = self.ensure_signer()?;
+			let id = self.next_proposal_id;
+			self.next_proposal_id = id.wrapping_add(1);
+			let proposal = Proposal {
+				action: action.clone(),
+				approvals: Vec::new(),
+				signer_epoch: self.signer_epoch,
+			};
+			self.env().emit_event(ProposalCreated { id, proposer, action });
+			self.add_approval(id, proposal, proposer)?;
+			Ok(id)
+		}
+
+		/// Approve a proposal, executing its action if this meets the threshold. Only signers may
+		/// call this.
+		#[ink(message)]
+		pub fn approve(&mut self, id: u32) -> Result<()> {
+			let signer = self.ensure_signer()?;
+			let proposal = self.proposals.get(id).ok_or(Error::NoProposal)?;
+			if proposal.signer_epoch != self.signer_epoch {
+				return Err(Error::StaleProposal)
+			}
+			if proposal.approvals.contains(&signer) {
+				return Err(Error::AlreadyApproved)
+			}
+			self.add_approval(id, proposal, signer)
+		}
+
+		/// The account ownership has been offered to, if it has not accepted yet.
+		#[ink(message)]
+		pub fn pending_owner(&self) -> Option<AccountId> {
+			self.pending_owner
+		}
+
+		/// Offer ownership of the bridge to another account. Only admins may call this.
+		///
+		/// The owner stays in charge until `new_owner` calls `accept_ownership`, which moves the
+		/// owner's admin role to it, so a mistyped account can't take the bridge out of anyone's
+		/// hands. Offering ownership again replaces the previous offer.
+		#[ink(message)]
+		pub fn transfer_ownership(&mut self, new_owner: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.pending_owner = Some(new_owner);
+			self.env()
+				.emit_event(OwnershipTransferStarted { previous_owner: self.owner, new_owner });
+			Ok(())
+		}
+
+		/// Accept ownership of the bridge. Only the account ownership has been offered to may
+		/// call this.
+		#[ink(message)]
+		pub fn accept_ownership(&mut self) -> Result<()> {
+			let new_owner = self.caller();
+			if self.pending_owner != Some(new_owner) {
+				return Err(Error::NotPendingOwner)
+			}
+			let previous_owner = self.owner;
+			self.grant(Role::Admin, new_owner);
+			if previous_owner != new_owner && self.roles.contains((Role::Admin, previous_owner)) {
+				self.revoke(Role::Admin, previous_owner)?;
+			}
+			self.owner = new_owner;
+			self.pending_owner = None;
+			self.env().emit_event(OwnershipTransferred { previous_owner, new_owner });
+			Ok(())
+		}
+
+		/// Change the fee a relayer must pay to submit a header. Only fee managers may call this.
+		#[ink(message)]
+		pub fn set_relay_fee(&mut self, relay_fee: Balance) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			self.schedule_parameter(BridgeParameter::RelayFee(relay_fee))
+		}
+
+		/// Change the fee a verifier must pay to verify a claim. Only fee managers may call this.
+		#[ink(message)]
+		pub fn set_verify_fee(&mut self, verify_fee: Balance) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			self.schedule_parameter(BridgeParameter::VerifyFee(verify_fee))
+		}
+
+		/// Change the easiest PoW target a header may declare. Only admins may call this.
+		///
+		/// Headers that are already in the db are not re-validated.
+		#[ink(message)]
+		pub fn set_difficulty_threshold(&mut self, difficulty: HashValue) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::DifficultyThreshold(difficulty))
+		}
+
+		/// Change the maximum number of siblings a merkle proof may have.
+		/// Only admins may call this.
+		#[ink(message)]
+		pub fn set_max_proof_length(&mut self, max_proof_length: u32) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MaxProofLength(max_proof_length))
+		}
+
+		/// Change the maximum number of headers that may be submitted in one batch.
+		/// Only admins may call this.
+		#[ink(message)]
+		pub fn set_max_batch_size(&mut self, max_batch_size: u32) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MaxBatchSize(max_batch_size))
+		}
+
+		/// Change for how many target chain blocks optimistically submitted headers may be
+		/// challenged, or disable optimistic submission with `None`. Only admins may call this.
+		///
+		/// Headers that are already pending keep their challenge period.
+		#[ink(message)]
+		pub fn set_challenge_period(
+			&mut self,
+			challenge_period: Option<BlockNumber>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::ChallengePeriod(challenge_period))
+		}
+
+		/// Change the bond a relayer must lock with each optimistically submitted header. Only
+		/// admins may call this.
+		#[ink(message)]
+		pub fn set_header_bond(&mut self, header_bond: Balance) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::HeaderBond(header_bond))
+		}
+
+		/// Require relayers to have bonded at least the given stake to submit headers, or let
+		/// anyone submit with `None`. Only admins may call this.
+		#[ink(message)]
+		pub fn set_min_relayer_stake(&mut self, min_relayer_stake: Option<Balance>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MinRelayerStake(min_relayer_stake))
+		}
+
+		/// Require every verification to be at least the given depth, on any source chain, even if
+		/// the verifier asks for less. Only admins may call this.
+		#[ink(message)]
+		pub fn set_min_confirmations(&mut self, min_confirmations: u64) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MinConfirmations(min_confirmations))
+		}
+
+		/// The depth every verification requires at least, see `set_min_confirmations`.
+		#[ink(message)]
+		pub fn min_confirmations(&self) -> u64 {
+			self.min_confirmations
+		}
+
+		/// Refuse verifications against a source chain once none of its headers has been accepted
+		/// for the given number of target chain blocks, or never refuse them with `None`. Depth
+		/// guarantees mean little once relaying has stopped. Only admins may call this.
+		#[ink(message)]
+		pub fn set_staleness_window(
+			&mut self,
+			staleness_window: Option<BlockNumber>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::StalenessWindow(staleness_window))
+		}
+
+		/// Enable or disable the relayer allowlist. While it is enabled, only relayers the owner
+		/// has added with `add_relayer` may submit headers. Only admins may call this.
+		#[ink(message)]
+		pub fn set_relayer_allowlist(&mut self, enabled: bool) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::RelayerAllowlist(enabled))
+		}
+
+		/// Add a relayer to the relayer allowlist. Only admins may call this.
+		#[ink(message)]
+		pub fn add_relayer(&mut self, relayer: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.relayer_allowlist.insert(relayer, &());
+			self.env().emit_event(RelayerSetChanged { relayer, allowed: true });
+			Ok(())
+		}
+
+		/// Remove a relayer from the relayer allowlist. Only admins may call this.
+		///
+		/// Headers the relayer has already submitted are kept, but their pending optimistic
+		/// headers can't be finalized while the allowlist is enabled.
+		#[ink(message)]
+		pub fn remove_relayer(&mut self, relayer: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.relayer_allowlist.remove(relayer);
+			self.env().emit_event(RelayerSetChanged { relayer, allowed: false });
+			Ok(())
+		}
+
+		/// Whether the given relayer is on the relayer allowlist.
+		#[ink(message)]
+		pub fn is_allowed_relayer(&self, relayer: AccountId) -> bool {
+			self.relayer_allowlist.contains(relayer)
+		}
+
+		/// Let the default chain's relay fee rise and decay with the rate of header submissions,
+		/// or fix it at `relay_fee` with `None`. Only fee managers may call this.
+		///
+		/// Setting a controller resets the fee to `relay_fee`. See `current_relay_fee`.
+		#[ink(message)]
+		pub fn set_fee_controller(&mut self, fee_controller: Option<FeeController>) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			self.schedule_parameter(BridgeParameter::FeeController(fee_controller))
+		}
+
+		/// Limit how many headers are accepted per target chain block, from each relayer and
+		/// overall, or remove the limit with `None`. Only admins may call this.
+		///
+		/// This keeps a griefing relayer from flooding the bridge with cheap fork headers. Headers
+		/// beyond the limit are rejected with `RateLimited`, and can be submitted again in a
+		/// later block. Every header of a batch counts.
+		#[ink(message)]
+		pub fn set_rate_limit(&mut self, rate_limit: Option<RateLimit>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::RateLimit(rate_limit))
+		}
+
+		/// Share each verify fee out between the relayer of the verified block, the treasury and
+		/// burning. The treasury and burn shares are in basis points and must add up to at most
+		/// 10,000; the relayer gets the rest. Only fee managers may call this.
+		#[ink(message)]
+		pub fn set_fee_split(&mut self, fee_split: FeeSplit) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			if u32::from(fee_split.treasury_bps) + u32::from(fee_split.burn_bps) > 10_000 {
+				return Err(Error::InvalidFeeSplit)
+			}
+			self.schedule_parameter(BridgeParameter::FeeSplit(fee_split))
+		}
+
+		/// How verify fees are shared out, see `set_fee_split`.
+		#[ink(message)]
+		pub fn fee_split(&self) -> FeeSplit {
+			self.fee_split
+		}
+
+		/// The total of verify fees burned.
+		#[ink(message)]
+		pub fn burned_fees(&self) -> Balance {
+			self.burned_fees
+		}
+
+		/// Allow or forbid headers that prove their work by merged mining, see
+		/// `submit_aux_pow_header`. Only admins may call this.
+		#[ink(message)]
+		pub fn set_merged_mining(&mut self, enabled: bool) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MergedMining(enabled))
+		}
+
+		/// Pause the bridge, for example when a relay attack is detected. Only pausers may call
+		/// this.
+		///
+		/// While paused, every message that submits headers or verifies claims fails with
+		/// `BridgePaused`. Queries, alert subscriptions and fee settlement keep working.
+		#[ink(message)]
+		pub fn pause(&mut self) -> Result<()> {
+			self.ensure_role(Role::Pauser)?;
+			self.ensure_no_multisig()?;
+			self.schedule_parameter(BridgeParameter::Paused(true))
+		}
+
+		/// Resume normal operation of a paused bridge. Only pausers may call this.
+		#[ink(message)]
+		pub fn unpause(&mut self) -> Result<()> {
+			self.ensure_role(Role::Pauser)?;
+			self.ensure_no_multisig()?;
+			self.schedule_parameter(BridgeParameter::Paused(false))
+		}
+
+		/// Set for how many target chain blocks parameter changes are queued before they can be
+		/// enacted, or zero to apply them immediately. Only admins may call this.
+		///
+		/// While the timelock is set, every owner setter, `pause` and `unpause` included, queues
+		/// its change instead of applying it, so users can exit before an adverse change takes
+		/// effect. The timelock itself is changed through the current timelock.
+		#[ink(message)]
+		pub fn set_parameter_timelock(&mut self, delay: BlockNumber) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::ParameterTimelock(delay))
+		}
+
+		/// For how many target chain blocks parameter changes are queued before they can be
+		/// enacted.
+		#[ink(message)]
+		pub fn parameter_timelock(&self) -> BlockNumber {
+			self.parameter_timelock
+		}
+
+		/// The parameter changes waiting out their timelock, oldest first.
+		#[ink(message)]
+		pub fn pending_changes(&self) -> Vec<PendingChange> {
+			self.pending_changes.clone()
+		}
+
+		/// Cancel a queued parameter change. Only admins may call this.
+		#[ink(message)]
+		pub fn cancel_parameter_change(&mut self, id: u32) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let change = self.take_pending_change(id)?;
+			self.env()
+				.emit_event(ParameterChangeCancelled { id, parameter: change.parameter });
+			Ok(())
+		}
+
+		/// Apply a queued parameter change once its timelock has passed. Anyone may call this.
+		#[ink(message)]
+		pub fn enact_parameter_change(&mut self, id: u32) -> Result<()> {
+			let change = self
+				.pending_changes
+				.iter()
+				.find(|change| change.id == id)
+				.copied()
+				.ok_or(Error::NoPendingChange)?;
+			if self.current_block() < change.enactable_at {
+				return Err(Error::ParameterChangeLocked)
+			}
+			self.take_pending_change(id)?;
+			self.apply_parameter(change.parameter);
+			Ok(())
+		}
+
+		/// Whether the bridge is currently paused.
+		#[ink(message)]
+		pub fn is_paused(&self) -> bool {
+			self.paused
+		}
+
+		/// Blacklist a default chain header, for example the first block of a known attacker's
+		/// fork. Only admins may call this.
+		///
+		/// The header, known yet or not, is rejected if submitted, as is any header that builds on
+		/// it or on one of its stored descendants. Verifications against any of them fail with
+		/// `BlacklistedHeader`. A blacklisted header that is already canon stays canon until a
+		/// heavier fork replaces it. At most `MAX_BLACKLISTED_HEADERS` headers may be blacklisted,
+		/// across all chains.
+		#[ink(message)]
+		pub fn blacklist_header(&mut self, block_hash: HashValue) -> Result<()> {
+			self.blacklist_chain_header(DEFAULT_CHAIN_ID, block_hash)
+		}
+
+		/// Blacklist a header of the given source chain, like `blacklist_header`. Only admins may
+		/// call this.
+		#[ink(message)]
+		pub fn blacklist_chain_header(
+			&mut self,
+			chain_id: ChainId,
+			block_hash: HashValue,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.load_chain(chain_id)?;
+			if self.blacklisted_headers.contains(&(chain_id, block_hash)) {
+				return Ok(())
+			}
+			if self.blacklisted_headers.len() >= MAX_BLACKLISTED_HEADERS as usize {
+				return Err(Error::BlacklistFull)
+			}
+			self.blacklisted_headers.push((chain_id, block_hash));
+			self.env().emit_event(HeaderBlacklisted { chain_id, block_hash });
+			Ok(())
+		}
+
+		/// Remove a default chain header from the blacklist. Only admins may call this.
+		#[ink(message)]
+		pub fn unblacklist_header(&mut self, block_hash: HashValue) -> Result<()> {
+			self.unblacklist_chain_header(DEFAULT_CHAIN_ID, block_hash)
+		}
+
+		/// Remove a header of the given source chain from the blacklist. Only admins may call
+		/// this.
+		#[ink(message)]
+		pub fn unblacklist_chain_header(
+			&mut self,
+			chain_id: ChainId,
+			block_hash: HashValue,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let index = self
+				.blacklisted_headers
+				.iter()
+				.position(|blacklisted| *blacklisted == (chain_id, block_hash))
+				.ok_or(Error::NotBlacklisted)?;
+			self.blacklisted_headers.swap_remove(index);
+			self.env().emit_event(HeaderUnblacklisted { chain_id, block_hash });
+			Ok(())
+		}
+
+		/// The default chain headers that are blacklisted, see `blacklist_header`.
+		#[ink(message)]
+		pub fn blacklisted_headers(&self) -> Vec<HashValue> {
+			self.chain_blacklisted_headers(DEFAULT_CHAIN_ID)
+		}
+
+		/// The headers of the given source chain that are blacklisted.
+		#[ink(message)]
+		pub fn chain_blacklisted_headers(&self, chain_id: ChainId) -> Vec<HashValue> {
+			self.blacklisted_headers
+				.iter()
+				.filter(|(blacklisted_chain, _)| *blacklisted_chain == chain_id)
+				.map(|(_, block_hash)| *block_hash)
+				.collect()
+		}
+
+		/// Halt the bridge instead of reorganizing a canon chain by more than the given number of
+		/// blocks, or never halt with `None`. Only admins may call this.
+		///
+		/// A fork with more work than the canon chain that would retract more blocks is stored,
+		/// but not made canon. Until the owner calls `resolve_halt`, every message that submits
+		/// headers or verifies claims fails with `BridgeHalted`, protecting verifiers from long
+		/// range attacks.
+		#[ink(message)]
+		pub fn set_max_reorg_depth(&mut self, max_reorg_depth: Option<u64>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::MaxReorgDepth(max_reorg_depth))
+		}
+
+		/// The fork that halted the bridge, if it is halted.
+		#[ink(message)]
+		pub fn halt(&self) -> Option<Halt> {
+			self.halt
+		}
+
+		/// Lift a halt. Only checkpoint managers may call this.
+		///
+		/// With `adopt_fork`, the fork that caused the halt becomes the canon chain if it still
+		/// has more work. Otherwise the canon chain is kept, and a header that extends the fork
+		/// beyond the canon chain's work again halts the bridge again, unless the maximum reorg
+		/// depth has been raised.
+		#[ink(message)]
+		pub fn resolve_halt(&mut self, adopt_fork: bool) -> Result<()> {
+			self.ensure_role(Role::CheckpointManager)?;
+			self.ensure_no_multisig()?;
+			self.lift_halt(adopt_fork)
+		}
+
+		/// Lift a halt, see `resolve_halt`.
+		fn lift_halt(&mut self, adopt_fork: bool) -> Result<()> {
+			let halt = self.halt.take().ok_or(Error::NotHalted)?;
+			if adopt_fork {
+				let mut chain = self.load_chain(halt.chain_id)?;
+				let tip =
+					self.headers.get((halt.chain_id, halt.block_hash)).ok_or(Error::ForkPruned)?;
+				let work =
+					self.chain_work.get((halt.chain_id, halt.block_hash)).unwrap_or_default();
+				if work > self.best_work(halt.chain_id, &chain) {
+					self.set_canon_tip(halt.chain_id, &mut chain, halt.block_hash, tip)?;
+					self.chains.insert(halt.chain_id, &chain);
+				}
+			}
+			self.env()
+				.emit_event(HaltResolved { block_hash: halt.block_hash, adopted: adopt_fork });
+			Ok(())
+		}
+
+		/// Schedule the bridge to be re-anchored at a newer trusted header, for when relaying has
+		/// stalled for too long to catch up header by header. Only checkpoint managers may call
+		/// this.
+		///
+		/// The reset can be enacted with `enact_checkpoint_reset` after `CHECKPOINT_RESET_DELAY`
+		/// target chain blocks, giving users time to react. Scheduling a reset replaces any
+		/// reset already scheduled.
+		#[ink(message)]
+		pub fn reset_checkpoint(&mut self, header: Header) -> Result<()> {
+			self.ensure_role(Role::CheckpointManager)?;
+			self.ensure_no_multisig()?;
+			self.schedule_checkpoint_reset(header)
+		}
+
+		/// Schedule a checkpoint reset, see `reset_checkpoint`.
+		fn schedule_checkpoint_reset(&mut self, header: Header) -> Result<()> {
+			if header == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			if header.height <= self.default_chain().best_height {
+				return Err(Error::CheckpointNotNewer)
+			}
+
+			let enactable_at = self.current_block().saturating_add(CHECKPOINT_RESET_DELAY);
+			self.scheduled_checkpoint = Some(ScheduledCheckpoint { header, enactable_at });
+			self.env().emit_event(CheckpointResetScheduled {
+				checkpoint_hash: self.header_hash(header),
+				checkpoint_height: header.height,
+				enactable_at,
+			});
+			Ok(())
+		}
+
+		/// Cancel the scheduled checkpoint reset. Only checkpoint managers may call this.
+		#[ink(message)]
+		pub fn cancel_checkpoint_reset(&mut self) -> Result<()> {
+			self.ensure_role(Role::CheckpointManager)?;
+			let scheduled = self.scheduled_checkpoint.take().ok_or(Error::NoCheckpointReset)?;
+			self.env().emit_event(CheckpointResetCancelled {
+				checkpoint_hash: self.header_hash(scheduled.header),
+			});
+			Ok(())
+		}
+
+		/// Re-anchor the bridge at the scheduled checkpoint once its timelock has passed. Anyone
+		/// may call this.
+		///
+		/// The checkpoint becomes the canon tip and the finalized block, and its work is counted
+		/// from scratch. The old chain's headers and canon entries are kept as an archive, below
+		/// the new pruned height, so they can neither be built upon nor pruned. Raises a
+		/// `CheckpointReset` alert.
+		#[ink(message)]
+		pub fn enact_checkpoint_reset(&mut self) -> Result<()> {
+			let scheduled = self.scheduled_checkpoint.ok_or(Error::NoCheckpointReset)?;
+			if self.current_block() < scheduled.enactable_at {
+				return Err(Error::CheckpointResetLocked)
+			}
+			let mut chain = self.default_chain();
+			let checkpoint = scheduled.header;
+			if checkpoint.height <= chain.best_height {
+				return Err(Error::CheckpointNotNewer)
+			}
+			self.scheduled_checkpoint = None;
+
+			let previous_tip = self.tip_hash(DEFAULT_CHAIN_ID, &chain);
+			let checkpoint_hash = self.header_hash(checkpoint);
+			let key = (DEFAULT_CHAIN_ID, checkpoint_hash);
+			self.headers.insert(key, &checkpoint);
+			self.canon_chain.insert((DEFAULT_CHAIN_ID, checkpoint.height), &checkpoint_hash);
+			self.headers_at_height
+				.insert((DEFAULT_CHAIN_ID, checkpoint.height), &Vec::from([checkpoint_hash]));
+			// Headers of the old chain can no longer be built upon, so none of them is a fork tip.
+			self.fork_tips.insert(DEFAULT_CHAIN_ID, &Vec::from([checkpoint_hash]));
+			self.fee_recipient.insert(key, &self.caller());
+			self.chain_work.insert(key, &Self::work_from_target(checkpoint.target));
+
+			chain.best_height = checkpoint.height;
+			chain.pruned_height = checkpoint.height;
+			chain.finalized_height = checkpoint.height;
+			chain.checkpoint_height = checkpoint.height;
+			self.checkpoint_header = checkpoint;
+			chain.header_count += 1;
+			chain.last_header_block = self.current_block();
+			chain.block_time_ema = None;
+			chain.last_tip_update = self.now();
+			self.chains.insert(DEFAULT_CHAIN_ID, &chain);
+
+			self.env().emit_event(CheckpointReset {
+				checkpoint_hash,
+				checkpoint_height: checkpoint.height,
+				previous_tip,
+			});
+			self.raise_alert(AlertKind::CheckpointReset);
+			self.notify_subscribers(HeaderNotification::NewTip {
+				chain_id: DEFAULT_CHAIN_ID,
+				block_hash: checkpoint_hash,
+				block_height: checkpoint.height,
+			});
+			Ok(())
+		}
+
+		/// The checkpoint reset waiting out its timelock, if any.
+		#[ink(message)]
+		pub fn scheduled_checkpoint(&self) -> Option<ScheduledCheckpoint> {
+			self.scheduled_checkpoint
+		}
+
+		/// Replace the contract's code, keeping its storage. Only admins may call this.
+		///
+		/// If the new code changes the storage layout, `migrate_storage` should be called straight
+		/// afterwards, and the bridge may want to be paused around the upgrade.
+		#[ink(message)]
+		pub fn upgrade(&mut self, code_hash: Hash) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.ensure_no_multisig()?;
+			self.upgrade_code(code_hash)
+		}
+
+		/// Replace the contract's code, see `upgrade`.
+		fn upgrade_code(&mut self, code_hash: Hash) -> Result<()> {
+			self.env().set_code_hash(&code_hash).map_err(|_| Error::UpgradeFailed)?;
+			self.env().emit_event(Upgraded { code_hash });
+			Ok(())
+		}
+
+		/// Bring the storage up to date with the layout expected by the current code, after an
+		/// upgrade, by running the migration from each older version in turn. Does nothing if the
+		/// storage is already current.
+		///
+		/// Anyone may call this, since migrating can only bring the storage into the shape the
+		/// code expects, and the roles that would otherwise be checked may not be migrated yet.
+		#[ink(message)]
+		pub fn migrate_storage(&mut self) -> Result<()> {
+			let from_version = self.storage_version;
+			if from_version > STORAGE_VERSION {
+				return Err(Error::UnsupportedStorageVersion)
+			}
+			if from_version == STORAGE_VERSION {
+				return Ok(())
+			}
+
+			for version in from_version..STORAGE_VERSION {
+				self.migrate_from(version)?;
+			}
+			self.storage_version = STORAGE_VERSION;
+			self.env().emit_event(Migrated { from_version, to_version: STORAGE_VERSION });
+
+			Ok(())
+		}
+
+		/// The number of verifications that have failed for the given reason in the current
+		/// failure epoch.
+		///
+		/// Only the calls that report a failure as a `false` or `None` outcome are counted:
+		/// `verify_transaction_outcome`, `verify_state_outcome`, `submit_and_verify` and
+		/// `verify_transaction_fee`. Every other message, including every header submission,
+		/// reports a failure as an error, and a call that returns an error reverts all of its
+		/// changes, including to these counts.
+		///
+		/// Failures are counted by reason alone, so the confirmations of an `InsufficientDepth`
+		/// reason are ignored.
+		#[ink(message)]
+		pub fn failure_count(&self, reason: VerificationFailure) -> u64 {
+			self.failure_counts
+				.get((self.failure_epoch, reason.reason()))
+				.unwrap_or_default()
+		}
+
+		/// The failure epoch whose counts `failure_count` reports.
+		#[ink(message)]
+		pub fn failure_epoch(&self) -> u32 {
+			self.failure_epoch
+		}
+
+		/// Start a new failure epoch, resetting every failure count to zero.
+		/// Only admins may call this.
+		#[ink(message)]
+		pub fn reset_failure_counts(&mut self) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.failure_epoch += 1;
+			Ok(())
+		}
+
+		/// The salt this deployment mixes into the identifiers it issues for verified claims.
+		#[ink(message)]
+		pub fn deployment_salt(&self) -> HashValue {
+			self.deployment_salt
+		}
+
+		/// This deployment's identifier for the given transaction hash or state claim leaf in the
+		/// given block, as reported when the claim is verified.
+		///
+		/// Unlike the leaf, which is fixed by the source chain, the identifier is unique to this
+		/// deployment, so receipts can't be replayed against another bridge of the same chain.
+		#[ink(message)]
+		pub fn claim_id(&self, leaf: HashValue, block_hash: HashValue) -> HashValue {
+			codec::claim_id(&self.deployment_salt, &leaf, &block_hash)
+		}
+
+		/// The version of the storage layout, see `STORAGE_VERSION`.
+		#[ink(message)]
+		pub fn storage_version(&self) -> u32 {
+			self.storage_version
+		}
+
+		/// Allow headers more than `prune_depth` blocks behind the tip to be pruned, or disallow
+		/// pruning with `None`. Only admins may call this.
+		///
+		/// The depth must cover the `MEDIAN_TIME_SPAN` ancestors, and the difficulty epoch if
+		/// retargeting, that are needed to validate new headers.
+		#[ink(message)]
+		pub fn set_prune_depth(&mut self, prune_depth: Option<u64>) -> Result<()> {
+			self.set_chain_prune_depth(DEFAULT_CHAIN_ID, prune_depth)
+		}
+
+		/// Set the prune depth of the given source chain, like `set_prune_depth`. Only admins may
+		/// call this.
+		///
+		/// Chains whose headers are proven final are not pruned, since their headers need not be
+		/// at consecutive heights, and can't be given a prune depth.
+		#[ink(message)]
+		pub fn set_chain_prune_depth(
+			&mut self,
+			chain_id: ChainId,
+			prune_depth: Option<u64>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			let chain = self.load_chain(chain_id)?;
+			if chain.consensus != ConsensusBackend::ProofOfWork {
+				return Err(Error::WrongConsensus)
+			}
+			let min_depth = Self::min_prune_depth(chain.retarget);
+			if prune_depth.is_some_and(|depth| depth < min_depth) {
+				return Err(Error::InvalidPruneDepth)
+			}
+			self.schedule_parameter(if chain_id == DEFAULT_CHAIN_ID {
+				BridgeParameter::PruneDepth(prune_depth)
+			} else {
+				BridgeParameter::ChainPruneDepth(chain_id, prune_depth)
+			})
+		}
+
+		/// Make the finalized height trail the tip by `finality_depth` blocks, or stop advancing
+		/// it with `None`. Only admins may call this.
+		///
+		/// The finalized height never decreases. It catches up the next time the tip changes.
+		#[ink(message)]
+		pub fn set_finality_depth(&mut self, finality_depth: Option<u64>) -> Result<()> {
+			self.set_chain_finality_depth(DEFAULT_CHAIN_ID, finality_depth)
+		}
+
+		/// Set the finality depth of the given source chain, like `set_finality_depth`. Only
+		/// admins may call this.
+		#[ink(message)]
+		pub fn set_chain_finality_depth(
+			&mut self,
+			chain_id: ChainId,
+			finality_depth: Option<u64>,
+		) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.load_chain(chain_id)?;
+			self.schedule_parameter(if chain_id == DEFAULT_CHAIN_ID {
+				BridgeParameter::FinalityDepth(finality_depth)
+			} else {
+				BridgeParameter::ChainFinalityDepth(chain_id, finality_depth)
+			})
+		}
+
+		/// The height of the highest finalized canon block. Blocks at or below it can never be
+		/// reorganized out of the canon chain.
+		#[ink(message)]
+		pub fn finalized_height(&self) -> u64 {
+			self.default_chain().finalized_height
+		}
+
+		/// How far behind the tip headers may be pruned, if pruning is enabled.
+		#[ink(message)]
+		pub fn prune_depth(&self) -> Option<u64> {
+			self.default_chain().prune_depth
+		}
+
+		/// Headers below this height have been pruned.
+		#[ink(message)]
+		pub fn pruned_height(&self) -> u64 {
+			self.default_chain().pruned_height
+		}
+
+		/// The hash and height of the checkpoint the default chain is anchored at, which is the
+		/// deployment checkpoint unless the owner has reset it.
+		#[ink(message)]
+		pub fn checkpoint(&self) -> (HashValue, u64) {
+			let height = self.default_chain().checkpoint_height;
+			let hash = self.canon_hash_at(height).expect("the checkpoint is always canon; qed");
+			(hash, height)
+		}
+
+		/// How this deployment is configured, for the default chain.
+		#[ink(message)]
+		pub fn bridge_config(&self) -> BridgeConfig {
+			let chain = self.default_chain();
+			BridgeConfig {
+				consensus: chain.consensus,
+				header_format: chain.header_format,
+				hash_algorithm: self.hash_algorithm,
+				retarget: chain.retarget,
+				difficulty_threshold: chain.difficulty_threshold,
+				relay_fee: chain.relay_fee,
+				verify_fee: chain.verify_fee,
+				prune_depth: chain.prune_depth,
+				checkpoint: self.checkpoint_header,
+				code_version: STORAGE_VERSION,
+			}
+		}
+
+		/// Remove up to `max_items` headers that are more than `prune_depth` blocks behind the tip,
+		/// lowest first, and return how many were removed. Anyone may call this, and the caller
+		/// is refunded the storage deposit of the removed entries.
+		///
+		/// A pruned header can no longer be verified against, or built upon. Its canon chain entry
+		/// is kept, so `canon_hash_at` still answers for pruned heights.
+		#[ink(message)]
+		pub fn prune(&mut self, max_items: u32) -> u32 {
+			self.prune_chain(DEFAULT_CHAIN_ID, max_items)
+		}
+
+		/// Remove old headers of the given source chain, like `prune`. Nothing is removed from an
+		/// unknown chain, or from one whose headers are proven final.
+		#[ink(message)]
+		pub fn prune_chain(&mut self, chain_id: ChainId, max_items: u32) -> u32 {
+			let Some(mut chain) = self.chains.get(chain_id) else { return 0 };
+			let Some(prune_depth) = chain.prune_depth else { return 0 };
+			if chain.consensus != ConsensusBackend::ProofOfWork {
+				return 0
+			}
+
+			let mut pruned = 0;
+			while pruned < max_items &&
+				chain.best_height.saturating_sub(chain.pruned_height) > prune_depth
+			{
+				let key = (chain_id, chain.pruned_height);
+				let mut hashes = self.headers_at_height.get(key).unwrap_or_default();
+				while pruned < max_items {
+					let Some(block_hash) = hashes.pop() else { break };
+					self.headers.remove((chain_id, block_hash));
+					self.fee_recipient.remove((chain_id, block_hash));
+					self.chain_work.remove((chain_id, block_hash));
+					self.header_provenance.remove((chain_id, block_hash));
+					self.aux_pow_parents.remove((chain_id, block_hash));
+					self.header_extensions.remove((chain_id, block_hash));
+					self.ancestor_links.remove((chain_id, block_hash));
+					self.children.remove((chain_id, block_hash));
+					self.remove_fork_tip(chain_id, block_hash);
+					self.free_verifications_used.remove((chain_id, block_hash));
+					self.verified_depths.remove((chain_id, block_hash));
+					chain.header_count = chain.header_count.saturating_sub(1);
+					if key.1 <= chain.finalized_height &&
+						self.canon_chain.get(key) != Some(block_hash)
+					{
+						chain.orphan_count = chain.orphan_count.saturating_sub(1);
+					}
+					pruned += 1;
+				}
+				if !hashes.is_empty() {
+					self.headers_at_height.insert(key, &hashes);
+					break
+				}
+				self.headers_at_height.remove(key);
+				chain.pruned_height += 1;
+			}
+
+			if pruned > 0 {
+				self.chains.insert(chain_id, &chain);
+				self.env().emit_event(HeadersPruned {
+					chain_id,
+					count: pruned,
+					pruned_height: chain.pruned_height,
+				});
+			}
+			pruned
+		}
+
+		/// The maximum number of siblings a merkle proof may have.
+		#[ink(message)]
+		pub fn max_proof_length(&self) -> u32 {
+			self.max_proof_length
+		}
+
+		/// The maximum number of headers that may be submitted in one batch.
+		#[ink(message)]
+		pub fn max_batch_size(&self) -> u32 {
+			self.max_batch_size
+		}
+
+		/// Sample the default chain's best height at most once per `sample_interval` target chain
+		/// blocks, or stop sampling with `None`. Only admins may call this.
+		#[ink(message)]
+		pub fn set_sample_interval(&mut self, sample_interval: Option<BlockNumber>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::SampleInterval(sample_interval))
+		}
+
+		/// Sample the default chain's best height, if a sample is due. Returns whether one was
+		/// taken. Anyone may call this.
+		///
+		/// Samples are also taken whenever the default chain's tip changes, but calling this lets
+		/// the samples show the bridge falling behind while no headers are submitted.
+		#[ink(message)]
+		pub fn record_height_sample(&mut self) -> bool {
+			let Some(interval) = self.sample_interval else { return false };
+			let block_number = self.current_block();
+			let last_sample = self
+				.sample_count
+				.checked_sub(1)
+				.and_then(|last| self.height_samples.get(last % MAX_HEIGHT_SAMPLES));
+			if last_sample.is_some_and(|sample| block_number < sample.block_number + interval) {
+				return false
+			}
+
+			let sample =
+				HeightSample { block_number, best_height: self.default_chain().best_height };
+			self.height_samples.insert(self.sample_count % MAX_HEIGHT_SAMPLES, &sample);
+			self.sample_count = self.sample_count.wrapping_add(1);
+			true
+		}
+
+		/// The last `MAX_HEIGHT_SAMPLES` best height samples, oldest first.
+		///
+		/// Comparing the samples with the source chain's history gives the bridge's lag over
+		/// time, against which relayers can be held to service level expectations.
+		#[ink(message)]
+		pub fn height_samples(&self) -> Vec<HeightSample> {
+			let first = self.sample_count.saturating_sub(MAX_HEIGHT_SAMPLES);
+			(first..self.sample_count)
+				.filter_map(|index| self.height_samples.get(index % MAX_HEIGHT_SAMPLES))
+				.collect()
+		}
+
+		/// The current estimate of the source chain's block time, in milliseconds.
+		///
+		/// This is an exponential moving average over the timestamp intervals of accepted headers
+		/// that extended the best chain. Returns `None` until at least one such header is known.
+		#[ink(message)]
+		pub fn estimated_block_time(&self) -> Option<u64> {
+			self.default_chain().block_time_ema
+		}
+
+		/// Whether the bridge appears to have stopped receiving new headers.
+		///
+		/// That is the case once no new tip has been accepted for `STALENESS_BLOCK_TIMES`
+		/// estimated source block times. Before there is an estimate, the bridge is never stale.
+		#[ink(message)]
+		pub fn is_stale(&self) -> bool {
+			self.default_chain().is_stale(self.now())
+		}
+
+		/// Everything needed to build a `verify_transaction` or `verify_state` call against the
+		/// given block, or `None` if the block is unknown.
+		#[ink(message)]
+		pub fn verification_context(&self, block_hash: HashValue) -> Option<VerificationContext> {
+			let chain = self.default_chain();
+			let header = self.headers.get((DEFAULT_CHAIN_ID, block_hash))?;
+			let canonical_hash = self.canon_chain.get((DEFAULT_CHAIN_ID, header.height));
+			let current_depth =
+				(canonical_hash == Some(block_hash)).then(|| chain.best_height - header.height);
+
+			Some(VerificationContext {
+				canonical_hash,
+				height: header.height,
+				current_depth,
+				required_fee: chain.verify_fee,
+				is_stale: chain.is_stale(self.now()),
+			})
+		}
+
+		/// Whether `verify_transaction` would accept a proof against the given block at
+		/// `min_depth`: the bridge is neither paused nor halted nor stale, and the block is known,
+		/// not blacklisted, and canon with at least `min_depth` or `min_confirmations`
+		/// confirmations, whichever is more.
+		///
+		/// No fee is charged, so wallets can poll cheaply before paying for a full verification.
+		/// Without a proof, the transaction itself is not checked against the block.
+		#[ink(message)]
+		pub fn is_transaction_confirmed(
+			&self,
+			_tx_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+		) -> bool {
+			let chain = self.default_chain();
+			if self.ensure_not_paused().is_err() ||
+				self.exceeds_staleness_window(&chain) ||
+				self.is_blacklisted(DEFAULT_CHAIN_ID, header_hash)
+			{
+				return false
+			}
+			let min_depth = min_depth.max(self.min_confirmations);
+			self.headers.get((DEFAULT_CHAIN_ID, header_hash)).is_some_and(|header| {
+				self.is_canon(DEFAULT_CHAIN_ID, header_hash) &&
+					chain.best_height - header.height >= min_depth
+			})
+		}
+
+		/// Approximate counts of the entries the bridge keeps in storage for the default chain,
+		/// and of the submission receipts kept for all chains.
+		///
+		/// Operators can use this to watch state growth and schedule pruning before storage
+		/// deposits become expensive. Stored canon headers are counted from the pruned height, so
+		/// the split between canon and fork headers is off by one while a height is partially
+		/// pruned.
+		#[ink(message)]
+		pub fn storage_report(&self) -> StorageReport {
+			let chain = self.default_chain();
+			let canon_headers = chain.best_height - chain.pruned_height + 1;
+			StorageReport {
+				canon_headers,
+				fork_headers: chain.header_count.saturating_sub(canon_headers),
+				canon_entries: chain.best_height - chain.checkpoint_height + 1,
+				receipts: self.receipt_count,
+				orphans: chain.orphan_count,
+			}
+		}
+
+		/// Which optional subsystems this deployment has enabled.
+		///
+		/// SDKs and consumer contracts can check this to adapt to how a deployment is configured,
+		/// instead of finding out from calls that fail.
+		#[ink(message)]
+		pub fn capabilities(&self) -> Capabilities {
+			let chain = self.default_chain();
+			Capabilities {
+				header_format: chain.header_format,
+				retargeting: chain.retarget.is_some(),
+				merged_mining: chain.merged_mining,
+				rolling_finality: chain.finality_depth.is_some(),
+				pruning: chain.prune_depth.is_some(),
+				fee_escrow: self.payout_depth.is_some() || self.escrow_safety_margin.is_some(),
+				subsidized_verifications: self.free_verifications > 0,
+				height_sampling: self.sample_interval.is_some(),
+				optimistic_submission: self.challenge_period.is_some(),
+				relayer_staking: self.min_relayer_stake.is_some(),
+				relayer_allowlist: self.allowlist_enabled,
+				rate_limiting: self.rate_limit.is_some(),
+				staleness_freeze: self.staleness_window.is_some(),
+				reorg_circuit_breaker: self.max_reorg_depth.is_some(),
+				dynamic_relay_fee: self.fee_controller.is_some(),
+				xcm_forwarding: self.xcm_destination.is_some(),
+				wrapped_asset: self.wrapped_asset.is_some(),
+				hash_algorithm: self.hash_algorithm,
+				crypto_extension: crypto::OFFLOADED,
+				test_helpers: cfg!(any(test, feature = "test-helpers")),
+			}
+		}
+
+		/// Subscribe the caller to bridge health alerts.
+		///
+		/// The caller must lock `ALERT_SUBSCRIPTION_DEPOSIT`, which is refunded on unsubscribing.
+		/// Subscribers are named as a topic on every `BridgeAlert` event.
+		#[ink(message, payable)]
+		pub fn subscribe_alerts(&mut self) -> Result<()> {
+			let deposit = self.env().transferred_value();
+			if deposit < ALERT_SUBSCRIPTION_DEPOSIT {
+				return Err(Error::InsufficientAlertDeposit)
+			}
+			let subscriber = self.caller();
+			if self.alert_deposits.contains(subscriber) {
+				return Err(Error::AlreadySubscribed)
+			}
+			if self.alert_subscribers.len() >= MAX_ALERT_SUBSCRIBERS as usize {
+				return Err(Error::AlertRegistryFull)
+			}
+
+			self.alert_subscribers.push(subscriber);
+			self.alert_deposits.insert(subscriber, &deposit);
+			self.env().emit_event(AlertSubscribed { subscriber });
+
+			Ok(())
+		}
+
+		/// Unsubscribe the caller from bridge health alerts and refund their deposit.
+		#[ink(message)]
+		pub fn unsubscribe_alerts(&mut self) -> Result<()> {
+			let subscriber = self.caller();
+			let deposit = self.alert_deposits.take(subscriber).ok_or(Error::NotSubscribed)?;
+			self.alert_subscribers.retain(|account| *account != subscriber);
+			self.env().transfer(subscriber, deposit).map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(AlertUnsubscribed { subscriber });
+
+			Ok(())
+		}
+
+		/// Forward every verified transaction to a parachain over XCM, or stop with `None`. Only
+		/// admins may call this.
+		///
+		/// After a transaction is verified, the bridge sends the parachain a `Transact` of
+		/// `codec::xcm_claim_call`, from its sovereign account, through the runtime's XCM chain
+		/// extension. The contract must be built with the `xcm-extension` feature, and its account
+		/// pays the delivery and execution fees out of the verify fees it collects. A message the
+		/// runtime fails to send does not fail the verification: `ClaimForwarded` reports it.
+		#[ink(message)]
+		pub fn set_xcm_destination(&mut self, destination: Option<XcmDestination>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if destination.is_some() && !xcm::ENABLED {
+				return Err(Error::XcmUnavailable)
+			}
+			self.schedule_parameter(BridgeParameter::XcmDestination(destination))
+		}
+
+		/// Mint the wrapped asset for a deposit locked on the default source chain.
+		///
+		/// A deposit is a transaction, given in its canonical encoding, that transfers value to
+		/// the wrapped asset's lock address. Once its block has the configured number of
+		/// confirmations, the deposited amount is minted to the account with the same 32 bytes as
+		/// the deposit's sender. The verify fee is paid as for `verify_transaction`. Each source
+		/// transaction is minted at most once. Returns the minted amount.
+		#[ink(message, payable)]
+		pub fn mint_deposit(
+			&mut self,
+			transaction: Vec<u8>,
+			header_hash: HashValue,
+			p: MerkleProof,
+		) -> Result<Balance> {
+			self.ensure_not_paused()?;
+			let asset = self.wrapped_asset.ok_or(Error::WrappedAssetDisabled)?;
+			let deposit =
+				codec::decode_transaction(&transaction).map_err(|_| Error::MalformedTransaction)?;
+			if deposit.recipient != asset.lock_address {
+				return Err(Error::NotADeposit)
+			}
+			let tx_hash = codec::transaction_hash(&deposit);
+			if self.minted_deposits.contains(tx_hash) {
+				return Err(Error::DepositAlreadyMinted)
+			}
+
+			let paid = self.env().transferred_value();
+			self.verify_tx(DEFAULT_CHAIN_ID, paid, tx_hash, header_hash, asset.confirmations, p)
+				.map_err(|error| match error {
+					VerificationError::Rejected(error) => error,
+					VerificationError::Failed(_) => Error::DepositNotProven,
+				})?;
+
+			self.minted_deposits.insert(tx_hash, &());
+			let beneficiary = AccountId::from(deposit.sender);
+			if !psp22::mint(asset.token, beneficiary, deposit.amount) {
+				return Err(Error::MintFailed)
+			}
+			self.env()
+				.emit_event(DepositMinted { tx_hash, beneficiary, amount: deposit.amount });
+			Ok(deposit.amount)
+		}
+
+		/// Burn `amount` of the caller's wrapped tokens, to have the locked deposit released to
+		/// `source_address` on the default source chain.
+		///
+		/// Emits `ReleaseRequested` with a nonce that increases with every release, for relayers
+		/// to prove on the source chain. Returns the nonce.
+		#[ink(message)]
+		pub fn burn_for_release(
+			&mut self,
+			amount: Balance,
+			source_address: HashValue,
+		) -> Result<u64> {
+			self.ensure_not_paused()?;
+			let asset = self.wrapped_asset.ok_or(Error::WrappedAssetDisabled)?;
+			let account = self.caller();
+			if !psp22::burn(asset.token, account, amount) {
+				return Err(Error::BurnFailed)
+			}
+
+			let nonce = self.release_nonce;
+			self.release_nonce += 1;
+			self.env()
+				.emit_event(ReleaseRequested { nonce, account, source_address, amount });
+			Ok(nonce)
+		}
+
+		/// The nonce that the next release request will be given.
+		#[ink(message)]
+		pub fn release_nonce(&self) -> u64 {
+			self.release_nonce
+		}
+
+		/// Whether the deposit made by the given source transaction has been minted.
+		#[ink(message)]
+		pub fn is_deposit_minted(&self, tx_hash: HashValue) -> bool {
+			self.minted_deposits.contains(tx_hash)
+		}
+
+		/// Set the token minted for proven deposits, or stop minting with `None`. Only admins may
+		/// call this.
+		#[ink(message)]
+		pub fn set_wrapped_asset(&mut self, asset: Option<WrappedAsset>) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.schedule_parameter(BridgeParameter::WrappedAsset(asset))
+		}
+
+		/// Ask to subscribe the calling contract to notifications of canon tip changes on any
+		/// source chain.
+		///
+		/// Whenever a new canon tip is accepted, or a reorg retracts canon blocks, the bridge calls
+		/// the caller's message with the given selector, passing a `HeaderNotification`. The call
+		/// may use at most `HEADER_CALLBACK_GAS_LIMIT` gas and cannot reenter the bridge; if it
+		/// fails the header is still accepted. Relayers pay for every callback, so notifications
+		/// only start once an admin approves the subscription, see `approve_header_subscriber`.
+		/// The caller must lock `HEADER_SUBSCRIPTION_DEPOSIT`, which is refunded on unsubscribing.
+		#[ink(message, payable)]
+		pub fn subscribe_headers(&mut self, selector: [u8; 4]) -> Result<()> {
+			let deposit = self.env().transferred_value();
+			if deposit < HEADER_SUBSCRIPTION_DEPOSIT {
+				return Err(Error::InsufficientSubscriptionDeposit)
+			}
+			let contract = self.caller();
+			if self.pending_header_subscribers.contains(contract) ||
+				self.header_subscribers.iter().any(|subscriber| subscriber.contract == contract)
+			{
+				return Err(Error::AlreadySubscribed)
+			}
+
+			self.pending_header_subscribers
+				.insert(contract, &HeaderSubscriber { contract, selector, deposit });
+			self.env()
+				.emit_event(HeaderSubscriptionRequested { subscriber: contract, selector });
+			Ok(())
+		}
+
+		/// Approve a contract's pending header subscription, after which it is notified of canon
+		/// tip changes. Only admins may call this.
+		#[ink(message)]
+		pub fn approve_header_subscriber(&mut self, contract: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			if self.header_subscribers.len() >= MAX_HEADER_SUBSCRIBERS as usize {
+				return Err(Error::HeaderRegistryFull)
+			}
+			let subscriber =
+				self.pending_header_subscribers.take(contract).ok_or(Error::NotSubscribed)?;
+			self.header_subscribers.push(subscriber);
+			self.env().emit_event(HeaderSubscribed { subscriber: contract });
+			Ok(())
+		}
+
+		/// Remove a contract's header subscription, approved or pending, and refund its deposit.
+		/// Only admins may call this.
+		#[ink(message)]
+		pub fn remove_header_subscriber(&mut self, contract: AccountId) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.drop_header_subscriber(contract)
+		}
+
+		/// Unsubscribe the calling contract from header notifications, or withdraw its pending
+		/// request, and refund its deposit.
+		#[ink(message)]
+		pub fn unsubscribe_headers(&mut self) -> Result<()> {
+			let contract = self.caller();
+			self.drop_header_subscriber(contract)
+		}
+
+		/// The contract's header subscription that is waiting for an admin's approval, if any.
+		#[ink(message)]
+		pub fn pending_header_subscriber(&self, contract: AccountId) -> Option<HeaderSubscriber> {
+			self.pending_header_subscribers.get(contract)
+		}
+
+		/// The contracts currently subscribed to header notifications.
+		#[ink(message)]
+		pub fn header_subscribers(&self) -> Vec<HeaderSubscriber> {
+			self.header_subscribers.clone()
+		}
+
+		/// The id of the given relayer's accepted submission of the given header.
+		///
+		/// Receipts can only be looked up for `SUBMISSION_RECEIPT_TTL` blocks after the submission,
+		/// long enough for relayer software to recover from RPC timeouts.
+		#[ink(message)]
+		pub fn submission_receipt(
+			&self,
+			relayer: AccountId,
+			header_hash: HashValue,
+		) -> Option<SubmissionId> {
+			self.submission_receipts
+				.get((relayer, header_hash))
+				.filter(|receipt| !self.receipt_expired(receipt))
+				.map(|receipt| receipt.id)
+		}
+
+		/// Delete an expired submission receipt, freeing its storage. Anyone may call this.
+		#[ink(message)]
+		pub fn remove_expired_receipt(
+			&mut self,
+			relayer: AccountId,
+			header_hash: HashValue,
+		) -> Result<()> {
+			let key = (relayer, header_hash);
+			let receipt = self.submission_receipts.get(key).ok_or(Error::UnknownReceipt)?;
+			if !self.receipt_expired(&receipt) {
+				return Err(Error::ReceiptNotExpired)
+			}
+			self.submission_receipts.remove(key);
+			self.receipt_count = self.receipt_count.saturating_sub(1);
+			Ok(())
+		}
+
+		/// Pay out an escrowed verify fee.
+		///
+		/// If the verified block is no longer canon, the fee is refunded to the verifier.
+		/// Otherwise it is added to the pending rewards of the block's relayer once the chain
+		/// reaches the release height.
+		/// Anyone may settle an escrow.
+		#[ink(message)]
+		pub fn settle_escrow(&mut self, escrow_id: u64) -> Result<()> {
+			let escrow = self.fee_escrows.get(escrow_id).ok_or(Error::UnknownEscrow)?;
+			let chain = self.load_chain(escrow.chain_id)?;
+
+			// The canon chain entry outlives the header itself if it gets pruned.
+			let (recipient, refunded) =
+				if self.canon_chain.get((escrow.chain_id, escrow.block_height)) ==
+					Some(escrow.block_hash)
+				{
+					if chain.best_height < escrow.release_height {
+						return Err(Error::EscrowNotMature)
+					}
+					(escrow.relayer, false)
+				} else {
+					(escrow.verifier, true)
+				};
+
+			self.fee_escrows.remove(escrow_id);
+			if refunded {
+				self.env()
+					.transfer(recipient, escrow.amount)
+					.map_err(|_| Error::PaymentFailed)?;
+			} else {
+				self.credit_reward(recipient, escrow.amount);
+				self.update_relayer_stats(recipient, |stats| {
+					stats.rewards_earned = stats.rewards_earned.saturating_add(escrow.amount)
+				});
+			}
+			self.env().emit_event(EscrowSettled { escrow_id, recipient, refunded });
+
+			Ok(())
+		}
+
+		/// Pay the caller all of the verify fees they have earned as a relayer.
+		#[ink(message)]
+		pub fn withdraw_rewards(&mut self) -> Result<()> {
+			let relayer = self.caller();
+			let amount = self.pending_rewards.take(relayer).ok_or(Error::NoPendingRewards)?;
+			self.env().transfer(relayer, amount).map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(RewardsWithdrawn { relayer, amount });
+
+			Ok(())
+		}
+
+		/// The verify fees the given relayer has earned but not yet withdrawn.
+		#[ink(message)]
+		pub fn pending_rewards(&self, relayer: AccountId) -> Balance {
+			self.pending_rewards.get(relayer).unwrap_or_default()
+		}
+
+		/// Let the first `free_verifications` verifications against each header be made without
+		/// paying the verify fee, to bootstrap usage of newly relayed blocks. Only fee managers may
+		/// call this.
+		///
+		/// The relayer is still paid, from the subsidy pool. Once the pool can't cover the fee, or
+		/// a header's free verifications are used up, the standard fee applies again.
+		#[ink(message)]
+		pub fn set_free_verifications(&mut self, free_verifications: u32) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			self.schedule_parameter(BridgeParameter::FreeVerifications(free_verifications))
+		}
+
+		/// The relay fees and slashed stake the bridge has collected and not yet spent.
+		#[ink(message)]
+		pub fn treasury_balance(&self) -> Balance {
+			self.treasury
+		}
+
+		/// Pay some of the treasury to the given account. Only admins may call this.
+		#[ink(message)]
+		pub fn spend_treasury(&mut self, to: AccountId, amount: Balance) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.withdraw_treasury(amount)?;
+			self.env().transfer(to, amount).map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(TreasurySpent { to: Some(to), amount });
+			Ok(())
+		}
+
+		/// Move some of the treasury into the subsidy pool, recycling relay fees into relayer
+		/// rewards for subsidized verifications. Only admins may call this.
+		#[ink(message)]
+		pub fn recycle_treasury(&mut self, amount: Balance) -> Result<()> {
+			self.ensure_role(Role::Admin)?;
+			self.withdraw_treasury(amount)?;
+			self.subsidy_pool = self.subsidy_pool.saturating_add(amount);
+			self.env().emit_event(TreasurySpent { to: None, amount });
+			Ok(())
+		}
+
+		/// Exempt an account, such as a public-good dApp or infrastructure contract, from the
+		/// verify fee for up to `quota` verifications per `FEE_EXEMPTION_PERIOD` target chain
+		/// blocks, or revoke its exemption with `None`. Only fee managers may call this.
+		///
+		/// A verification that the account does not transfer the verify fee for is then free
+		/// while it has quota left, before its credits or the subsidy pool are drawn on. Nobody
+		/// pays for it, so the relayer of the verified block is not paid either.
+		#[ink(message)]
+		pub fn set_fee_exemption(&mut self, account: AccountId, quota: Option<u32>) -> Result<()> {
+			self.ensure_role(Role::FeeManager)?;
+			if let Some(quota) = quota {
+				self.fee_exemptions.insert(account, &quota);
+			} else {
+				self.fee_exemptions.remove(account);
+				self.fee_exemption_usage.remove(account);
+			}
+			self.env().emit_event(FeeExemptionSet { account, quota });
+			Ok(())
+		}
+
+		/// How many more fee exempt verifications the given account may make in the current
+		/// period, or `None` if it is not exempted.
+		#[ink(message)]
+		pub fn fee_exemptions_left(&self, account: AccountId) -> Option<u32> {
+			let quota = self.fee_exemptions.get(account)?;
+			Some(quota.saturating_sub(self.fee_exemptions_used(account)))
+		}
+
+		/// Add the transferred value to the caller's prepaid verification credits.
+		///
+		/// Verifications that the caller does not transfer the verify fee for draw it from their
+		/// credits instead, as long as they cover it.
+		#[ink(message, payable)]
+		pub fn deposit_credits(&mut self) -> Result<()> {
+			let account = self.caller();
+			let amount = self.env().transferred_value();
+			let credits = self.credits_of(account).saturating_add(amount);
+			self.credits.insert(account, &credits);
+			self.env().emit_event(CreditsDeposited { account, amount });
+			Ok(())
+		}
+
+		/// Pay the caller all of their unused verification credits.
+		#[ink(message)]
+		pub fn withdraw_credits(&mut self) -> Result<()> {
+			let account = self.caller();
+			let amount = self.credits.take(account).ok_or(Error::NoCredits)?;
+			self.env().transfer(account, amount).map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(CreditsWithdrawn { account, amount });
+			Ok(())
+		}
+
+		/// The prepaid verification credits the given account has left.
+		#[ink(message)]
+		pub fn credits_of(&self, account: AccountId) -> Balance {
+			self.credits.get(account).unwrap_or_default()
+		}
+
+		/// Pay up to `allowance` of the given user's verify fees from the caller's prepaid
+		/// credits, replacing any allowance the caller gave them before. An allowance of zero ends
+		/// the sponsorship.
+		///
+		/// Verifications that the user does not transfer the verify fee for, and their own credits
+		/// do not cover, are then paid from the sponsor's credits while the allowance and credits
+		/// last. A user can only have one sponsor at a time.
+		#[ink(message)]
+		pub fn sponsor(&mut self, user: AccountId, allowance: Balance) -> Result<()> {
+			let sponsor = self.caller();
+			if self.sponsorships.get(user).is_some_and(|current| current.sponsor != sponsor) {
+				return Err(Error::AlreadySponsored)
+			}
+			if allowance == 0 {
+				self.sponsorships.remove(user);
+			} else {
+				self.sponsorships.insert(user, &Sponsorship { sponsor, allowance });
+			}
+			self.env().emit_event(SponsorshipSet { sponsor, user, allowance });
+			Ok(())
+		}
+
+		/// The sponsor of the given user and how much more of their fees it will pay, if any.
+		#[ink(message)]
+		pub fn sponsorship_of(&self, user: AccountId) -> Option<Sponsorship> {
+			self.sponsorships.get(user)
+		}
+
+		/// Add the transferred value to the subsidy pool. Anyone may call this.
+		#[ink(message, payable)]
+		pub fn fund_subsidies(&mut self) {
+			self.subsidy_pool = self.subsidy_pool.saturating_add(self.env().transferred_value());
+		}
+
+		/// The funds left to pay for subsidized verifications.
+		#[ink(message)]
+		pub fn subsidy_pool(&self) -> Balance {
+			self.subsidy_pool
+		}
+
+		/// How many more verifications against the given header can be subsidized, ignoring the
+		/// funds left in the subsidy pool.
+		#[ink(message)]
+		pub fn free_verifications_left(&self, header_hash: HashValue) -> u32 {
+			let key = (DEFAULT_CHAIN_ID, header_hash);
+			if !self.headers.contains(key) {
+				return 0
+			}
+			let used = self.free_verifications_used.get(key).unwrap_or_default();
+			self.free_verifications.saturating_sub(used)
+		}
+
+		/// Recompute the linkage and cumulative work of the canon chain between the given heights,
+		/// inclusive, and attest to its consistency with a `ChainConsistencyAttested` event.
+		///
+		/// Each canon block in the range must be stored under its own hash at its own height.
+		/// Each one above `from_height` must also be the child of the canon block below it,
+		/// satisfy its PoW target, and have the cumulative work of its parent plus its own.
+		/// At most `MAX_CONSISTENCY_RANGE` blocks are checked at once. Anyone may call this.
+		#[ink(message)]
+		pub fn assert_chain_consistency(&self, from_height: u64, to_height: u64) -> Result<()> {
+			if from_height > to_height {
+				return Err(Error::InvalidRange)
+			}
+			if to_height - from_height >= MAX_CONSISTENCY_RANGE {
+				return Err(Error::RangeTooLarge)
+			}
+			let chain = self.default_chain();
+			if from_height < chain.pruned_height || to_height > chain.best_height {
+				return Err(Error::InvalidRange)
+			}
+
+			// The hash and cumulative work of the block below the one being checked.
+			let mut parent: Option<(HashValue, U256)> = None;
+			for height in from_height..=to_height {
+				let block_hash =
+					self.canon_chain.get((DEFAULT_CHAIN_ID, height)).ok_or(Error::InvalidRange)?;
+				let key = (DEFAULT_CHAIN_ID, block_hash);
+				let header = self.headers.get(key).ok_or(Error::InconsistentChain)?;
+				let work = self.chain_work.get(key).ok_or(Error::InconsistentChain)?;
+				let extension = self.header_extensions.get(key).unwrap_or_default();
+				if header.height != height ||
+					codec::header_v1_hash(self.hash_algorithm, &header, &extension) != block_hash
+				{
+					return Err(Error::InconsistentChain)
+				}
+				if let Some((parent_hash, parent_work)) = parent {
+					let expected_work =
+						parent_work.saturating_add(Self::work_from_target(header.target));
+					if header.parent != parent_hash ||
+						!Self::meets_target(
+							self.aux_pow_parents.get(key).unwrap_or(block_hash),
+							header.target,
+						) || work != expected_work
+					{
+						return Err(Error::InconsistentChain)
+					}
+				}
+				parent = Some((block_hash, work));
+			}
+
+			let (to_hash, work) = parent.expect("the range is not empty; qed");
+			self.env().emit_event(ChainConsistencyAttested {
+				auditor: self.caller(),
+				from_height,
+				to_height,
+				to_hash,
+				work,
+			});
+
+			Ok(())
+		}
+
+		/// The fee escrow with the given id, if it has not yet been settled.
+		#[ink(message)]
+		pub fn fee_escrow(&self, escrow_id: u64) -> Option<FeeEscrow> {
+			self.fee_escrows.get(escrow_id)
+		}
+
+		/// The accounts currently subscribed to bridge health alerts.
+		#[ink(message)]
+		pub fn alert_subscribers(&self) -> Vec<AccountId> {
+			self.alert_subscribers.clone()
+		}
+
+		/// Helper function to hash a block header.
+		/// It would be pretty reasonable to just put this inline.
+		/// But we provide it to help avoid bit-level errors from hashing differently.
+		///
+		/// This is the hash under the default `HashAlgorithm::Sha2x256`, see `hash_algorithm`.
+		pub fn hash_header(header: Header) -> HashValue {
+			codec::header_hash(&header)
+		}
+
+		/// The hash function this deployment identifies native headers and state claims by.
+		#[ink(message)]
+		pub fn hash_algorithm(&self) -> HashAlgorithm {
+			self.hash_algorithm
+		}
+
+		/// The hash of a native header under this deployment's hash algorithm.
+		fn header_hash(&self, header: Header) -> HashValue {
+			codec::header_hash_with(self.hash_algorithm, &header)
+		}
+
+		/// The storage leaf of a state claim under this deployment's hash algorithm.
+		fn claim_leaf(&self, claim: StateClaim) -> HashValue {
+			codec::state_claim_leaf_with(self.hash_algorithm, &claim)
+		}
+
+		/// The cumulative work of the given header and its ancestors back to the checkpoint,
+		/// if the header is known.
+		#[ink(message)]
+		pub fn chain_work(&self, header_hash: HashValue) -> Option<U256> {
+			self.chain_work.get((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// The expected number of hashes needed to find a block hash below the given target.
+		pub fn work_from_target(target: HashValue) -> U256 {
+			U256::from_be_bytes(target).work()
+		}
+
+		/// Whether a block hash is below the given target, comparing both as 256 bit numbers.
+		pub fn meets_target(block_hash: HashValue, target: HashValue) -> bool {
+			U256::from_be_bytes(block_hash) < U256::from_be_bytes(target)
+		}
+
+		/// Hash a state claim into the leaf that is committed to in a block's storage root, under
+		/// the default `HashAlgorithm::Sha2x256`.
+		pub fn hash_claim(claim: StateClaim) -> HashValue {
+			codec::state_claim_leaf(&claim)
+		}
+
+		/// Whether a default chain header is stored, canon or not.
+		#[ink(message)]
+		pub fn header_is_known(&self, header_hash: HashValue) -> bool {
+			self.headers.contains((DEFAULT_CHAIN_ID, header_hash))
+		}
+
+		/// Whether a default chain header is in the canon chain.
+		#[ink(message)]
+		pub fn header_is_canon(&self, header_hash: HashValue) -> bool {
+			self.is_canon(DEFAULT_CHAIN_ID, header_hash)
+		}
+
+		/// Whether a header is in the canon chain of the given source chain. The stored header is
+		/// its own height index, so this is a single lookup of the canon block at its height.
+		fn is_canon(&self, chain_id: ChainId, header_hash: HashValue) -> bool {
+			self.headers.get((chain_id, header_hash)).is_some_and(|header| {
+				self.canon_chain.get((chain_id, header.height)) == Some(header_hash)
+			})
+		}
+
+		/// The state of the given source chain, or `UnknownChain` if it is not registered.
+		fn load_chain(&self, chain_id: ChainId) -> Result<SourceChain> {
+			self.chains.get(chain_id).ok_or(Error::UnknownChain)
+		}
+
+		/// The state of the default source chain, which the constructor always registers.
+		fn default_chain(&self) -> SourceChain {
+			self.chains
+				.get(DEFAULT_CHAIN_ID)
+				.expect("the default chain is always registered; qed")
+		}
+
+		/// The hash of the tip of the given chain's canon chain.
+		fn tip_hash(&self, chain_id: ChainId, chain: &SourceChain) -> HashValue {
+			self.canon_chain
+				.get((chain_id, chain.best_height))
+				.expect("the canon chain always has a tip; qed")
+		}
+
+		/// Make the given header the tip of the canon chain.
+		///
+		/// Walks back through the new tip's ancestors until reaching a block that is already canon.
+		/// Then the canon chain entries above that common ancestor are replaced with the new
+		/// branch. Canon entries above the new tip are removed too, because a heavier chain may be
+		/// shorter than the one it replaces.
+		///
+		/// Emits `CanonBlockRetracted` for each evicted block from the top down, then
+		/// `CanonBlockAdded` for each new canon block from the bottom up, then `ReorgOccurred` if
+		/// any blocks were retracted. Returns the number of retracted blocks.
+		///
+		/// Fails if the new tip's branch has been cut off from the canon chain by pruning, or forks
+		/// from it below the finalized height.
+		fn set_canon_tip(
+			&mut self,
+			chain_id: ChainId,
+			chain: &mut SourceChain,
+			tip_hash: HashValue,
+			tip: Header,
+		) -> Result<u64> {
+			let old_tip = self.tip_hash(chain_id, chain);
+
+			let mut branch = Vec::new();
+			let mut hash = tip_hash;
+			let mut header = tip;
+			while self.canon_chain.get((chain_id, header.height)) != Some(hash) {
+				branch.push((header.height, hash));
+				hash = header.parent;
+				header = self.headers.get((chain_id, hash)).ok_or(Error::ForkPruned)?;
+			}
+			let common_ancestor = hash;
+			if header.height < chain.finalized_height {
+				return Err(Error::ConflictsWithFinalized)
+			}
+			let retracted = chain.best_height.saturating_sub(header.height);
+
+			for height in (header.height + 1..=chain.best_height).rev() {
+				if let Some(block_hash) = self.canon_chain.take((chain_id, height)) {
+					self.verified_depths.remove((chain_id, block_hash));
+					if let Some(relayer) = self.fee_recipient.get((chain_id, block_hash)) {
+						self.update_relayer_stats(relayer, |stats| {
+							stats.canon_headers = stats.canon_headers.saturating_sub(1)
+						});
+					}
+					self.env().emit_event(CanonBlockRetracted {
+						chain_id,
+						block_hash,
+						block_height: height,
+					});
+				}
+			}
+			for (block_height, block_hash) in branch.into_iter().rev() {
+				self.canon_chain.insert((chain_id, block_height), &block_hash);
+				if let Some(relayer) = self.fee_recipient.get((chain_id, block_hash)) {
+					self.update_relayer_stats(relayer, |stats| stats.canon_headers += 1);
+				}
+				self.env().emit_event(CanonBlockAdded { chain_id, block_hash, block_height });
+			}
+			chain.best_height = tip.height;
+			chain.last_tip_update = self.now();
+			if let Some(depth) = chain.finality_depth {
+				let finalized_height =
+					chain.finalized_height.max(chain.best_height.saturating_sub(depth));
+				// Every fork header at a newly finalized height is now an orphan.
+				for height in chain.finalized_height + 1..=finalized_height {
+					let stored = self.headers_at_height.get((chain_id, height)).unwrap_or_default();
+					chain.orphan_count += stored.len().saturating_sub(1) as u64;
+				}
+				chain.finalized_height = finalized_height;
+			}
+
+			if retracted > 0 {
+				self.env().emit_event(ReorgOccurred {
+					chain_id,
+					common_ancestor,
+					retracted_tip: old_tip,
+					new_tip: tip_hash,
+					depth: retracted,
+				});
+				self.notify_subscribers(HeaderNotification::Reorg {
+					chain_id,
+					common_ancestor,
+					new_tip: tip_hash,
+					depth: retracted,
+				});
+			}
+			self.notify_subscribers(HeaderNotification::NewTip {
+				chain_id,
+				block_hash: tip_hash,
+				block_height: tip.height,
+			});
+			Ok(retracted)
+		}
+
+		/// Remove a contract's approved or pending header subscription and refund its deposit.
+		fn drop_header_subscriber(&mut self, contract: AccountId) -> Result<()> {
+			let subscriber = match self.pending_header_subscribers.take(contract) {
+				Some(subscriber) => subscriber,
+				None => {
+					let index = self
+						.header_subscribers
+						.iter()
+						.position(|subscriber| subscriber.contract == contract)
+						.ok_or(Error::NotSubscribed)?;
+					self.header_subscribers.remove(index)
+				},
+			};
+			self.env()
+				.transfer(contract, subscriber.deposit)
+				.map_err(|_| Error::PaymentFailed)?;
+			self.env().emit_event(HeaderUnsubscribed { subscriber: contract });
+			Ok(())
+		}
+
+		/// Call every header subscriber's callback with the given notification, emitting a
+		/// `HeaderCallback` for each. Failed callbacks are otherwise ignored.
+		fn notify_subscribers(&self, notification: HeaderNotification) {
+			for subscriber in &self.header_subscribers {
+				let delivered = Self::invoke_callback(subscriber, notification);
+				self.env().emit_event(HeaderCallback {
+					subscriber: subscriber.contract,
+					notification,
+					delivered,
+				});
+			}
+		}
+
+		/// Call a subscriber's callback, returning whether it succeeded.
+		fn invoke_callback(
+			subscriber: &HeaderSubscriber,
+			notification: HeaderNotification,
+		) -> bool {
+			let call = build_call::<Environment>()
+				.call(subscriber.contract)
+				.gas_limit(HEADER_CALLBACK_GAS_LIMIT)
+				.exec_input(
+					ExecutionInput::new(Selector::new(subscriber.selector)).push_arg(notification),
+				)
+				.returns::<()>();
+			matches!(call.try_invoke(), Ok(Ok(())))
+		}
+
+		/// Recover the compressed public key that made the given signature over a header hash,
+		/// and check that the given account is derived from it.
+		fn recover_signer(
+			&self,
+			header_hash: HashValue,
+			signature: &[u8; 65],
+			account: AccountId,
+		) -> Result<[u8; 33]> {
+			let signer = self
+				.env()
+				.ecdsa_recover(signature, &header_hash)
+				.map_err(|_| Error::InvalidSignature)?;
+
+			let mut signer_account = <Blake2x256 as HashOutput>::Type::default();
+			ink::env::hash_bytes::<Blake2x256>(&signer, &mut signer_account);
+			if AccountId::from(signer_account) != account {
+				return Err(Error::InvalidSignature)
+			}
+			Ok(signer)
+		}
+
+		/// Emit a `BridgeAlert` of the given kind for every alert subscriber.
+		fn raise_alert(&self, kind: AlertKind) {
+			for subscriber in &self.alert_subscribers {
+				self.env().emit_event(BridgeAlert { subscriber: *subscriber, kind });
+			}
+		}
+
+		/// Validate a new header of the given source chain, add it to the db and update the canon
+		/// chain if needed.
+		///
+		/// The caller is responsible for charging the relay fee. Returns the header's hash.
+		fn accept_header(&mut self, chain_id: ChainId, header: Header) -> Result<HashValue> {
+			self.accept_header_as(
+				chain_id,
+				header,
+				self.header_hash(header),
+				HeaderFormat::Native,
+				None,
+				self.caller(),
+			)
+		}
+
+		/// Accept a new default chain header, or pool it if its parent is not known yet, along
+		/// with the provenance to store once it is accepted. Returns the header's hash if it was
+		/// accepted now.
+		fn accept_or_pool(
+			&mut self,
+			header: Header,
+			provenance: Option<HeaderProvenance>,
+		) -> Result<Option<HashValue>> {
+			match self.accept_header(DEFAULT_CHAIN_ID, header) {
+				Err(Error::UnknownParent) => self.pool_orphan(header, provenance).map(|_| None),
+				result => result.map(Some),
+			}
+		}
+
+		/// Hold a default chain header whose parent is unknown until its parent is accepted.
+		fn pool_orphan(
+			&mut self,
+			header: Header,
+			provenance: Option<HeaderProvenance>,
+		) -> Result<()> {
+			let block_hash = self.check_orphan(header)?;
+			let submitter = self.caller();
+			let now = self.current_block();
+			self.orphan_headers.retain(|orphan| orphan.expires_at >= now);
+			self.count_submission(submitter)?;
+			self.orphan_headers.push(OrphanHeader {
+				header,
+				block_hash,
+				submitter,
+				expires_at: now.saturating_add(ORPHAN_HEADER_TTL),
+				provenance,
+			});
+			self.env()
+				.emit_event(HeaderPooled { block_hash, parent: header.parent, submitter });
+			Ok(())
+		}
+
+		/// Check a header like `pool_orphan` does before pooling it, without the rate limit.
+		/// Returns the header's hash.
+		fn check_orphan(&self, header: Header) -> Result<HashValue> {
+			let chain = self.default_chain();
+			// The parent must be able to arrive and become canon: above the finalized and pruned
+			// heights, and close enough to the tip for the pool to bridge the gap.
+			let parent_height = header.height.saturating_sub(1);
+			if parent_height <= chain.finalized_height ||
+				parent_height < chain.pruned_height ||
+				parent_height > chain.best_height.saturating_add(MAX_ORPHAN_HEADERS as u64)
+			{
+				return Err(Error::UnknownParent)
+			}
+			// Checks that don't need the parent, so the pool can't be filled for free.
+			let block_hash = self.header_hash(header);
+			if U256::from_be_bytes(header.target) > U256::from_be_bytes(chain.difficulty_threshold)
+			{
+				return Err(Error::InvalidDifficultyTarget)
+			}
+			if !Self::meets_target(block_hash, header.target) {
+				return Err(Error::PoWThresholdNotMet)
+			}
+			if self.is_blacklisted(DEFAULT_CHAIN_ID, block_hash) ||
+				self.is_blacklisted(DEFAULT_CHAIN_ID, header.parent)
+			{
+				return Err(Error::BlacklistedHeader)
+			}
+			let submitter = self.caller();
+			self.ensure_may_relay(submitter)?;
+
+			// Expired headers are about to be dropped from the pool.
+			let now = self.current_block();
+			let pooled: Vec<_> =
+				self.orphan_headers.iter().filter(|orphan| orphan.expires_at >= now).collect();
+			if pooled.iter().any(|orphan| orphan.block_hash == block_hash) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			// No relayer may take more than their share of the pool.
+			let pooled_by_submitter =
+				pooled.iter().filter(|orphan| orphan.submitter == submitter).count();
+			if pooled.len() >= MAX_ORPHAN_HEADERS ||
+				pooled_by_submitter >= MAX_ORPHAN_HEADERS_PER_RELAYER
+			{
+				return Err(Error::OrphanPoolFull)
+			}
+			Ok(block_hash)
+		}
+
+		/// Accept the pooled descendants of a newly accepted default chain header, dropping those
+		/// that have expired or turn out to be invalid.
+		fn connect_orphans(&mut self, parent_hash: HashValue) -> Result<()> {
+			let mut parents = Vec::from([parent_hash]);
+			while let Some(parent_hash) = parents.pop() {
+				let now = self.current_block();
+				let (children, rest) = core::mem::take(&mut self.orphan_headers)
+					.into_iter()
+					.partition::<Vec<_>, _>(|orphan| orphan.header.parent == parent_hash);
+				self.orphan_headers = rest;
+				for orphan in children.into_iter().filter(|orphan| orphan.expires_at >= now) {
+					match self.store_header(
+						DEFAULT_CHAIN_ID,
+						orphan.header,
+						orphan.block_hash,
+						HeaderFormat::Native,
+						None,
+						orphan.submitter,
+						false,
+					) {
+						Ok(block_hash) => {
+							if let Some(provenance) = orphan.provenance {
+								self.header_provenance
+									.insert((DEFAULT_CHAIN_ID, block_hash), &provenance);
+							}
+							parents.push(block_hash)
+						},
+						// A header that fails once stored, while becoming canon, fails the whole
+						// call, as its own submission would have.
+						Err(error)
+							if self.headers.contains((DEFAULT_CHAIN_ID, orphan.block_hash)) =>
+							return Err(error),
+						Err(_) => {},
+					}
+				}
+			}
+			Ok(())
+		}
+
+		/// Like `accept_header`, for a header that was submitted by the given account in the given
+		/// format and has the given hash in that format, and optionally proves its work by merged
+		/// mining. Any pooled descendants of a default chain header are accepted with it.
+		fn accept_header_as(
+			&mut self,
+			chain_id: ChainId,
+			header: Header,
+			header_hash: HashValue,
+			format: HeaderFormat,
+			aux_pow: Option<&AuxPow>,
+			submitter: AccountId,
+		) -> Result<HashValue> {
+			let header_hash =
+				self.store_header(chain_id, header, header_hash, format, aux_pow, submitter, true)?;
+			if chain_id == DEFAULT_CHAIN_ID {
+				self.connect_orphans(header_hash)?;
+			}
+			Ok(header_hash)
+		}
+
+		/// Validate and store a header like `accept_header_as`, without its pooled descendants.
+		/// The header counts towards the submitter's rate limit unless it already has, when it
+		/// was pooled.
+		#[allow(clippy::too_many_arguments)]
+		fn store_header(
+			&mut self,
+			chain_id: ChainId,
+			header: Header,
+			header_hash: HashValue,
+			format: HeaderFormat,
+			aux_pow: Option<&AuxPow>,
+			submitter: AccountId,
+			rate_limited: bool,
+		) -> Result<HashValue> {
+			let (mut chain, parent, aux_pow_parent) =
+				self.check_header(chain_id, header, header_hash, format, aux_pow, submitter)?;
+			if rate_limited {
+				self.count_submission(submitter)?;
+			}
+
+			self.headers.insert((chain_id, header_hash), &header);
+			self.fee_recipient.insert((chain_id, header_hash), &submitter);
+			let work = self.link_header(chain_id, &header, header_hash);
+			if let Some(parent_hash) = aux_pow_parent {
+				self.aux_pow_parents.insert((chain_id, header_hash), &parent_hash);
+			}
+			let mut siblings =
+				self.headers_at_height.get((chain_id, header.height)).unwrap_or_default();
+			siblings.push(header_hash);
+			self.headers_at_height.insert((chain_id, header.height), &siblings);
+			self.remove_fork_tip(chain_id, header.parent);
+			let mut fork_tips = self.fork_tips.get(chain_id).unwrap_or_default();
+			fork_tips.push(header_hash);
+			self.fork_tips.insert(chain_id, &fork_tips);
+
+			let submission_id = self.next_submission_id;
+			self.next_submission_id += 1;
+			let receipt =
+				SubmissionReceipt { id: submission_id, block_number: self.current_block() };
+			self.submission_receipts.insert((submitter, header_hash), &receipt);
+			self.receipt_count += 1;
+			chain.header_count += 1;
+			chain.last_header_block = self.current_block();
+			self.record_relayed_header(submitter);
+
+			self.env().emit_event(HeaderSubmitted {
+				submission_id,
+				chain_id,
+				block_hash: header_hash,
+				block_height: header.height,
+				submitter,
+			});
+
+			// Heaviest chain rule: a header only becomes the new tip if it has more cumulative
+			// work than the current one.
+			let mut is_new_tip = work > self.best_work(chain_id, &chain);
+			if is_new_tip {
+				let tip_hash = self.tip_hash(chain_id, &chain);
+				let depth = self
+					.fork_point(chain_id, header_hash, tip_hash)
+					.and_then(|hash| self.headers.get((chain_id, hash)))
+					.map_or(0, |fork_point| chain.best_height.saturating_sub(fork_point.height));
+				if self.max_reorg_depth.is_some_and(|max_depth| depth > max_depth) {
+					// Keep the header, which the owner may still adopt, but not as the tip. Halting
+					// must not fail the submission, or the halt would be reverted with it.
+					is_new_tip = false;
+					self.halt = Some(Halt { chain_id, block_hash: header_hash, depth });
+					self.env().emit_event(BridgeHalted {
+						chain_id,
+						block_hash: header_hash,
+						depth,
+					});
+					self.raise_alert(AlertKind::Halted);
+				}
+			}
+			if is_new_tip {
+				chain.observe_block_interval(header.timestamp.saturating_sub(parent.timestamp));
+				let retracted = self.set_canon_tip(chain_id, &mut chain, header_hash, header)?;
+				if retracted >= DEEP_REORG_ALERT_DEPTH {
+					self.raise_alert(AlertKind::DeepReorg);
+				}
+			}
+			self.chains.insert(chain_id, &chain);
+			if chain_id == DEFAULT_CHAIN_ID {
+				self.observe_header_arrival();
+				if is_new_tip {
+					self.record_height_sample();
+				}
+			}
+
+			Ok(header_hash)
+		}
+
+		/// Check a header like `store_header` does before storing it, without the rate limit.
+		/// Returns the header's chain and parent, and the hash of the parent block it was merged
+		/// mined in, if it was.
+		fn check_header(
+			&self,
+			chain_id: ChainId,
+			header: Header,
+			header_hash: HashValue,
+			format: HeaderFormat,
+			aux_pow: Option<&AuxPow>,
+			submitter: AccountId,
+		) -> Result<(SourceChain, Header, Option<HashValue>)> {
+			let chain = self.load_chain(chain_id)?;
+			if chain.consensus != ConsensusBackend::ProofOfWork {
+				return Err(Error::WrongConsensus)
+			}
+			self.ensure_may_relay(submitter)?;
+			if chain.header_format != format {
+				return Err(Error::WrongHeaderFormat)
+			}
+			if header == Header::default() {
+				return Err(Error::DefaultHeader)
+			}
+			if header_hash == [0; 32] {
+				return Err(Error::ZeroHash)
+			}
+			if self.headers.contains((chain_id, header_hash)) {
+				return Err(Error::HeaderAlreadySubmitted)
+			}
+			if chain
+				.prune_depth
+				.is_some_and(|depth| chain.best_height.saturating_sub(header.height) > depth)
+			{
+				return Err(Error::BelowPruneHorizon)
+			}
+			let parent = self.headers.get((chain_id, header.parent)).ok_or(Error::UnknownParent)?;
+			if header.height != parent.height + 1 {
+				return Err(Error::IncorrectHeight)
+			}
+			if self.is_blacklisted(chain_id, header_hash) ||
+				self.is_blacklisted(chain_id, header.parent)
+			{
+				return Err(Error::BlacklistedHeader)
+			}
+			// Every header at or below the finalized height other than the canon one is on a fork
+			// that can never become canon.
+			if parent.height < chain.finalized_height ||
+				(parent.height == chain.finalized_height &&
+					!self.is_canon(chain_id, header.parent))
+			{
+				return Err(Error::ConflictsWithFinalized)
+			}
+			if header.timestamp <= self.median_time_past(chain_id, parent) {
+				return Err(Error::TimestampTooOld)
+			}
+			if header.timestamp > self.now().saturating_add(MAX_FUTURE_BLOCK_TIME) {
+				return Err(Error::TimestampTooFarInFuture)
+			}
+			if U256::from_be_bytes(header.target) > U256::from_be_bytes(chain.difficulty_threshold) ||
+				chain.retarget.is_some_and(|params| {
+					header.target !=
+						self.expected_target(chain_id, &chain, &header, &parent, params)
+				}) {
+				return Err(Error::InvalidDifficultyTarget)
+			}
+			let aux_pow_parent = match aux_pow {
+				None => None,
+				Some(_) if !chain.merged_mining => return Err(Error::MergedMiningDisabled),
+				Some(aux_pow) => Some(self.check_aux_pow(header_hash, header.target, aux_pow)?),
+			};
+			if aux_pow_parent.is_none() && !Self::meets_target(header_hash, header.target) {
+				return Err(Error::PoWThresholdNotMet)
+			}
+			Ok((chain, parent, aux_pow_parent))
+		}
+
+		/// Check that a merged mining proof commits to the given header hash and that its parent
+		/// block meets the given target. Returns the parent block's hash.
+		fn check_aux_pow(
+			&self,
+			header_hash: HashValue,
+			target: HashValue,
+			aux_pow: &AuxPow,
+		) -> Result<HashValue> {
+			if aux_pow.coinbase_branch.len() > self.max_proof_length as usize ||
+				aux_pow.chain_branch.len() > MAX_MERGED_MINING_DEPTH
+			{
+				return Err(Error::ProofTooLarge)
+			}
+
+			let parent_hash = codec::bitcoin_header_hash(&aux_pow.parent_header);
+			if !Self::meets_target(parent_hash, target) {
+				return Err(Error::PoWThresholdNotMet)
+			}
+
+			let coinbase_root = aux_pow.coinbase_branch.iter().fold(
+				codec::bitcoin_transaction_hash(&aux_pow.coinbase_transaction),
+				|node, sibling| codec::bitcoin_merkle_node(&node, sibling),
+			);
+			if coinbase_root != aux_pow.parent_header.merkle_root {
+				return Err(Error::InvalidAuxPow)
+			}
+
+			let leaves = 1u32 << aux_pow.chain_branch.len();
+			if aux_pow.chain_index >= leaves {
+				return Err(Error::InvalidAuxPow)
+			}
+			let chain_root = aux_pow.chain_branch.iter().enumerate().fold(
+				header_hash,
+				|node, (depth, sibling)| {
+					if aux_pow.chain_index >> depth & 1 == 1 {
+						codec::bitcoin_merkle_node(sibling, &node)
+					} else {
+						codec::bitcoin_merkle_node(&node, sibling)
+					}
+				},
+			);
+
+			// The commitment must appear exactly once, so that a coinbase cannot commit to
+			// several roots for the same chain.
+			let coinbase = &aux_pow.coinbase_transaction;
+			let mut markers = coinbase
+				.windows(codec::MERGED_MINING_MAGIC.len())
+				.enumerate()
+				.filter(|(_, window)| *window == codec::MERGED_MINING_MAGIC);
+			let (Some((position, _)), None) = (markers.next(), markers.next()) else {
+				return Err(Error::InvalidAuxPow)
+			};
+			let commitment = &coinbase[position + codec::MERGED_MINING_MAGIC.len()..];
+			if commitment.len() < 36 ||
+				commitment[..32] != chain_root ||
+				commitment[32..36] != leaves.to_le_bytes()
+			{
+				return Err(Error::InvalidAuxPow)
+			}
+
+			Ok(parent_hash)
+		}
+
+		/// Whether a submission receipt can no longer be looked up.
+		fn receipt_expired(&self, receipt: &SubmissionReceipt) -> bool {
+			self.current_block().saturating_sub(receipt.block_number) >= SUBMISSION_RECEIPT_TTL
+		}
+
+		/// Add the treasury's and the burned share of a verify fee to their totals, and return
+		/// the relayer's share.
+		fn split_verify_fee(&mut self, verify_fee: Balance) -> Balance {
+			let (to_treasury, burned) = self.fee_split.shares(verify_fee);
+			self.treasury = self.treasury.saturating_add(to_treasury);
+			self.burned_fees = self.burned_fees.saturating_add(burned);
+			verify_fee - to_treasury - burned
+		}
+
+		/// Count a header accepted from a relayer, ranking the relayer if it is their first.
+		fn record_relayed_header(&mut self, relayer: AccountId) {
+			let mut stats = self.relayer_stats.get(relayer).unwrap_or_default();
+			if stats.headers_submitted == 0 &&
+				self.ranked_relayers.len() < MAX_RANKED_RELAYERS as usize
+			{
+				self.ranked_relayers.push(relayer);
+			}
+			stats.headers_submitted += 1;
+			self.relayer_stats.insert(relayer, &stats);
+		}
+
+		/// Apply a change to a relayer's stats.
+		fn update_relayer_stats(
+			&mut self,
+			relayer: AccountId,
+			update: impl FnOnce(&mut RelayerStats),
+		) {
+			let mut stats = self.relayer_stats.get(relayer).unwrap_or_default();
+			update(&mut stats);
+			self.relayer_stats.insert(relayer, &stats);
+		}
+
+		/// Whether a block is blacklisted or descends from a stored blacklisted block.
+		fn is_blacklisted(&self, chain_id: ChainId, block_hash: HashValue) -> bool {
+			self.blacklisted_headers.iter().any(|&(blacklisted_chain, blacklisted)| {
+				blacklisted_chain == chain_id &&
+					(blacklisted == block_hash ||
+						self.headers.get((chain_id, blacklisted)).is_some_and(|header| {
+							self.ancestor_at(chain_id, block_hash, header.height) ==
+								Some(blacklisted)
+						}))
+			})
+		}
+
+		/// Record a newly stored header's cumulative work, skip list links, and place among its
+		/// parent's children. A parent that is not stored, as on a chain of final headers with
+		/// gaps between them, gets no children. Returns the header's cumulative work.
+		fn link_header(
+			&mut self,
+			chain_id: ChainId,
+			header: &Header,
+			header_hash: HashValue,
+		) -> U256 {
+			let work = self
+				.chain_work
+				.get((chain_id, header.parent))
+				.unwrap_or_default()
+				.saturating_add(Self::work_from_target(header.target));
+			self.chain_work.insert((chain_id, header_hash), &work);
+			let links = self.links_above(chain_id, header.parent);
+			self.ancestor_links.insert((chain_id, header_hash), &links);
+			if self.headers.contains((chain_id, header.parent)) {
+				let mut children = self.children.get((chain_id, header.parent)).unwrap_or_default();
+				children.push(header_hash);
+				self.children.insert((chain_id, header.parent), &children);
+			}
+			work
+		}
+
+		/// Remove a header from its chain's fork tips, if it is one.
+		fn remove_fork_tip(&mut self, chain_id: ChainId, block_hash: HashValue) {
+			let mut fork_tips = self.fork_tips.get(chain_id).unwrap_or_default();
+			if let Some(index) = fork_tips.iter().position(|tip| *tip == block_hash) {
+				fork_tips.swap_remove(index);
+				self.fork_tips.insert(chain_id, &fork_tips);
+			}
+		}
+
+		/// The skip list links of a new child of the given parent, see `ancestor_links`.
+		fn links_above(&self, chain_id: ChainId, parent: HashValue) -> Vec<HashValue> {
+			let mut links = Vec::from([parent]);
+			// The ancestor 2^(i+1) blocks below the child is 2^i blocks below the one 2^i below.
+			while let Some(next) = self
+				.ancestor_links
+				.get((chain_id, links[links.len() - 1]))
+				.and_then(|above| above.get(links.len() - 1).copied())
+			{
+				links.push(next);
+			}
+			links
+		}
+
+		/// The ancestor at the given height of the given block, in a number of storage reads
+		/// logarithmic in their distance. `None` if the block is unknown, below the height, or its
+		/// ancestors do not reach down to the height.
+		fn ancestor_at(
+			&self,
+			chain_id: ChainId,
+			block_hash: HashValue,
+			height: u64,
+		) -> Option<HashValue> {
+			let mut distance =
+				self.headers.get((chain_id, block_hash))?.height.checked_sub(height)?;
+			let mut hash = block_hash;
+			while distance > 0 {
+				let links = self.ancestor_links.get((chain_id, hash))?;
+				let step = (distance.ilog2() as usize).min(links.len().checked_sub(1)?);
+				hash = links[step];
+				distance -= 1 << step;
+			}
+			Some(hash)
+		}
+
+		/// The highest block that is the given blocks or an ancestor of both, found by skip list
+		/// jumps. `None` if either block is unknown or their fork point has been pruned.
+		fn fork_point(&self, chain_id: ChainId, a: HashValue, b: HashValue) -> Option<HashValue> {
+			let a_height = self.headers.get((chain_id, a))?.height;
+			let b_height = self.headers.get((chain_id, b))?.height;
+			let height = a_height.min(b_height);
+			let mut a = self.ancestor_at(chain_id, a, height)?;
+			let mut b = self.ancestor_at(chain_id, b, height)?;
+			while a != b {
+				let a_links = self.ancestor_links.get((chain_id, a))?;
+				let b_links = self.ancestor_links.get((chain_id, b))?;
+				// Jump as far as possible while staying below the fork point, or to the parents if
+				// they are the fork point.
+				let step = (0..a_links.len().min(b_links.len()))
+					.rev()
+					.find(|&i| a_links[i] != b_links[i])
+					.unwrap_or(0);
+				a = *a_links.get(step)?;
+				b = *b_links.get(step)?;
+			}
+			Some(a)
+		}
+
+		/// The median timestamp of the given header and up to `MEDIAN_TIME_SPAN - 1` of its
+		/// ancestors. Fewer blocks are considered close to the checkpoint.
+		fn median_time_past(&self, chain_id: ChainId, header: Header) -> u64 {
+			let mut timestamps = Vec::with_capacity(MEDIAN_TIME_SPAN);
+			let mut next = Some(header);
+			while let Some(ancestor) = next {
+				timestamps.push(ancestor.timestamp);
+				if timestamps.len() == MEDIAN_TIME_SPAN {
+					break
+				}
+				next = self.headers.get((chain_id, ancestor.parent));
+			}
+			timestamps.sort_unstable();
+			timestamps[timestamps.len() / 2]
+		}
+
+		/// The target that the given header must declare under difficulty retargeting.
+		fn expected_target(
+			&self,
+			chain_id: ChainId,
+			chain: &SourceChain,
+			header: &Header,
+			parent: &Header,
+			params: RetargetParams,
+		) -> HashValue {
+			if !header.height.is_multiple_of(params.interval) {
+				return parent.target
+			}
+
+			// Like Bitcoin, measure the epoch from its first block to its last one, or from the
+			// lowest stored block if the epoch reaches below it.
+			let first_height =
+				parent.height.saturating_sub(params.interval - 1).max(chain.pruned_height);
+			let first = self
+				.ancestor_at(chain_id, header.parent, first_height)
+				.and_then(|hash| self.headers.get((chain_id, hash)))
+				.unwrap_or(*parent);
+			let timespan = parent
+				.timestamp
+				.saturating_sub(first.timestamp)
+				.clamp(params.target_timespan / 4, params.target_timespan.saturating_mul(4));
+
+			let target = core::cmp::min(
+				U256::from_be_bytes(parent.target)
+					.saturating_mul_div(timespan, params.target_timespan),
+				U256::from_be_bytes(chain.difficulty_threshold),
+			);
+			match chain.header_format {
+				HeaderFormat::Native => target.to_be_bytes(),
+				// Bitcoin headers can only declare targets with a compact encoding.
+				HeaderFormat::Bitcoin => U256::from_compact(target.to_compact())
+					.expect("compact encodings of 256 bit targets expand; qed")
+					.to_be_bytes(),
+			}
+		}
+
+		/// The header that the bridge stores for a Bitcoin header at the given height, or `None`
+		/// if the header's target is invalid.
+		///
+		/// Hashes are stored in display byte order, like the bridge's other hashes of Bitcoin data.
+		fn from_bitcoin_header(header: BitcoinHeader, height: u64) -> Option<Header> {
+			let mut parent = header.prev_block_hash;
+			parent.reverse();
+			let mut transactions_root = header.merkle_root;
+			transactions_root.reverse();
+			Some(Header {
+				height,
+				parent,
+				storage_root: [0; 32],
+				transactions_root,
+				receipts_root: [0; 32],
+				target: U256::from_compact(header.bits)?.to_be_bytes(),
+				pow_nonce: header.nonce.into(),
+				timestamp: u64::from(header.time) * 1000,
+			})
+		}
+
+		/// The cumulative work of the given chain's current canon tip.
+		fn best_work(&self, chain_id: ChainId, chain: &SourceChain) -> U256 {
+			self.chain_work
+				.get((chain_id, self.tip_hash(chain_id, chain)))
+				.unwrap_or_default()
+		}
+
+		/// The checks shared by transaction and state verification.
+		///
+		/// `paid` is the value the verifier has transferred for this verification, and `root`
+		/// selects which of the block's merkle roots the claim is checked against.
+		#[allow(clippy::too_many_arguments)]
+		fn verify_claim(
+			&mut self,
+			chain_id: ChainId,
+			paid: Balance,
+			claim_hash: HashValue,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+			root: impl FnOnce(&Header) -> HashValue,
+		) -> VerificationResult {
+			let proof_length = p.siblings.len();
+			self.verify_in_block(chain_id, paid, block_hash, min_depth, proof_length, |header| {
+				MerkleProof::check_merkle_proof(claim_hash, p, root(header))
+			})
+		}
+
+		/// The checks shared by all verifications against a block.
+		///
+		/// `proof_length` is the number of hashes or nodes in the verifier's proof, and `proven`
+		/// checks the proof against the block's header.
+		fn verify_in_block(
+			&mut self,
+			chain_id: ChainId,
+			paid: Balance,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof_length: usize,
+			proven: impl FnOnce(&Header) -> bool,
+		) -> VerificationResult {
+			self.verify_batch_in_block(
+				chain_id,
+				paid,
+				block_hash,
+				min_depth,
+				proof_length,
+				1,
+				proven,
+			)
+		}
+
+		/// The checks of `verify_in_block` for a number of claims proven together, whose combined
+		/// fee is discounted, see `batch_verify_fee`. `proof_length` is that of the longest proof.
+		#[allow(clippy::too_many_arguments)]
+		fn verify_batch_in_block(
+			&mut self,
+			chain_id: ChainId,
+			paid: Balance,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof_length: usize,
+			claims: u32,
+			proven: impl FnOnce(&Header) -> bool,
+		) -> VerificationResult {
+			let min_depth = min_depth.max(self.min_confirmations);
+			if block_hash == [0; 32] {
+				return Err(Error::ZeroHash.into())
+			}
+			if proof_length > self.max_proof_length as usize {
+				return Err(Error::ProofTooLarge.into())
+			}
+			let chain = self.load_chain(chain_id)?;
+			if self.exceeds_staleness_window(&chain) {
+				return Err(Error::BridgeStale.into())
+			}
+			let verify_fee = fees::discounted_fee(chain.verify_fee, claims);
+			let exempt = paid < verify_fee && self.use_fee_exemption();
+			let required_fee = if paid >= verify_fee {
+				verify_fee
+			} else if exempt ||
+				self.draw_credits(self.caller(), verify_fee) ||
+				self.draw_sponsorship(verify_fee) ||
+				self.subsidize(chain_id, block_hash, verify_fee)
+			{
+				0
+			} else {
+				return Err(Error::InsufficientVerifyFee.into())
+			};
+			self.refund_excess(paid, required_fee)?;
+			let Some(header) = self.headers.get((chain_id, block_hash)) else {
+				return Err(VerificationFailure::UnknownBlock.into())
+			};
+			if self.is_blacklisted(chain_id, block_hash) {
+				return Err(Error::BlacklistedHeader.into())
+			}
+			// A block that has already been verified at the requested depth is known to be canon
+			// and deep enough, so only the proof needs checking.
+			let verified_depth = self.verified_depths.get((chain_id, block_hash));
+			let pre_verified = verified_depth.is_some_and(|depth| depth >= min_depth);
+			let is_canon = pre_verified || self.is_canon(chain_id, block_hash);
+			if header.height <= chain.finalized_height && !is_canon {
+				return Err(Error::ConflictsWithFinalized.into())
+			}
+
+			// The relayer of the block is paid for checking the claim, unless the verification was
+			// free. A failure returned as an error reverts the payment with the rest of the call,
+			// so only the callers reporting failures as an outcome pay for failed checks.
+			if let Some(relayer) =
+				self.fee_recipient.get((chain_id, block_hash)).filter(|_| !exempt)
+			{
+				let verify_fee = self.split_verify_fee(verify_fee);
+				match self.fee_release_height(&header, min_depth) {
+					Some(release_height) if !is_canon || chain.best_height < release_height => self
+						.escrow_verify_fee(
+							chain_id,
+							block_hash,
+							&header,
+							relayer,
+							release_height,
+							verify_fee,
+						),
+					_ => {
+						self.credit_reward(relayer, verify_fee);
+						self.update_relayer_stats(relayer, |stats| {
+							stats.rewards_earned = stats.rewards_earned.saturating_add(verify_fee)
+						});
+						self.env().emit_event(RelayerPaid {
+							relayer,
+							block_hash,
+							amount: verify_fee,
+						});
+					},
+				}
+			}
+
+			let depth = chain.best_height.saturating_sub(header.height);
+			let failure = if !is_canon {
+				Some(VerificationFailure::NotCanon)
+			} else if !pre_verified && depth < min_depth {
+				Some(VerificationFailure::InsufficientDepth { have: depth, need: min_depth })
+			} else if !proven(&header) {
+				Some(VerificationFailure::InvalidProof)
+			} else {
+				None
+			};
+			match failure {
+				Some(failure) => Err(failure.into()),
+				None => {
+					if !pre_verified && verified_depth.is_none_or(|verified| verified < depth) {
+						self.verified_depths.insert((chain_id, block_hash), &depth);
+					}
+					Ok(())
+				},
+			}
+		}
+
+		/// Verify a storage read of a Substrate source chain, whose block `storage_root` is the
+		/// root of its base-16 Patricia-Merkle state trie.
+		///
+		/// `value` is `None` for a key that is not in storage, and `proof` holds the trie nodes and
+		/// hashed values of a SCALE decoded `StorageProof`, in any order. Otherwise the
+		/// verification is like `verify_chain_state`, and its `StateVerified` event has
+		/// `codec::substrate_storage_claim_hash` as the claim hash.
+		#[ink(message, payable)]
+		pub fn verify_substrate_storage(
+			&mut self,
+			chain_id: ChainId,
+			key: Vec<u8>,
+			value: Option<Vec<u8>>,
+			block_hash: HashValue,
+			min_depth: u64,
+			proof: Vec<Vec<u8>>,
+		) -> VerificationResult {
+			self.ensure_not_paused()?;
+			let claim_hash = codec::substrate_storage_claim_hash(&key, &value);
+			let paid = self.env().transferred_value();
+			let result = self.verify_in_block(
+				chain_id,
+				paid,
+				block_hash,
+				min_depth,
+				proof.len(),
+				|header| substrate_trie::read(&header.storage_root, &key, &proof) == Ok(value),
+			);
+			self.emit_state_verified(chain_id, claim_hash, block_hash, result)
+		}
+
+		/// The RLP encoded account at the given address of a Merkle-Patricia state trie, or
+		/// `None` if the proof does not show one.
+		fn read_account(
+			state_root: &HashValue,
+			address: &[u8; 20],
+			proof: &[Vec<u8>],
+		) -> Option<Vec<u8>> {
+			mpt::read(state_root, &codec::keccak_256(address), proof).ok().flatten()
+		}
+
+		/// The value of a slot of a Merkle-Patricia storage trie, or `None` if the proof is
+		/// invalid. Slots that are not in the trie are zero.
+		fn read_storage(
+			storage_root: &HashValue,
+			slot: &HashValue,
+			proof: &[Vec<u8>],
+		) -> Option<HashValue> {
+			let Some(encoded) = mpt::read(storage_root, &codec::keccak_256(slot), proof).ok()?
+			else {
+				return Some([0; 32])
+			};
+			// Values are stored as RLP strings of the minimal big-endian bytes of the word.
+			let bytes = rlp::decode(&encoded)?.bytes()?;
+			if bytes.len() > 32 || bytes.first() == Some(&0) {
+				return None
+			}
+			let mut value = [0; 32];
+			value[32 - bytes.len()..].copy_from_slice(bytes);
+			Some(value)
+		}
+
+		/// Attest to a state claim and emit its `StateVerified` event, if the verification
+		/// succeeded.
+		fn emit_state_verified(
+			&mut self,
+			chain_id: ChainId,
+			claim_hash: HashValue,
+			block_hash: HashValue,
+			result: VerificationResult,
+		) -> VerificationResult {
+			result?;
+			self.record_attestation(chain_id, claim_hash, block_hash);
+			self.emit_state_event(chain_id, claim_hash, block_hash, None);
+			Ok(())
+		}
+
+		/// Emit the `StateVerified` event for a verification that was not rejected.
+		fn emit_state_event(
+			&mut self,
+			chain_id: ChainId,
+			claim_hash: HashValue,
+			block_hash: HashValue,
+			failure: Option<VerificationFailure>,
+		) {
+			self.env().emit_event(StateVerified {
+				chain_id,
+				claim_hash,
+				block_hash,
+				claim_id: self.claim_id(claim_hash, block_hash),
+				verifier: self.caller(),
+				outcome: failure.is_none(),
+				failure,
+			});
+		}
+
+		/// Split a verification result into an error rejecting the call, and whether the claim
+		/// holds. A failure is counted here, as only calls that report it as an outcome rather
+		/// than an error keep the count.
+		fn outcome(&mut self, result: VerificationResult) -> Result<bool> {
+			match result {
+				Ok(()) => Ok(true),
+				Err(VerificationError::Rejected(error)) => Err(error),
+				Err(VerificationError::Failed(failure)) => {
+					self.record_failure(failure);
+					Ok(false)
+				},
+			}
+		}
+
+		/// Pay the verify fee for a verification against the given block from the subsidy pool,
+		/// if the block has free verifications left and the pool can cover it. Returns whether
+		/// the fee was paid.
+		fn subsidize(&mut self, chain_id: ChainId, block_hash: HashValue, fee: Balance) -> bool {
+			let key = (chain_id, block_hash);
+			let used = self.free_verifications_used.get(key).unwrap_or_default();
+			if !self.headers.contains(key) ||
+				used >= self.free_verifications ||
+				self.subsidy_pool < fee
+			{
+				return false
+			}
+
+			self.free_verifications_used.insert(key, &(used + 1));
+			self.subsidy_pool -= fee;
+			self.env().emit_event(VerificationSubsidized {
+				verifier: self.caller(),
+				block_hash,
+				amount: fee,
+			});
+			true
+		}
+
+		/// Fail if the given relayer is not on the relayer allowlist while it is enabled, or a
+		/// minimum relayer stake is set and they have not bonded it.
+		fn ensure_may_relay(&self, relayer: AccountId) -> Result<()> {
+			if self.allowlist_enabled && !self.relayer_allowlist.contains(relayer) {
+				return Err(Error::RelayerNotAllowed)
+			}
+			if self.min_relayer_stake.is_some_and(|min| self.stake_of(relayer).bonded < min) {
+				return Err(Error::InsufficientStake)
+			}
+			Ok(())
+		}
+
+		/// Check that the caller has paid at least the given relay fee, add it to the treasury and
+		/// refund any excess.
+		fn charge_relay_fee(&mut self, relay_fee: Balance) -> Result<()> {
+			let paid = self.env().transferred_value();
+			if paid < relay_fee {
+				return Err(Error::InsufficientRelayFee)
+			}
+			self.treasury = self.treasury.saturating_add(relay_fee);
+			self.refund_excess(paid, relay_fee)
+		}
+
+		/// The number of fee exempt verifications the given account has made in the current period.
+		fn fee_exemptions_used(&self, account: AccountId) -> u32 {
+			let period = self.current_block() / FEE_EXEMPTION_PERIOD;
+			match self.fee_exemption_usage.get(account) {
+				Some((last_period, used)) if last_period == period => used,
+				_ => 0,
+			}
+		}
+
+		/// Draw a verify fee for the caller from their sponsor's credits, within the sponsor's
+		/// allowance. Returns whether the sponsor covered it.
+		fn draw_sponsorship(&mut self, fee: Balance) -> bool {
+			let user = self.caller();
+			let Some(mut sponsorship) = self.sponsorships.get(user) else { return false };
+			if sponsorship.allowance < fee || !self.draw_credits(sponsorship.sponsor, fee) {
+				return false
+			}
+			sponsorship.allowance -= fee;
+			if sponsorship.allowance == 0 {
+				self.sponsorships.remove(user);
+			} else {
+				self.sponsorships.insert(user, &sponsorship);
+			}
+			true
+		}
+
+		/// Use one of the caller's fee exempt verifications. Returns whether it had any left.
+		fn use_fee_exemption(&mut self) -> bool {
+			let account = self.caller();
+			if self.fee_exemptions_left(account).unwrap_or_default() == 0 {
+				return false
+			}
+			let period = self.current_block() / FEE_EXEMPTION_PERIOD;
+			let used = self.fee_exemptions_used(account);
+			self.fee_exemption_usage.insert(account, &(period, used + 1));
+			true
+		}
+
+		/// Draw a verify fee from an account's prepaid credits. Returns whether they covered it.
+		fn draw_credits(&mut self, account: AccountId, fee: Balance) -> bool {
+			let credits = self.credits_of(account);
+			if credits < fee {
+				return false
+			}
+			if credits == fee {
+				self.credits.remove(account);
+			} else {
+				self.credits.insert(account, &(credits - fee));
+			}
+			true
+		}
+
+		/// Take an amount out of the treasury, failing if it holds less.
+		fn withdraw_treasury(&mut self, amount: Balance) -> Result<()> {
+			self.treasury = self.treasury.checked_sub(amount).ok_or(Error::InsufficientTreasury)?;
+			Ok(())
+		}
+
+		/// Return to the caller whatever they paid beyond the required fee.
+		fn refund_excess(&self, paid: Balance, required_fee: Balance) -> Result<()> {
+			let excess = paid.saturating_sub(required_fee);
+			if excess > 0 {
+				self.env().transfer(self.caller(), excess).map_err(|_| Error::PaymentFailed)?;
+			}
+			Ok(())
+		}
+
+		/// The relay fee of the given chain right now, see `current_relay_fee`.
+		fn relay_fee_of(&self, chain_id: ChainId, chain: &SourceChain) -> Balance {
+			if chain_id == DEFAULT_CHAIN_ID {
+				self.current_relay_fee()
+			} else {
+				chain.relay_fee
+			}
+		}
+
+		/// Adjust the controlled relay fee for a newly accepted default chain header.
+		fn observe_header_arrival(&mut self) {
+			let Some(controller) = self.fee_controller else { return };
+			let now = self.now();
+			self.surge_fee = controller
+				.surged_fee(self.current_relay_fee(), now.saturating_sub(self.last_header_arrival));
+			self.last_header_arrival = now;
+		}
+
+		/// Count a header accepted from the given relayer towards the rate limit, failing if it
+		/// would exceed the limit. Nothing is counted while no limit is set.
+		fn count_submission(&mut self, relayer: AccountId) -> Result<()> {
+			let Some((relayer_count, block_count)) = self.submission_counts(relayer)? else {
+				return Ok(())
+			};
+			let now = self.current_block();
+			self.relayer_submissions.insert(relayer, &(now, relayer_count + 1));
+			self.block_submissions = (now, block_count + 1);
+			Ok(())
+		}
+
+		/// The number of headers the given relayer and everyone have submitted in the current
+		/// block, or `None` if submissions are not rate limited. Fails if either is at its limit.
+		fn submission_counts(&self, relayer: AccountId) -> Result<Option<(u32, u32)>> {
+			let Some(limit) = self.rate_limit else { return Ok(None) };
+			let now = self.current_block();
+			let count_in = |(block_number, count): (BlockNumber, u32)| {
+				if block_number == now {
+					count
+				} else {
+					0
+				}
+			};
+			let relayer_count = self.relayer_submissions.get(relayer).map_or(0, count_in);
+			let block_count = count_in(self.block_submissions);
+			if relayer_count >= limit.per_relayer || block_count >= limit.per_block {
+				return Err(Error::RateLimited)
+			}
+			Ok(Some((relayer_count, block_count)))
+		}
+
+		/// Slash all of a relayer's stake, bonded or unbonding, for the given invalid header, and
+		/// reward the reporter. Returns whether there was any stake to slash.
+		fn slash(
+			&mut self,
+			relayer: AccountId,
+			reporter: AccountId,
+			block_hash: HashValue,
+		) -> bool {
+			let Some(stake) = self.relayer_stakes.take(relayer) else { return false };
+			let amount = stake.bonded.saturating_add(stake.unbonding);
+			let reward = amount.saturating_mul(SLASH_REPORTER_PERCENT) / 100;
+			self.credit_reward(reporter, reward);
+			self.treasury = self.treasury.saturating_add(amount - reward);
+			self.env()
+				.emit_event(RelayerSlashed { relayer, reporter, block_hash, amount, reward });
+			amount > 0
+		}
+
+		/// Add to the rewards a relayer can withdraw.
+		fn credit_reward(&mut self, relayer: AccountId, amount: Balance) {
+			let pending = self.pending_rewards(relayer);
+			self.pending_rewards.insert(relayer, &pending.saturating_add(amount));
+		}
+
+		/// Count a failed verification towards the current failure epoch.
+		fn record_failure(&mut self, failure: VerificationFailure) {
+			let key = (self.failure_epoch, failure.reason());
+			let count = self.failure_counts.get(key).unwrap_or_default();
+			self.failure_counts.insert(key, &count.saturating_add(1));
+		}
+
+		/// Verify a transaction against a block's transactions root, and report it if it holds.
+		fn verify_tx(
+			&mut self,
+			chain_id: ChainId,
+			paid: Balance,
+			tx_hash: HashValue,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			self.verify_claim(chain_id, paid, tx_hash, block_hash, min_depth, p, |header| {
+				header.transactions_root
+			})?;
+
+			self.record_verified_transaction(chain_id, tx_hash, block_hash);
+			self.emit_tx_verified(chain_id, tx_hash, block_hash, min_depth, None);
+			self.forward_transaction(chain_id, tx_hash, block_hash, min_depth);
+			Ok(())
+		}
+
+		/// Verify a transaction like `verify_tx`, for a call that reports a failed verification as
+		/// an outcome, so that its event, failure count and fee payment are kept.
+		fn verify_tx_outcome(
+			&mut self,
+			chain_id: ChainId,
+			paid: Balance,
+			tx_hash: HashValue,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> Result<bool> {
+			let result = self.verify_tx(chain_id, paid, tx_hash, block_hash, min_depth, p);
+			if let Err(VerificationError::Failed(failure)) = result {
+				self.emit_tx_verified(chain_id, tx_hash, block_hash, min_depth, Some(failure));
+			}
+			self.outcome(result)
+		}
+
+		/// Emit the `TransactionVerified` event for a verification that was not rejected.
+		fn emit_tx_verified(
+			&mut self,
+			chain_id: ChainId,
+			tx_hash: HashValue,
+			block_hash: HashValue,
+			min_depth: u64,
+			failure: Option<VerificationFailure>,
+		) {
+			self.env().emit_event(TransactionVerified {
+				chain_id,
+				tx_hash,
+				block_hash,
+				claim_id: self.claim_id(tx_hash, block_hash),
+				depth: min_depth,
+				verifier: self.caller(),
+				outcome: failure.is_none(),
+				failure,
+			});
+		}
+
+		/// Send a verified transaction to the XCM destination, if there is one.
+		fn forward_transaction(
+			&mut self,
+			chain_id: ChainId,
+			tx_hash: HashValue,
+			block_hash: HashValue,
+			depth: u64,
+		) {
+			let Some(destination) = self.xcm_destination else { return };
+			let claim_id = self.claim_id(tx_hash, block_hash);
+			let call = codec::xcm_claim_call(
+				destination.call_index,
+				chain_id,
+				&tx_hash,
+				&block_hash,
+				&claim_id,
+				depth,
 			);
+			let sent = xcm::send_transact(destination.para_id, &call) == Some(true);
+			self.env()
+				.emit_event(ClaimForwarded { claim_id, para_id: destination.para_id, sent });
+		}
+
+		/// Record the successful verification of a state claim against a block.
+		fn record_attestation(
+			&mut self,
+			chain_id: ChainId,
+			claim_hash: HashValue,
+			block_hash: HashValue,
+		) {
+			let best_height = self.chains.get(chain_id).map_or(0, |chain| chain.best_height);
+			let height = self.headers.get((chain_id, block_hash)).map_or(0, |header| header.height);
+			let mut attestations = self.attestations.get(claim_hash).unwrap_or_default();
+			if attestations.len() >= MAX_ATTESTATIONS_PER_CLAIM {
+				attestations.remove(0);
+			}
+			attestations.push(Attestation {
+				chain_id,
+				claim_hash,
+				block_hash,
+				depth: best_height.saturating_sub(height),
+				verifier: self.caller(),
+				timestamp: self.now(),
+			});
+			self.attestations.insert(claim_hash, &attestations);
+		}
+
+		/// Remember that a transaction has been verified against a block, unless it already was.
+		fn record_verified_transaction(
+			&mut self,
+			chain_id: ChainId,
+			tx_hash: HashValue,
+			block_hash: HashValue,
+		) {
+			let key = (chain_id, tx_hash, block_hash);
+			if !self.verified_transactions.contains(key) {
+				let best_height = self.chains.get(chain_id).map_or(0, |chain| chain.best_height);
+				self.verified_transactions.insert(key, &best_height);
+			}
+		}
+
+		/// The best height from which a verify fee for the given block may be paid to its relayer,
+		/// or `None` if verify fees are never escrowed.
+		fn fee_release_height(&self, header: &Header, min_depth: u64) -> Option<u64> {
+			let by_payout_depth =
+				self.payout_depth.map(|depth| header.height.saturating_add(depth));
+			let by_safety_margin = self
+				.escrow_safety_margin
+				.map(|margin| header.height.saturating_add(min_depth).saturating_add(margin));
+			by_payout_depth.max(by_safety_margin)
+		}
+
+		/// Hold the verify fee for the given block until its chain reaches the release height.
+		fn escrow_verify_fee(
+			&mut self,
+			chain_id: ChainId,
+			block_hash: HashValue,
+			header: &Header,
+			relayer: AccountId,
+			release_height: u64,
+			amount: Balance,
+		) {
+			let escrow_id = self.next_escrow_id;
+			self.next_escrow_id += 1;
+
+			let escrow = FeeEscrow {
+				verifier: self.caller(),
+				chain_id,
+				block_hash,
+				block_height: header.height,
+				relayer,
+				release_height,
+				amount,
+			};
+			self.fee_escrows.insert(escrow_id, &escrow);
+			self.env().emit_event(FeeEscrowed {
+				escrow_id,
+				verifier: escrow.verifier,
+				block_hash,
+				release_height,
+				amount: escrow.amount,
+			});
+		}
+
+		/// Advance the contract's view of the target chain's clock and block number.
+		///
+		/// Only available in test builds or with the `test-helpers` feature.
+		#[cfg(any(test, feature = "test-helpers"))]
+		#[ink(message)]
+		pub fn fast_forward(&mut self, milliseconds: Timestamp, blocks: BlockNumber) {
+			self.test_overrides.clock_offset += milliseconds;
+			self.test_overrides.block_offset += blocks;
+		}
+
+		/// Make the contract treat every subsequent call as coming from the given account, or
+		/// from the real caller again if `None`.
+		///
+		/// Only available in test builds or with the `test-helpers` feature.
+		#[cfg(any(test, feature = "test-helpers"))]
+		#[ink(message)]
+		pub fn impersonate(&mut self, account: Option<AccountId>) {
+			self.test_overrides.caller = account;
+		}
+
+		/// Fail unless the caller holds the role.
+		fn ensure_role(&self, role: Role) -> Result<()> {
+			if !self.has_role(role, self.caller()) {
+				return Err(Error::MissingRole)
+			}
+			Ok(())
+		}
+
+		/// The shallowest prune depth that keeps the `MEDIAN_TIME_SPAN` ancestors, and the
+		/// difficulty epoch if retargeting, that are needed to validate new headers.
+		fn min_prune_depth(retarget: Option<RetargetParams>) -> u64 {
+			retarget.map_or(0, |params| params.interval).max(MEDIAN_TIME_SPAN as u64)
+		}
+
+		/// Migrate storage left behind by code of the given version to the next version.
+		fn migrate_from(&mut self, version: u32) -> Result<()> {
+			match version {
+				// Version 1 had a single owner, which now needs the admin role to govern.
+				1 => self.grant(Role::Admin, self.owner),
+				_ => return Err(Error::UnsupportedStorageVersion),
+			}
+			Ok(())
+		}
+
+		/// Fail if critical actions must be proposed and approved by signers.
+		fn ensure_no_multisig(&self) -> Result<()> {
+			if self.signer_threshold > 0 {
+				return Err(Error::MultisigRequired)
+			}
+			Ok(())
+		}
+
+		/// Fail unless the caller is a signer, returning the caller otherwise.
+		fn ensure_signer(&self) -> Result<AccountId> {
+			let caller = self.caller();
+			if !self.signers.contains(&caller) {
+				return Err(Error::NotSigner)
+			}
+			Ok(caller)
+		}
+
+		/// Record a signer's approval of a proposal, and execute its action once the threshold
+		/// is met.
+		fn add_approval(
+			&mut self,
+			id: u32,
+			mut proposal: Proposal,
+			signer: AccountId,
+		) -> Result<()> {
+			proposal.approvals.push(signer);
+			let approvals = proposal.approvals.len() as u32;
+			self.env().emit_event(ProposalApproved { id, signer, approvals });
+			if approvals < self.signer_threshold {
+				self.proposals.insert(id, &proposal);
+				return Ok(())
+			}
+
+			self.proposals.remove(id);
+			match proposal.action {
+				CriticalAction::Upgrade(code_hash) => self.upgrade_code(code_hash)?,
+				CriticalAction::ResetCheckpoint(header) =>
+					self.schedule_checkpoint_reset(header)?,
+				CriticalAction::Pause => self.schedule_parameter(BridgeParameter::Paused(true))?,
+				CriticalAction::Unpause =>
+					self.schedule_parameter(BridgeParameter::Paused(false))?,
+				CriticalAction::ResolveHalt(adopt_fork) => self.lift_halt(adopt_fork)?,
+				CriticalAction::SetSigners(signers, threshold) =>
+					self.replace_signers(signers, threshold)?,
+			}
+			self.env().emit_event(ProposalExecuted { id });
+			Ok(())
+		}
+
+		/// Replace the signer set and threshold, invalidating the approvals of open proposals.
+		fn replace_signers(&mut self, signers: Vec<AccountId>, threshold: u32) -> Result<()> {
+			governance::check_signer_set(&signers, threshold)?;
+			self.signers = signers.clone();
+			self.signer_threshold = threshold;
+			self.signer_epoch = self.signer_epoch.wrapping_add(1);
+			self.env().emit_event(SignersChanged { signers, threshold });
+			Ok(())
+		}
+
+		/// Grant a role to an account, if it doesn't hold it yet.
+		fn grant(&mut self, role: Role, account: AccountId) {
+			if self.roles.insert((role, account), &()).is_some() {
+				return
+			}
+			if role == Role::Admin {
+				self.admin_count += 1;
+			}
+			self.env().emit_event(RoleGranted { role, account });
+		}
+
+		/// Revoke a role from an account, if it holds it.
+		fn revoke(&mut self, role: Role, account: AccountId) -> Result<()> {
+			if !self.roles.contains((role, account)) {
+				return Ok(())
+			}
+			if role == Role::Admin {
+				if self.admin_count <= 1 {
+					return Err(Error::LastAdmin)
+				}
+				self.admin_count -= 1;
+			}
+			self.roles.remove((role, account));
+			self.env().emit_event(RoleRevoked { role, account });
+			Ok(())
+		}
+
+		/// Whether no header has been accepted on the chain for longer than the staleness window,
+		/// so that verifications against it are refused.
+		fn exceeds_staleness_window(&self, chain: &SourceChain) -> bool {
+			self.staleness_window.is_some_and(|window| {
+				self.current_block().saturating_sub(chain.last_header_block) > window
+			})
+		}
+
+		/// Fail if the bridge is paused.
+		fn ensure_not_paused(&self) -> Result<()> {
+			if self.paused {
+				return Err(Error::BridgePaused)
+			}
+			if self.halt.is_some() {
+				return Err(Error::BridgeHalted)
+			}
+			Ok(())
+		}
+
+		/// Queue a parameter change behind the parameter timelock, or apply it straight away if
+		/// no timelock is set.
+		fn schedule_parameter(&mut self, parameter: BridgeParameter) -> Result<()> {
+			if self.parameter_timelock == 0 {
+				self.apply_parameter(parameter);
+				return Ok(())
+			}
+			if self.pending_changes.len() >= MAX_PENDING_CHANGES {
+				return Err(Error::TooManyPendingChanges)
+			}
+			let id = self.next_change_id;
+			self.next_change_id = id.wrapping_add(1);
+			let enactable_at = self.current_block().saturating_add(self.parameter_timelock);
+			self.pending_changes.push(PendingChange { id, parameter, enactable_at });
+			self.env().emit_event(ParameterChangeScheduled { id, parameter, enactable_at });
+			Ok(())
+		}
+
+		/// Remove a queued parameter change.
+		fn take_pending_change(&mut self, id: u32) -> Result<PendingChange> {
+			let index = self
+				.pending_changes
+				.iter()
+				.position(|change| change.id == id)
+				.ok_or(Error::NoPendingChange)?;
+			Ok(self.pending_changes.remove(index))
+		}
+
+		/// Set a bridge parameter to its new value and announce the change.
+		///
+		/// Parameters specific to a source chain apply to the default chain, unless they name
+		/// another one.
+		fn apply_parameter(&mut self, parameter: BridgeParameter) {
+			let mut chain = self.default_chain();
+			match parameter {
+				BridgeParameter::RelayFee(fee) => chain.relay_fee = fee,
+				BridgeParameter::VerifyFee(fee) => chain.verify_fee = fee,
+				BridgeParameter::DifficultyThreshold(threshold) =>
+					chain.difficulty_threshold = threshold,
+				BridgeParameter::MaxProofLength(length) => self.max_proof_length = length,
+				BridgeParameter::MaxBatchSize(size) => self.max_batch_size = size,
+				BridgeParameter::PruneDepth(depth) => chain.prune_depth = depth,
+				BridgeParameter::FinalityDepth(depth) => chain.finality_depth = depth,
+				BridgeParameter::FreeVerifications(count) => self.free_verifications = count,
+				BridgeParameter::SampleInterval(interval) => self.sample_interval = interval,
+				BridgeParameter::MergedMining(enabled) => chain.merged_mining = enabled,
+				BridgeParameter::ChallengePeriod(period) => self.challenge_period = period,
+				BridgeParameter::HeaderBond(bond) => self.header_bond = bond,
+				BridgeParameter::MinRelayerStake(stake) => self.min_relayer_stake = stake,
+				BridgeParameter::RelayerAllowlist(enabled) => self.allowlist_enabled = enabled,
+				BridgeParameter::RateLimit(limit) => self.rate_limit = limit,
+				BridgeParameter::FeeSplit(split) => self.fee_split = split,
+				BridgeParameter::MinConfirmations(depth) => self.min_confirmations = depth,
+				BridgeParameter::MaxReorgDepth(depth) => self.max_reorg_depth = depth,
+				BridgeParameter::StalenessWindow(window) => self.staleness_window = window,
+				BridgeParameter::WrappedAsset(asset) => self.wrapped_asset = asset,
+				BridgeParameter::ParameterTimelock(delay) => self.parameter_timelock = delay,
+				BridgeParameter::Paused(paused) => {
+					// Pausing is announced with its own events, and only if it changes anything.
+					if paused && !self.paused {
+						self.paused = true;
+						self.env().emit_event(Paused { account: self.caller() });
+						self.raise_alert(AlertKind::Paused);
+					} else if !paused && self.paused {
+						self.paused = false;
+						self.env().emit_event(Unpaused { account: self.caller() });
+					}
+					return
+				},
+				BridgeParameter::FeeController(controller) => {
+					self.fee_controller = controller;
+					self.surge_fee = chain.relay_fee;
+					self.last_header_arrival = self.now();
+				},
+				BridgeParameter::XcmDestination(destination) => self.xcm_destination = destination,
+				BridgeParameter::ChainPruneDepth(chain_id, depth) =>
+					self.update_chain(chain_id, |chain| chain.prune_depth = depth),
+				BridgeParameter::ChainFinalityDepth(chain_id, depth) =>
+					self.update_chain(chain_id, |chain| chain.finality_depth = depth),
+			}
+			self.chains.insert(DEFAULT_CHAIN_ID, &chain);
+			self.env().emit_event(ParameterChanged { parameter });
+		}
+
+		/// Change a registered source chain other than the default one.
+		fn update_chain(&mut self, chain_id: ChainId, update: impl FnOnce(&mut SourceChain)) {
+			if let Some(mut chain) = self.chains.get(chain_id) {
+				update(&mut chain);
+				self.chains.insert(chain_id, &chain);
+			}
+		}
+
+		/// The account calling the contract.
+		fn caller(&self) -> AccountId {
+			self.test_overrides.caller.unwrap_or_else(|| self.env().caller())
+		}
+
+		/// The target chain's current block timestamp.
+		fn now(&self) -> Timestamp {
+			self.env().block_timestamp() + self.test_overrides.clock_offset
+		}
+
+		/// The target chain's current block number.
+		fn current_block(&self) -> BlockNumber {
+			self.env().block_number() + self.test_overrides.block_offset
+		}
+
+		/// This function is not graded. It is just for collecting feedback.
+		/// On a scale from 0 - 100, with zero being extremely easy and 100 being extremely hard,
+		/// how hard did you find the exercises in this section?
+		pub fn how_hard_was_this_section() -> u8 {
+			todo!()
+		}
+
+		/// This function is not graded. It is just for collecting feedback.
+		/// About how much time (in minutes) did you spend on the exercises in this section?
+		pub fn how_many_minutes_did_you_spend_on_this_section() -> u32 {
+			todo!()
+		}
+	}
+
+	/// The bridge's own verification messages, for contracts calling it through the
+	/// `SpvVerifier` trait.
+	impl SpvVerifier for SpvBridge {
+		#[ink(message, payable)]
+		fn verify_transaction(
+			&mut self,
+			tx_hash: HashValue,
+			header_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			SpvBridge::verify_transaction(self, tx_hash, header_hash, min_depth, p)
+		}
+
+		#[ink(message, payable)]
+		fn verify_state(
+			&mut self,
+			claim: StateClaim,
+			block_hash: HashValue,
+			min_depth: u64,
+			p: MerkleProof,
+		) -> VerificationResult {
+			SpvBridge::verify_state(self, claim, block_hash, min_depth, p)
+		}
+	}
+
+	// The contract macro can't take file modules, so the tests are included instead.
+	#[cfg(test)]
+	mod tests {
+		include!("tests.rs");
+	}
+
+	/// End-to-end tests against a substrate-contracts-node, which move real balances.
+	///
+	/// Run them with `cargo test --features e2e-tests`. They build the contract with
+	/// cargo-contract and start `substrate-contracts-node` from the `PATH`, unless
+	/// `CONTRACTS_NODE` points at another binary.
+	#[cfg(all(test, feature = "e2e-tests"))]
+	mod e2e_tests {
+		use super::*;
+		use bridge_mock::BridgeMockRef;
+		use ink_e2e::{build_message, AccountKeyring};
+		use scale::Encode;
+
+		type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+		const THRESHOLD: HashValue = [
+			63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0,
+		];
+		const RELAY_FEE: Balance = 1_000_000_000;
+		const VERIFY_FEE: Balance = 100_000_000;
+		const BLOCK_TIME: u64 = 60_000;
+		const TX_HASH: HashValue = [7; 32];
+
+		/// A checkpoint authored two hours ago, so that its children are neither too far in the
+		/// node's future nor stale.
+		fn checkpoint() -> Header {
+			let now = std::time::SystemTime::now()
+				.duration_since(std::time::UNIX_EPOCH)
+				.unwrap()
+				.as_millis() as u64;
+			Header {
+				height: 100,
+				target: THRESHOLD,
+				pow_nonce: 1,
+				timestamp: now - 120 * BLOCK_TIME,
+				..Default::default()
+			}
+		}
+
+		/// A mined child of the parent, whose only transaction is `TX_HASH`.
+		fn mined_child(parent: Header) -> Header {
+			mined_child_with_transactions_root(parent, MerkleProof::hash_leaf(&TX_HASH))
+		}
+
+		/// A mined child of the parent with the given transactions root.
+		fn mined_child_with_transactions_root(parent: Header, tx_root: HashValue) -> Header {
+			let mut child = Header {
+				height: parent.height + 1,
+				parent: SpvBridge::hash_header(parent),
+				transactions_root: tx_root,
+				target: THRESHOLD,
+				pow_nonce: 1,
+				timestamp: parent.timestamp + BLOCK_TIME,
+				..Default::default()
+			};
+			while SpvBridge::hash_header(child) >= child.target {
+				child.pow_nonce += 1;
+			}
+			child
+		}
+
+		/// The header notifications the mock consumer has received.
+		async fn notifications(
+			client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, ink::env::DefaultEnvironment>,
+			consumer: AccountId,
+		) -> Vec<bridge_mock::HeaderNotification> {
+			let query =
+				build_message::<BridgeMockRef>(consumer).call(|consumer| consumer.notifications());
+			client.call_dry_run(&ink_e2e::bob(), &query, 0, None).await.return_value()
+		}
+
+		#[ink_e2e::test]
+		async fn relay_fee_is_locked_and_excess_refunded(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let constructor = SpvBridgeRef::new(checkpoint(), THRESHOLD, RELAY_FEE, VERIFY_FEE);
+			let bridge = client
+				.instantiate("spv_bridge", &ink_e2e::alice(), constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+			let locked_before = client.balance(bridge).await.unwrap();
+
+			// Bob overpays, and only the relay fee stays in the contract.
+			let submit = build_message::<SpvBridgeRef>(bridge)
+				.call(|bridge| bridge.submit_new_header(mined_child(checkpoint())));
+			let result = client
+				.call(&ink_e2e::bob(), submit, 2 * RELAY_FEE, None)
+				.await
+				.expect("submit failed");
+			assert_eq!(result.return_value(), Ok(()));
+			assert_eq!(client.balance(bridge).await.unwrap(), locked_before + RELAY_FEE);
+
+			// Underpaying is rejected before any value moves.
+			let underpaid = build_message::<SpvBridgeRef>(bridge)
+				.call(|bridge| bridge.submit_new_header(mined_child(mined_child(checkpoint()))));
+			assert!(client.call(&ink_e2e::bob(), underpaid, RELAY_FEE - 1, None).await.is_err());
+			assert_eq!(client.balance(bridge).await.unwrap(), locked_before + RELAY_FEE);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn verify_fee_is_paid_out_to_the_relayer(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let constructor = SpvBridgeRef::new(checkpoint(), THRESHOLD, RELAY_FEE, VERIFY_FEE);
+			let bridge = client
+				.instantiate("spv_bridge", &ink_e2e::alice(), constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+			let bob = ink_e2e::account_id(AccountKeyring::Bob);
+
+			let child = mined_child(checkpoint());
+			let submit = build_message::<SpvBridgeRef>(bridge)
+				.call(|bridge| bridge.submit_new_header(child));
+			client
+				.call(&ink_e2e::bob(), submit, RELAY_FEE, None)
+				.await
+				.expect("submit failed");
+
+			// Charlie's verify fee is held by the contract until Bob withdraws it.
+			let locked_before = client.balance(bridge).await.unwrap();
+			let verify = build_message::<SpvBridgeRef>(bridge).call(|bridge| {
+				bridge.verify_transaction(
+					TX_HASH,
+					SpvBridge::hash_header(child),
+					0,
+					MerkleProof::default(),
+				)
+			});
+			let result = client
+				.call(&ink_e2e::charlie(), verify, VERIFY_FEE, None)
+				.await
+				.expect("verify failed");
+			assert_eq!(result.return_value(), Ok(()));
+			assert_eq!(client.balance(bridge).await.unwrap(), locked_before + VERIFY_FEE);
+
+			let pending =
+				build_message::<SpvBridgeRef>(bridge).call(|bridge| bridge.pending_rewards(bob));
+			let pending = client.call_dry_run(&ink_e2e::bob(), &pending, 0, None).await;
+			assert_eq!(pending.return_value(), VERIFY_FEE);
+
+			let withdraw =
+				build_message::<SpvBridgeRef>(bridge).call(|bridge| bridge.withdraw_rewards());
+			client.call(&ink_e2e::bob(), withdraw, 0, None).await.expect("withdraw failed");
+			assert_eq!(client.balance(bridge).await.unwrap(), locked_before);
+
+			Ok(())
+		}
+
+		#[ink_e2e::test]
+		async fn approved_header_subscribers_are_notified(
+			mut client: ink_e2e::Client<C, E>,
+		) -> E2EResult<()> {
+			let constructor = SpvBridgeRef::new(checkpoint(), THRESHOLD, RELAY_FEE, VERIFY_FEE);
+			let bridge = client
+				.instantiate("spv_bridge", &ink_e2e::alice(), constructor, 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+			let consumer = client
+				.instantiate("bridge_mock", &ink_e2e::alice(), BridgeMockRef::new(), 0, None)
+				.await
+				.expect("instantiate failed")
+				.account_id;
+			let subscribe = build_message::<BridgeMockRef>(consumer)
+				.call(|consumer| consumer.subscribe(bridge));
+			let result = client
+				.call(&ink_e2e::bob(), subscribe, HEADER_SUBSCRIPTION_DEPOSIT, None)
+				.await
+				.expect("subscribe failed");
+			assert!(result.return_value());
+
+			// G---A---B
+			//      \
+			//       --C---D---E
+			let a_header = mined_child(checkpoint());
+			let b_header = mined_child(a_header);
+			let c_header = mined_child_with_transactions_root(a_header, [1; 32]);
+			let d_header = mined_child(c_header);
+			let e_header = mined_child(d_header);
+			let submit = |header: Header| {
+				build_message::<SpvBridgeRef>(bridge)
+					.call(move |bridge| bridge.submit_new_header(header))
+			};
+			// Nothing is sent until an admin approves the subscription.
+			client
+				.call(&ink_e2e::bob(), submit(a_header), RELAY_FEE, None)
+				.await
+				.expect("submit failed");
+			assert_eq!(notifications(&mut client, consumer).await, vec![]);
+			let approve = build_message::<SpvBridgeRef>(bridge)
+				.call(|bridge| bridge.approve_header_subscriber(consumer));
+			client.call(&ink_e2e::alice(), approve, 0, None).await.expect("approve failed");
+
+			for header in [b_header, c_header, d_header] {
+				client
+					.call(&ink_e2e::bob(), submit(header), RELAY_FEE, None)
+					.await
+					.expect("submit failed");
+			}
+			let new_tip = |header: Header| HeaderNotification::NewTip {
+				chain_id: DEFAULT_CHAIN_ID,
+				block_hash: SpvBridge::hash_header(header),
+				block_height: header.height,
+			};
+			let expected = vec![
+				new_tip(b_header),
+				HeaderNotification::Reorg {
+					chain_id: DEFAULT_CHAIN_ID,
+					common_ancestor: SpvBridge::hash_header(a_header),
+					new_tip: SpvBridge::hash_header(d_header),
+					depth: 1,
+				},
+				new_tip(d_header),
+			];
+			assert_eq!(notifications(&mut client, consumer).await.encode(), expected.encode());
+
+			// A failing callback doesn't stop the header from being accepted.
+			let fail = build_message::<BridgeMockRef>(consumer)
+				.call(|consumer| consumer.set_failing(true));
+			client.call(&ink_e2e::bob(), fail, 0, None).await.expect("set_failing failed");
+			let result = client
+				.call(&ink_e2e::bob(), submit(e_header), RELAY_FEE, None)
+				.await
+				.expect("submit failed");
+			assert_eq!(result.return_value(), Ok(()));
+			assert_eq!(notifications(&mut client, consumer).await.encode(), expected.encode());
+
+			Ok(())
+		}
+
+		/// Gas measurements of the relaying and verification paths, at several chain lengths and
+		/// reorg depths, to catch storage layout regressions.
+		///
+		/// Run them with `cargo test --features e2e-benchmarks -- --nocapture`. The report is
+		/// printed and written as JSON lines to the file named by `GAS_REPORT`, by default
+		/// `gas-report.jsonl`, one line per measurement:
+		///
+		/// ```json
+		/// {"message":"submit_new_header","chain_length":16,"reorg_depth":0,"ref_time":1,"proof_size":2}
+		/// ```
+		#[cfg(feature = "e2e-benchmarks")]
+		mod benchmarks {
+			use super::*;
+			use ink::env::DefaultEnvironment;
+			use std::io::Write;
+
+			/// The number of headers above the checkpoint the chain is grown to before measuring.
+			const CHAIN_LENGTHS: [usize; 3] = [1, 16, 64];
+
+			/// The number of canon headers a fork displaces when it takes over.
+			const REORG_DEPTHS: [usize; 3] = [1, 4, 16];
+
+			/// The number of headers in a measured `submit_headers` batch.
+			const BATCH_SIZE: usize = 8;
+
+			/// One line of the gas report.
+			struct Measurement {
+				message: &'static str,
+				chain_length: usize,
+				reorg_depth: usize,
+				ref_time: u64,
+				proof_size: u64,
+			}
+
+			impl Measurement {
+				fn to_json(&self) -> String {
+					format!(
+						"{{\"message\":\"{}\",\"chain_length\":{},\"reorg_depth\":{},\"ref_time\":{},\"proof_size\":{}}}",
+						self.message, self.chain_length, self.reorg_depth, self.ref_time, self.proof_size
+					)
+				}
+			}
+
+			/// The gas a call consumed, as its reference time and proof size.
+			fn gas<V: scale::Decode>(
+				result: &ink_e2e::CallResult<ink_e2e::PolkadotConfig, DefaultEnvironment, V>,
+			) -> (u64, u64) {
+				let gas = result.dry_run.exec_result.gas_consumed;
+				(gas.ref_time(), gas.proof_size())
+			}
+
+			/// Submit the headers in batches of at most `DEFAULT_MAX_BATCH_SIZE`.
+			async fn submit_all(
+				client: &mut ink_e2e::Client<ink_e2e::PolkadotConfig, DefaultEnvironment>,
+				bridge: AccountId,
+				headers: &[Header],
+			) {
+				for batch in headers.chunks(DEFAULT_MAX_BATCH_SIZE as usize) {
+					let fee = RELAY_FEE * batch.len() as Balance;
+					let submit = build_message::<SpvBridgeRef>(bridge)
+						.call(|bridge| bridge.submit_headers(batch.to_vec()));
+					client.call(&ink_e2e::bob(), submit, fee, None).await.expect("submit failed");
+				}
+			}
+
+			/// `count` mined headers on top of the parent, each with a single transaction, see
+			/// `only_transaction`, starting at `seed`.
+			fn chain_on(parent: Header, count: usize, seed: u8) -> Vec<Header> {
+				let mut headers: Vec<Header> = Vec::with_capacity(count);
+				for i in 0..count {
+					let parent = headers.last().copied().unwrap_or(parent);
+					let root = MerkleProof::hash_leaf(&only_transaction(seed, i));
+					headers.push(mined_child_with_transactions_root(parent, root));
+				}
+				headers
+			}
+
+			/// The only transaction of the `i`-th header of a `chain_on` the given seed.
+			fn only_transaction(seed: u8, i: usize) -> HashValue {
+				[seed.wrapping_add(i as u8); 32]
+			}
+
+			#[ink_e2e::test]
+			async fn gas_report(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+				let mut report = Vec::new();
+				for chain_length in CHAIN_LENGTHS {
+					let constructor =
+						SpvBridgeRef::new(checkpoint(), THRESHOLD, RELAY_FEE, VERIFY_FEE);
+					let bridge = client
+						.instantiate("spv_bridge", &ink_e2e::alice(), constructor, 0, None)
+						.await
+						.expect("instantiate failed")
+						.account_id;
+					let mut chain = vec![checkpoint()];
+					chain.extend(chain_on(checkpoint(), chain_length, 0));
+					submit_all(&mut client, bridge, &chain[1..]).await;
+					let mut record = |message, reorg_depth, (ref_time, proof_size)| {
+						report.push(Measurement {
+							message,
+							chain_length,
+							reorg_depth,
+							ref_time,
+							proof_size,
+						})
+					};
+
+					// Verification against the tip, then extending it.
+					let tip = *chain.last().unwrap();
+					let verify = build_message::<SpvBridgeRef>(bridge).call(|bridge| {
+						bridge.verify_transaction(
+							only_transaction(0, chain_length - 1),
+							SpvBridge::hash_header(tip),
+							0,
+							MerkleProof::default(),
+						)
+					});
+					let result = client
+						.call(&ink_e2e::bob(), verify, VERIFY_FEE, None)
+						.await
+						.expect("verify failed");
+					record("verify_transaction", 0, gas(&result));
+
+					let child = mined_child_with_transactions_root(tip, [0xaa; 32]);
+					let submit = build_message::<SpvBridgeRef>(bridge)
+						.call(|bridge| bridge.submit_new_header(child));
+					let result = client
+						.call(&ink_e2e::bob(), submit, RELAY_FEE, None)
+						.await
+						.expect("submit failed");
+					record("submit_new_header", 0, gas(&result));
+					chain.push(child);
+
+					let batch = chain_on(child, BATCH_SIZE, 0xb0);
+					let fee = RELAY_FEE * BATCH_SIZE as Balance;
+					let submit = build_message::<SpvBridgeRef>(bridge)
+						.call(|bridge| bridge.submit_headers(batch.clone()));
+					let result = client
+						.call(&ink_e2e::bob(), submit, fee, None)
+						.await
+						.expect("submit failed");
+					record("submit_headers", 0, gas(&result));
+					chain.extend(batch);
+
+					// Forks from `depth` blocks below the tip, each taking over with its last
+					// header. Deeper forks than the chain is long can't be built.
+					for (fork, depth) in REORG_DEPTHS.into_iter().enumerate() {
+						if depth >= chain.len() {
+							continue
+						}
+						let fork_point = chain[chain.len() - 1 - depth];
+						let fork = chain_on(fork_point, depth + 1, 0xc0 + 0x20 * fork as u8);
+						let (takeover, rest) = fork.split_last().unwrap();
+						submit_all(&mut client, bridge, rest).await;
+						let takeover = *takeover;
+						let submit = build_message::<SpvBridgeRef>(bridge)
+							.call(|bridge| bridge.submit_new_header(takeover));
+						let result = client
+							.call(&ink_e2e::bob(), submit, RELAY_FEE, None)
+							.await
+							.expect("submit failed");
+						record("submit_new_header", depth, gas(&result));
+						chain.truncate(chain.len() - depth);
+						chain.extend(fork);
+					}
+				}
+
+				let path =
+					std::env::var("GAS_REPORT").unwrap_or_else(|_| "gas-report.jsonl".into());
+				let mut file = std::fs::File::create(&path)?;
+				for measurement in &report {
+					writeln!(file, "{}", measurement.to_json())?;
+				}
+
+				Ok(())
+			}
 		}
 	}
 }
@@ -0,0 +1,153 @@
+//! Merkle mountain range proofs, as produced by Substrate's MMR pallet.
+//!
+//! An MMR over `n` leaves is a list of perfect binary trees, one for each set bit of `n`, the
+//! largest on the left. Its root bags the trees' peaks from the right: the rightmost peak is
+//! hashed with the one to its left, that with the next one, and so on.
+//!
+//! A proof for one leaf lists the peaks to the left of the leaf's tree, the siblings on the path
+//! from the leaf to its peak, and then all the peaks to the right bagged into a single node.
+
+use crate::{codec, spv_bridge::HashValue};
+use ink::prelude::vec::Vec;
+
+/// Compute the root of an MMR with `leaf_count` leaves from a proof that `leaf` is the node of
+/// the leaf at `leaf_index`. Returns `None` if the proof has the wrong number of items.
+pub fn root_from_proof(
+	leaf: HashValue,
+	leaf_index: u64,
+	leaf_count: u64,
+	items: &[HashValue],
+) -> Option<HashValue> {
+	if leaf_index >= leaf_count {
+		return None
+	}
+
+	let mut items = items.iter();
+	let mut peaks = Vec::new();
+	let mut first_leaf = 0;
+	for height in (0..u64::BITS).rev() {
+		let size = 1 << height;
+		if leaf_count & size == 0 {
+			continue
+		}
+		if leaf_index < first_leaf {
+			// The peaks right of the leaf's tree come bagged into one item.
+			peaks.push(*items.next()?);
+			break
+		}
+		if leaf_index < first_leaf + size {
+			let offset = leaf_index - first_leaf;
+			let mut node = leaf;
+			for depth in 0..height {
+				let sibling = items.next()?;
+				node = if (offset >> depth) & 1 == 1 {
+					codec::mmr_node(sibling, &node)
+				} else {
+					codec::mmr_node(&node, sibling)
+				};
+			}
+			peaks.push(node);
+		} else {
+			peaks.push(*items.next()?);
+		}
+		first_leaf += size;
+	}
+	if items.next().is_some() {
+		return None
+	}
+
+	Some(bag_peaks(&peaks))
+}
+
+/// Bag the peaks of an MMR, from the right.
+fn bag_peaks(peaks: &[HashValue]) -> HashValue {
+	let mut peaks = peaks.iter().rev();
+	let last = *peaks.next().expect("an MMR has at least one peak; qed");
+	peaks.fold(last, |root, peak| codec::mmr_node(&root, peak))
+}
+
+/// Build the MMR over the given leaf nodes and a proof for the leaf at `leaf_index`, returning
+/// the root and the proof's items.
+#[cfg(test)]
+pub(crate) fn build_proof(leaves: &[HashValue], leaf_index: usize) -> (HashValue, Vec<HashValue>) {
+	let mut peaks = Vec::new();
+	let mut path = Vec::new();
+	let mut own_peak = 0;
+	let mut first_leaf = 0;
+	for height in (0..usize::BITS).rev() {
+		let size = 1 << height;
+		if leaves.len() & size == 0 {
+			continue
+		}
+		let mut level = leaves[first_leaf..first_leaf + size].to_vec();
+		let mut index = leaf_index.wrapping_sub(first_leaf);
+		let contains_leaf = index < size;
+		while level.len() > 1 {
+			if contains_leaf {
+				path.push(level[index ^ 1]);
+				index /= 2;
+			}
+			level = level.chunks(2).map(|pair| codec::mmr_node(&pair[0], &pair[1])).collect();
+		}
+		if contains_leaf {
+			own_peak = peaks.len();
+		}
+		peaks.push(level[0]);
+		first_leaf += size;
+	}
+
+	let mut items = peaks[..own_peak].to_vec();
+	items.extend(path);
+	if own_peak + 1 < peaks.len() {
+		items.push(bag_peaks(&peaks[own_peak + 1..]));
+	}
+	(bag_peaks(&peaks), items)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn leaves(count: u8) -> Vec<HashValue> {
+		(0..count).map(|i| [i; 32]).collect()
+	}
+
+	#[test]
+	fn proofs_of_every_leaf_verify() {
+		crate::crypto::register_unsupported_extension();
+		for count in 1..=11u8 {
+			let leaves = leaves(count);
+			for (index, leaf) in leaves.iter().enumerate() {
+				let (root, items) = build_proof(&leaves, index);
+				assert_eq!(
+					root_from_proof(*leaf, index as u64, count.into(), &items),
+					Some(root),
+					"leaf {index} of {count}"
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn bad_proofs_do_not_verify() {
+		crate::crypto::register_unsupported_extension();
+		let leaves = leaves(7);
+		let (root, items) = build_proof(&leaves, 4);
+		// Leaves 4 and 5 form a tree of two between a tree of four and a tree of one.
+		assert_eq!(items.len(), 3);
+		assert_eq!(
+			root,
+			codec::mmr_node(
+				&codec::mmr_node(&leaves[6], &codec::mmr_node(&leaves[4], &leaves[5])),
+				&items[0]
+			)
+		);
+
+		assert_ne!(root_from_proof(leaves[5], 4, 7, &items), Some(root));
+		assert_ne!(root_from_proof(leaves[4], 5, 7, &items), Some(root));
+		assert_ne!(root_from_proof(leaves[4], 4, 8, &items), Some(root));
+		assert_eq!(root_from_proof(leaves[4], 4, 7, &items[1..]), None);
+		assert_eq!(root_from_proof(leaves[4], 4, 7, &[items.clone(), items].concat()), None);
+		assert_eq!(root_from_proof(leaves[4], 7, 7, &[]), None);
+	}
+}
@@ -0,0 +1,283 @@
+//! Proofs of reads from Ethereum's Merkle-Patricia tries.
+//!
+//! A proof is the list of trie nodes on the path from the root to the key, each RLP encoded.
+//! Nodes whose encoding is shorter than 32 bytes are embedded in their parent instead of being
+//! referenced by hash, so they do not appear in the proof.
+
+use crate::{codec, rlp, spv_bridge::HashValue};
+use ink::prelude::vec::Vec;
+
+/// Why a trie proof could not be checked.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ProofError {
+	/// A node the path leads to is not in the proof, or does not hash to its reference
+	MissingNode,
+	/// A node is not a valid trie node
+	MalformedNode,
+	/// The proof has nodes that are not on the path to the key
+	UnusedNodes,
+}
+
+/// Look up the value of a key in the trie with the given root.
+///
+/// Returns `Ok(None)` if the proof shows that the key is not in the trie.
+pub fn read(
+	root: &HashValue,
+	key: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	let nibbles: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+	let mut path = &nibbles[..];
+	let mut proof = proof.iter();
+
+	let mut next = NodeRef::Hash(*root);
+	let value = loop {
+		let node = match next {
+			NodeRef::Hash(hash) => {
+				let node = proof.next().ok_or(ProofError::MissingNode)?;
+				if codec::keccak_256(node) != hash {
+					return Err(ProofError::MissingNode)
+				}
+				node.as_slice()
+			},
+			NodeRef::Inline(node) => node,
+		};
+		let items = rlp::decode(node)
+			.and_then(|node| node.items())
+			.ok_or(ProofError::MalformedNode)?;
+
+		match items.len() {
+			// A branch, with a child for each nibble and a value for the key ending here.
+			17 => {
+				let Some((nibble, rest)) = path.split_first() else { break non_empty(items[16])? };
+				match NodeRef::from_item(items[usize::from(*nibble)])? {
+					Some(child) => next = child,
+					None => break None,
+				}
+				path = rest;
+			},
+			// A leaf or an extension, whose first item is its hex-prefix encoded path.
+			2 => {
+				let encoded_path = items[0].bytes().ok_or(ProofError::MalformedNode)?;
+				let (is_leaf, node_path) = decode_hex_prefix(encoded_path)?;
+				if !path.starts_with(&node_path) {
+					break None
+				}
+				path = &path[node_path.len()..];
+				if is_leaf {
+					if !path.is_empty() {
+						break None
+					}
+					break non_empty(items[1])?
+				}
+				next = NodeRef::from_item(items[1])?.ok_or(ProofError::MalformedNode)?;
+			},
+			_ => return Err(ProofError::MalformedNode),
+		}
+	};
+
+	if proof.next().is_some() {
+		return Err(ProofError::UnusedNodes)
+	}
+	Ok(value.map(<[u8]>::to_vec))
+}
+
+/// How a node refers to a child.
+enum NodeRef<'a> {
+	Hash(HashValue),
+	Inline(&'a [u8]),
+}
+
+impl<'a> NodeRef<'a> {
+	/// The child referred to by a node's item, or `None` if the item is empty.
+	fn from_item(item: rlp::Item<'a>) -> Result<Option<Self>, ProofError> {
+		if item.is_list {
+			return Ok(Some(NodeRef::Inline(item.raw)))
+		}
+		match item.payload.len() {
+			0 => Ok(None),
+			32 => Ok(Some(NodeRef::Hash(item.payload.try_into().expect("length checked; qed")))),
+			_ => Err(ProofError::MalformedNode),
+		}
+	}
+}
+
+/// A node's value, or `None` if it is empty.
+fn non_empty(item: rlp::Item<'_>) -> Result<Option<&[u8]>, ProofError> {
+	let value = item.bytes().ok_or(ProofError::MalformedNode)?;
+	Ok((!value.is_empty()).then_some(value))
+}
+
+/// Decode a hex-prefix encoded path into whether it belongs to a leaf, and its nibbles.
+fn decode_hex_prefix(encoded: &[u8]) -> Result<(bool, Vec<u8>), ProofError> {
+	let first = *encoded.first().ok_or(ProofError::MalformedNode)?;
+	let flag = first >> 4;
+	if flag > 3 || (flag & 1 == 0 && first & 0x0f != 0) {
+		return Err(ProofError::MalformedNode)
+	}
+	let mut nibbles = Vec::with_capacity(encoded.len() * 2);
+	if flag & 1 == 1 {
+		nibbles.push(first & 0x0f);
+	}
+	nibbles.extend(encoded[1..].iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+	Ok((flag >= 2, nibbles))
+}
+
+/// A minimal trie builder, for building test fixtures.
+#[cfg(test)]
+pub(crate) mod build {
+	use super::*;
+	use crate::rlp::encode;
+
+	fn hex_prefix(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
+		let flag = if is_leaf { 2 } else { 0 } + (nibbles.len() % 2) as u8;
+		let mut padded = Vec::from([flag]);
+		if nibbles.len().is_multiple_of(2) {
+			padded.push(0);
+		}
+		padded.extend(nibbles);
+		padded.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+	}
+
+	/// The encoding of the node holding the given entries, whose keys are nibble paths below
+	/// the node. Nodes on the path to `target` that are referenced by hash are added to `proof`.
+	fn node(
+		entries: &[(Vec<u8>, Vec<u8>)],
+		target: Option<&[u8]>,
+		proof: &mut Vec<Vec<u8>>,
+	) -> Vec<u8> {
+		if let [(path, value)] = entries {
+			return encode::list(&[encode::bytes(&hex_prefix(path, true)), encode::bytes(value)])
+		}
+
+		let shared = (0..)
+			.take_while(|i| {
+				entries.iter().all(|(path, _)| path.len() > *i && path[*i] == entries[0].0[*i])
+			})
+			.count();
+		if shared > 0 {
+			let rest: Vec<_> = entries
+				.iter()
+				.map(|(path, value)| (path[shared..].to_vec(), value.clone()))
+				.collect();
+			let prefix = &entries[0].0[..shared];
+			let target = target
+				.filter(|target| target.starts_with(prefix))
+				.map(|target| &target[shared..]);
+			let child = reference(node(&rest, target, proof), target.is_some(), proof);
+			return encode::list(&[
+				encode::bytes(&hex_prefix(&entries[0].0[..shared], false)),
+				child,
+			])
+		}
+
+		let mut items = Vec::new();
+		for nibble in 0..16u8 {
+			let children: Vec<_> = entries
+				.iter()
+				.filter(|(path, _)| path.first() == Some(&nibble))
+				.map(|(path, value)| (path[1..].to_vec(), value.clone()))
+				.collect();
+			if children.is_empty() {
+				items.push(encode::bytes(&[]));
+				continue
+			}
+			let target = target
+				.filter(|target| target.first() == Some(&nibble))
+				.map(|target| &target[1..]);
+			items.push(reference(node(&children, target, proof), target.is_some(), proof));
+		}
+		let value = entries
+			.iter()
+			.find(|(path, _)| path.is_empty())
+			.map_or(Vec::new(), |entry| entry.1.clone());
+		items.push(encode::bytes(&value));
+		encode::list(&items)
+	}
+
+	/// How a parent refers to the given child node.
+	fn reference(child: Vec<u8>, on_path: bool, proof: &mut Vec<Vec<u8>>) -> Vec<u8> {
+		if child.len() < 32 {
+			return child
+		}
+		let hash = codec::keccak_256(&child);
+		if on_path {
+			// Children are built before their parents, so collect the proof bottom up.
+			proof.insert(0, child);
+		}
+		encode::bytes(&hash)
+	}
+
+	/// Build the trie holding the given entries, returning its root and a proof for `key`.
+	pub(crate) fn trie(entries: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> (HashValue, Vec<Vec<u8>>) {
+		let to_nibbles = |key: &[u8]| -> Vec<u8> {
+			key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+		};
+		let entries: Vec<_> =
+			entries.iter().map(|(key, value)| (to_nibbles(key), value.clone())).collect();
+		let mut proof = Vec::new();
+		let root = node(&entries, Some(&to_nibbles(key)), &mut proof);
+		let hash = codec::keccak_256(&root);
+		proof.insert(0, root);
+		(hash, proof)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries() -> Vec<(Vec<u8>, Vec<u8>)> {
+		// Keys sharing prefixes, so the trie has branches, extensions, leaves and inline nodes.
+		[
+			(&b"\x01\x23\x45"[..], &b"short"[..]),
+			(b"\x01\x23\x46", &[0xee; 40]),
+			(b"\x01\x99", b"x"),
+			(b"\xab\xcd\xef", &[0x11; 33]),
+			(b"\xab", &[0x22; 50]),
+		]
+		.into_iter()
+		.map(|(key, value)| (key.to_vec(), value.to_vec()))
+		.collect()
+	}
+
+	#[test]
+	fn reads_every_key() {
+		crate::crypto::register_unsupported_extension();
+		let entries = entries();
+		for (key, value) in &entries {
+			let (root, proof) = build::trie(&entries, key);
+			assert_eq!(read(&root, key, &proof), Ok(Some(value.clone())), "key {key:?}");
+		}
+	}
+
+	#[test]
+	fn proves_absence() {
+		crate::crypto::register_unsupported_extension();
+		let entries = entries();
+		let (root, proof) = build::trie(&entries, b"\x01\x23\x47");
+		assert_eq!(read(&root, b"\x01\x23\x47", &proof), Ok(None));
+		let (root, proof) = build::trie(&entries, b"\x77");
+		assert_eq!(read(&root, b"\x77", &proof), Ok(None));
+	}
+
+	#[test]
+	fn rejects_bad_proofs() {
+		crate::crypto::register_unsupported_extension();
+		let entries = entries();
+		let key = b"\x01\x23\x46";
+		let (root, proof) = build::trie(&entries, key);
+		assert!(proof.len() > 1);
+
+		assert_eq!(read(&[0; 32], key, &proof), Err(ProofError::MissingNode));
+		assert_eq!(read(&root, key, &proof[..proof.len() - 1]), Err(ProofError::MissingNode));
+		let mut extended = proof.clone();
+		extended.push(proof[0].clone());
+		assert_eq!(read(&root, key, &extended), Err(ProofError::UnusedNodes));
+
+		let mut tampered = proof.clone();
+		let last = tampered.last_mut().unwrap();
+		*last.last_mut().unwrap() ^= 1;
+		assert_eq!(read(&root, key, &tampered), Err(ProofError::MissingNode));
+	}
+}
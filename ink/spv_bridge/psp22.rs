@@ -0,0 +1,68 @@
+//! Calls to the bridge's wrapped asset, a PSP22 token contract that lets the bridge mint and
+//! burn.
+//!
+//! Messages are selected the way OpenBrush's PSP22 extensions define them, by the first four
+//! bytes of the BLAKE2 hash of `<Trait>::<message>`.
+
+use ink::{
+	env::{
+		call::{build_call, ExecutionInput, Selector},
+		DefaultEnvironment,
+	},
+	prelude::string::String,
+	primitives::AccountId,
+};
+
+type Balance = <DefaultEnvironment as ink::env::Environment>::Balance;
+
+/// The errors a PSP22 token returns.
+#[derive(Debug, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+pub enum PSP22Error {
+	/// An error specific to the token
+	Custom(String),
+	/// The account's balance is too low
+	InsufficientBalance,
+	/// The caller's allowance is too low
+	InsufficientAllowance,
+	/// The recipient is the zero address
+	ZeroRecipientAddress,
+	/// The sender is the zero address
+	ZeroSenderAddress,
+	/// The recipient contract rejected the transfer
+	SafeTransferCheckFailed(String),
+}
+
+/// Mint `amount` of the token to `account`, returning whether the token accepted the call.
+pub fn mint(token: AccountId, account: AccountId, amount: Balance) -> bool {
+	let call = build_call::<DefaultEnvironment>()
+		.call(token)
+		.exec_input(
+			ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Mintable::mint")))
+				.push_arg(account)
+				.push_arg(amount),
+		)
+		.returns::<Result<(), PSP22Error>>();
+	// The off-chain test environment cannot call contracts.
+	if cfg!(test) {
+		return true
+	}
+	matches!(call.try_invoke(), Ok(Ok(Ok(()))))
+}
+
+/// Burn `amount` of the token from `account`, returning whether the token accepted the call.
+pub fn burn(token: AccountId, account: AccountId, amount: Balance) -> bool {
+	let call = build_call::<DefaultEnvironment>()
+		.call(token)
+		.exec_input(
+			ExecutionInput::new(Selector::new(ink::selector_bytes!("PSP22Burnable::burn")))
+				.push_arg(account)
+				.push_arg(amount),
+		)
+		.returns::<Result<(), PSP22Error>>();
+	// The off-chain test environment cannot call contracts.
+	if cfg!(test) {
+		return true
+	}
+	matches!(call.try_invoke(), Ok(Ok(Ok(()))))
+}
@@ -0,0 +1,165 @@
+//! A decoder for Ethereum's Recursive Length Prefix encoding.
+//!
+//! Only canonical encodings are accepted: single bytes below 0x80 must not be wrapped in a
+//! string prefix, and lengths must use the short form when they can and have no leading zeros.
+
+use ink::prelude::vec::Vec;
+
+/// A decoded RLP item.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Item<'a> {
+	/// The item's complete encoding, prefix included
+	pub raw: &'a [u8],
+	/// The string's bytes, or the concatenated encodings of the list's items
+	pub payload: &'a [u8],
+	/// Whether the item is a list rather than a string
+	pub is_list: bool,
+}
+
+impl<'a> Item<'a> {
+	/// The string's bytes, or `None` if the item is a list.
+	pub fn bytes(&self) -> Option<&'a [u8]> {
+		(!self.is_list).then_some(self.payload)
+	}
+
+	/// The list's items, or `None` if the item is a string or its payload is malformed.
+	pub fn items(&self) -> Option<Vec<Item<'a>>> {
+		if !self.is_list {
+			return None
+		}
+		let mut items = Vec::new();
+		let mut rest = self.payload;
+		while !rest.is_empty() {
+			let (item, remainder) = split_first(rest)?;
+			items.push(item);
+			rest = remainder;
+		}
+		Some(items)
+	}
+}
+
+/// Decode bytes that hold exactly one RLP item.
+pub fn decode(bytes: &[u8]) -> Option<Item<'_>> {
+	match split_first(bytes)? {
+		(item, []) => Some(item),
+		_ => None,
+	}
+}
+
+/// Decode the RLP item at the start of the bytes, returning it and the bytes after it.
+pub fn split_first(bytes: &[u8]) -> Option<(Item<'_>, &[u8])> {
+	let prefix = *bytes.first()?;
+	let (header_len, payload_len, is_list) = match prefix {
+		0x00..=0x7f =>
+			return Some((
+				Item { raw: &bytes[..1], payload: &bytes[..1], is_list: false },
+				&bytes[1..],
+			)),
+		0x80..=0xb7 => (1, usize::from(prefix - 0x80), false),
+		0xb8..=0xbf => {
+			let len_len = usize::from(prefix - 0xb7);
+			(1 + len_len, long_length(bytes.get(1..1 + len_len)?)?, false)
+		},
+		0xc0..=0xf7 => (1, usize::from(prefix - 0xc0), true),
+		0xf8..=0xff => {
+			let len_len = usize::from(prefix - 0xf7);
+			(1 + len_len, long_length(bytes.get(1..1 + len_len)?)?, true)
+		},
+	};
+
+	let end = header_len.checked_add(payload_len)?;
+	let payload = bytes.get(header_len..end)?;
+	// A single byte below 0x80 is its own encoding.
+	if !is_list && payload_len == 1 && payload[0] < 0x80 {
+		return None
+	}
+	Some((Item { raw: &bytes[..end], payload, is_list }, &bytes[end..]))
+}
+
+/// Read the big-endian length of a long string or list, rejecting non-canonical lengths.
+fn long_length(bytes: &[u8]) -> Option<usize> {
+	if bytes.first() == Some(&0) || bytes.len() > core::mem::size_of::<usize>() {
+		return None
+	}
+	let length = bytes.iter().fold(0usize, |length, byte| (length << 8) | usize::from(*byte));
+	(length > 55).then_some(length)
+}
+
+/// Minimal encoders, for building test fixtures.
+#[cfg(test)]
+pub(crate) mod encode {
+	use ink::prelude::vec::Vec;
+
+	fn prefix(offset: u8, length: usize) -> Vec<u8> {
+		if length <= 55 {
+			return Vec::from([offset + length as u8])
+		}
+		let digits: Vec<u8> =
+			length.to_be_bytes().into_iter().skip_while(|digit| *digit == 0).collect();
+		let mut prefix = Vec::from([offset + 55 + digits.len() as u8]);
+		prefix.extend(digits);
+		prefix
+	}
+
+	pub(crate) fn bytes(bytes: &[u8]) -> Vec<u8> {
+		if bytes.len() == 1 && bytes[0] < 0x80 {
+			return bytes.to_vec()
+		}
+		let mut encoded = prefix(0x80, bytes.len());
+		encoded.extend(bytes);
+		encoded
+	}
+
+	pub(crate) fn list(items: &[Vec<u8>]) -> Vec<u8> {
+		let payload = items.concat();
+		let mut encoded = prefix(0xc0, payload.len());
+		encoded.extend(payload);
+		encoded
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_strings_and_lists() {
+		assert_eq!(decode(&[0x05]).unwrap().bytes(), Some(&[0x05][..]));
+		assert_eq!(decode(&[0x80]).unwrap().bytes(), Some(&[][..]));
+		assert_eq!(decode(&[0x82, 0xab, 0xcd]).unwrap().bytes(), Some(&[0xab, 0xcd][..]));
+
+		let long = [0xaa; 60];
+		let encoded = encode::bytes(&long);
+		assert_eq!(&encoded[..2], &[0xb8, 60]);
+		assert_eq!(decode(&encoded).unwrap().bytes(), Some(&long[..]));
+
+		// ["cat", ["dog"], ""]
+		let encoded = encode::list(&[
+			encode::bytes(b"cat"),
+			encode::list(&[encode::bytes(b"dog")]),
+			encode::bytes(b""),
+		]);
+		let items = decode(&encoded).unwrap().items().unwrap();
+		assert_eq!(items.len(), 3);
+		assert_eq!(items[0].bytes(), Some(&b"cat"[..]));
+		assert_eq!(items[1].items().unwrap()[0].bytes(), Some(&b"dog"[..]));
+		assert_eq!(items[1].raw, &encode::list(&[encode::bytes(b"dog")])[..]);
+		assert_eq!(items[2].bytes(), Some(&[][..]));
+		assert_eq!(items[1].bytes(), None);
+	}
+
+	#[test]
+	fn rejects_malformed_encodings() {
+		assert_eq!(decode(&[]), None);
+		// Truncated and trailing bytes.
+		assert_eq!(decode(&[0x82, 0xab]), None);
+		assert_eq!(decode(&[0x81, 0xab, 0x00]), None);
+		// A single small byte wrapped in a string prefix.
+		assert_eq!(decode(&[0x81, 0x05]), None);
+		// A long length that fits the short form, or has a leading zero.
+		assert_eq!(decode(&[[0xb8, 2, 0xab, 0xcd].as_slice()].concat()), None);
+		assert_eq!(decode(&[[0xb9, 0, 60].as_slice(), &[0xaa; 60]].concat()), None);
+		// A list whose payload is not a sequence of items.
+		assert_eq!(decode(&[0xc2, 0x82, 0xab]).unwrap().items(), None);
+	}
+}
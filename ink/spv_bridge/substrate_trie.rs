@@ -0,0 +1,371 @@
+//! Proofs of reads from Substrate's base-16 Patricia-Merkle state tries.
+//!
+//! Nodes use the `LayoutV1` encoding and are referenced by their Blake2-256 hash. A proof is the
+//! unordered set of nodes a `StorageProof` carries, which may cover several keys at once, so
+//! unused nodes are allowed. Nodes whose encoding is shorter than 32 bytes are embedded in their
+//! parent, and values of 33 bytes or more are stored in the proof under their hash.
+
+use crate::{codec, mpt::ProofError, spv_bridge::HashValue};
+use ink::prelude::{collections::BTreeMap, vec::Vec};
+use scale::Decode;
+
+/// Look up the value of a key in the trie with the given root.
+///
+/// Returns `Ok(None)` if the proof shows that the key is not in the trie.
+pub fn read(
+	root: &HashValue,
+	key: &[u8],
+	proof: &[Vec<u8>],
+) -> Result<Option<Vec<u8>>, ProofError> {
+	let nodes: BTreeMap<HashValue, &[u8]> =
+		proof.iter().map(|node| (codec::blake2_256(node), node.as_slice())).collect();
+	let lookup = |hash: &HashValue| nodes.get(hash).copied().ok_or(ProofError::MissingNode);
+
+	let nibbles: Vec<u8> = key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+	let mut path = &nibbles[..];
+	let mut next = NodeRef::Hash(*root);
+	let value = loop {
+		let encoded = match next {
+			NodeRef::Hash(hash) => lookup(&hash)?,
+			NodeRef::Inline(node) => node,
+		};
+		match Node::decode(encoded)? {
+			Node::Empty => break None,
+			Node::Leaf { partial, value } => break (path == partial).then_some(value),
+			Node::Branch { partial, value, children } => {
+				if !path.starts_with(&partial) {
+					break None
+				}
+				path = &path[partial.len()..];
+				let Some((nibble, rest)) = path.split_first() else { break value };
+				match children[usize::from(*nibble)] {
+					Some(child) => next = child,
+					None => break None,
+				}
+				path = rest;
+			},
+		}
+	};
+
+	match value {
+		None => Ok(None),
+		Some(Value::Inline(value)) => Ok(Some(value.to_vec())),
+		Some(Value::Hashed(hash)) => Ok(Some(lookup(&hash)?.to_vec())),
+	}
+}
+
+/// How a node refers to a child.
+#[derive(Copy, Clone)]
+enum NodeRef<'a> {
+	Hash(HashValue),
+	Inline(&'a [u8]),
+}
+
+/// A value stored in a node.
+enum Value<'a> {
+	Inline(&'a [u8]),
+	Hashed(HashValue),
+}
+
+/// A decoded trie node. `partial` is the node's part of the key, in nibbles.
+#[allow(clippy::large_enum_variant)]
+enum Node<'a> {
+	Empty,
+	Leaf { partial: Vec<u8>, value: Value<'a> },
+	Branch { partial: Vec<u8>, value: Option<Value<'a>>, children: [Option<NodeRef<'a>>; 16] },
+}
+
+impl<'a> Node<'a> {
+	fn decode(encoded: &'a [u8]) -> Result<Self, ProofError> {
+		let mut input = Input(encoded);
+		let first = input.byte()?;
+		// The header's high bits give the node kind, and its low bits the partial key length.
+		let (is_leaf, has_value, hashed_value, length_bits) = match first {
+			0x00 if encoded.len() == 1 => return Ok(Node::Empty),
+			_ if first >> 6 == 0b01 => (true, true, false, 6),
+			_ if first >> 6 == 0b10 => (false, false, false, 6),
+			_ if first >> 6 == 0b11 => (false, true, false, 6),
+			_ if first >> 5 == 0b001 => (true, true, true, 5),
+			_ if first >> 4 == 0b0001 => (false, true, true, 4),
+			_ => return Err(ProofError::MalformedNode),
+		};
+
+		let mask = (1u8 << length_bits) - 1;
+		let mut length = usize::from(first & mask);
+		if length == usize::from(mask) {
+			loop {
+				let byte = input.byte()?;
+				length += usize::from(byte);
+				if byte < 255 {
+					break
+				}
+			}
+		}
+		let partial = input.nibbles(length)?;
+
+		let value = if !has_value {
+			None
+		} else if hashed_value {
+			Some(Value::Hashed(input.take(32)?.try_into().expect("length checked; qed")))
+		} else {
+			Some(Value::Inline(input.prefixed()?))
+		};
+
+		let node = if is_leaf {
+			Node::Leaf { partial, value: value.expect("leaves have values; qed") }
+		} else {
+			let bitmap =
+				u16::from_le_bytes(input.take(2)?.try_into().expect("length checked; qed"));
+			let mut children = [None; 16];
+			for (nibble, child) in children.iter_mut().enumerate() {
+				if bitmap & (1 << nibble) == 0 {
+					continue
+				}
+				let reference = input.prefixed()?;
+				*child = Some(match reference.len() {
+					32 => NodeRef::Hash(reference.try_into().expect("length checked; qed")),
+					0..=31 => NodeRef::Inline(reference),
+					_ => return Err(ProofError::MalformedNode),
+				});
+			}
+			Node::Branch { partial, value, children }
+		};
+
+		if !input.0.is_empty() {
+			return Err(ProofError::MalformedNode)
+		}
+		Ok(node)
+	}
+}
+
+/// The unread part of a node's encoding.
+struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+	fn take(&mut self, length: usize) -> Result<&'a [u8], ProofError> {
+		if self.0.len() < length {
+			return Err(ProofError::MalformedNode)
+		}
+		let (taken, rest) = self.0.split_at(length);
+		self.0 = rest;
+		Ok(taken)
+	}
+
+	fn byte(&mut self) -> Result<u8, ProofError> {
+		Ok(self.take(1)?[0])
+	}
+
+	/// Bytes prefixed with their SCALE compact length.
+	fn prefixed(&mut self) -> Result<&'a [u8], ProofError> {
+		let length = scale::Compact::<u32>::decode(&mut self.0)
+			.map_err(|_| ProofError::MalformedNode)?
+			.0;
+		self.take(length as usize)
+	}
+
+	/// A partial key of `length` nibbles, left padded with a zero nibble if its length is odd.
+	fn nibbles(&mut self, length: usize) -> Result<Vec<u8>, ProofError> {
+		let bytes = self.take(length.div_ceil(2))?;
+		let mut nibbles: Vec<u8> = bytes.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect();
+		if length % 2 == 1 {
+			if nibbles[0] != 0 {
+				return Err(ProofError::MalformedNode)
+			}
+			nibbles.remove(0);
+		}
+		Ok(nibbles)
+	}
+}
+
+/// A minimal trie builder, for building test fixtures.
+#[cfg(test)]
+pub(crate) mod build {
+	use super::*;
+	use scale::Encode;
+
+	fn header(prefix: u8, length_bits: u32, length: usize) -> Vec<u8> {
+		let mask = (1usize << length_bits) - 1;
+		if length < mask {
+			return Vec::from([prefix | length as u8])
+		}
+		let mut header = Vec::from([prefix | mask as u8]);
+		let mut rest = length - mask;
+		while rest >= 255 {
+			header.push(255);
+			rest -= 255;
+		}
+		header.push(rest as u8);
+		header
+	}
+
+	fn partial(nibbles: &[u8]) -> Vec<u8> {
+		let mut padded = Vec::new();
+		if nibbles.len() % 2 == 1 {
+			padded.push(0);
+		}
+		padded.extend(nibbles);
+		padded.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect()
+	}
+
+	/// The encoding of a value, and whether it is hashed. Hashed values on the path to the
+	/// target are added to `proof`.
+	fn value(value: &[u8], on_path: bool, proof: &mut Vec<Vec<u8>>) -> (Vec<u8>, bool) {
+		if value.len() < 33 {
+			return (value.to_vec().encode(), false)
+		}
+		if on_path {
+			proof.push(value.to_vec());
+		}
+		(codec::blake2_256(value).to_vec(), true)
+	}
+
+	/// The encoding of the node holding the given entries, whose keys are nibble paths below
+	/// the node. Nodes on the path to `target` that are referenced by hash are added to `proof`.
+	fn node(
+		entries: &[(Vec<u8>, Vec<u8>)],
+		target: Option<&[u8]>,
+		proof: &mut Vec<Vec<u8>>,
+	) -> Vec<u8> {
+		if let [(path, leaf_value)] = entries {
+			let on_path = target == Some(path.as_slice());
+			let (value, hashed) = value(leaf_value, on_path, proof);
+			let (prefix, length_bits) = if hashed { (0x20, 5) } else { (0x40, 6) };
+			return [header(prefix, length_bits, path.len()), partial(path), value].concat()
+		}
+
+		let shared = (0..)
+			.take_while(|i| {
+				entries.iter().all(|(path, _)| path.len() > *i && path[*i] == entries[0].0[*i])
+			})
+			.count();
+		let prefix = &entries[0].0[..shared];
+		let target = target
+			.filter(|target| target.starts_with(prefix))
+			.map(|target| &target[shared..]);
+
+		let branch_value = entries.iter().find(|(path, _)| path.len() == shared).map(|entry| {
+			let on_path = target.is_some_and(|target| target.is_empty());
+			value(&entry.1, on_path, proof)
+		});
+		let (header_prefix, length_bits) = match branch_value {
+			None => (0x80, 6),
+			Some((_, false)) => (0xc0, 6),
+			Some((_, true)) => (0x10, 4),
+		};
+
+		let mut bitmap = 0u16;
+		let mut children = Vec::new();
+		for nibble in 0..16u8 {
+			let below: Vec<_> = entries
+				.iter()
+				.filter(|(path, _)| path.get(shared) == Some(&nibble))
+				.map(|(path, value)| (path[shared + 1..].to_vec(), value.clone()))
+				.collect();
+			if below.is_empty() {
+				continue
+			}
+			bitmap |= 1 << nibble;
+			let target = target
+				.filter(|target| target.first() == Some(&nibble))
+				.map(|target| &target[1..]);
+			let child = node(&below, target, proof);
+			if child.len() < 32 {
+				children.push(child.encode());
+			} else {
+				let hash = codec::blake2_256(&child);
+				if target.is_some() {
+					proof.push(child);
+				}
+				children.push(hash.to_vec().encode());
+			}
+		}
+
+		[
+			header(header_prefix, length_bits, shared),
+			partial(prefix),
+			branch_value.map_or(Vec::new(), |(value, _)| value),
+			bitmap.to_le_bytes().to_vec(),
+			children.concat(),
+		]
+		.concat()
+	}
+
+	/// Build the trie holding the given entries, returning its root and a proof for `key`.
+	pub(crate) fn trie(entries: &[(Vec<u8>, Vec<u8>)], key: &[u8]) -> (HashValue, Vec<Vec<u8>>) {
+		let to_nibbles = |key: &[u8]| -> Vec<u8> {
+			key.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect()
+		};
+		let entries: Vec<_> =
+			entries.iter().map(|(key, value)| (to_nibbles(key), value.clone())).collect();
+		let mut proof = Vec::new();
+		let root = if entries.is_empty() {
+			Vec::from([0])
+		} else {
+			node(&entries, Some(&to_nibbles(key)), &mut proof)
+		};
+		let hash = codec::blake2_256(&root);
+		proof.push(root);
+		(hash, proof)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries() -> Vec<(Vec<u8>, Vec<u8>)> {
+		// Keys sharing prefixes and a key that is a prefix of others, so the trie has branches
+		// with and without values, leaves, inline nodes and hashed values.
+		[
+			(&b"\x01\x23\x45"[..], &b"short"[..]),
+			(b"\x01\x23\x46", &[0xee; 40]),
+			(b"\x01\x23", &[0x33; 20]),
+			(b"\x01\x99", b"x"),
+			(b"\xab\xcd\xef", &[0x11; 33]),
+			(b"\xab", &[0x22; 50]),
+			(&[0x5a; 40], b"long key"),
+		]
+		.into_iter()
+		.map(|(key, value)| (key.to_vec(), value.to_vec()))
+		.collect()
+	}
+
+	#[test]
+	fn reads_every_key() {
+		let entries = entries();
+		for (key, value) in &entries {
+			let (root, proof) = build::trie(&entries, key);
+			assert_eq!(read(&root, key, &proof), Ok(Some(value.clone())), "key {key:?}");
+		}
+	}
+
+	#[test]
+	fn proves_absence() {
+		let entries = entries();
+		for key in [&b"\x01\x23\x47"[..], b"\x01", b"\x77", b"\xab\xcd"] {
+			let (root, proof) = build::trie(&entries, key);
+			assert_eq!(read(&root, key, &proof), Ok(None), "key {key:?}");
+		}
+		let (root, proof) = build::trie(&[], b"\x01");
+		assert_eq!(read(&root, b"\x01", &proof), Ok(None));
+	}
+
+	#[test]
+	fn rejects_bad_proofs() {
+		let entries = entries();
+		let key = b"\x01\x23\x46";
+		let (root, proof) = build::trie(&entries, key);
+
+		assert_eq!(read(&[0; 32], key, &proof), Err(ProofError::MissingNode));
+		// The hashed value is missing
+		assert_eq!(read(&root, key, &proof[1..]), Err(ProofError::MissingNode));
+		// Nodes for other keys may be included
+		let (_, other_proof) = build::trie(&entries, b"\xab");
+		assert_eq!(
+			read(&root, key, &[proof.clone(), other_proof].concat()),
+			Ok(Some(vec![0xee; 40]))
+		);
+		// A proof for one key does not show the absence of another
+		assert_eq!(read(&root, b"\xab\xcd\xef", &proof), Err(ProofError::MissingNode));
+	}
+}
@@ -0,0 +1,257 @@
+//! The hashes and signed messages of Tendermint (CometBFT) chains.
+//!
+//! Tendermint hashes a header as a merkle tree over the protobuf encodings of its fields, and
+//! validators sign the protobuf encoding of a canonical vote. Only the protobuf needed for those
+//! is implemented here: fields are encoded in order, with zero and empty values omitted, as the
+//! Go implementation does.
+//!
+//! Its merkle trees follow RFC 6962: leaves and inner nodes are hashed with SHA-256 behind the
+//! prefixes 0 and 1, and a tree over `n` items splits at the largest power of two below `n`.
+
+use crate::{
+	finality::{PartSetHeader, TendermintHeader, TendermintTime},
+	spv_bridge::HashValue,
+};
+use ink::{
+	env::hash::{HashOutput, Sha2x256},
+	prelude::vec::Vec,
+};
+
+/// The vote type of precommits.
+const PRECOMMIT_TYPE: u64 = 2;
+
+/// The hash identifying a header, which the block id that validators sign commits to.
+pub fn header_hash(header: &TendermintHeader) -> HashValue {
+	let mut fields = Vec::from([
+		[varint_field(1, header.version.block), varint_field(2, header.version.app)].concat(),
+		bytes_field(1, &header.chain_id),
+		varint_field(1, header.height),
+		timestamp(&header.time),
+		[
+			bytes_field(1, &header.last_block_id.hash),
+			bytes_field(2, &part_set_header(&header.last_block_id.part_set_header)),
+		]
+		.concat(),
+	]);
+	for hash in [
+		&header.last_commit_hash[..],
+		&header.data_hash,
+		&header.validators_hash,
+		&header.next_validators_hash,
+		&header.consensus_hash,
+		&header.app_hash,
+		&header.last_results_hash,
+		&header.evidence_hash,
+		&header.proposer_address,
+	] {
+		fields.push(bytes_field(1, hash));
+	}
+	merkle_root(&fields)
+}
+
+/// The hash that a header's validators hash commits a validator set with: the merkle root of
+/// the validators' Ed25519 public keys and voting powers, in the set's order.
+pub fn validators_hash(validators: &[([u8; 32], u64)]) -> HashValue {
+	let validators: Vec<_> = validators
+		.iter()
+		.map(|(public_key, power)| {
+			[bytes_field(1, &bytes_field(1, public_key)), varint_field(2, *power)].concat()
+		})
+		.collect();
+	merkle_root(&validators)
+}
+
+/// The message that a validator signs to precommit to the block with the given hash and part
+/// set header, in the given round of voting at its height.
+pub fn vote_sign_bytes(
+	chain_id: &[u8],
+	height: u64,
+	round: u32,
+	block_hash: &HashValue,
+	part_set_header: &PartSetHeader,
+	time: &TendermintTime,
+) -> Vec<u8> {
+	let block_id =
+		[bytes_field(1, block_hash), bytes_field(2, &self::part_set_header(part_set_header))]
+			.concat();
+	let vote = [
+		varint_field(1, PRECOMMIT_TYPE),
+		sfixed64_field(2, height),
+		sfixed64_field(3, round.into()),
+		bytes_field(4, &block_id),
+		bytes_field(5, &timestamp(time)),
+		bytes_field(6, chain_id),
+	]
+	.concat();
+	// The vote is signed with its length in front.
+	let mut bytes = Vec::new();
+	varint(&mut bytes, vote.len() as u64);
+	bytes.extend(vote);
+	bytes
+}
+
+fn part_set_header(header: &PartSetHeader) -> Vec<u8> {
+	[varint_field(1, header.total.into()), bytes_field(2, &header.hash)].concat()
+}
+
+fn timestamp(time: &TendermintTime) -> Vec<u8> {
+	// Negative integers are encoded as their 64 bit two's complement.
+	[varint_field(1, time.seconds as u64), varint_field(2, i64::from(time.nanos) as u64)].concat()
+}
+
+fn varint(bytes: &mut Vec<u8>, mut value: u64) {
+	while value >= 0x80 {
+		bytes.push(value as u8 | 0x80);
+		value >>= 7;
+	}
+	bytes.push(value as u8);
+}
+
+fn varint_field(field: u8, value: u64) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	if value != 0 {
+		bytes.push(field << 3);
+		varint(&mut bytes, value);
+	}
+	bytes
+}
+
+fn sfixed64_field(field: u8, value: u64) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	if value != 0 {
+		bytes.push(field << 3 | 1);
+		bytes.extend(value.to_le_bytes());
+	}
+	bytes
+}
+
+fn bytes_field(field: u8, value: &[u8]) -> Vec<u8> {
+	let mut bytes = Vec::new();
+	if !value.is_empty() {
+		bytes.push(field << 3 | 2);
+		varint(&mut bytes, value.len() as u64);
+		bytes.extend_from_slice(value);
+	}
+	bytes
+}
+
+fn merkle_root(items: &[Vec<u8>]) -> HashValue {
+	match items {
+		[] => sha2_256(&[]),
+		[item] => sha2_256(&[&[0], &item[..]].concat()),
+		_ => {
+			let split = 1 << (usize::BITS - 1 - (items.len() - 1).leading_zeros());
+			let left = merkle_root(&items[..split]);
+			let right = merkle_root(&items[split..]);
+			sha2_256(&[&[1], &left[..], &right[..]].concat())
+		},
+	}
+}
+
+fn sha2_256(bytes: &[u8]) -> HashValue {
+	let mut hash = <Sha2x256 as HashOutput>::Type::default();
+	ink::env::hash_bytes::<Sha2x256>(bytes, &mut hash);
+	hash
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::finality::{BlockId, TendermintVersion};
+
+	fn hex<const N: usize>(text: &str) -> [u8; N] {
+		let bytes: Vec<u8> = (0..text.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+			.collect();
+		bytes.try_into().unwrap()
+	}
+
+	#[test]
+	fn merkle_roots_split_at_powers_of_two() {
+		let leaf = |item: u8| sha2_256(&[0, item]);
+		let inner =
+			|left: HashValue, right: HashValue| sha2_256(&[&[1], &left[..], &right[..]].concat());
+		let items: Vec<_> = (0..5).map(|item| Vec::from([item])).collect();
+		assert_eq!(merkle_root(&[]), sha2_256(&[]));
+		assert_eq!(merkle_root(&items[..1]), leaf(0));
+		assert_eq!(merkle_root(&items[..3]), inner(inner(leaf(0), leaf(1)), leaf(2)));
+		assert_eq!(
+			merkle_root(&items),
+			inner(inner(inner(leaf(0), leaf(1)), inner(leaf(2), leaf(3))), leaf(4))
+		);
+	}
+
+	#[test]
+	fn header_hash_matches_tendermint() {
+		// The header of Tendermint's `header_with_known_hash.json` test vector.
+		let header = TendermintHeader {
+			version: TendermintVersion { block: 11, app: 1 },
+			chain_id: b"dockerchain".to_vec(),
+			height: 1608,
+			time: TendermintTime { seconds: 1_600_101_234, nanos: 211_914_210 },
+			last_block_id: BlockId {
+				hash: hex::<32>("D3B2CC7EDAFF87433A5DBCDCDF4077A56AACDE3606034262B0CDB120F62EB40B")
+					.to_vec(),
+				part_set_header: PartSetHeader {
+					total: 1,
+					hash: hex::<32>(
+						"3AB411EAFE9A3B7AC013B0214990E5653112A39909289E3EA9211F07B8CD6EED",
+					)
+					.to_vec(),
+				},
+			},
+			last_commit_hash: hex::<32>(
+				"47071B86EFC28BEC17543967975F35191BA9BEC9C2AD77E86F63B149528D71A1",
+			)
+			.to_vec(),
+			data_hash: hex::<32>(
+				"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+			)
+			.to_vec(),
+			validators_hash: hex(
+				"5E20520EC80B84044B64BA0C55B1C06D543BBD57955C27B8A9999EC526BF703C",
+			),
+			next_validators_hash: hex(
+				"5E20520EC80B84044B64BA0C55B1C06D543BBD57955C27B8A9999EC526BF703C",
+			),
+			consensus_hash: hex("048091BC7DDC283F77BFBF91D73C44DA58C3DF8A9CBC867405D8B7F3DAADA22F"),
+			app_hash: hex::<8>("0000000000000000").to_vec(),
+			last_results_hash: hex::<32>(
+				"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+			)
+			.to_vec(),
+			evidence_hash: hex::<32>(
+				"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+			)
+			.to_vec(),
+			proposer_address: hex::<20>("C8657A30D20C3BAD414624A1A963373DD500CCD3").to_vec(),
+		};
+		assert_eq!(
+			header_hash(&header),
+			hex("F30A71F2409FB15AACAEDB6CC122DFA2525BEE9CAE521721B06BFDCA291B8D56")
+		);
+	}
+
+	#[test]
+	fn validators_hash_matches_tendermint() {
+		// The single validator of a Gaia test chain, from tendermint-rpc's fixtures.
+		let validator = hex("b28af61af42fc899b99b2214d2023e56d3d2bf0524c99405605f1a6f3bce2cfb");
+		assert_eq!(
+			validators_hash(&[(validator, 100_000)]),
+			hex("0A6CA9001DB07E985DF9043045B392588DF7C1C720E30EBAEFDC8A848C551D6A")
+		);
+	}
+
+	#[test]
+	fn varints_and_timestamps() {
+		let mut bytes = Vec::new();
+		varint(&mut bytes, 300);
+		assert_eq!(bytes, [0xac, 0x02]);
+		assert_eq!(varint_field(1, 0), []);
+		assert_eq!(bytes_field(1, &[]), []);
+		// Negative nanos take ten bytes, as in Go.
+		let time = TendermintTime { seconds: 1, nanos: -1 };
+		assert_eq!(timestamp(&time).len(), 2 + 1 + 10);
+	}
+}
@@ -0,0 +1,5663 @@
+// The unit tests of the contract, which `lib.rs` includes into its `tests` module.
+
+// The threshold is set so that we have roughly 1 in 4 chance of finding a valid block.
+const THRESHOLD: [u8; 32] = [
+	63, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+const RELAY_FEE: u128 = 1_000;
+const VERIFY_FEE: u128 = 100;
+// Ten minutes, like Bitcoin.
+const BLOCK_TIME: u64 = 600_000;
+// The target chain's clock, a day after the source checkpoint was authored.
+const TARGET_CHAIN_NOW: u64 = 144 * BLOCK_TIME;
+
+use super::*;
+use crate::finality::{
+	BlockId, CommitSig, DigestItem, GrandpaCommit, GrandpaConsensusLog, PartSetHeader, Precommit,
+	ScheduledChange, SignedPrecommit, TendermintTime, TendermintVersion, GRANDPA_ENGINE_ID,
+};
+
+fn default_accounts() -> ink::env::test::DefaultAccounts<ink::env::DefaultEnvironment> {
+	ink::env::test::default_accounts::<Environment>()
+}
+
+fn set_next_caller(caller: AccountId) {
+	ink::env::test::set_caller::<Environment>(caller);
+}
+
+fn set_balance(account: AccountId, balance: Balance) {
+	ink::env::test::set_account_balance::<Environment>(account, balance);
+}
+
+fn balance_of(account: AccountId) -> Balance {
+	ink::env::test::get_account_balance::<Environment>(account).unwrap()
+}
+
+type Event = <SpvBridge as ink::reflect::ContractEventBase>::Type;
+
+fn decode_events() -> Vec<Event> {
+	ink::env::test::recorded_events()
+		.map(|event| scale::Decode::decode(&mut &event.data[..]).unwrap())
+		.collect()
+}
+
+fn count_alerts(kind: AlertKind) -> usize {
+	decode_events()
+		.into_iter()
+		.filter(|event| matches!(event, Event::BridgeAlert(alert) if alert.kind == kind))
+		.count()
+}
+
+/// A child of the given header whose only transaction is `[0; 32]`, proven by the default
+/// proof.
+fn make_child(parent: Header) -> Header {
+	make_child_with_transactions_root(parent, MerkleProof::hash_leaf(&[0; 32]))
+}
+
+fn make_child_with_transactions_root(parent: Header, tx_root: HashValue) -> Header {
+	make_child_with_roots(parent, tx_root, [0; 32])
+}
+
+fn make_child_with_roots(parent: Header, tx_root: HashValue, storage_root: HashValue) -> Header {
+	mine(Header {
+		height: parent.height + 1,
+		parent: SpvBridge::hash_header(parent),
+		storage_root,
+		transactions_root: tx_root,
+		receipts_root: [0; 32],
+		target: THRESHOLD,
+		pow_nonce: 1,
+		timestamp: parent.timestamp + BLOCK_TIME,
+	})
+}
+
+/// Increment the header's nonce until its hash meets its own PoW target.
+fn mine(mut header: Header) -> Header {
+	while SpvBridge::hash_header(header) >= header.target {
+		header.pow_nonce += 1;
+	}
+	header
+}
+
+/// Build a merkle tree over the given leaves, duplicating the last node of any level with
+/// an odd number of nodes. Returns the root and an inclusion proof for every leaf.
+fn merkle_tree(leaves: &[HashValue]) -> (HashValue, Vec<MerkleProof>) {
+	let mut proofs: Vec<MerkleProof> = leaves.iter().map(|_| MerkleProof::default()).collect();
+	let mut level: Vec<_> = leaves.iter().map(MerkleProof::hash_leaf).collect();
+	let mut depth = 0;
+	while level.len() > 1 {
+		if level.len() % 2 == 1 {
+			level.push(*level.last().unwrap());
+		}
+		for (leaf, proof) in proofs.iter_mut().enumerate() {
+			let index = leaf >> depth;
+			proof.siblings.push(level[index ^ 1]);
+			proof.directions |= ((index & 1) as u64) << depth;
+		}
+		level = level.chunks(2).map(|pair| MerkleProof::hash_pair(&pair[0], &pair[1])).collect();
+		depth += 1;
+	}
+	(level[0], proofs)
+}
+
+fn source_genesis_header() -> Header {
+	DeployConfig::default_for_tests().checkpoint
+}
+
+/// The off-chain environment uses alice as the contract account by default, which muddles
+/// balance checks. Give the contract its own account instead, and a crypto extension that
+/// leaves the cryptography to the contract.
+fn set_contract_account() {
+	ink::env::test::set_callee::<Environment>(AccountId::from([0xff; 32]));
+	// Leave room for source chain timestamps to advance without running ahead of the
+	// target chain's clock.
+	ink::env::test::set_block_timestamp::<Environment>(TARGET_CHAIN_NOW);
+	crypto::register_unsupported_extension();
+}
+
+pub fn deploy_bridge(deployer: AccountId) -> (SpvBridge, Header) {
+	set_contract_account();
+	set_next_caller(deployer);
+
+	let config = DeployConfig {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		..DeployConfig::default_for_tests()
+	};
+	(SpvBridge::new_with_config(config), config.checkpoint)
+}
+
+fn deploy_bridge_with_fee_escrow(
+	deployer: AccountId,
+	payout_depth: Option<u64>,
+	safety_margin: Option<u64>,
+) -> (SpvBridge, Header) {
+	set_contract_account();
+	set_next_caller(deployer);
+
+	let config = DeployConfig {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		payout_depth,
+		escrow_safety_margin: safety_margin,
+		..DeployConfig::default_for_tests()
+	};
+	(SpvBridge::new_with_config(config), config.checkpoint)
+}
+
+#[ink::test]
+fn test_constructor_works() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (spv_bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	assert_eq!(
+		spv_bridge.fee_recipient.get((DEFAULT_CHAIN_ID, genesis_hash)),
+		Some(default_accounts.alice)
+	);
+}
+
+#[ink::test]
+fn test_submit_extend_longest_chain() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let child_header = make_child(genesis_header);
+	let child_hash = SpvBridge::hash_header(child_header);
+
+	let relay_response =
+		ink::env::pay_with_call!(bridge.submit_new_header(child_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// Validate Storage
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 100)), Some(genesis_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 101)), Some(child_hash));
+
+	assert_eq!(
+		bridge.fee_recipient.get((DEFAULT_CHAIN_ID, genesis_hash)),
+		Some(default_accounts.alice)
+	);
+	assert_eq!(
+		bridge.fee_recipient.get((DEFAULT_CHAIN_ID, child_hash)),
+		Some(default_accounts.alice)
+	);
+}
+
+#[ink::test]
+fn test_submit_side_chain() {
+	// We start by creating a linear source chain that looks like this
+	// G---A---B
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	let b_hash = SpvBridge::hash_header(b_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// Now we create a fork in the source chain.
+	// The fork is not long enough to cause a re-org.
+	// We should be able to submit the header successfully, but it should not cause a re-org
+	// G---A---B
+	//  \
+	//   --C
+
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let c_hash = SpvBridge::hash_header(c_header);
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// Validate Storage
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 100)), Some(genesis_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 101)), Some(a_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 102)), Some(b_hash));
+
+	assert_eq!(
+		bridge.fee_recipient.get((DEFAULT_CHAIN_ID, genesis_hash)),
+		Some(default_accounts.alice)
+	);
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, a_hash)), Some(default_accounts.alice));
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, b_hash)), Some(default_accounts.alice));
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, c_hash)), Some(default_accounts.alice));
+}
+
+#[ink::test]
+fn test_submit_reorg_chain() {
+	// We start by creating a linear source chain that looks like this
+	// G---A
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// Now we create a fork in the source chain.
+	// The fork is long enough to cause a re-org.
+	// G---A
+	//  \
+	//   --C---D
+
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let c_hash = SpvBridge::hash_header(c_header);
+	let d_header = make_child_with_transactions_root(c_header, [1; 32]);
+	let d_hash = SpvBridge::hash_header(d_header);
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// Validate Storage
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 100)), Some(genesis_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 101)), Some(c_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 102)), Some(d_hash));
+
+	assert_eq!(
+		bridge.fee_recipient.get((DEFAULT_CHAIN_ID, genesis_hash)),
+		Some(default_accounts.alice)
+	);
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, a_hash)), Some(default_accounts.alice));
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, c_hash)), Some(default_accounts.alice));
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, d_hash)), Some(default_accounts.alice));
+}
+
+#[ink::test]
+fn test_tx_verification_success() {
+	// We start by creating a linear source chain that looks like this
+	// G---A
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transactions = [[1u8; 32], [2u8; 32], [3u8; 32]];
+	let (tx_root, proofs) = merkle_tree(&transactions);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	for (tx_hash, proof) in transactions.into_iter().zip(proofs) {
+		assert_eq!(
+			ink::env::pay_with_call!(
+				bridge.verify_transaction(tx_hash, a_hash, 0, proof),
+				VERIFY_FEE
+			),
+			Ok(())
+		);
+	}
+}
+
+#[ink::test]
+fn test_tx_verification_failure() {
+	// We start by creating a linear source chain that looks like this
+	// G---A
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transactions = [[1u8; 32], [2u8; 32], [3u8; 32]];
+	let (tx_root, proofs) = merkle_tree(&transactions);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// A transaction that is not in the block
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([4u8; 32], a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	// A transaction that is in the block, but with another transaction's proof
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(transactions[0], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+}
+
+//TODO There are many more ways that a transaction or state verification can fail,
+// that we have not yet tested for.
+// You would be wise to add some tests of your own to ensure your code is working as
+// expected.
+
+#[ink::test]
+fn test_state_verification_success() {
+	// We start by creating a linear source chain that looks like this
+	// G---A
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let claim = StateClaim { key: 0, value: 0 };
+	let other_claim = StateClaim { key: 1, value: 0 };
+	let (storage_root, proofs) =
+		merkle_tree(&[SpvBridge::hash_claim(claim), SpvBridge::hash_claim(other_claim)]);
+	let a_header = make_child_with_roots(genesis_header, [0; 32], storage_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state(claim, a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_state_verification_failure() {
+	// We start by creating a linear source chain that looks like this
+	// G---A
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let claim = StateClaim { key: 0, value: 0 };
+	let other_claim = StateClaim { key: 1, value: 0 };
+	let (storage_root, proofs) =
+		merkle_tree(&[SpvBridge::hash_claim(claim), SpvBridge::hash_claim(other_claim)]);
+	let a_header = make_child_with_roots(genesis_header, [0; 32], storage_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+
+	// The claimed value differs from the one committed to in storage
+	let false_claim = StateClaim { key: 0, value: 1 };
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state(false_claim, a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	// The claim is checked against the storage root, not the transactions root
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(SpvBridge::hash_claim(claim), a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+}
+
+#[ink::test]
+fn test_estimated_block_time_tracks_tip_intervals() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.estimated_block_time(), None);
+
+	// The first observed interval seeds the average.
+	let a_header = make_child(genesis_header);
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+	assert_eq!(bridge.estimated_block_time(), Some(BLOCK_TIME));
+
+	// A slower block moves the average by one eighth of the difference.
+	let mut b_header = make_child(a_header);
+	b_header.timestamp = a_header.timestamp + 2 * BLOCK_TIME;
+	let b_header = mine(b_header);
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+	assert_eq!(bridge.estimated_block_time(), Some(BLOCK_TIME + BLOCK_TIME / 8));
+
+	// Side chain blocks do not affect the estimate.
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let relay_response = ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE);
+	assert_eq!(relay_response, Ok(()));
+	assert_eq!(bridge.estimated_block_time(), Some(BLOCK_TIME + BLOCK_TIME / 8));
+}
+
+#[ink::test]
+fn test_alert_subscription_lifecycle() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT - 1),
+		Err(Error::InsufficientAlertDeposit)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT),
+		Err(Error::AlreadySubscribed)
+	);
+	assert_eq!(bridge.alert_subscribers(), vec![default_accounts.bob]);
+
+	let balance_before = balance_of(default_accounts.bob);
+	assert_eq!(bridge.unsubscribe_alerts(), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), balance_before + ALERT_SUBSCRIPTION_DEPOSIT);
+	assert_eq!(bridge.alert_subscribers(), vec![]);
+	assert_eq!(bridge.unsubscribe_alerts(), Err(Error::NotSubscribed));
+}
+
+#[ink::test]
+fn test_alert_registry_is_bounded() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	for i in 0..MAX_ALERT_SUBSCRIBERS {
+		let subscriber = AccountId::from([0x10 + i as u8; 32]);
+		set_balance(subscriber, 1_000_000);
+		set_next_caller(subscriber);
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT),
+			Ok(())
+		);
+	}
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT),
+		Err(Error::AlertRegistryFull)
+	);
+}
+
+#[ink::test]
+fn test_deep_reorg_raises_alert() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT),
+		Ok(())
+	);
+
+	// G---A1---...---A6
+	//  \
+	//   --B1---...---B7
+	set_next_caller(default_accounts.alice);
+	let mut tip = genesis_header;
+	for _ in 0..DEEP_REORG_ALERT_DEPTH {
+		tip = make_child(tip);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE), Ok(()));
+	}
+	assert_eq!(count_alerts(AlertKind::DeepReorg), 0);
+
+	let mut tip = genesis_header;
+	for _ in 0..=DEEP_REORG_ALERT_DEPTH {
+		tip = make_child_with_transactions_root(tip, [1; 32]);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE), Ok(()));
+	}
+	assert_eq!(count_alerts(AlertKind::DeepReorg), 1);
+}
+
+#[ink::test]
+fn test_heavier_shorter_fork_becomes_canon() {
+	// G---A---B
+	//  \
+	//   --C      (C has a target four times harder than A and B)
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	for header in [a_header, b_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	let mut c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	c_header.target = [15; 32];
+	let c_header = mine(c_header);
+	let c_hash = SpvBridge::hash_header(c_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE), Ok(()));
+
+	assert!(bridge.chain_work(c_hash) > bridge.chain_work(SpvBridge::hash_header(b_header)));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 100)), Some(genesis_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 101)), Some(c_hash));
+	assert_eq!(bridge.canon_chain.get((DEFAULT_CHAIN_ID, 102)), None);
+	assert_eq!(bridge.best_height(), 101);
+}
+
+#[ink::test]
+fn test_header_is_known_and_canon() {
+	// G---A
+	//  \
+	//   --C---D
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let a_header = make_child(genesis_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let d_header = make_child(c_header);
+	let [a_hash, c_hash, d_hash] = [a_header, c_header, d_header].map(SpvBridge::hash_header);
+	assert!(bridge.header_is_canon(genesis_hash));
+	assert!(!bridge.header_is_known(a_hash));
+	assert!(!bridge.header_is_canon(a_hash));
+
+	for header in [a_header, c_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+	assert!(bridge.header_is_canon(a_hash));
+	assert!(bridge.header_is_known(c_hash));
+	assert!(!bridge.header_is_canon(c_hash));
+
+	// The reorg to D moves the canon block at A's height to C.
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE), Ok(()));
+	assert!(bridge.header_is_known(a_hash));
+	assert!(!bridge.header_is_canon(a_hash));
+	assert!(bridge.header_is_canon(c_hash));
+	assert!(bridge.header_is_canon(d_hash));
+}
+
+#[ink::test]
+fn test_header_target_must_not_exceed_threshold() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	let mut a_header = make_child(genesis_header);
+	a_header.target = [64; 32];
+	let a_header = mine(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::InvalidDifficultyTarget)
+	);
+}
+
+#[ink::test]
+fn test_escrowed_fee_released_after_confirmations() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) =
+		deploy_bridge_with_fee_escrow(default_accounts.alice, None, Some(2));
+	set_next_caller(default_accounts.bob);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], a_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	let escrow = bridge.fee_escrow(0).expect("fee should be escrowed");
+	assert_eq!(escrow.release_height, 103);
+	assert_eq!(bridge.settle_escrow(0), Err(Error::EscrowNotMature));
+
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	for header in [b_header, c_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	assert_eq!(bridge.settle_escrow(0), Ok(()));
+	assert_eq!(bridge.pending_rewards(default_accounts.bob), VERIFY_FEE);
+	assert_eq!(bridge.settle_escrow(0), Err(Error::UnknownEscrow));
+}
+
+#[ink::test]
+fn test_escrowed_fee_refunded_after_reorg() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) =
+		deploy_bridge_with_fee_escrow(default_accounts.alice, None, Some(2));
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], a_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+
+	// G---A
+	//  \
+	//   --C---D
+	set_next_caller(default_accounts.alice);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let d_header = make_child(c_header);
+	for header in [c_header, d_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	let bob_balance = balance_of(default_accounts.bob);
+	assert_eq!(bridge.settle_escrow(0), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), bob_balance + VERIFY_FEE);
+}
+
+#[ink::test]
+fn test_fees_paid_only_after_payout_depth() {
+	// G---A---B
+	//  \
+	//   --C
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) =
+		deploy_bridge_with_fee_escrow(default_accounts.alice, Some(2), None);
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	for header in [a_header, b_header, c_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	set_next_caller(default_accounts.alice);
+	let verify = |bridge: &mut SpvBridge, header: Header| {
+		let block_hash = SpvBridge::hash_header(header);
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], block_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		)
+	};
+
+	// The genesis block is deep enough, so its relayer is paid immediately.
+	assert_eq!(verify(&mut bridge, genesis_header), Ok(()));
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), VERIFY_FEE);
+	assert_eq!(bridge.fee_escrow(0), None);
+
+	// A only has one confirmation, so its fee is escrowed.
+	assert_eq!(verify(&mut bridge, a_header), Ok(()));
+	assert_eq!(bridge.fee_escrow(0).map(|escrow| escrow.release_height), Some(103));
+
+	// C is not canon, so its relayer cannot farm fees from it however deep it is.
+	assert_eq!(
+		verify(&mut bridge, c_header),
+		Err(VerificationError::Failed(VerificationFailure::NotCanon))
+	);
+	assert!(bridge.fee_escrow(1).is_some());
+	let alice_balance = balance_of(default_accounts.alice);
+	assert_eq!(bridge.settle_escrow(1), Ok(()));
+	assert_eq!(balance_of(default_accounts.alice), alice_balance + VERIFY_FEE);
+}
+
+#[ink::test]
+fn test_targets_follow_retargeting() {
+	const POW_LIMIT: HashValue = [127; 32];
+	set_contract_account();
+	let genesis_header = source_genesis_header();
+	let params = RetargetParams { interval: 4, target_timespan: 4 * BLOCK_TIME };
+	let mut bridge =
+		SpvBridge::new_with_retargeting(genesis_header, POW_LIMIT, RELAY_FEE, VERIFY_FEE, params);
+
+	// Blocks arrive twice as fast as expected
+	let fast_child = |parent: Header, target: HashValue| {
+		let mut child = make_child(parent);
+		child.timestamp = parent.timestamp + BLOCK_TIME / 2;
+		child.target = target;
+		mine(child)
+	};
+
+	// The target may not change mid epoch
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_new_header(fast_child(genesis_header, [31; 32])),
+			RELAY_FEE
+		),
+		Err(Error::InvalidDifficultyTarget)
+	);
+	let mut tip = genesis_header;
+	for _ in 0..3 {
+		tip = fast_child(tip, THRESHOLD);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE), Ok(()));
+	}
+
+	// The epoch took 1.5 block times rather than 4, so the target shrinks to 3/8 of 63.
+	let mut expected_target = [0; 32];
+	expected_target[0] = 23;
+	expected_target[1] = 160;
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(fast_child(tip, THRESHOLD)), RELAY_FEE),
+		Err(Error::InvalidDifficultyTarget)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_new_header(fast_child(tip, expected_target)),
+			RELAY_FEE
+		),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_timestamp_validation() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	// Build up enough ancestors for a full median time window.
+	let mut tip = genesis_header;
+	for _ in 0..MEDIAN_TIME_SPAN {
+		tip = make_child(tip);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE), Ok(()));
+	}
+
+	// Timestamps may go backwards, but only as far as the median time past.
+	let median_time_past = tip.timestamp - 5 * BLOCK_TIME;
+	let with_timestamp = |timestamp| {
+		let mut child = make_child(tip);
+		child.timestamp = timestamp;
+		mine(child)
+	};
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_new_header(with_timestamp(median_time_past)),
+			RELAY_FEE
+		),
+		Err(Error::TimestampTooOld)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_new_header(with_timestamp(TARGET_CHAIN_NOW + MAX_FUTURE_BLOCK_TIME + 1)),
+			RELAY_FEE
+		),
+		Err(Error::TimestampTooFarInFuture)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_new_header(with_timestamp(median_time_past + 1)),
+			RELAY_FEE
+		),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_verification_context() {
+	// G---A---B
+	//  \
+	//   --C
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let c_hash = SpvBridge::hash_header(c_header);
+	for header in [a_header, b_header, c_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	assert_eq!(bridge.verification_context([0; 32]), None);
+	assert_eq!(
+		bridge.verification_context(a_hash),
+		Some(VerificationContext {
+			canonical_hash: Some(a_hash),
+			height: 101,
+			current_depth: Some(1),
+			required_fee: VERIFY_FEE,
+			is_stale: false,
+		})
+	);
+	let c_context = bridge.verification_context(c_hash).unwrap();
+	assert_eq!(c_context.canonical_hash, Some(a_hash));
+	assert_eq!(c_context.current_depth, None);
+
+	// Relaying stops for long enough that the bridge goes stale.
+	ink::env::test::set_block_timestamp::<Environment>(
+		TARGET_CHAIN_NOW + (STALENESS_BLOCK_TIMES + 1) * BLOCK_TIME,
+	);
+	assert!(bridge.verification_context(a_hash).unwrap().is_stale);
+}
+
+#[ink::test]
+fn test_submit_and_verify() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transactions = [[1u8; 32], [2u8; 32]];
+	let (tx_root, proofs) = merkle_tree(&transactions);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(a_header, transactions[0], proofs[0].clone(), 1),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Err(Error::DepthUnreachable)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(a_header, transactions[0], proofs[0].clone(), 0),
+			RELAY_FEE + VERIFY_FEE - 1
+		),
+		Err(Error::InsufficientVerifyFee)
+	);
+	assert!(!bridge.header_is_known(a_hash));
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(a_header, transactions[0], proofs[0].clone(), 0),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Ok(true)
+	);
+	assert!(bridge.header_is_canon(a_hash));
+}
+
+#[ink::test]
+fn test_submit_headers_batch() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	let batch = vec![a_header, b_header, c_header];
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(batch.clone()), 3 * RELAY_FEE - 1),
+		Err(Error::InsufficientRelayFee)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![a_header, c_header]), 2 * RELAY_FEE),
+		Err(Error::NonContiguousBatch)
+	);
+
+	let events_before = ink::env::test::recorded_events().count();
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_headers(batch), 3 * RELAY_FEE), Ok(()));
+	assert_eq!(bridge.best_height(), 103);
+	assert_eq!(
+		bridge.canon_chain.get((DEFAULT_CHAIN_ID, 103)),
+		Some(SpvBridge::hash_header(c_header))
+	);
+	let submitted = decode_events()
+		.into_iter()
+		.skip(events_before)
+		.filter(|event| matches!(event, Event::HeaderSubmitted(_)))
+		.count();
+	assert_eq!(submitted, 3);
+}
+
+#[ink::test]
+fn test_submission_receipts() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	let b_hash = SpvBridge::hash_header(b_header);
+	for header in [a_header, b_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	assert_eq!(bridge.submission_receipt(default_accounts.alice, a_hash), Some(0));
+	assert_eq!(bridge.submission_receipt(default_accounts.alice, b_hash), Some(1));
+	assert_eq!(bridge.submission_receipt(default_accounts.bob, a_hash), None);
+	assert_eq!(
+		bridge.remove_expired_receipt(default_accounts.alice, a_hash),
+		Err(Error::ReceiptNotExpired)
+	);
+
+	for _ in 0..SUBMISSION_RECEIPT_TTL {
+		ink::env::test::advance_block::<Environment>();
+	}
+	assert_eq!(bridge.submission_receipt(default_accounts.alice, a_hash), None);
+	assert_eq!(bridge.remove_expired_receipt(default_accounts.alice, a_hash), Ok(()));
+	assert_eq!(
+		bridge.remove_expired_receipt(default_accounts.alice, a_hash),
+		Err(Error::UnknownReceipt)
+	);
+}
+
+#[ink::test]
+fn test_chain_state_getters() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	assert_eq!(bridge.best_header_hash(), genesis_hash);
+
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	assert_eq!(bridge.best_height(), 101);
+	assert_eq!(bridge.best_header_hash(), a_hash);
+	assert_eq!(bridge.canon_hash_at(100), Some(genesis_hash));
+	assert_eq!(bridge.canon_hash_at(102), None);
+	assert_eq!(bridge.header_by_hash(a_hash), Some(a_header));
+	assert_eq!(bridge.header_by_hash([0; 32]), None);
+	assert_eq!(bridge.relay_fee(), RELAY_FEE);
+	assert_eq!(bridge.verify_fee(), VERIFY_FEE);
+}
+
+#[ink::test]
+fn test_reorg_emits_event() {
+	// G---A
+	//  \
+	//   --C---D
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let a_header = make_child(genesis_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let d_header = make_child(c_header);
+	for header in [a_header, c_header, d_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	let reorgs: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::ReorgOccurred(reorg) => Some(reorg),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(reorgs.len(), 1);
+	assert_eq!(reorgs[0].common_ancestor, genesis_hash);
+	assert_eq!(reorgs[0].retracted_tip, SpvBridge::hash_header(a_header));
+	assert_eq!(reorgs[0].new_tip, SpvBridge::hash_header(d_header));
+	assert_eq!(reorgs[0].depth, 1);
+}
+
+#[ink::test]
+fn test_helpers_adjust_time_and_caller() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	bridge.impersonate(Some(default_accounts.bob));
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, a_hash)), Some(default_accounts.bob));
+	bridge.impersonate(None);
+	assert_eq!(bridge.caller(), default_accounts.alice);
+
+	assert!(!bridge.is_stale());
+	bridge.fast_forward((STALENESS_BLOCK_TIMES + 1) * BLOCK_TIME, 0);
+	assert!(bridge.is_stale());
+
+	bridge.fast_forward(0, SUBMISSION_RECEIPT_TTL);
+	assert_eq!(bridge.submission_receipt(default_accounts.bob, a_hash), None);
+}
+
+#[ink::test]
+fn test_reorg_event_order() {
+	// G---A---B
+	//  \
+	//   --C---D---E
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let d_header = make_child(c_header);
+	let e_header = make_child(d_header);
+	for header in [a_header, b_header, c_header, d_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	let events_before = ink::env::test::recorded_events().count();
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(e_header), RELAY_FEE), Ok(()));
+	let hash = SpvBridge::hash_header;
+	let summary: Vec<_> = decode_events()
+		.into_iter()
+		.skip(events_before)
+		.map(|event| match event {
+			Event::HeaderSubmitted(e) => ("submitted", e.block_hash),
+			Event::CanonBlockRetracted(e) => ("retracted", e.block_hash),
+			Event::CanonBlockAdded(e) => ("added", e.block_hash),
+			Event::ReorgOccurred(e) => ("reorg", e.new_tip),
+			_ => ("other", [0; 32]),
+		})
+		.collect();
+	assert_eq!(
+		summary,
+		vec![
+			("submitted", hash(e_header)),
+			("retracted", hash(b_header)),
+			("retracted", hash(a_header)),
+			("added", hash(c_header)),
+			("added", hash(d_header)),
+			("added", hash(e_header)),
+			("reorg", hash(e_header)),
+		]
+	);
+}
+
+#[ink::test]
+fn test_verification_pays_before_reporting() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+
+	let events_before = ink::env::test::recorded_events().count();
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	let events: Vec<_> = decode_events().into_iter().skip(events_before).collect();
+	assert_eq!(events.len(), 2);
+	assert!(matches!(
+		&events[0],
+		Event::RelayerPaid(paid) if paid.relayer == default_accounts.alice
+	));
+	assert!(matches!(&events[1], Event::TransactionVerified(verified) if verified.outcome));
+}
+
+#[ink::test]
+fn test_verification_events() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let claim = StateClaim { key: 0, value: 0 };
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	// Failures returned as an error are reverted, so they report nothing.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state(claim, genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	assert!(!decode_events().iter().any(|event| matches!(event, Event::StateVerified(_))));
+
+	let storage_root = MerkleProof::hash_leaf(&SpvBridge::hash_claim(claim));
+	let a_header = make_child_with_roots(genesis_header, [0; 32], storage_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(a_header, [7; 32], MerkleProof::default(), 0),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Ok(false)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state(claim, a_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+
+	let events = decode_events();
+	let tx_event = events.iter().find_map(|event| match event {
+		Event::TransactionVerified(verified) => Some(verified),
+		_ => None,
+	});
+	let tx_event = tx_event.expect("transaction verification should be reported");
+	assert_eq!(tx_event.tx_hash, [7; 32]);
+	assert_eq!(tx_event.block_hash, a_hash);
+	assert_eq!(tx_event.verifier, default_accounts.bob);
+	assert!(!tx_event.outcome);
+	assert_eq!(tx_event.failure, Some(VerificationFailure::InvalidProof));
+
+	let state_event = events.iter().find_map(|event| match event {
+		Event::StateVerified(verified) => Some(verified),
+		_ => None,
+	});
+	let state_event = state_event.expect("state verification should be reported");
+	assert_eq!(state_event.claim_hash, SpvBridge::hash_claim(claim));
+	assert_eq!(state_event.block_hash, a_hash);
+	assert_eq!(state_event.verifier, default_accounts.bob);
+	assert!(state_event.outcome);
+	assert_eq!(state_event.failure, None);
+
+	// A failure reported as an outcome is reported by the event too.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state_outcome(claim, genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(false)
+	);
+	let state_event = decode_events().into_iter().rev().find_map(|event| match event {
+		Event::StateVerified(verified) => Some(verified),
+		_ => None,
+	});
+	let state_event = state_event.expect("state verification should be reported");
+	assert_eq!(state_event.block_hash, genesis_hash);
+	assert!(!state_event.outcome);
+	assert_eq!(state_event.failure, Some(VerificationFailure::InvalidProof));
+}
+
+#[ink::test]
+fn test_owner_sets_parameters() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.owner(), default_accounts.alice);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_relay_fee(1), Err(Error::MissingRole));
+	assert_eq!(bridge.set_verify_fee(1), Err(Error::MissingRole));
+	assert_eq!(bridge.set_difficulty_threshold([1; 32]), Err(Error::MissingRole));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_relay_fee(1), Ok(()));
+	assert_eq!(bridge.set_verify_fee(2), Ok(()));
+	assert_eq!(bridge.set_difficulty_threshold([1; 32]), Ok(()));
+	assert_eq!(bridge.relay_fee(), 1);
+	assert_eq!(bridge.verify_fee(), 2);
+	assert_eq!(bridge.default_chain().difficulty_threshold, [1; 32]);
+
+	let changes: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::ParameterChanged(change) => Some(change.parameter),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(
+		changes,
+		vec![
+			BridgeParameter::RelayFee(1),
+			BridgeParameter::VerifyFee(2),
+			BridgeParameter::DifficultyThreshold([1; 32]),
+		]
+	);
+}
+
+#[ink::test]
+fn test_proof_and_batch_limits() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	assert_eq!(bridge.set_max_proof_length(1), Ok(()));
+	assert_eq!(bridge.set_max_batch_size(1), Ok(()));
+
+	let proof = MerkleProof { siblings: vec![[1; 32]; 2], directions: 0 };
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], genesis_hash, 0, proof),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Rejected(Error::ProofTooLarge))
+	);
+
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![a_header, b_header]), 2 * RELAY_FEE),
+		Err(Error::BatchTooLarge)
+	);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_headers(vec![a_header]), RELAY_FEE), Ok(()));
+}
+
+#[ink::test]
+fn test_pause_blocks_submission_and_verification() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	ink::env::pay_with_call!(bridge.subscribe_alerts(), ALERT_SUBSCRIPTION_DEPOSIT).unwrap();
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.pause(), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.pause(), Ok(()));
+	assert!(bridge.is_paused());
+	assert_eq!(count_alerts(AlertKind::Paused), 1);
+
+	let a_header = make_child(genesis_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::BridgePaused)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![a_header]), RELAY_FEE),
+		Err(Error::BridgePaused)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Rejected(Error::BridgePaused))
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state(
+				StateClaim { key: 0, value: 0 },
+				genesis_hash,
+				0,
+				MerkleProof::default()
+			),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Rejected(Error::BridgePaused))
+	);
+	assert_eq!(bridge.best_height(), genesis_header.height);
+
+	assert_eq!(bridge.unpause(), Ok(()));
+	assert!(!bridge.is_paused());
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+}
+
+#[ink::test]
+fn test_upgrade_is_admin_only_and_migrations_run_in_order() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.upgrade([1; 32].into()), Err(Error::MissingRole));
+
+	// Storage that is already current is left alone.
+	assert_eq!(bridge.migrate_storage(), Ok(()));
+	assert!(!decode_events().iter().any(|event| matches!(event, Event::Migrated(_))));
+
+	// Storage left behind by older code is brought up to date.
+	bridge.storage_version = STORAGE_VERSION - 1;
+	assert_eq!(bridge.migrate_storage(), Ok(()));
+	assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::Migrated(Migrated { from_version, to_version })
+			if *from_version == STORAGE_VERSION - 1 && *to_version == STORAGE_VERSION
+	)));
+
+	// Storage written by newer code can't be understood.
+	bridge.storage_version = STORAGE_VERSION + 1;
+	assert_eq!(bridge.migrate_storage(), Err(Error::UnsupportedStorageVersion));
+}
+
+#[ink::test]
+fn test_migrating_from_a_single_owner_grants_it_the_admin_role() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	// Version 1 storage has an owner but no roles.
+	bridge.storage_version = 1;
+	bridge.roles.remove((Role::Admin, default_accounts.alice));
+	bridge.admin_count = 0;
+	assert_eq!(bridge.set_relay_fee(1), Err(Error::MissingRole));
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.migrate_storage(), Ok(()));
+	assert_eq!(bridge.storage_version(), STORAGE_VERSION);
+	assert!(bridge.has_role(Role::Admin, default_accounts.alice));
+	assert!(!bridge.has_role(Role::Admin, default_accounts.bob));
+
+	bridge.storage_version = 0;
+	assert_eq!(bridge.migrate_storage(), Err(Error::UnsupportedStorageVersion));
+}
+
+#[ink::test]
+fn test_verification_failures_are_counted() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let transaction =
+		SourceTransaction { sender: [1; 32], recipient: [2; 32], amount: 5_000, fee: 12, nonce: 0 };
+	let tx_hash = codec::transaction_hash(&transaction);
+	let (tx_root, proofs) = merkle_tree(&[tx_hash, [8; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	// Only calls that report a failure as an outcome keep its count.
+	let encoded = codec::encode_transaction(&transaction);
+	let mut verify = |block_hash, min_depth, proof: &MerkleProof| {
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_fee(encoded.clone(), block_hash, min_depth, proof.clone()),
+			VERIFY_FEE
+		)
+		.map(|receipt| receipt.is_some())
+	};
+	assert_eq!(verify([9; 32], 0, &proofs[0]), Ok(false));
+	assert_eq!(verify(a_hash, 1, &proofs[0]), Ok(false));
+	assert_eq!(verify(a_hash, 0, &proofs[1]), Ok(false));
+	assert_eq!(verify(genesis_hash, 0, &proofs[0]), Ok(false));
+	assert_eq!(verify(a_hash, 0, &proofs[0]), Ok(true));
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(make_child(a_header), tx_hash, proofs[0].clone(), 0),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Ok(false)
+	);
+	// The main verify messages have counterparts that report failures as an outcome.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_outcome(tx_hash, [9; 32], 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(false)
+	);
+	let claim = StateClaim { key: 1, value: 2 };
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state_outcome(claim, a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(false)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_outcome(tx_hash, a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(true)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(tx_hash, a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+
+	assert_eq!(bridge.failure_count(VerificationFailure::UnknownBlock), 2);
+	assert_eq!(
+		bridge.failure_count(VerificationFailure::InsufficientDepth { have: 0, need: 1 }),
+		1
+	);
+	assert_eq!(bridge.failure_count(VerificationFailure::InvalidProof), 4);
+	assert_eq!(bridge.failure_count(VerificationFailure::NotCanon), 0);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.reset_failure_counts(), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.reset_failure_counts(), Ok(()));
+	assert_eq!(bridge.failure_epoch(), 1);
+	assert_eq!(bridge.failure_count(VerificationFailure::InvalidProof), 0);
+}
+
+#[ink::test]
+fn test_outcomes_keep_rejections_as_errors() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+
+	// A failed check becomes a counted `false`, while a rejected call stays an error.
+	assert_eq!(bridge.outcome(Ok(())), Ok(true));
+	assert_eq!(
+		bridge.outcome(Err(VerificationError::Failed(VerificationFailure::NotCanon))),
+		Ok(false)
+	);
+	assert_eq!(
+		bridge.outcome(Err(VerificationError::Rejected(Error::InsufficientVerifyFee))),
+		Err(Error::InsufficientVerifyFee)
+	);
+	assert_eq!(bridge.failure_count(VerificationFailure::NotCanon), 1);
+
+	let claim = StateClaim { key: 1, value: 2 };
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state_outcome(claim, genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE - 1
+		),
+		Err(Error::InsufficientVerifyFee)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_outcome([1; 32], [9; 32], 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(false)
+	);
+	assert_eq!(bridge.failure_count(VerificationFailure::UnknownBlock), 1);
+	assert_eq!(bridge.failure_count(VerificationFailure::InvalidProof), 0);
+}
+
+#[ink::test]
+fn test_zero_header_and_hash_are_rejected() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(Header::default()), RELAY_FEE),
+		Err(Error::DefaultHeader)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([1; 32], [0; 32], 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Rejected(Error::ZeroHash))
+	);
+}
+
+#[ink::test]
+#[should_panic(expected = "checkpoint must be a real header")]
+fn test_constructor_rejects_default_header() {
+	SpvBridge::new(Header::default(), THRESHOLD, RELAY_FEE, VERIFY_FEE);
+}
+
+#[ink::test]
+fn test_relayers_withdraw_rewards() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	set_next_caller(default_accounts.alice);
+	for _ in 0..2 {
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], a_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		)
+		.unwrap();
+	}
+	assert_eq!(bridge.pending_rewards(default_accounts.bob), 2 * VERIFY_FEE);
+	assert_eq!(bridge.withdraw_rewards(), Err(Error::NoPendingRewards));
+
+	set_next_caller(default_accounts.bob);
+	let bob_balance = balance_of(default_accounts.bob);
+	assert_eq!(bridge.withdraw_rewards(), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), bob_balance + 2 * VERIFY_FEE);
+	assert_eq!(bridge.pending_rewards(default_accounts.bob), 0);
+	assert_eq!(bridge.withdraw_rewards(), Err(Error::NoPendingRewards));
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::RewardsWithdrawn(withdrawn) if withdrawn.amount == 2 * VERIFY_FEE
+	)));
+}
+
+#[ink::test]
+fn test_chain_consistency_assertions() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	ink::env::pay_with_call!(bridge.submit_headers(vec![a_header, b_header]), 2 * RELAY_FEE)
+		.unwrap();
+
+	assert_eq!(bridge.assert_chain_consistency(100, 102), Ok(()));
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::ChainConsistencyAttested(attested)
+			if attested.to_hash == SpvBridge::hash_header(b_header)
+	)));
+	assert_eq!(bridge.assert_chain_consistency(101, 100), Err(Error::InvalidRange));
+	assert_eq!(bridge.assert_chain_consistency(99, 101), Err(Error::InvalidRange));
+	assert_eq!(bridge.assert_chain_consistency(100, 103), Err(Error::InvalidRange));
+	assert_eq!(
+		bridge.assert_chain_consistency(0, MAX_CONSISTENCY_RANGE),
+		Err(Error::RangeTooLarge)
+	);
+
+	// Corrupt the recorded work of the middle block.
+	bridge
+		.chain_work
+		.insert((DEFAULT_CHAIN_ID, SpvBridge::hash_header(a_header)), &U256::ONE);
+	assert_eq!(bridge.assert_chain_consistency(100, 102), Err(Error::InconsistentChain));
+	assert_eq!(bridge.assert_chain_consistency(102, 102), Ok(()));
+}
+
+#[ink::test]
+fn test_prune_old_headers() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) =
+		deploy_bridge_with_fee_escrow(default_accounts.alice, None, Some(MEDIAN_TIME_SPAN as u64));
+	assert_eq!(bridge.prune(10), 0);
+	assert_eq!(bridge.set_prune_depth(Some(1)), Err(Error::InvalidPruneDepth));
+	let prune_depth = MEDIAN_TIME_SPAN as u64;
+	assert_eq!(bridge.set_prune_depth(Some(prune_depth)), Ok(()));
+
+	// G---A---...
+	//  \
+	//   --F
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let f_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let f_hash = SpvBridge::hash_header(f_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+	ink::env::pay_with_call!(bridge.submit_new_header(f_header), RELAY_FEE).unwrap();
+	ink::env::pay_with_call!(
+		bridge.verify_transaction([0; 32], a_hash, 0, MerkleProof::default()),
+		VERIFY_FEE
+	)
+	.unwrap();
+	let mut tip = a_header;
+	for _ in 0..prune_depth + 1 {
+		tip = make_child(tip);
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+
+	// The genesis height and A's height are beyond the prune depth. Pruning can stop part
+	// way through a height.
+	assert_eq!(bridge.prune(2), 2);
+	assert_eq!(bridge.pruned_height(), 101);
+	assert_eq!(bridge.prune(10), 1);
+	assert_eq!(bridge.pruned_height(), 102);
+	assert_eq!(bridge.prune(10), 0);
+
+	assert!(!bridge.header_is_known(a_hash));
+	assert!(!bridge.header_is_known(f_hash));
+	assert_eq!(bridge.canon_hash_at(101), Some(a_hash));
+	assert_eq!(bridge.header_by_hash(SpvBridge::hash_header(genesis_header)), None);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(f_header)), RELAY_FEE),
+		Err(Error::UnknownParent)
+	);
+
+	// Fees escrowed against a pruned block still go to its relayer.
+	assert_eq!(bridge.settle_escrow(0), Ok(()));
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), VERIFY_FEE);
+}
+
+#[ink::test]
+fn test_no_reorg_below_finalized_height() {
+	// G---A---B---C
+	//  \       \
+	//   --F      --D
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.set_finality_depth(Some(2)), Ok(()));
+	let a_header = make_child(genesis_header);
+	let f_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let f_hash = SpvBridge::hash_header(f_header);
+	for header in [a_header, f_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.finalized_height(), 100);
+
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	ink::env::pay_with_call!(bridge.submit_headers(vec![b_header, c_header]), 2 * RELAY_FEE)
+		.unwrap();
+	assert_eq!(bridge.finalized_height(), 101);
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(f_header)), RELAY_FEE),
+		Err(Error::ConflictsWithFinalized)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], f_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Rejected(Error::ConflictsWithFinalized))
+	);
+
+	// Forks above the finalized height are still allowed.
+	let d_header = make_child_with_transactions_root(b_header, [1; 32]);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE), Ok(()));
+}
+
+#[ink::test]
+fn test_verify_transaction_fee() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transaction =
+		SourceTransaction { sender: [1; 32], recipient: [2; 32], amount: 5_000, fee: 12, nonce: 0 };
+	let tx_hash = codec::transaction_hash(&transaction);
+	let (tx_root, proofs) = merkle_tree(&[tx_hash, [8; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	let encoded = codec::encode_transaction(&transaction);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_fee(encoded.clone(), a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(Some(TransactionFeeReceipt {
+			tx_hash,
+			block_hash: a_hash,
+			claim_id: bridge.claim_id(tx_hash, a_hash),
+			amount: 5_000,
+			fee: 12
+		}))
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_fee(encoded.clone(), a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Ok(None)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction_fee(encoded[1..].to_vec(), a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(Error::MalformedTransaction)
+	);
+}
+
+#[ink::test]
+fn test_claim_ids_are_unique_per_deployment() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let other_bridge =
+		SpvBridge::new_with_salt(genesis_header, THRESHOLD, RELAY_FEE, VERIFY_FEE, [9; 32]);
+	assert_eq!(bridge.deployment_salt(), [0xff; 32]);
+	assert_eq!(other_bridge.deployment_salt(), [9; 32]);
+	assert_ne!(
+		bridge.claim_id([7; 32], genesis_hash),
+		other_bridge.claim_id([7; 32], genesis_hash)
+	);
+	assert_ne!(bridge.claim_id([7; 32], genesis_hash), bridge.claim_id([7; 32], [1; 32]));
+
+	ink::env::pay_with_call!(
+		bridge.verify_transaction([0; 32], genesis_hash, 0, MerkleProof::default()),
+		VERIFY_FEE
+	)
+	.unwrap();
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::TransactionVerified(verified)
+			if verified.claim_id == bridge.claim_id([0; 32], genesis_hash)
+	)));
+}
+
+#[ink::test]
+fn test_bridge_several_source_chains() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let other_checkpoint = Header { height: 500, pow_nonce: 2, ..source_genesis_header() };
+	let params = ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE * 2,
+		verify_fee: VERIFY_FEE * 2,
+		retarget: None,
+		prune_depth: None,
+		finality_depth: None,
+	};
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.register_chain(7, other_checkpoint, params), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.register_chain(7, other_checkpoint, params), Ok(()));
+	assert_eq!(
+		bridge.register_chain(DEFAULT_CHAIN_ID, other_checkpoint, params),
+		Err(Error::ChainAlreadyRegistered)
+	);
+	assert_eq!(bridge.source_chain(7).map(|chain| chain.best_height), Some(500));
+
+	// Each chain only accepts children of its own headers, at its own relay fee.
+	let transactions = [[1u8; 32], [2u8; 32]];
+	let (tx_root, proofs) = merkle_tree(&transactions);
+	let a_header = make_child_with_transactions_root(other_checkpoint, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::UnknownParent)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![a_header]), RELAY_FEE),
+		Err(Error::InsufficientRelayFee)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![a_header]), RELAY_FEE * 2),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(8, vec![a_header]), RELAY_FEE * 2),
+		Err(Error::UnknownChain)
+	);
+	assert_eq!(bridge.chain_canon_hash_at(7, 501), Some(a_hash));
+	assert_eq!(bridge.best_height(), genesis_header.height);
+	assert_eq!(bridge.header_by_hash(a_hash), None);
+
+	// Claims are verified against the chain they are made about.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_chain_transaction(7, transactions[0], a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE * 2
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(transactions[1], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::UnknownBlock))
+	);
+}
+
+#[ink::test]
+fn test_is_transaction_confirmed() {
+	// G---A---B
+	//  \
+	//   --C
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let c_hash = SpvBridge::hash_header(c_header);
+	for header in [a_header, b_header, c_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+
+	let tx_hash = [7; 32];
+	assert!(bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+	assert!(bridge.is_transaction_confirmed(tx_hash, a_hash, 1));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, a_hash, 2));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, c_hash, 0));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, [9; 32], 0));
+
+	// Everything that would make `verify_transaction` refuse the block counts too.
+	let b_hash = SpvBridge::hash_header(b_header);
+	assert!(bridge.is_transaction_confirmed(tx_hash, b_hash, 0));
+	assert_eq!(bridge.set_min_confirmations(1), Ok(()));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, b_hash, 0));
+	assert!(bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+	assert_eq!(bridge.blacklist_header(a_hash), Ok(()));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+	assert_eq!(bridge.unblacklist_header(a_hash), Ok(()));
+	assert_eq!(bridge.pause(), Ok(()));
+	assert!(!bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+	assert_eq!(bridge.unpause(), Ok(()));
+	assert_eq!(bridge.set_staleness_window(Some(1)), Ok(()));
+	assert!(bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+	bridge.fast_forward(0, 2);
+	assert!(!bridge.is_transaction_confirmed(tx_hash, a_hash, 0));
+}
+
+#[ink::test]
+fn test_storage_report() {
+	// G---A---B
+	//  \
+	//   --F
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.set_finality_depth(Some(1)), Ok(()));
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let f_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	for header in [a_header, f_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(
+		bridge.storage_report(),
+		StorageReport {
+			canon_headers: 2,
+			fork_headers: 1,
+			canon_entries: 2,
+			receipts: 2,
+			orphans: 0,
+		}
+	);
+
+	// Finalizing A's height orphans F.
+	ink::env::pay_with_call!(bridge.submit_new_header(make_child(a_header)), RELAY_FEE).unwrap();
+	bridge.fast_forward(0, SUBMISSION_RECEIPT_TTL);
+	assert_eq!(bridge.remove_expired_receipt(default_accounts.alice, a_hash), Ok(()));
+	assert_eq!(
+		bridge.storage_report(),
+		StorageReport {
+			canon_headers: 3,
+			fork_headers: 1,
+			canon_entries: 3,
+			receipts: 2,
+			orphans: 1,
+		}
+	);
+}
+
+#[ink::test]
+fn test_signed_header_provenance() {
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts().alice);
+	let secret_key = secp256k1::SecretKey::from_slice(&[7; 32]).unwrap();
+	let signer = secret_key.public_key(secp256k1::SECP256K1).serialize();
+	let mut relayer = [0; 32];
+	ink::env::hash_bytes::<Blake2x256>(&signer, &mut relayer);
+	let relayer = AccountId::from(relayer);
+	set_balance(relayer, 1_000_000);
+	set_next_caller(relayer);
+
+	let sign = |header_hash: HashValue| {
+		let message = secp256k1::Message::from_slice(&header_hash).unwrap();
+		let (recovery_id, compact) = secp256k1::SECP256K1
+			.sign_ecdsa_recoverable(&message, &secret_key)
+			.serialize_compact();
+		let mut signature = [0; 65];
+		signature[..64].copy_from_slice(&compact);
+		signature[64] = recovery_id.to_i32() as u8;
+		signature
+	};
+
+	// A signature over another header, or by another account's key, is rejected.
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_signed_header(a_header, sign(SpvBridge::hash_header(b_header))),
+			RELAY_FEE
+		),
+		Err(Error::InvalidSignature)
+	);
+	bridge.impersonate(Some(default_accounts().bob));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_signed_header(a_header, sign(a_hash)), RELAY_FEE),
+		Err(Error::InvalidSignature)
+	);
+	bridge.impersonate(None);
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_signed_header(a_header, sign(a_hash)), RELAY_FEE),
+		Ok(())
+	);
+	assert_eq!(
+		bridge.header_provenance(a_hash),
+		Some(HeaderProvenance { relayer, signer, signature: sign(a_hash) })
+	);
+	assert_eq!(bridge.header_provenance(SpvBridge::hash_header(genesis_header)), None);
+}
+
+#[ink::test]
+fn test_subsidized_verifications() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let verify = |bridge: &mut SpvBridge| {
+		bridge.verify_transaction([0; 32], genesis_hash, 0, MerkleProof::default())
+	};
+	assert_eq!(verify(&mut bridge), Err(VerificationError::Rejected(Error::InsufficientVerifyFee)));
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_free_verifications(2), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_free_verifications(2), Ok(()));
+	assert_eq!(bridge.free_verifications_left(genesis_hash), 2);
+
+	// Without funds in the pool, verifications are not subsidized.
+	assert_eq!(verify(&mut bridge), Err(VerificationError::Rejected(Error::InsufficientVerifyFee)));
+	ink::env::pay_with_call!(bridge.fund_subsidies(), 3 * VERIFY_FEE);
+	ink::env::test::set_value_transferred::<Environment>(0);
+
+	assert_eq!(verify(&mut bridge), Ok(()));
+	assert_eq!(verify(&mut bridge), Ok(()));
+	assert_eq!(verify(&mut bridge), Err(VerificationError::Rejected(Error::InsufficientVerifyFee)));
+	assert_eq!(bridge.free_verifications_left(genesis_hash), 0);
+	assert_eq!(bridge.subsidy_pool(), VERIFY_FEE);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), 2 * VERIFY_FEE);
+
+	// The standard fee applies once the header's free verifications are used up.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], genesis_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+}
+
+fn from_hex(text: &str) -> Vec<u8> {
+	(0..text.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+		.collect()
+}
+
+#[ink::test]
+fn test_track_bitcoin_headers() {
+	// The first three blocks of Bitcoin mainnet.
+	let genesis = from_hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c");
+	let block_1 = from_hex("010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299");
+	let block_2 = from_hex("010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61");
+
+	set_contract_account();
+	ink::env::test::set_block_timestamp::<Environment>(1_700_000_000_000);
+	set_next_caller(default_accounts().alice);
+	let mut bridge = SpvBridge::new_bitcoin(genesis.clone(), 0, RELAY_FEE, VERIFY_FEE);
+	assert_eq!(
+		bridge.best_header_hash().to_vec(),
+		from_hex("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+	);
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_bitcoin_header(block_2.clone()), RELAY_FEE),
+		Err(Error::UnknownParent)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_bitcoin_header(block_1[1..].to_vec()), RELAY_FEE),
+		Err(Error::MalformedHeader)
+	);
+	for header in [block_1, block_2] {
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.submit_bitcoin_header(header), RELAY_FEE),
+			Ok(())
+		);
+	}
+	assert_eq!(bridge.best_height(), 2);
+	assert_eq!(
+		bridge.best_header_hash().to_vec(),
+		from_hex("000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd")
+	);
+
+	// Headers in the bridge's own format are not accepted on a Bitcoin chain.
+	let genesis_header = bridge.header_by_hash(bridge.canon_hash_at(0).unwrap()).unwrap();
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(genesis_header)), RELAY_FEE),
+		Err(Error::WrongHeaderFormat)
+	);
+}
+
+#[ink::test]
+fn test_best_height_samples() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	assert!(!bridge.record_height_sample());
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_sample_interval(Some(10)), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_sample_interval(Some(10)), Ok(()));
+
+	assert!(bridge.record_height_sample());
+	assert!(!bridge.record_height_sample());
+
+	// A new tip is sampled once the interval has passed.
+	bridge.fast_forward(0, 10);
+	let a_header = make_child(genesis_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+	bridge.fast_forward(0, 5);
+	ink::env::pay_with_call!(bridge.submit_new_header(make_child(a_header)), RELAY_FEE).unwrap();
+	assert_eq!(
+		bridge.height_samples(),
+		vec![
+			HeightSample { block_number: 0, best_height: 100 },
+			HeightSample { block_number: 10, best_height: 101 },
+		]
+	);
+
+	// The ring buffer keeps the most recent samples.
+	for _ in 0..MAX_HEIGHT_SAMPLES {
+		bridge.fast_forward(0, 10);
+		assert!(bridge.record_height_sample());
+	}
+	let samples = bridge.height_samples();
+	assert_eq!(samples.len(), MAX_HEIGHT_SAMPLES as usize);
+	assert_eq!(samples[0], HeightSample { block_number: 25, best_height: 102 });
+	assert_eq!(samples.last().unwrap().block_number, 15 + 10 * MAX_HEIGHT_SAMPLES);
+}
+
+#[ink::test]
+fn test_merged_mined_headers() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	// A header whose own hash misses its target.
+	let mut a_header = make_child(genesis_header);
+	while SpvBridge::hash_header(a_header) < a_header.target {
+		a_header.pow_nonce += 1;
+	}
+	let a_hash = SpvBridge::hash_header(a_header);
+
+	// The header is the second leaf of a merged mining tree with two leaves.
+	let chain_root = codec::bitcoin_merkle_node(&[7; 32], &a_hash);
+	let mut coinbase_transaction = vec![1, 2, 3];
+	coinbase_transaction.extend(codec::MERGED_MINING_MAGIC);
+	coinbase_transaction.extend(chain_root);
+	coinbase_transaction.extend(2u32.to_le_bytes());
+	coinbase_transaction.extend([0; 4]);
+	let mut parent_header = BitcoinHeader {
+		version: 1,
+		prev_block_hash: [0; 32],
+		merkle_root: codec::bitcoin_merkle_node(
+			&codec::bitcoin_transaction_hash(&coinbase_transaction),
+			&[9; 32],
+		),
+		time: 0,
+		bits: 0,
+		nonce: 0,
+	};
+	while !SpvBridge::meets_target(codec::bitcoin_header_hash(&parent_header), THRESHOLD) {
+		parent_header.nonce += 1;
+	}
+	let aux_pow = AuxPow {
+		coinbase_transaction,
+		coinbase_branch: vec![[9; 32]],
+		chain_branch: vec![[7; 32]],
+		chain_index: 1,
+		parent_header,
+	};
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::PoWThresholdNotMet)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_aux_pow_header(a_header, aux_pow.clone()),
+			RELAY_FEE
+		),
+		Err(Error::MergedMiningDisabled)
+	);
+	assert_eq!(bridge.set_merged_mining(true), Ok(()));
+
+	// The proof must commit to the header at the right position.
+	let misplaced = AuxPow { chain_index: 0, ..aux_pow.clone() };
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_aux_pow_header(a_header, misplaced), RELAY_FEE),
+		Err(Error::InvalidAuxPow)
+	);
+	let mut wrong_coinbase = aux_pow.clone();
+	wrong_coinbase.coinbase_transaction[0] = 0;
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_aux_pow_header(a_header, wrong_coinbase), RELAY_FEE),
+		Err(Error::InvalidAuxPow)
+	);
+
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_aux_pow_header(a_header, aux_pow), RELAY_FEE),
+		Ok(())
+	);
+	assert!(bridge.header_is_canon(a_hash));
+	assert_eq!(bridge.aux_pow_parent(a_hash), Some(codec::bitcoin_header_hash(&parent_header)));
+	assert_eq!(bridge.assert_chain_consistency(100, 101), Ok(()));
+}
+
+#[ink::test]
+fn test_pre_verified_blocks() {
+	// G---A---B
+	//  \
+	//   --C---D---E
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let (tx_root, proofs) = merkle_tree(&[[1; 32], [2; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	for header in [a_header, b_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+
+	assert_eq!(bridge.verified_depth(a_hash), None);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([1; 32], a_hash, 1, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(bridge.verified_depth(a_hash), Some(1));
+
+	// Later verifications against the block still check their proofs.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([2; 32], a_hash, 1, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([2; 32], a_hash, 1, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([2; 32], a_hash, 2, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InsufficientDepth { have: 1, need: 2 }))
+	);
+
+	// Retracting the block forgets that it was verified.
+	let c_header = make_child_with_transactions_root(genesis_header, [3; 32]);
+	let d_header = make_child(c_header);
+	let e_header = make_child(d_header);
+	for header in [c_header, d_header, e_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.verified_depth(a_hash), None);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([2; 32], a_hash, 1, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::NotCanon))
+	);
+}
+
+const GRANDPA_CHAIN_ID: ChainId = 9;
+
+fn ed25519_key(seed: u8) -> ed25519_zebra::SigningKey {
+	ed25519_zebra::SigningKey::from([seed; 32])
+}
+
+fn ed25519_public(seed: u8) -> [u8; 32] {
+	ed25519_zebra::VerificationKey::from(&ed25519_key(seed)).into()
+}
+
+fn substrate_child(parent: &SubstrateHeader, digest: Vec<DigestItem>) -> SubstrateHeader {
+	SubstrateHeader {
+		parent_hash: codec::substrate_header_hash(parent),
+		number: parent.number + 1,
+		state_root: [parent.number as u8; 32],
+		extrinsics_root: [0; 32],
+		digest,
+	}
+}
+
+fn signed_precommit(
+	seed: u8,
+	target: &SubstrateHeader,
+	round: u64,
+	set_id: u64,
+) -> SignedPrecommit {
+	let precommit = Precommit {
+		target_hash: codec::substrate_header_hash(target),
+		target_number: target.number,
+	};
+	let payload = codec::grandpa_precommit_payload(&precommit, round, set_id);
+	SignedPrecommit {
+		precommit,
+		signature: ed25519_key(seed).sign(&payload).into(),
+		id: ed25519_public(seed),
+	}
+}
+
+/// A justification of `header` in round 1 of set `set_id`, with a precommit for it by
+/// each of the given authorities.
+fn justify(header: &SubstrateHeader, set_id: u64, seeds: &[u8]) -> GrandpaJustification {
+	GrandpaJustification {
+		round: 1,
+		commit: GrandpaCommit {
+			target_hash: codec::substrate_header_hash(header),
+			target_number: header.number,
+			precommits: seeds
+				.iter()
+				.map(|seed| signed_precommit(*seed, header, 1, set_id))
+				.collect(),
+		},
+		votes_ancestries: Vec::new(),
+	}
+}
+
+fn scheduled_change(seeds: &[u8], delay: u32) -> DigestItem {
+	let log = GrandpaConsensusLog::ScheduledChange(ScheduledChange {
+		next_authorities: seeds.iter().map(|seed| (ed25519_public(*seed), 1)).collect(),
+		delay,
+	});
+	DigestItem::Consensus(GRANDPA_ENGINE_ID, scale::Encode::encode(&log))
+}
+
+/// Deploy a bridge and register a GRANDPA chain whose checkpoint is at height 10, with
+/// four authorities of equal weight in set 3. The prune depth it asks for is ignored.
+fn deploy_grandpa_bridge() -> (SpvBridge, SubstrateHeader) {
+	let (mut bridge, _) = deploy_bridge(default_accounts().alice);
+	let checkpoint = SubstrateHeader {
+		parent_hash: [1; 32],
+		number: 10,
+		state_root: [2; 32],
+		extrinsics_root: [3; 32],
+		digest: Vec::new(),
+	};
+	let params = ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		retarget: None,
+		prune_depth: Some(1),
+		finality_depth: None,
+	};
+	let authorities = (1..=4).map(|seed| (ed25519_public(seed), 1)).collect();
+	bridge
+		.register_grandpa_chain(GRANDPA_CHAIN_ID, checkpoint.clone(), params, 3, authorities)
+		.unwrap();
+	(bridge, checkpoint)
+}
+
+#[ink::test]
+fn test_grandpa_chain() {
+	let (mut bridge, checkpoint) = deploy_grandpa_bridge();
+	assert_eq!(
+		bridge.chain_canon_hash_at(GRANDPA_CHAIN_ID, 10),
+		Some(codec::substrate_header_hash(&checkpoint))
+	);
+	let a = substrate_child(&checkpoint, Vec::new());
+	let a_hash = codec::substrate_header_hash(&a);
+	let submit = |bridge: &mut SpvBridge, header: &SubstrateHeader, justification| {
+		ink::env::pay_with_call!(
+			bridge.submit_grandpa_header(GRANDPA_CHAIN_ID, header.clone(), justification),
+			RELAY_FEE
+		)
+	};
+
+	// Two of four is not more than two thirds of the weight.
+	assert_eq!(
+		submit(&mut bridge, &a, justify(&a, 3, &[1, 2])),
+		Err(Error::InsufficientSignatures)
+	);
+	// Precommits signed for another set or by outsiders don't count.
+	assert_eq!(submit(&mut bridge, &a, justify(&a, 2, &[1, 2, 3])), Err(Error::InvalidSignature));
+	assert_eq!(submit(&mut bridge, &a, justify(&a, 3, &[1, 2, 5])), Err(Error::InvalidSignature));
+	assert_eq!(submit(&mut bridge, &a, justify(&a, 3, &[1, 2, 2])), Err(Error::DuplicateVote));
+	// The commit must be for the submitted header.
+	let other = SubstrateHeader { state_root: [9; 32], ..a.clone() };
+	assert_eq!(
+		submit(&mut bridge, &a, justify(&other, 3, &[1, 2, 3])),
+		Err(Error::InvalidJustification)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_grandpa_header(GRANDPA_CHAIN_ID, a.clone(), justify(&a, 3, &[1, 2, 3])),
+			RELAY_FEE - 1
+		),
+		Err(Error::InsufficientRelayFee)
+	);
+
+	assert_eq!(submit(&mut bridge, &a, justify(&a, 3, &[1, 2, 3])), Ok(()));
+	assert_eq!(bridge.chain_canon_hash_at(GRANDPA_CHAIN_ID, 11), Some(a_hash));
+	let chain = bridge.source_chain(GRANDPA_CHAIN_ID).unwrap();
+	assert_eq!((chain.best_height, chain.finalized_height), (11, 11));
+	assert_eq!(
+		submit(&mut bridge, &a, justify(&a, 3, &[1, 2, 3])),
+		Err(Error::HeaderAlreadySubmitted)
+	);
+
+	// PoW headers are not accepted for a GRANDPA chain.
+	let pow_header = make_child(source_genesis_header());
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_chain_headers(GRANDPA_CHAIN_ID, vec![pow_header]),
+			RELAY_FEE
+		),
+		Err(Error::WrongConsensus)
+	);
+}
+
+#[ink::test]
+fn test_grandpa_precommits_for_descendants() {
+	let (mut bridge, checkpoint) = deploy_grandpa_bridge();
+	let a = substrate_child(&checkpoint, Vec::new());
+	let b = substrate_child(&a, Vec::new());
+	let c = substrate_child(&b, Vec::new());
+
+	// A precommit for C counts for A when the justification links C back to A.
+	let mut justification = justify(&a, 3, &[1, 2]);
+	justification.commit.precommits.push(signed_precommit(3, &c, 1, 3));
+	let submit = |bridge: &mut SpvBridge, justification| {
+		ink::env::pay_with_call!(
+			bridge.submit_grandpa_header(GRANDPA_CHAIN_ID, a.clone(), justification),
+			RELAY_FEE
+		)
+	};
+	justification.votes_ancestries = vec![c.clone()];
+	assert_eq!(submit(&mut bridge, justification.clone()), Err(Error::InvalidJustification));
+	justification.votes_ancestries = vec![b, c];
+	assert_eq!(submit(&mut bridge, justification), Ok(()));
+}
+
+#[ink::test]
+fn test_grandpa_chain_ancestry_and_pruning() {
+	let (mut bridge, checkpoint) = deploy_grandpa_bridge();
+	let submit = |bridge: &mut SpvBridge, header: &SubstrateHeader| {
+		ink::env::pay_with_call!(
+			bridge.submit_grandpa_header(
+				GRANDPA_CHAIN_ID,
+				header.clone(),
+				justify(header, 3, &[1, 2, 3])
+			),
+			RELAY_FEE
+		)
+	};
+	let checkpoint_hash = codec::substrate_header_hash(&checkpoint);
+	let a = substrate_child(&checkpoint, Vec::new());
+	let b = substrate_child(&a, Vec::new());
+	// D is relayed without its parent C.
+	let d = substrate_child(&substrate_child(&b, Vec::new()), Vec::new());
+	let [a_hash, b_hash, d_hash] = [&a, &b, &d].map(codec::substrate_header_hash);
+	for header in [&a, &b, &d] {
+		assert_eq!(submit(&mut bridge, header), Ok(()));
+	}
+
+	// Final headers are linked like relayed ones, up to the gap below D.
+	assert_eq!(bridge.children.get((GRANDPA_CHAIN_ID, a_hash)), Some(vec![b_hash]));
+	assert_eq!(bridge.fork_tips.get(GRANDPA_CHAIN_ID), Some(vec![d_hash]));
+	assert_eq!(bridge.ancestor_at(GRANDPA_CHAIN_ID, b_hash, 10), Some(checkpoint_hash));
+	assert_eq!(bridge.fork_point(GRANDPA_CHAIN_ID, a_hash, b_hash), Some(a_hash));
+	assert_eq!(bridge.ancestor_at(GRANDPA_CHAIN_ID, d_hash, 11), None);
+	assert!(bridge.chain_work.contains((GRANDPA_CHAIN_ID, d_hash)));
+
+	// Their heights have gaps, so they are never pruned.
+	assert_eq!(bridge.source_chain(GRANDPA_CHAIN_ID).unwrap().prune_depth, None);
+	assert_eq!(bridge.set_chain_prune_depth(GRANDPA_CHAIN_ID, Some(1)), Err(Error::WrongConsensus));
+	assert_eq!(bridge.prune_chain(GRANDPA_CHAIN_ID, 10), 0);
+	assert!(bridge.headers.contains((GRANDPA_CHAIN_ID, a_hash)));
+
+	// Descendants of a blacklisted final header are refused.
+	assert_eq!(bridge.blacklist_chain_header(GRANDPA_CHAIN_ID, d_hash), Ok(()));
+	assert_eq!(
+		submit(&mut bridge, &substrate_child(&d, Vec::new())),
+		Err(Error::BlacklistedHeader)
+	);
+}
+
+#[ink::test]
+fn test_grandpa_authority_handoff() {
+	let (mut bridge, checkpoint) = deploy_grandpa_bridge();
+	let submit = |bridge: &mut SpvBridge, header: &SubstrateHeader, justification| {
+		ink::env::pay_with_call!(
+			bridge.submit_grandpa_header(GRANDPA_CHAIN_ID, header.clone(), justification),
+			RELAY_FEE
+		)
+	};
+
+	// Delayed and forced changes can't be followed.
+	let delayed = substrate_child(&checkpoint, vec![scheduled_change(&[5, 6], 5)]);
+	assert_eq!(
+		submit(&mut bridge, &delayed, justify(&delayed, 3, &[1, 2, 3])),
+		Err(Error::UnsupportedAuthorityChange)
+	);
+	let forced = GrandpaConsensusLog::ForcedChange(
+		0,
+		ScheduledChange { next_authorities: vec![(ed25519_public(5), 1)], delay: 0 },
+	);
+	let forced = substrate_child(
+		&checkpoint,
+		vec![DigestItem::Consensus(GRANDPA_ENGINE_ID, scale::Encode::encode(&forced))],
+	);
+	assert_eq!(
+		submit(&mut bridge, &forced, justify(&forced, 3, &[1, 2, 3])),
+		Err(Error::UnsupportedAuthorityChange)
+	);
+
+	// The current set finalizes the header that hands off to the next one.
+	let a = substrate_child(&checkpoint, vec![scheduled_change(&[5, 6], 0)]);
+	assert_eq!(submit(&mut bridge, &a, justify(&a, 3, &[1, 2, 3])), Ok(()));
+	let state = bridge.grandpa_state(GRANDPA_CHAIN_ID).unwrap();
+	assert_eq!(state.set_id, 4);
+	assert_eq!(state.enacted_at, 11);
+	assert_eq!(state.authorities, vec![(ed25519_public(5), 1), (ed25519_public(6), 1)]);
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::GrandpaAuthoritiesChanged(changed)
+			if changed.set_id == 4 && changed.enacted_at == 11
+	)));
+
+	// Headers after the handoff need the new set.
+	let b = substrate_child(&a, Vec::new());
+	assert_eq!(submit(&mut bridge, &b, justify(&b, 3, &[1, 2])), Err(Error::InvalidSignature));
+	assert_eq!(submit(&mut bridge, &b, justify(&b, 4, &[5, 6])), Ok(()));
+}
+
+#[ink::test]
+fn test_capabilities() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	assert_eq!(
+		bridge.capabilities(),
+		Capabilities {
+			header_format: HeaderFormat::Native,
+			retargeting: false,
+			merged_mining: false,
+			rolling_finality: false,
+			pruning: false,
+			fee_escrow: false,
+			subsidized_verifications: false,
+			height_sampling: false,
+			optimistic_submission: false,
+			relayer_staking: false,
+			relayer_allowlist: false,
+			rate_limiting: false,
+			staleness_freeze: false,
+			reorg_circuit_breaker: false,
+			dynamic_relay_fee: false,
+			xcm_forwarding: false,
+			wrapped_asset: false,
+			hash_algorithm: HashAlgorithm::Sha2x256,
+			crypto_extension: cfg!(feature = "crypto-extension"),
+			test_helpers: true,
+		}
+	);
+
+	assert_eq!(bridge.set_prune_depth(Some(MEDIAN_TIME_SPAN as u64)), Ok(()));
+	assert_eq!(bridge.set_merged_mining(true), Ok(()));
+	assert_eq!(bridge.set_free_verifications(1), Ok(()));
+	let capabilities = bridge.capabilities();
+	assert!(capabilities.pruning && capabilities.merged_mining);
+	assert!(capabilities.subsidized_verifications);
+	assert!(!capabilities.rolling_finality);
+
+	let (bridge, _) = deploy_bridge_with_fee_escrow(default_accounts.alice, Some(6), None);
+	assert!(bridge.capabilities().fee_escrow);
+}
+
+#[ink::test]
+fn test_beefy_chain() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let checkpoint = Header { height: 500, pow_nonce: 2, ..source_genesis_header() };
+	let params = ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		retarget: None,
+		prune_depth: None,
+		finality_depth: None,
+	};
+	let secret_keys: Vec<_> =
+		(1..=4u8).map(|i| secp256k1::SecretKey::from_slice(&[i; 32]).unwrap()).collect();
+	let authorities = secret_keys
+		.iter()
+		.map(|key| key.public_key(secp256k1::SECP256K1).serialize())
+		.collect();
+	assert_eq!(bridge.register_beefy_chain(7, checkpoint, params, 3, authorities), Ok(()));
+
+	// Headers 501 to 505 are the MMR's leaves.
+	let mut headers = vec![make_child(checkpoint)];
+	for _ in 0..4 {
+		headers.push(make_child(*headers.last().unwrap()));
+	}
+	let leaves: Vec<_> = headers.iter().map(codec::mmr_leaf_hash).collect();
+	let (mmr_root, items) = mmr::build_proof(&leaves, 2);
+	let commitment = BeefyCommitment {
+		payload: vec![(BEEFY_MMR_ROOT_ID, mmr_root.to_vec())],
+		block_number: 506,
+		validator_set_id: 3,
+	};
+	let sign = |commitment: &BeefyCommitment, signers: usize| -> Vec<_> {
+		let message =
+			secp256k1::Message::from_slice(&codec::beefy_commitment_hash(commitment)).unwrap();
+		secret_keys
+			.iter()
+			.enumerate()
+			.map(|(i, key)| {
+				(i < signers).then(|| {
+					let (recovery_id, compact) = secp256k1::SECP256K1
+						.sign_ecdsa_recoverable(&message, key)
+						.serialize_compact();
+					let mut signature = [0; 65];
+					signature[..64].copy_from_slice(&compact);
+					signature[64] = recovery_id.to_i32() as u8;
+					signature
+				})
+			})
+			.collect()
+	};
+
+	// Three of the four authorities must sign.
+	assert_eq!(
+		bridge.submit_beefy_commitment(7, commitment.clone(), sign(&commitment, 2)),
+		Err(Error::InsufficientSignatures)
+	);
+	let mut forged = sign(&commitment, 3);
+	forged.swap(0, 1);
+	assert_eq!(
+		bridge.submit_beefy_commitment(7, commitment.clone(), forged),
+		Err(Error::InvalidSignature)
+	);
+	let other_set = BeefyCommitment { validator_set_id: 4, ..commitment.clone() };
+	assert_eq!(
+		bridge.submit_beefy_commitment(7, other_set.clone(), sign(&other_set, 4)),
+		Err(Error::UnknownValidatorSet)
+	);
+	assert_eq!(bridge.submit_beefy_commitment(7, commitment.clone(), sign(&commitment, 3)), Ok(()));
+	assert_eq!(
+		bridge.submit_beefy_commitment(7, commitment.clone(), sign(&commitment, 4)),
+		Err(Error::StaleCommitment)
+	);
+	assert_eq!(bridge.beefy_state(7).unwrap().mmr_root, mmr_root);
+
+	// Headers are proven against the MMR root rather than relayed one by one.
+	let proof = MmrProof { leaf_index: 2, leaf_count: 5, items };
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_mmr_header(7, headers[3], proof.clone()), RELAY_FEE),
+		Err(Error::InvalidMmrProof)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_mmr_header(7, headers[2], proof), RELAY_FEE),
+		Ok(())
+	);
+	let proven_hash = SpvBridge::hash_header(headers[2]);
+	assert_eq!(bridge.chain_canon_hash_at(7, 503), Some(proven_hash));
+	let chain = bridge.source_chain(7).unwrap();
+	assert_eq!(chain.consensus, ConsensusBackend::Beefy);
+	assert_eq!((chain.best_height, chain.finalized_height), (503, 503));
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_chain_transaction(7, [0; 32], proven_hash, 0, MerkleProof::default()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+
+	// PoW headers are not accepted.
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![headers[0]]), RELAY_FEE),
+		Err(Error::WrongConsensus)
+	);
+}
+
+const TENDERMINT_CHAIN_ID: ChainId = 11;
+
+fn hex<const N: usize>(text: &str) -> [u8; N] {
+	let bytes: Vec<u8> = (0..text.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+		.collect();
+	bytes.try_into().unwrap()
+}
+
+fn chain_params() -> ChainParams {
+	ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		retarget: None,
+		prune_depth: None,
+		finality_depth: None,
+	}
+}
+
+fn tendermint_header(
+	height: u64,
+	validators: &[([u8; 32], u64)],
+	next_validators: &[([u8; 32], u64)],
+) -> TendermintHeader {
+	TendermintHeader {
+		version: TendermintVersion { block: 11, app: 0 },
+		chain_id: b"test-chain".to_vec(),
+		height,
+		time: TendermintTime { seconds: height as i64, nanos: 0 },
+		last_block_id: BlockId {
+			hash: vec![height as u8; 32],
+			part_set_header: PartSetHeader { total: 1, hash: vec![1; 32] },
+		},
+		last_commit_hash: vec![2; 32],
+		data_hash: Vec::new(),
+		validators_hash: tendermint::validators_hash(validators),
+		next_validators_hash: tendermint::validators_hash(next_validators),
+		consensus_hash: [3; 32],
+		app_hash: vec![4; 32],
+		last_results_hash: Vec::new(),
+		evidence_hash: Vec::new(),
+		proposer_address: vec![5; 20],
+	}
+}
+
+/// A validator set of the given Ed25519 keys, each with one unit of voting power.
+fn validator_set(seeds: &[u8]) -> Vec<([u8; 32], u64)> {
+	seeds.iter().map(|seed| (ed25519_public(*seed), 1)).collect()
+}
+
+/// A commit of `header` in round 0, signed by the given validators of `set`.
+fn tendermint_commit(header: &TendermintHeader, set: &[u8], signers: &[u8]) -> TendermintCommit {
+	let part_set_header = PartSetHeader { total: 1, hash: vec![6; 32] };
+	let signatures = set
+		.iter()
+		.map(|seed| {
+			signers.contains(seed).then(|| {
+				let timestamp = TendermintTime { seconds: 7, nanos: i32::from(*seed) };
+				let sign_bytes = tendermint::vote_sign_bytes(
+					&header.chain_id,
+					header.height,
+					0,
+					&tendermint::header_hash(header),
+					&part_set_header,
+					&timestamp,
+				);
+				CommitSig { timestamp, signature: ed25519_key(*seed).sign(&sign_bytes).into() }
+			})
+		})
+		.collect();
+	TendermintCommit { round: 0, part_set_header, signatures }
+}
+
+#[ink::test]
+fn test_tendermint_chain_accepts_real_commits() {
+	// Height 10 of a Gaia test chain, from tendermint-rpc's fixtures.
+	let validators =
+		vec![(hex("b28af61af42fc899b99b2214d2023e56d3d2bf0524c99405605f1a6f3bce2cfb"), 100_000)];
+	let validators_hash = hex("0A6CA9001DB07E985DF9043045B392588DF7C1C720E30EBAEFDC8A848C551D6A");
+	let header = TendermintHeader {
+		version: TendermintVersion { block: 11, app: 0 },
+		chain_id: b"ibc-0".to_vec(),
+		height: 10,
+		time: TendermintTime { seconds: 1_639_772_867, nanos: 875_954_829 },
+		last_block_id: BlockId {
+			hash: hex::<32>("CD0A81D2658C56FD65587E4502E4BC89955002B5B89F986C7D63A5AF184FBC92")
+				.to_vec(),
+			part_set_header: PartSetHeader {
+				total: 1,
+				hash: hex::<32>("6E41BBA804019240A672F9AC6625BAA6D40B3887D6F6BE3E64CF54D93F021B76")
+					.to_vec(),
+			},
+		},
+		last_commit_hash: hex::<32>(
+			"3A9BEC015F1A923C17EF81007945F552AD4E35DDA445AFA91296564DCB748357",
+		)
+		.to_vec(),
+		data_hash: hex::<32>("E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855")
+			.to_vec(),
+		validators_hash,
+		next_validators_hash: validators_hash,
+		consensus_hash: hex("048091BC7DDC283F77BFBF91D73C44DA58C3DF8A9CBC867405D8B7F3DAADA22F"),
+		app_hash: hex::<32>("A7139E6F28A9506629C60E3FDFD1A325E99177A4A3C8FA131732B6170C113C8E")
+			.to_vec(),
+		last_results_hash: hex::<32>(
+			"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+		)
+		.to_vec(),
+		evidence_hash: hex::<32>(
+			"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+		)
+		.to_vec(),
+		proposer_address: hex::<20>("BB22AD764B674CC08753B24175E2FC61B22B1419").to_vec(),
+	};
+	let mut commit = TendermintCommit {
+		round: 0,
+		part_set_header: PartSetHeader {
+			total: 1,
+			hash: hex::<32>("ABFFFBEDF37CB50B0F5617D2A85110B838EA2B3E3E56CCA1E09A27DCD5BBF769")
+				.to_vec(),
+		},
+		signatures: vec![Some(CommitSig {
+			timestamp: TendermintTime { seconds: 1_639_772_868, nanos: 900_125_770 },
+			signature: hex(concat!(
+				"91e7b89b6bd58101ebae4b9e2e4f5d7618e4302833fb566a48f62f9a02379c3a",
+				"551ac4794f737df1cb23c8e2618b051c063e50c05a948e30c91f474e819bf509",
+			)),
+		})],
+	};
+
+	let (mut bridge, _) = deploy_bridge(default_accounts().alice);
+	let checkpoint = TendermintHeader { height: 9, ..header.clone() };
+	assert_eq!(
+		bridge.register_tendermint_chain(
+			TENDERMINT_CHAIN_ID,
+			checkpoint,
+			chain_params(),
+			validators.clone()
+		),
+		Ok(())
+	);
+	let mut submit = |commit: TendermintCommit, validators: Vec<([u8; 32], u64)>| {
+		ink::env::pay_with_call!(
+			bridge.submit_tendermint_header(
+				TENDERMINT_CHAIN_ID,
+				header.clone(),
+				commit,
+				validators,
+				None
+			),
+			RELAY_FEE
+		)
+	};
+
+	assert_eq!(
+		submit(commit.clone(), vec![(validators[0].0, 100_001)]),
+		Err(Error::ValidatorSetMismatch)
+	);
+	commit.round = 1;
+	assert_eq!(submit(commit.clone(), validators.clone()), Err(Error::InvalidSignature));
+	commit.round = 0;
+	assert_eq!(submit(commit, validators), Ok(()));
+	assert_eq!(
+		bridge.chain_canon_hash_at(TENDERMINT_CHAIN_ID, 10),
+		Some(hex("EB917FF229E0987637F20EDB8114CAC3F967D843C5CC480969D64D7A368F077F"))
+	);
+}
+
+#[ink::test]
+fn test_tendermint_validator_set_changes() {
+	let (mut bridge, _) = deploy_bridge(default_accounts().alice);
+	let first_set = validator_set(&[1, 2, 3, 4]);
+	let checkpoint = tendermint_header(5, &first_set, &first_set);
+	assert_eq!(
+		bridge.register_tendermint_chain(
+			TENDERMINT_CHAIN_ID,
+			checkpoint.clone(),
+			chain_params(),
+			validator_set(&[1, 2, 3])
+		),
+		Err(Error::ValidatorSetMismatch)
+	);
+	bridge
+		.register_tendermint_chain(
+			TENDERMINT_CHAIN_ID,
+			checkpoint,
+			chain_params(),
+			first_set.clone(),
+		)
+		.unwrap();
+	let submit = |bridge: &mut SpvBridge,
+	              header: &TendermintHeader,
+	              commit: TendermintCommit,
+	              validators: &[([u8; 32], u64)],
+	              next_validators: Option<Vec<([u8; 32], u64)>>| {
+		ink::env::pay_with_call!(
+			bridge.submit_tendermint_header(
+				TENDERMINT_CHAIN_ID,
+				header.clone(),
+				commit,
+				validators.to_vec(),
+				next_validators
+			),
+			RELAY_FEE
+		)
+	};
+
+	// The first set hands off to the second at height 6.
+	let second_set = validator_set(&[3, 4, 5, 6]);
+	let a = tendermint_header(6, &first_set, &second_set);
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&a,
+			tendermint_commit(&a, &[1, 2, 3, 4], &[1, 2]),
+			&first_set,
+			Some(second_set.clone())
+		),
+		Err(Error::InsufficientSignatures)
+	);
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&a,
+			tendermint_commit(&a, &[1, 2, 3], &[1, 2, 3]),
+			&first_set,
+			Some(second_set.clone())
+		),
+		Err(Error::InvalidJustification)
+	);
+	let commit = tendermint_commit(&a, &[1, 2, 3, 4], &[1, 2, 3]);
+	assert_eq!(
+		submit(&mut bridge, &a, commit.clone(), &first_set, None),
+		Err(Error::ValidatorSetMismatch)
+	);
+	assert_eq!(
+		submit(&mut bridge, &a, commit.clone(), &first_set, Some(second_set.clone())),
+		Ok(())
+	);
+	assert_eq!(
+		bridge.tendermint_state(TENDERMINT_CHAIN_ID),
+		Some(TendermintState {
+			network: b"test-chain".to_vec(),
+			validators: second_set.clone(),
+			trusted_height: 6,
+		})
+	);
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::TendermintValidatorsChanged(changed) if changed.trusted_height == 6
+	)));
+	assert_eq!(
+		submit(&mut bridge, &a, commit, &first_set, Some(second_set.clone())),
+		Err(Error::HeaderAlreadySubmitted)
+	);
+
+	// Skipping ahead, a set that shares too little power with the trusted one is not
+	// trusted, even with all its signatures.
+	let stranger_set = validator_set(&[7, 8, 9, 10]);
+	let b = tendermint_header(9, &stranger_set, &stranger_set);
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&b,
+			tendermint_commit(&b, &[7, 8, 9, 10], &[7, 8, 9, 10]),
+			&stranger_set,
+			None
+		),
+		Err(Error::UntrustedValidatorSet)
+	);
+	// Half of the trusted set signing is enough.
+	let third_set = validator_set(&[5, 6, 7, 8]);
+	let b = tendermint_header(9, &third_set, &third_set);
+	assert_eq!(
+		submit(&mut bridge, &b, tendermint_commit(&b, &[5, 6, 7, 8], &[5, 6, 7]), &third_set, None),
+		Ok(())
+	);
+	assert_eq!(bridge.source_chain(TENDERMINT_CHAIN_ID).unwrap().finalized_height, 9);
+
+	// Headers up to the trusted one may be committed by older sets.
+	let c = tendermint_header(8, &second_set, &second_set);
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&c,
+			tendermint_commit(&c, &[3, 4, 5, 6], &[3, 4, 5, 6]),
+			&second_set,
+			None
+		),
+		Err(Error::UnknownValidatorSet)
+	);
+}
+
+const SYNC_COMMITTEE_CHAIN_ID: ChainId = 13;
+
+/// The SSZ root of a sync committee with the given members and aggregate public key, and
+/// the branch of each chunk of its keys, see `submit_sync_committee_keys`.
+fn sync_committee_root(
+	keys: &[[u8; 48]],
+	aggregate: &[u8; 48],
+) -> (HashValue, Vec<Vec<HashValue>>) {
+	let chunk_roots: Vec<_> = keys
+		.chunks(SYNC_COMMITTEE_KEYS_PER_CHUNK as usize)
+		.map(|chunk| {
+			let leaves: Vec<_> = chunk.iter().map(codec::ssz_public_key_root).collect();
+			codec::ssz_merkleize(&leaves)
+		})
+		.collect();
+	let aggregate_root = codec::ssz_public_key_root(aggregate);
+	let root = codec::ssz_node(&codec::ssz_merkleize(&chunk_roots), &aggregate_root);
+	let branches = (0..chunk_roots.len())
+		.map(|chunk| {
+			let pair = chunk & !1;
+			let other_pair = codec::ssz_node(&chunk_roots[pair ^ 2], &chunk_roots[pair ^ 3]);
+			vec![chunk_roots[chunk ^ 1], other_pair, aggregate_root]
+		})
+		.collect();
+	(root, branches)
+}
+
+fn submit_committee_keys(
+	bridge: &mut SpvBridge,
+	chain_id: ChainId,
+	keys: &[[u8; 48]],
+	aggregate: &[u8; 48],
+) {
+	let (root, branches) = sync_committee_root(keys, aggregate);
+	let chunks = keys.chunks(SYNC_COMMITTEE_KEYS_PER_CHUNK as usize);
+	for (chunk, (keys, branch)) in chunks.zip(branches).enumerate() {
+		assert_eq!(
+			bridge.submit_sync_committee_keys(chain_id, root, chunk as u32, keys.to_vec(), branch),
+			Ok(())
+		);
+	}
+}
+
+/// A sync committee of eight distinct BLS keys derived from `seed`, each held by every
+/// eighth member: the members' secret keys, public keys and the committee's root.
+fn sync_committee(seed: u64) -> (Vec<bls12_381::Scalar>, Vec<[u8; 48]>, HashValue) {
+	let secret_keys: Vec<_> = (0..SYNC_COMMITTEE_SIZE)
+		.map(|member| bls12_381::Scalar::from(seed * 8 + u64::from(member % 8) + 1))
+		.collect();
+	let distinct: Vec<_> =
+		secret_keys[..8].iter().map(|key| crypto::bls_public_key(*key)).collect();
+	let public_keys: Vec<_> =
+		(0..SYNC_COMMITTEE_SIZE as usize).map(|member| distinct[member % 8]).collect();
+	let (root, _) = sync_committee_root(&public_keys, &public_keys[0]);
+	(secret_keys, public_keys, root)
+}
+
+/// The root of a beacon state with the given current and next sync committees, and the
+/// branches proving them, in a state tree of depth 5.
+fn beacon_state(
+	current: HashValue,
+	next: HashValue,
+) -> (HashValue, Vec<HashValue>, Vec<HashValue>) {
+	let rest: Vec<HashValue> = (1..5).map(|i| [i; 32]).collect();
+	let current_branch = [&[next][..], &rest].concat();
+	let next_branch = [&[current][..], &rest].concat();
+	let root = codec::ssz_branch_root(&current, &current_branch, CURRENT_SYNC_COMMITTEE_INDEX);
+	(root, current_branch, next_branch)
+}
+
+fn beacon_header(slot: u64, state_root: HashValue) -> BeaconBlockHeader {
+	BeaconBlockHeader {
+		slot,
+		proposer_index: 1,
+		parent_root: [slot as u8; 32],
+		state_root,
+		body_root: [2; 32],
+	}
+}
+
+#[ink::test]
+fn test_sync_committee_chain() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let period = 10;
+	let (secret_keys, keys, committee_root) = sync_committee(1);
+	let (next_secret_keys, next_keys, next_committee_root) = sync_committee(2);
+	let (state_root, committee_branch, next_committee_branch) =
+		beacon_state(committee_root, next_committee_root);
+	let checkpoint = beacon_header(period * SLOTS_PER_SYNC_COMMITTEE_PERIOD, state_root);
+	let genesis_validators_root = [3; 32];
+	let altair = BeaconFork { epoch: 0, version: [1, 0, 0, 0], state_depth: 5 };
+	let register = |bridge: &mut SpvBridge, forks: Vec<BeaconFork>, branch: &[HashValue]| {
+		bridge.register_sync_committee_chain(
+			SYNC_COMMITTEE_CHAIN_ID,
+			checkpoint,
+			chain_params(),
+			genesis_validators_root,
+			forks,
+			committee_root,
+			branch.to_vec(),
+		)
+	};
+
+	// The first fork must be in effect at the checkpoint, and later ones follow it.
+	let later = BeaconFork { epoch: checkpoint.slot / SLOTS_PER_EPOCH + 1, ..altair };
+	for forks in [vec![], vec![later], vec![altair, later, later]] {
+		assert_eq!(
+			register(&mut bridge, forks, &committee_branch),
+			Err(Error::InvalidForkSchedule)
+		);
+	}
+	// The branch must prove the committee at the fork's state depth.
+	assert_eq!(
+		register(&mut bridge, vec![altair], &next_committee_branch),
+		Err(Error::InvalidCommitteeProof)
+	);
+	let electra = BeaconFork { state_depth: 6, ..altair };
+	assert_eq!(
+		register(&mut bridge, vec![electra], &committee_branch),
+		Err(Error::InvalidCommitteeProof)
+	);
+	assert_eq!(register(&mut bridge, vec![altair], &committee_branch), Ok(()));
+	let chain = bridge.source_chain(SYNC_COMMITTEE_CHAIN_ID).unwrap();
+	assert_eq!(chain.consensus, ConsensusBackend::SyncCommittee);
+	assert_eq!(
+		bridge.chain_canon_hash_at(SYNC_COMMITTEE_CHAIN_ID, checkpoint.slot),
+		Some(codec::beacon_header_root(&checkpoint))
+	);
+
+	// The first `signers` members of the committee sign the header under the given fork
+	// version.
+	let sign = |header: &BeaconBlockHeader,
+	            secret_keys: &[bls12_381::Scalar],
+	            signers: usize,
+	            version: [u8; 4]| {
+		let mut participation = vec![0; SYNC_COMMITTEE_SIZE as usize / 8];
+		(0..signers).for_each(|member| participation[member / 8] |= 1 << (member % 8));
+		let signing_root = codec::sync_committee_signing_root(
+			&codec::beacon_header_root(header),
+			version,
+			&genesis_validators_root,
+		);
+		let secret_key = secret_keys[..signers].iter().sum();
+		(participation, crypto::bls_sign(secret_key, &signing_root))
+	};
+	let submit_at = |bridge: &mut SpvBridge,
+	                 header: &BeaconBlockHeader,
+	                 signature_slot: u64,
+	                 (participation, signature): (Vec<u8>, [u8; 96]),
+	                 next_committee: Option<NextSyncCommittee>| {
+		ink::env::pay_with_call!(
+			bridge.submit_sync_committee_header(
+				SYNC_COMMITTEE_CHAIN_ID,
+				*header,
+				signature_slot,
+				participation,
+				signature,
+				next_committee
+			),
+			RELAY_FEE
+		)
+	};
+	// Signed in the slot after the header's.
+	let submit = |bridge: &mut SpvBridge,
+	              header: &BeaconBlockHeader,
+	              signed: (Vec<u8>, [u8; 96]),
+	              next_committee: Option<NextSyncCommittee>| {
+		submit_at(bridge, header, header.slot + 1, signed, next_committee)
+	};
+
+	// Headers can't be checked until the whole committee is known.
+	let a = beacon_header(checkpoint.slot + 1, state_root);
+	let version = altair.version;
+	assert_eq!(
+		submit(&mut bridge, &a, sign(&a, &secret_keys, 400, version), None),
+		Err(Error::MissingCommitteeKeys)
+	);
+	let (_, branches) = sync_committee_root(&keys, &keys[0]);
+	let chunk_keys = |keys: &[[u8; 48]], chunk: usize| {
+		let chunk_size = SYNC_COMMITTEE_KEYS_PER_CHUNK as usize;
+		keys[chunk * chunk_size..(chunk + 1) * chunk_size].to_vec()
+	};
+	assert_eq!(
+		bridge.submit_sync_committee_keys(
+			SYNC_COMMITTEE_CHAIN_ID,
+			committee_root,
+			1,
+			chunk_keys(&next_keys, 1),
+			branches[1].clone()
+		),
+		Err(Error::InvalidCommitteeProof)
+	);
+	assert_eq!(
+		bridge.submit_sync_committee_keys(
+			SYNC_COMMITTEE_CHAIN_ID,
+			next_committee_root,
+			0,
+			chunk_keys(&next_keys, 0),
+			branches[0].clone()
+		),
+		Err(Error::UnknownValidatorSet)
+	);
+	submit_committee_keys(&mut bridge, SYNC_COMMITTEE_CHAIN_ID, &keys, &keys[0]);
+
+	// Two thirds of the committee must sign.
+	assert_eq!(
+		submit(&mut bridge, &a, sign(&a, &secret_keys, 341, version), None),
+		Err(Error::InsufficientSignatures)
+	);
+	let (participation, _) = sign(&a, &secret_keys, 400, version);
+	let (_, forged) = sign(&a, &secret_keys, 399, version);
+	assert_eq!(
+		submit(&mut bridge, &a, (participation, forged), None),
+		Err(Error::InvalidSignature)
+	);
+	assert_eq!(submit(&mut bridge, &a, sign(&a, &secret_keys, 342, version), None), Ok(()));
+	assert_eq!(
+		bridge.chain_canon_hash_at(SYNC_COMMITTEE_CHAIN_ID, a.slot),
+		Some(codec::beacon_header_root(&a))
+	);
+	let chain = bridge.source_chain(SYNC_COMMITTEE_CHAIN_ID).unwrap();
+	assert_eq!(chain.finalized_height, a.slot);
+
+	// A header proves the next committee against its state root.
+	let b = beacon_header(a.slot + 1, state_root);
+	let next_committee =
+		|root, branch: &[HashValue]| Some(NextSyncCommittee { root, branch: branch.to_vec() });
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&b,
+			sign(&b, &secret_keys, 512, version),
+			next_committee([9; 32], &next_committee_branch)
+		),
+		Err(Error::InvalidCommitteeProof)
+	);
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&b,
+			sign(&b, &secret_keys, 512, version),
+			next_committee(next_committee_root, &next_committee_branch)
+		),
+		Ok(())
+	);
+	let state = bridge.sync_committee_state(SYNC_COMMITTEE_CHAIN_ID).unwrap();
+	assert_eq!(state.next_committee_root, Some(next_committee_root));
+
+	// Signatures from the next period are made by the next committee, which takes over,
+	// under the version of the fork in effect at the slot before the signature's.
+	let boundary = (period + 1) * SLOTS_PER_SYNC_COMMITTEE_PERIOD;
+	let bellatrix =
+		BeaconFork { epoch: boundary / SLOTS_PER_EPOCH + 1, version: [2, 0, 0, 0], state_depth: 5 };
+	assert_eq!(
+		bridge.add_beacon_fork(SYNC_COMMITTEE_CHAIN_ID, altair),
+		Err(Error::InvalidForkSchedule)
+	);
+	assert_eq!(bridge.add_beacon_fork(SYNC_COMMITTEE_CHAIN_ID, bellatrix), Ok(()));
+	submit_committee_keys(&mut bridge, SYNC_COMMITTEE_CHAIN_ID, &next_keys, &next_keys[0]);
+	let (next_state_root, _, _) = beacon_state(next_committee_root, [8; 32]);
+	let c = beacon_header(boundary + SLOTS_PER_EPOCH, next_state_root);
+	assert_eq!(
+		submit_at(&mut bridge, &c, c.slot, sign(&c, &next_secret_keys, 400, version), None),
+		Err(Error::IncorrectHeight)
+	);
+	assert_eq!(
+		submit(&mut bridge, &c, sign(&c, &secret_keys, 400, bellatrix.version), None),
+		Err(Error::InvalidSignature)
+	);
+	assert_eq!(
+		submit(&mut bridge, &c, sign(&c, &next_secret_keys, 400, version), None),
+		Err(Error::InvalidSignature)
+	);
+	// Its state proves the committee that signed it, not the one after.
+	assert_eq!(
+		submit(
+			&mut bridge,
+			&c,
+			sign(&c, &next_secret_keys, 400, bellatrix.version),
+			next_committee(next_committee_root, &next_committee_branch)
+		),
+		Err(Error::InvalidCommitteeProof)
+	);
+	assert_eq!(
+		submit(&mut bridge, &c, sign(&c, &next_secret_keys, 400, bellatrix.version), None),
+		Ok(())
+	);
+	let state = bridge.sync_committee_state(SYNC_COMMITTEE_CHAIN_ID).unwrap();
+	assert_eq!(
+		(state.period, state.committee_root, state.next_committee_root),
+		(period + 1, next_committee_root, None)
+	);
+	assert_eq!(state.forks, [altair, bellatrix]);
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::SyncCommitteeRotated(rotated) if rotated.period == period + 1
+	)));
+
+	// Periods beyond the next committee's are unknown.
+	let d = beacon_header((period + 3) * SLOTS_PER_SYNC_COMMITTEE_PERIOD, state_root);
+	assert_eq!(
+		submit(&mut bridge, &d, sign(&d, &next_secret_keys, 512, bellatrix.version), None),
+		Err(Error::UnknownValidatorSet)
+	);
+}
+
+#[ink::test]
+fn test_sync_committee_chain_accepts_real_aggregates() {
+	// The checkpoint and sync committee update of Snowbridge's Ethereum light client test
+	// fixtures, from a local Electra testnet. Its committee has eight distinct keys.
+	let distinct = [
+		"9977f1c8b731a8d5558146bfb86caea26434f3c5878b589bf280a42c9159e700e9df0e4086296c20b011d2e78c27d373",
+		"a8d4c7c27795a725961317ef5953a7032ed6d83739db8b0e8a72353d1b8b4439427f7efa2c89caa03cc9f28f8cbab8ac",
+		"81283b7a20e1ca460ebd9bbd77005d557370cabb1f9a44f530c4c4c66230f675f8df8b4c2818851aa7d77a80ca5a4a5e",
+		"88c141df77cd9d8d7a71a75c826c41a9c9f03c6ee1b180f3e7852f6a280099ded351b58d66e653af8e42816a4d8f532e",
+		"b89bebc699769726a318c8e9971bd3171297c61aea4a6578a7a4f94b547dcba5bac16a89108b6b6a1fe3695d1a874a0b",
+		"a99a76ed7796f7be22d5b7e85deeb7c5677e88e511e0b337618f8c4eb61349b4bf2d153f649f7b53359fe8b94a38e44c",
+		"a3a32b0f8b4ddb83f1a0a853d81dd725dfe577d4f4c3db8ece52ce2b026eca84815c1a7e8e92a4de3d755733bf7e4a9b",
+		"ab0bdda0f85f842f431beaccf1250bf1fd7ba51b4100fd64364b6401fda85bb0069b3e715b58819684e7fc0b10a72a34",
+	];
+	let members = concat!(
+		"0102345116170606421657207360352351042317624000313137505430136334",
+		"5402554377477761172143060635653235721116531611703543314155320442",
+		"5631145054566456752403363171455630233737314577274147560532024156",
+		"7404042076315472276375441750227740456245171275367420377075477104",
+		"1670256777403330437072260324237040377556052327502752173124210022",
+		"7570610734451636162114746350221724344725100577375401331253441143",
+		"4134111141076060621011073650435633012302335465155054442541403020",
+		"2523250163617112614537701062246620210107413713176222106615016640",
+	);
+	let keys: Vec<[u8; 48]> = members
+		.bytes()
+		.map(|member| hex(distinct[usize::from(member - b'0')]))
+		.collect();
+	let aggregate = hex(concat!(
+		"88fdbe3b47a74601391cbd98b9bbae1b59a0b0c9",
+		"03eaffb60aaeae2ee16b2bcb300242df8c4871fb51bbddb527164151",
+	));
+	let (committee_root, _) = sync_committee_root(&keys, &aggregate);
+	let checkpoint = BeaconBlockHeader {
+		slot: 64,
+		proposer_index: 2,
+		parent_root: hex("f3c09d828948462f79335270e169fe886d1665ce05b83a1dc14b68185a076add"),
+		state_root: hex("99db10aa40b277bf9875cdb55958c0cfe3ac01718bd919eeb31ea9a091f911e2"),
+		body_root: hex("f434d729c044c87220f5a2316e59800c35242f48870fe902e25d5933d5f6b3f3"),
+	};
+	let committee_branch = [
+		"caeec1857155609f05fafdcb96272bd51b925b15fa82d63f8ce3e4bcab3329c4",
+		"058baa5628d6156e55ab99da54244be4a071978528f2eb3b19a4f4d7ab36f870",
+		"5f89984c1068b616e99589e161d2bb73b92c68b3422ef309ace434894b4503ae",
+		"b219710639054ed8c37b96be2e1ec476f2a9211a7d52d89d73f014719558efef",
+		"487cdceeac880292127b3b891364bea647472208abfe43b7cc3500f5b436e418",
+		"a1381fdc64967103fe79c0705727851ce61e7f91bee7e3e7759f9283c91ff7ff",
+	]
+	.map(hex)
+	.to_vec();
+	let header = BeaconBlockHeader {
+		slot: 129,
+		proposer_index: 3,
+		parent_root: hex("bdf2dd55a235731bdd69198123053670502c79b50ec67360758f10b78b556f0a"),
+		state_root: hex("0ff974398fb60e7be37977062a1d3d0a065ebbae6d39f5cb06324239154c6207"),
+		body_root: hex("b72b305d88b3049241835fd5c3f0444448ab2b56d94d381111de5255be5fc8d0"),
+	};
+	let signature = hex(concat!(
+		"adefb37062fffed9b86c04d0107651fbb6b7c899e028fa111d3eb45c1644abbe",
+		"78f49cacec2cf4a317bf0aff6bafed8b05e0a1c0bd953ccd76993038e7e44e09",
+		"3e8c23d43dcb6b9d5a85944bde4933444e1365a0d87ef0e08e9e4857ed19dff5",
+	));
+	// The fixture's next committee is its current one.
+	let next_committee = NextSyncCommittee {
+		root: committee_root,
+		branch: [
+			"caeec1857155609f05fafdcb96272bd51b925b15fa82d63f8ce3e4bcab3329c4",
+			"432170c5dd9c312a9c8cb204abb63a923b75642d186b0df5ba67504f49358510",
+			"70ddcd855b0d38d017ea568141d22f48bc30875c00b49c830209d5769e4cbd9c",
+			"6765d3894ab9e98a81483f67e15dad96ac242acbd8ee8828e83cfabdb4ab4985",
+			"227f4888b5f7530054abfdfa54ec340d5b0eeda6507b7d06ef49ae28a98f5792",
+			"a1381fdc64967103fe79c0705727851ce61e7f91bee7e3e7759f9283c91ff7ff",
+		]
+		.map(hex)
+		.to_vec(),
+	};
+
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let genesis_validators_root =
+		hex("270d43e74ce340de4bca2b1936beca0f4f5408d9e78aec4850920baf659d5b69");
+	let deneb = BeaconFork { epoch: 0, version: [4, 0, 0, 0], state_depth: 5 };
+	let electra = BeaconFork { epoch: 2, version: [5, 0, 0, 0], state_depth: 6 };
+	// A schedule with a later fork, whose version the committee did not sign under.
+	let fulu = BeaconFork { epoch: 4, version: [6, 0, 0, 0], state_depth: 6 };
+	for (chain_id, forks) in [
+		(SYNC_COMMITTEE_CHAIN_ID, vec![deneb, electra, fulu]),
+		(SYNC_COMMITTEE_CHAIN_ID + 1, vec![deneb, electra]),
+	] {
+		assert_eq!(
+			bridge.register_sync_committee_chain(
+				chain_id,
+				checkpoint,
+				chain_params(),
+				genesis_validators_root,
+				forks,
+				committee_root,
+				committee_branch.clone()
+			),
+			Ok(())
+		);
+		submit_committee_keys(&mut bridge, chain_id, &keys, &aggregate);
+	}
+
+	let submit = |bridge: &mut SpvBridge, chain_id| {
+		ink::env::pay_with_call!(
+			bridge.submit_sync_committee_header(
+				chain_id,
+				header,
+				130,
+				vec![0xff; SYNC_COMMITTEE_SIZE as usize / 8],
+				signature,
+				Some(next_committee.clone())
+			),
+			RELAY_FEE
+		)
+	};
+	assert_eq!(submit(&mut bridge, SYNC_COMMITTEE_CHAIN_ID), Err(Error::InvalidSignature));
+	assert_eq!(submit(&mut bridge, SYNC_COMMITTEE_CHAIN_ID + 1), Ok(()));
+	assert_eq!(
+		bridge.chain_canon_hash_at(SYNC_COMMITTEE_CHAIN_ID + 1, 129),
+		Some(hex("4677dc644a0a971d1057b263926ecc7eda3114c4fa2e370236ae4c41f5928979"))
+	);
+	let state = bridge.sync_committee_state(SYNC_COMMITTEE_CHAIN_ID + 1).unwrap();
+	assert_eq!(state.next_committee_root, Some(committee_root));
+}
+
+#[ink::test]
+fn test_ethereum_state_verification() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	// An account whose storage has one slot set, next to another account
+	let address = [0xaa; 20];
+	let (slot, value) = ([1; 32], [[0; 30].as_slice(), &[0x12, 0x34]].concat());
+	let storage_entries = [(codec::keccak_256(&slot).to_vec(), rlp::encode::bytes(&value[30..]))];
+	let (storage_root, storage_proof) =
+		mpt::build::trie(&storage_entries, &codec::keccak_256(&slot));
+	let (_, absent_proof) = mpt::build::trie(&storage_entries, &codec::keccak_256(&[2; 32]));
+	let account = rlp::encode::list(&[
+		rlp::encode::bytes(&[1]),
+		rlp::encode::bytes(&[0x0f, 0x42, 0x40]),
+		rlp::encode::bytes(&storage_root),
+		rlp::encode::bytes(&[0xcc; 32]),
+	]);
+	let state_entries = [
+		(codec::keccak_256(&address).to_vec(), account.clone()),
+		(codec::keccak_256(&[0xbb; 20]).to_vec(), rlp::encode::list(&[])),
+	];
+	let (state_root, account_proof) =
+		mpt::build::trie(&state_entries, &codec::keccak_256(&address));
+
+	let a_header = make_child_with_roots(genesis_header, [0; 32], state_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_account(address, account.clone(), a_hash, 0, account_proof.clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_account([0xbb; 20], account, a_hash, 0, account_proof.clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+
+	let proof = StorageProof { account_proof: account_proof.clone(), storage_proof };
+	let value: HashValue = value.try_into().unwrap();
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_storage(address, slot, value, a_hash, 0, proof.clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_storage(address, slot, [0; 32], a_hash, 0, proof),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	// A slot missing from the storage trie is zero
+	let proof = StorageProof { account_proof, storage_proof: absent_proof };
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_storage(address, [2; 32], [0; 32], a_hash, 0, proof),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_substrate_storage_verification() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	let entries = [
+		(b"System Account alice".to_vec(), [7; 48].to_vec()),
+		(b"System Account bob".to_vec(), [9; 48].to_vec()),
+		(b"System Number".to_vec(), 100u32.to_le_bytes().to_vec()),
+	];
+	let (state_root, proof) = substrate_trie::build::trie(&entries, &entries[0].0);
+	let (_, absent_proof) = substrate_trie::build::trie(&entries, b"System Account carol");
+
+	let a_header = make_child_with_roots(genesis_header, [0; 32], state_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	let key = entries[0].0.clone();
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_substrate_storage(
+				DEFAULT_CHAIN_ID,
+				key.clone(),
+				Some(entries[0].1.clone()),
+				a_hash,
+				0,
+				proof.clone()
+			),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_substrate_storage(
+				DEFAULT_CHAIN_ID,
+				key,
+				Some(entries[1].1.clone()),
+				a_hash,
+				0,
+				proof
+			),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_substrate_storage(
+				DEFAULT_CHAIN_ID,
+				b"System Account carol".to_vec(),
+				None,
+				a_hash,
+				0,
+				absent_proof
+			),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_optimistic_submission() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let bond = 500;
+	let a_header = make_child(genesis_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_optimistic_header(a_header), RELAY_FEE + bond),
+		Err(Error::OptimisticModeDisabled)
+	);
+	assert_eq!(bridge.set_challenge_period(Some(10)), Ok(()));
+	assert_eq!(bridge.set_header_bond(bond), Ok(()));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_optimistic_header(a_header), RELAY_FEE),
+		Err(Error::InsufficientBond)
+	);
+
+	// A valid header, a header that does not meet its target, and an orphan
+	let a_hash = SpvBridge::hash_header(a_header);
+	let mut bad_pow = a_header;
+	while SpvBridge::hash_header(bad_pow) < bad_pow.target {
+		bad_pow.pow_nonce += 1;
+	}
+	let orphan = mine(Header { parent: [9; 32], ..a_header });
+	for header in [a_header, bad_pow, orphan] {
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.submit_optimistic_header(header), RELAY_FEE + bond),
+			Ok(())
+		);
+	}
+	assert_eq!(bridge.pending_header(a_hash).map(|pending| pending.bond), Some(bond));
+	assert!(!bridge.header_is_known(a_hash));
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.challenge_header(a_hash, FraudProof::InvalidPoW), Err(Error::FraudNotProven));
+	assert_eq!(
+		bridge.challenge_header(a_hash, FraudProof::BadParentLink),
+		Err(Error::FraudNotProven)
+	);
+	let bad_pow_hash = SpvBridge::hash_header(bad_pow);
+	assert_eq!(bridge.challenge_header(bad_pow_hash, FraudProof::InvalidPoW), Ok(()));
+	assert_eq!(bridge.pending_header(bad_pow_hash), None);
+	assert_eq!(bridge.pending_rewards(default_accounts.bob), bond);
+	assert_eq!(bridge.finalize_pending_header(a_hash), Err(Error::ChallengePeriodNotOver));
+
+	// Once the challenge period is over, the orphan can no longer be challenged
+	bridge.fast_forward(0, 11);
+	let orphan_hash = SpvBridge::hash_header(orphan);
+	assert_eq!(
+		bridge.challenge_header(orphan_hash, FraudProof::BadParentLink),
+		Err(Error::ChallengePeriodOver)
+	);
+	assert_eq!(bridge.finalize_pending_header(orphan_hash), Err(Error::UnknownParent));
+	assert_eq!(bridge.finalize_pending_header(a_hash), Ok(()));
+	assert_eq!(bridge.best_header_hash(), a_hash);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), bond);
+	assert_eq!(bridge.pending_header(a_hash), None);
+}
+
+#[ink::test]
+fn test_relayer_staking() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let stake = 10_000;
+	assert_eq!(bridge.set_min_relayer_stake(Some(stake)), Ok(()));
+	set_balance(default_accounts.bob, 1_000_000);
+	set_balance(default_accounts.django, 1_000_000);
+
+	let a_header = make_child(genesis_header);
+	let a_hash = SpvBridge::hash_header(a_header);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::InsufficientStake)
+	);
+	assert_eq!(ink::env::pay_with_call!(bridge.register_relayer(), stake), Ok(()));
+	assert_eq!(bridge.stake_of(default_accounts.bob).bonded, stake);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	// Unbonded stake can't be used to submit, and stays slashable until withdrawn
+	assert_eq!(bridge.unbond(), Ok(()));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(a_header)), RELAY_FEE),
+		Err(Error::InsufficientStake)
+	);
+	assert_eq!(bridge.withdraw_unbonded(), Err(Error::NothingToWithdraw));
+
+	set_next_caller(default_accounts.charlie);
+	assert_eq!(bridge.slash_relayer(a_hash, default_accounts.charlie), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.slash_relayer(a_hash, default_accounts.charlie), Ok(()));
+	assert_eq!(bridge.stake_of(default_accounts.bob), RelayerStake::default());
+	assert_eq!(
+		bridge.pending_rewards(default_accounts.charlie),
+		stake * SLASH_REPORTER_PERCENT / 100
+	);
+	assert_eq!(bridge.slash_relayer(a_hash, default_accounts.charlie), Err(Error::NothingToSlash));
+
+	// Without slashing, unbonded stake can be withdrawn after the delay
+	set_next_caller(default_accounts.django);
+	assert_eq!(ink::env::pay_with_call!(bridge.register_relayer(), stake), Ok(()));
+	assert_eq!(bridge.unbond(), Ok(()));
+	bridge.fast_forward(0, UNBONDING_DELAY);
+	assert_eq!(bridge.withdraw_unbonded(), Ok(()));
+	assert_eq!(bridge.stake_of(default_accounts.django), RelayerStake::default());
+}
+
+#[ink::test]
+fn test_relayer_allowlist() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	set_balance(default_accounts.bob, 1_000_000);
+	assert_eq!(bridge.set_relayer_allowlist(true), Ok(()));
+	assert!(bridge.capabilities().relayer_allowlist);
+
+	let a_header = make_child(genesis_header);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.add_relayer(default_accounts.bob), Err(Error::MissingRole));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE),
+		Err(Error::RelayerNotAllowed)
+	);
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.add_relayer(default_accounts.bob), Ok(()));
+	assert!(bridge.is_allowed_relayer(default_accounts.bob));
+	set_next_caller(default_accounts.bob);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.remove_relayer(default_accounts.bob), Ok(()));
+	set_next_caller(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(a_header)), RELAY_FEE),
+		Err(Error::RelayerNotAllowed)
+	);
+}
+
+#[ink::test]
+fn test_rate_limit() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	set_balance(default_accounts.bob, 1_000_000);
+	assert_eq!(bridge.set_rate_limit(Some(RateLimit { per_relayer: 2, per_block: 3 })), Ok(()));
+
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	let d_header = make_child(c_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![a_header, b_header]), RELAY_FEE * 2),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE),
+		Err(Error::RateLimited)
+	);
+
+	// Another relayer may still use what is left of the block's limit
+	set_next_caller(default_accounts.bob);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE), Ok(()));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE),
+		Err(Error::RateLimited)
+	);
+
+	bridge.fast_forward(0, 1);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE), Ok(()));
+}
+
+#[ink::test]
+fn test_dynamic_relay_fee() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let controller = FeeController { target_block_time: BLOCK_TIME, step_percent: 50 };
+	assert_eq!(bridge.set_fee_controller(Some(controller)), Ok(()));
+	assert_eq!(bridge.current_relay_fee(), RELAY_FEE);
+
+	// Headers arriving faster than the target block time raise the fee
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.current_relay_fee(), RELAY_FEE * 3 / 2);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE),
+		Err(Error::InsufficientRelayFee)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE * 3 / 2),
+		Ok(())
+	);
+	assert_eq!(bridge.current_relay_fee(), RELAY_FEE * 9 / 4);
+
+	// And it decays back to the relay fee when they don't
+	bridge.fast_forward(BLOCK_TIME, 0);
+	assert_eq!(bridge.current_relay_fee(), RELAY_FEE * 9 / 8);
+	bridge.fast_forward(BLOCK_TIME, 0);
+	assert_eq!(bridge.current_relay_fee(), RELAY_FEE);
+	assert_eq!(bridge.relay_fee(), RELAY_FEE);
+}
+
+#[ink::test]
+fn test_overpayment_is_refunded() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	set_balance(default_accounts.bob, 1_000_000);
+	let (tx_root, proofs) = merkle_tree(&[[1; 32], [2; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	let b_header = make_child(a_header);
+	set_next_caller(default_accounts.bob);
+
+	// Underpaying is rejected, exact payment is kept and overpayment is refunded
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE - 1),
+		Err(Error::InsufficientRelayFee)
+	);
+	let balance = balance_of(default_accounts.bob);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), balance - RELAY_FEE);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE * 3), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), balance - RELAY_FEE * 2);
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([1; 32], a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE - 1
+		),
+		Err(Error::InsufficientVerifyFee.into())
+	);
+	let balance = balance_of(default_accounts.bob);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([1; 32], a_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([2; 32], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE * 5
+		),
+		Ok(())
+	);
+	assert_eq!(balance_of(default_accounts.bob), balance - VERIFY_FEE * 2);
+}
+
+#[ink::test]
+fn test_treasury() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	assert_eq!(bridge.treasury_balance(), 0);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![a_header, b_header]), RELAY_FEE * 3),
+		Ok(())
+	);
+	assert_eq!(bridge.treasury_balance(), RELAY_FEE * 2);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.spend_treasury(default_accounts.bob, 1), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(
+		bridge.spend_treasury(default_accounts.charlie, RELAY_FEE * 2 + 1),
+		Err(Error::InsufficientTreasury)
+	);
+	let balance = balance_of(default_accounts.charlie);
+	assert_eq!(bridge.spend_treasury(default_accounts.charlie, RELAY_FEE), Ok(()));
+	assert_eq!(balance_of(default_accounts.charlie), balance + RELAY_FEE);
+	assert_eq!(bridge.recycle_treasury(RELAY_FEE), Ok(()));
+	assert_eq!(bridge.subsidy_pool(), RELAY_FEE);
+	assert_eq!(bridge.treasury_balance(), 0);
+}
+
+#[ink::test]
+fn test_verification_credits() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let (tx_root, proofs) = merkle_tree(&[[1; 32], [2; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	ink::env::test::set_value_transferred::<Environment>(0);
+	assert_eq!(
+		bridge.verify_transaction([1; 32], a_hash, 0, proofs[0].clone()),
+		Err(Error::InsufficientVerifyFee.into())
+	);
+	assert_eq!(ink::env::pay_with_call!(bridge.deposit_credits(), VERIFY_FEE * 3 / 2), Ok(()));
+	ink::env::test::set_value_transferred::<Environment>(0);
+	assert_eq!(bridge.verify_transaction([1; 32], a_hash, 0, proofs[0].clone()), Ok(()));
+	assert_eq!(bridge.credits_of(default_accounts.bob), VERIFY_FEE / 2);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), VERIFY_FEE);
+	assert_eq!(
+		bridge.verify_transaction([2; 32], a_hash, 0, proofs[1].clone()),
+		Err(Error::InsufficientVerifyFee.into())
+	);
+
+	let balance = balance_of(default_accounts.bob);
+	assert_eq!(bridge.withdraw_credits(), Ok(()));
+	assert_eq!(balance_of(default_accounts.bob), balance + VERIFY_FEE / 2);
+	assert_eq!(bridge.withdraw_credits(), Err(Error::NoCredits));
+}
+
+#[ink::test]
+fn test_spv_verifier_trait() {
+	let default_accounts = default_accounts();
+	set_next_caller(default_accounts.alice);
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let claim = StateClaim { key: 0, value: 0 };
+	let (storage_root, state_proofs) = merkle_tree(&[SpvBridge::hash_claim(claim)]);
+	let (tx_root, tx_proofs) = merkle_tree(&[[1; 32]]);
+	let a_header = make_child_with_roots(genesis_header, tx_root, storage_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	// The fee is paid by transferring value with the call, as a consumer contract would
+	ink::env::test::transfer_in::<Environment>(VERIFY_FEE);
+	assert_eq!(
+		SpvVerifier::verify_transaction(&mut bridge, [1; 32], a_hash, 0, tx_proofs[0].clone()),
+		Ok(())
+	);
+	ink::env::test::transfer_in::<Environment>(VERIFY_FEE);
+	assert_eq!(
+		SpvVerifier::verify_state(&mut bridge, claim, a_hash, 0, state_proofs[0].clone()),
+		Ok(())
+	);
+	ink::env::test::transfer_in::<Environment>(VERIFY_FEE);
+	assert_eq!(
+		SpvVerifier::verify_state(
+			&mut bridge,
+			StateClaim { key: 0, value: 1 },
+			a_hash,
+			0,
+			state_proofs[0].clone()
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+}
+
+#[ink::test]
+fn test_header_subscriptions_need_approval() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	let consumer = AccountId::from([0x42; 32]);
+	set_balance(consumer, 1_000_000);
+	set_next_caller(consumer);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.subscribe_headers([1, 2, 3, 4]),
+			HEADER_SUBSCRIPTION_DEPOSIT - 1
+		),
+		Err(Error::InsufficientSubscriptionDeposit)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.subscribe_headers([1, 2, 3, 4]),
+			HEADER_SUBSCRIPTION_DEPOSIT
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.subscribe_headers([1, 2, 3, 4]),
+			HEADER_SUBSCRIPTION_DEPOSIT
+		),
+		Err(Error::AlreadySubscribed)
+	);
+	let subscriber = HeaderSubscriber {
+		contract: consumer,
+		selector: [1, 2, 3, 4],
+		deposit: HEADER_SUBSCRIPTION_DEPOSIT,
+	};
+	assert_eq!(bridge.pending_header_subscriber(consumer), Some(subscriber));
+	assert_eq!(bridge.header_subscribers(), vec![]);
+
+	// Only admins approve subscriptions, and only pending ones.
+	assert_eq!(bridge.approve_header_subscriber(consumer), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.approve_header_subscriber(default_accounts.bob), Err(Error::NotSubscribed));
+	assert_eq!(bridge.approve_header_subscriber(consumer), Ok(()));
+	assert_eq!(bridge.pending_header_subscriber(consumer), None);
+	assert_eq!(bridge.header_subscribers(), vec![subscriber]);
+
+	// Requests beyond the registry's size wait until a slot frees up.
+	for i in 1..=MAX_HEADER_SUBSCRIBERS as u8 {
+		let contract = AccountId::from([i; 32]);
+		set_balance(contract, 1_000_000);
+		set_next_caller(contract);
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.subscribe_headers([i; 4]), HEADER_SUBSCRIPTION_DEPOSIT),
+			Ok(())
+		);
+		set_next_caller(default_accounts.alice);
+		let expected =
+			if i < MAX_HEADER_SUBSCRIBERS as u8 { Ok(()) } else { Err(Error::HeaderRegistryFull) };
+		assert_eq!(bridge.approve_header_subscriber(contract), expected);
+	}
+
+	let last = AccountId::from([MAX_HEADER_SUBSCRIBERS as u8; 32]);
+	let balance_before = balance_of(last);
+	assert_eq!(bridge.remove_header_subscriber(last), Ok(()));
+	assert_eq!(balance_of(last), balance_before + HEADER_SUBSCRIPTION_DEPOSIT);
+	assert_eq!(bridge.pending_header_subscriber(last), None);
+
+	set_next_caller(consumer);
+	let balance_before = balance_of(consumer);
+	assert_eq!(bridge.unsubscribe_headers(), Ok(()));
+	assert_eq!(balance_of(consumer), balance_before + HEADER_SUBSCRIPTION_DEPOSIT);
+	assert!(bridge.header_subscribers().iter().all(|s| s.contract != consumer));
+	assert_eq!(bridge.unsubscribe_headers(), Err(Error::NotSubscribed));
+
+	let events = decode_events();
+	let count = |matches: fn(&Event) -> bool| events.iter().filter(|e| matches(e)).count();
+	let subscribers = MAX_HEADER_SUBSCRIBERS as usize;
+	assert_eq!(count(|e| matches!(e, Event::HeaderSubscriptionRequested(_))), subscribers + 1);
+	assert_eq!(count(|e| matches!(e, Event::HeaderSubscribed(_))), subscribers);
+	assert_eq!(count(|e| matches!(e, Event::HeaderUnsubscribed(_))), 2);
+}
+
+#[ink::test]
+#[cfg(feature = "xcm-extension")]
+fn test_xcm_forwarding() {
+	use scale::Decode;
+	use std::{cell::RefCell, rc::Rc};
+
+	/// The parachains and calls that the runtime has sent `Transact`s to.
+	type Sent = Rc<RefCell<Vec<(u32, Vec<u8>)>>>;
+
+	/// Stands in for the runtime, recording what it sends. It only has a channel to
+	/// parachain 2000.
+	struct MockExtension(Sent);
+
+	impl ink::env::test::ChainExtension for MockExtension {
+		fn func_id(&self) -> u32 {
+			xcm::XCM_TRANSACT
+		}
+
+		fn call(&mut self, input: &[u8], output: &mut Vec<u8>) -> u32 {
+			let input = Vec::<u8>::decode(&mut &input[..]).unwrap();
+			let (para_id, call) = <(u32, Vec<u8>)>::decode(&mut &input[..]).unwrap();
+			self.0.borrow_mut().push((para_id, call));
+			scale::Encode::encode_to(&(para_id == 2000), output);
+			0
+		}
+	}
+
+	let sent = Rc::new(RefCell::new(Vec::new()));
+	ink::env::test::register_chain_extension(MockExtension(sent.clone()));
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transactions = [[1u8; 32], [2u8; 32]];
+	let (tx_root, proofs) = merkle_tree(&transactions);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+
+	// Nothing is forwarded until a destination is set.
+	let verify = |bridge: &mut SpvBridge, index: usize| {
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(transactions[index], a_hash, 0, proofs[index].clone()),
+			VERIFY_FEE
+		)
+	};
+	assert_eq!(verify(&mut bridge, 0), Ok(()));
+	assert!(sent.borrow().is_empty());
+
+	let destination = XcmDestination { para_id: 2000, call_index: [50, 1] };
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_xcm_destination(Some(destination)), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_xcm_destination(Some(destination)), Ok(()));
+	assert!(bridge.capabilities().xcm_forwarding);
+	assert_eq!(verify(&mut bridge, 1), Ok(()));
+	let claim_id = bridge.claim_id(transactions[1], a_hash);
+	assert_eq!(
+		*sent.borrow(),
+		[(
+			2000,
+			codec::xcm_claim_call(
+				[50, 1],
+				DEFAULT_CHAIN_ID,
+				&transactions[1],
+				&a_hash,
+				&claim_id,
+				0
+			)
+		)]
+	);
+
+	// A failed send doesn't fail the verification, but is reported.
+	let unreachable = XcmDestination { para_id: 2001, ..destination };
+	assert_eq!(bridge.set_xcm_destination(Some(unreachable)), Ok(()));
+	assert_eq!(verify(&mut bridge, 0), Ok(()));
+	let forwarded: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::ClaimForwarded(ClaimForwarded { para_id, sent, .. }) => Some((para_id, sent)),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(forwarded, [(2000, true), (2001, false)]);
+	assert_eq!(sent.borrow().len(), 2);
+}
+
+#[ink::test]
+#[cfg(not(feature = "xcm-extension"))]
+fn test_xcm_forwarding_needs_xcm_extension() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let destination = XcmDestination { para_id: 2000, call_index: [50, 1] };
+	assert_eq!(bridge.set_xcm_destination(Some(destination)), Err(Error::XcmUnavailable));
+	assert_eq!(bridge.set_xcm_destination(None), Ok(()));
+	assert!(!bridge.capabilities().xcm_forwarding);
+}
+
+#[ink::test]
+fn test_mint_deposit() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let deposit =
+		SourceTransaction { sender: [7; 32], recipient: [2; 32], amount: 5_000, fee: 12, nonce: 0 };
+	let transfer = SourceTransaction { recipient: [3; 32], ..deposit };
+	let tx_hash = codec::transaction_hash(&deposit);
+	let (tx_root, proofs) = merkle_tree(&[tx_hash, codec::transaction_hash(&transfer)]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	let encoded = codec::encode_transaction(&deposit);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.mint_deposit(encoded.clone(), a_hash, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(Error::WrappedAssetDisabled)
+	);
+	let asset =
+		WrappedAsset { token: AccountId::from([9; 32]), lock_address: [2; 32], confirmations: 1 };
+	assert_eq!(bridge.set_wrapped_asset(Some(asset)), Ok(()));
+	assert!(bridge.capabilities().wrapped_asset);
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.mint_deposit(codec::encode_transaction(&transfer), a_hash, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(Error::NotADeposit)
+	);
+	// The deposit's block needs a confirmation
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.mint_deposit(encoded.clone(), a_hash, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(Error::DepositNotProven)
+	);
+
+	let b_header = make_child(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE).unwrap();
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.mint_deposit(encoded.clone(), a_hash, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Ok(5_000)
+	);
+	assert!(bridge.is_deposit_minted(tx_hash));
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::DepositMinted(minted)
+			if minted.beneficiary == AccountId::from([7; 32]) && minted.amount == 5_000
+	)));
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.mint_deposit(encoded, a_hash, proofs[0].clone()),
+			VERIFY_FEE
+		),
+		Err(Error::DepositAlreadyMinted)
+	);
+}
+
+#[ink::test]
+fn test_burn_for_release() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	assert_eq!(bridge.burn_for_release(100, [7; 32]), Err(Error::WrappedAssetDisabled));
+
+	let asset =
+		WrappedAsset { token: AccountId::from([9; 32]), lock_address: [2; 32], confirmations: 1 };
+	assert_eq!(bridge.set_wrapped_asset(Some(asset)), Ok(()));
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.burn_for_release(100, [7; 32]), Ok(0));
+	assert_eq!(bridge.burn_for_release(50, [8; 32]), Ok(1));
+	assert_eq!(bridge.release_nonce(), 2);
+
+	let releases: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::ReleaseRequested(release) => Some(release),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(releases.len(), 2);
+	assert_eq!(releases[1].nonce, 1);
+	assert_eq!(releases[1].account, default_accounts.bob);
+	assert_eq!(releases[1].source_address, [8; 32]);
+	assert_eq!(releases[1].amount, 50);
+}
+
+#[ink::test]
+fn test_verify_event() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let (receipts_root, proofs) = merkle_tree(&[[4; 32], [5; 32]]);
+	// The event is not in the transactions tree, only the receipts tree
+	let a_header = mine(Header { receipts_root, ..make_child(genesis_header) });
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_event([5; 32], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_event([4; 32], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([5; 32], a_hash, 0, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationError::Failed(VerificationFailure::InvalidProof))
+	);
+	let verified: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::EventVerified(verified) => Some(verified.event_hash),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(verified, vec![[5; 32]]);
+}
+
+#[ink::test]
+fn test_hash_algorithms() {
+	let default_accounts = default_accounts();
+	set_contract_account();
+	let genesis_header = source_genesis_header();
+	let claim = StateClaim { key: 1, value: 2 };
+	for algorithm in [
+		HashAlgorithm::Sha2x256,
+		HashAlgorithm::DoubleSha256,
+		HashAlgorithm::Keccak256,
+		HashAlgorithm::Blake2x256,
+	] {
+		set_next_caller(default_accounts.alice);
+		let mut bridge = SpvBridge::new_with_hash_algorithm(
+			genesis_header,
+			THRESHOLD,
+			RELAY_FEE,
+			VERIFY_FEE,
+			algorithm,
+		);
+		assert_eq!(bridge.hash_algorithm(), algorithm);
+		let genesis_hash = codec::header_hash_with(algorithm, &genesis_header);
+		assert!(bridge.header_is_known(genesis_hash));
+
+		let (storage_root, proofs) =
+			merkle_tree(&[codec::state_claim_leaf_with(algorithm, &claim)]);
+		let mut child = Header {
+			height: genesis_header.height + 1,
+			parent: genesis_hash,
+			storage_root,
+			timestamp: BLOCK_TIME,
+			..genesis_header
+		};
+		while codec::header_hash_with(algorithm, &child) >= child.target {
+			child.pow_nonce += 1;
+		}
+		let child_hash = codec::header_hash_with(algorithm, &child);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(child), RELAY_FEE), Ok(()));
+		assert!(bridge.header_is_canon(child_hash));
+		assert_eq!(
+			ink::env::pay_with_call!(
+				bridge.verify_state(claim, child_hash, 0, proofs[0].clone()),
+				VERIFY_FEE
+			),
+			Ok(())
+		);
+	}
+}
+
+#[ink::test]
+fn test_versioned_headers() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+
+	// Without extension bytes, a V1 header hashes like the bare header
+	let a_header = make_child(genesis_header);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_versioned_header(VersionedHeader::V1(HeaderV1 {
+				header: a_header,
+				extension: vec![]
+			})),
+			RELAY_FEE
+		),
+		Ok(())
+	);
+	assert!(bridge.header_is_canon(SpvBridge::hash_header(a_header)));
+
+	// Extension bytes are committed to, so the header is found by the extended hash
+	let extension = vec![1, 2, 3];
+	let mut b_header = make_child(a_header);
+	while codec::header_v1_hash(HashAlgorithm::Sha2x256, &b_header, &extension) >= b_header.target {
+		b_header.pow_nonce += 1;
+	}
+	let b_hash = codec::header_v1_hash(HashAlgorithm::Sha2x256, &b_header, &extension);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_versioned_header(VersionedHeader::V1(HeaderV1 {
+				header: b_header,
+				extension
+			})),
+			RELAY_FEE
+		),
+		Ok(())
+	);
+	assert!(bridge.header_is_canon(b_hash));
+	assert!(!bridge.header_is_known(SpvBridge::hash_header(b_header)));
+
+	let c_header = mine(Header { parent: b_hash, ..make_child(b_header) });
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.assert_chain_consistency(100, 103), Ok(()));
+}
+
+#[ink::test]
+fn test_reset_checkpoint() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	let checkpoint = Header { height: 500, parent: [5; 32], ..genesis_header };
+	let checkpoint_hash = SpvBridge::hash_header(checkpoint);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.reset_checkpoint(checkpoint), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.reset_checkpoint(genesis_header), Err(Error::CheckpointNotNewer));
+	assert_eq!(bridge.reset_checkpoint(checkpoint), Ok(()));
+
+	assert_eq!(bridge.enact_checkpoint_reset(), Err(Error::CheckpointResetLocked));
+	bridge.fast_forward(0, CHECKPOINT_RESET_DELAY);
+	assert_eq!(bridge.enact_checkpoint_reset(), Ok(()));
+	assert_eq!(bridge.scheduled_checkpoint(), None);
+	assert_eq!(bridge.enact_checkpoint_reset(), Err(Error::NoCheckpointReset));
+	assert!(bridge.header_is_canon(checkpoint_hash));
+	assert!(decode_events().iter().any(|event| matches!(
+		event,
+		Event::CheckpointReset(reset) if reset.previous_tip == SpvBridge::hash_header(a_header)
+	)));
+
+	// The old chain is archived and can no longer become canon
+	let b_header = make_child(a_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE),
+		Err(Error::ConflictsWithFinalized)
+	);
+
+	let d_header = make_child(checkpoint);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE), Ok(()));
+	assert!(bridge.header_is_canon(SpvBridge::hash_header(d_header)));
+}
+
+#[ink::test]
+fn test_ancestor_queries() {
+	// G---A1---...---A20
+	//      \
+	//       --B2
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	let mut hashes = vec![genesis_hash];
+	let mut tip = genesis_header;
+	for _ in 0..20 {
+		tip = make_child(tip);
+		hashes.push(SpvBridge::hash_header(tip));
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+	let a1_header = bridge.header_by_hash(hashes[1]).unwrap();
+	let b2_header = make_child_with_transactions_root(a1_header, [1; 32]);
+	let b2_hash = SpvBridge::hash_header(b2_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(b2_header), RELAY_FEE).unwrap();
+
+	let tip_hash = hashes[20];
+	for (offset, hash) in hashes.iter().enumerate() {
+		let height = genesis_header.height + offset as u64;
+		assert_eq!(bridge.ancestor_of(tip_hash, height), Some(*hash), "height {height}");
+	}
+	assert_eq!(bridge.ancestor_of(tip_hash, genesis_header.height - 1), None);
+	assert_eq!(bridge.ancestor_of(tip_hash, genesis_header.height + 21), None);
+	assert_eq!(bridge.ancestor_of(b2_hash, genesis_header.height), Some(genesis_hash));
+
+	assert!(bridge.is_ancestor(hashes[1], b2_hash));
+	assert!(bridge.is_ancestor(tip_hash, tip_hash));
+	assert!(!bridge.is_ancestor(hashes[2], b2_hash));
+	assert!(!bridge.is_ancestor(b2_hash, tip_hash));
+}
+
+#[ink::test]
+fn test_fork_tips_and_children() {
+	// G---A1---A2
+	//  \
+	//   --B1
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	assert_eq!(bridge.get_fork_tips(), vec![(genesis_hash, genesis_header.height)]);
+
+	let a1_header = make_child(genesis_header);
+	let a1_hash = SpvBridge::hash_header(a1_header);
+	let a2_header = make_child(a1_header);
+	let a2_hash = SpvBridge::hash_header(a2_header);
+	let b1_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let b1_hash = SpvBridge::hash_header(b1_header);
+	for header in [a1_header, b1_header, a2_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+
+	let mut tips = bridge.get_fork_tips();
+	tips.sort_by_key(|(_, height)| *height);
+	assert_eq!(tips, vec![(b1_hash, b1_header.height), (a2_hash, a2_header.height)]);
+	assert_eq!(bridge.children_of(genesis_hash), vec![a1_hash, b1_hash]);
+	assert_eq!(bridge.children_of(a1_hash), vec![a2_hash]);
+	assert!(bridge.children_of(a2_hash).is_empty());
+}
+
+#[ink::test]
+fn test_common_ancestor() {
+	// G---A1---...---A12
+	//      \
+	//       B2---...---B9
+	//             \
+	//              C6
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let mut a_headers = vec![genesis_header];
+	for _ in 0..12 {
+		a_headers.push(make_child(*a_headers.last().unwrap()));
+	}
+	let mut b_headers = vec![make_child_with_transactions_root(a_headers[1], [1; 32])];
+	for _ in 0..7 {
+		b_headers.push(make_child(*b_headers.last().unwrap()));
+	}
+	let c6_header = make_child_with_transactions_root(b_headers[3], [2; 32]);
+	for header in a_headers[1..].iter().chain(&b_headers).chain([&c6_header]) {
+		ink::env::pay_with_call!(bridge.submit_new_header(*header), RELAY_FEE).unwrap();
+	}
+	let hash = SpvBridge::hash_header;
+	let (a1_hash, a12_hash) = (hash(a_headers[1]), hash(a_headers[12]));
+	let (b5_hash, b9_hash) = (hash(b_headers[3]), hash(b_headers[7]));
+
+	assert_eq!(bridge.common_ancestor(a12_hash, b9_hash), Some(a1_hash));
+	assert_eq!(bridge.common_ancestor(b9_hash, a12_hash), Some(a1_hash));
+	assert_eq!(bridge.common_ancestor(hash(c6_header), b9_hash), Some(b5_hash));
+	assert_eq!(bridge.common_ancestor(b5_hash, b9_hash), Some(b5_hash));
+	assert_eq!(bridge.common_ancestor(a12_hash, a12_hash), Some(a12_hash));
+	assert_eq!(bridge.common_ancestor(a12_hash, [0xab; 32]), None);
+}
+
+#[ink::test]
+fn test_canon_range() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let mut hashes = vec![SpvBridge::hash_header(genesis_header)];
+	let mut tip = genesis_header;
+	for _ in 0..5 {
+		tip = make_child(tip);
+		hashes.push(SpvBridge::hash_header(tip));
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+	let height = genesis_header.height;
+
+	let page = bridge.canon_range(height - 2, 4);
+	assert_eq!(page, vec![(height, hashes[0]), (height + 1, hashes[1])]);
+	let page = bridge.canon_range(height + 2, 10);
+	let expected: Vec<_> = (2..6).map(|i| (height + i, hashes[i as usize])).collect();
+	assert_eq!(page, expected);
+	assert!(bridge.canon_range(height + 6, 10).is_empty());
+	assert!(bridge.canon_range(u64::MAX, u32::MAX).is_empty());
+
+	// Pages are capped however large the limit.
+	bridge.fast_forward(MAX_CANON_RANGE_PAGE as u64 * BLOCK_TIME, 0);
+	for _ in 0..MAX_CANON_RANGE_PAGE {
+		tip = make_child(tip);
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.canon_range(height, u32::MAX).len(), MAX_CANON_RANGE_PAGE as usize);
+}
+
+#[ink::test]
+fn test_verify_transactions() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32], [3; 32], [4; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let header_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	let batch: Vec<_> = (0..4).map(|i| (tx_hashes[i], i as u64, proofs[i].clone())).collect();
+
+	let fee = bridge.batch_verify_fee(4);
+	assert_eq!(fee, VERIFY_FEE + 3 * VERIFY_FEE / 2);
+	assert_eq!(bridge.batch_verify_fee(1), VERIFY_FEE);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transactions(batch.clone(), header_hash, 0),
+			fee - 1
+		),
+		Err(Error::InsufficientVerifyFee.into())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.verify_transactions(batch.clone(), header_hash, 0), fee),
+		Ok(())
+	);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), fee);
+	let verified = decode_events()
+		.into_iter()
+		.filter(|event| matches!(event, Event::TransactionVerified(e) if e.outcome))
+		.count();
+	assert_eq!(verified, 4);
+
+	// A proof of the right transaction at the wrong position fails the whole batch.
+	let mut misplaced = batch.clone();
+	misplaced[2].1 = 3;
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.verify_transactions(misplaced, header_hash, 0), fee),
+		Err(VerificationFailure::InvalidProof.into())
+	);
+	// Direction bits beyond the proof's path are not a position.
+	let mut overlong = batch[..1].to_vec();
+	overlong[0].2.directions |= 1 << 2;
+	overlong[0].1 = overlong[0].2.directions;
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.verify_transactions(overlong, header_hash, 0), VERIFY_FEE),
+		Err(VerificationFailure::InvalidProof.into())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.verify_transactions(vec![], header_hash, 0), fee),
+		Err(Error::EmptyBatch.into())
+	);
+}
+
+#[ink::test]
+fn test_verify_state_batch() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let claims: Vec<_> = (0..8).map(|key| StateClaim { key, value: key * 10 }).collect();
+	let leaves: Vec<_> = claims.iter().map(|claim| SpvBridge::hash_claim(*claim)).collect();
+	let (root, _) = merkle_tree(&leaves);
+	let header = make_child_with_roots(genesis_header, [0; 32], root);
+	let block_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+
+	// Claims 1, 2 and 3 only need leaf 0 and the root's right child from the proof.
+	let pair = MerkleProof::hash_pair;
+	let nodes: Vec<_> = leaves.iter().map(MerkleProof::hash_leaf).collect();
+	let right = pair(&pair(&nodes[4], &nodes[5]), &pair(&nodes[6], &nodes[7]));
+	let proof =
+		MerkleMultiproof { indices: vec![1, 2, 3], hashes: vec![nodes[0], right], depth: 3 };
+	let fee = bridge.batch_verify_fee(3);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_state_batch(claims[1..4].to_vec(), block_hash, 0, proof.clone()),
+			fee
+		),
+		Ok(())
+	);
+	let verified = decode_events()
+		.into_iter()
+		.filter(|event| matches!(event, Event::StateVerified(_)))
+		.count();
+	assert_eq!(verified, 3);
+
+	let mut extra_hash = proof.clone();
+	extra_hash.hashes.push([0; 32]);
+	let mut unsorted = proof.clone();
+	unsorted.indices = vec![2, 1, 3];
+	let mut wrong_claim = claims[1..4].to_vec();
+	wrong_claim[1].value += 1;
+	for (claims, proof) in [
+		(wrong_claim, proof.clone()),
+		(claims[1..4].to_vec(), extra_hash),
+		(claims[1..4].to_vec(), unsorted),
+		(claims[1..3].to_vec(), proof),
+	] {
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.verify_state_batch(claims, block_hash, 0, proof), fee),
+			Err(VerificationFailure::InvalidProof.into())
+		);
+	}
+}
+
+#[ink::test]
+fn test_was_verified() {
+	// G---A1---A2
+	//  \
+	//   --B1---B2---B3
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let a1_header = make_child_with_transactions_root(genesis_header, root);
+	let a1_hash = SpvBridge::hash_header(a1_header);
+	let a2_header = make_child(a1_header);
+	for header in [a1_header, a2_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.was_verified(tx_hashes[0], a1_hash), None);
+
+	ink::env::pay_with_call!(
+		bridge.verify_transaction(tx_hashes[0], a1_hash, 1, proofs[0].clone()),
+		VERIFY_FEE
+	)
+	.unwrap();
+	assert_eq!(bridge.was_verified(tx_hashes[0], a1_hash), Some(a2_header.height));
+	assert_eq!(bridge.was_verified(tx_hashes[1], a1_hash), None);
+
+	// A failed verification is not recorded.
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(tx_hashes[1], a1_hash, 5, proofs[1].clone()),
+			VERIFY_FEE
+		),
+		Err(VerificationFailure::InsufficientDepth { have: 1, need: 5 }.into())
+	);
+	assert_eq!(bridge.was_verified(tx_hashes[1], a1_hash), None);
+
+	// Nor does a verification outlive its block's place in the canon chain.
+	let b1_header = make_child_with_transactions_root(genesis_header, [9; 32]);
+	let b2_header = make_child(b1_header);
+	let b3_header = make_child(b2_header);
+	for header in [b1_header, b2_header, b3_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.was_verified(tx_hashes[0], a1_hash), None);
+}
+
+#[ink::test]
+fn test_attestations() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let claim = StateClaim { key: 1, value: 2 };
+	let claim_hash = SpvBridge::hash_claim(claim);
+	let (root, proofs) = merkle_tree(&[claim_hash, [7; 32]]);
+	let header = make_child_with_roots(genesis_header, [0; 32], root);
+	let block_hash = SpvBridge::hash_header(header);
+	let child = make_child(header);
+	for header in [header, child] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert!(bridge.attestations(claim_hash).is_empty());
+
+	set_next_caller(default_accounts.bob);
+	set_balance(default_accounts.bob, 1_000_000);
+	ink::env::pay_with_call!(
+		bridge.verify_state(claim, block_hash, 0, proofs[0].clone()),
+		VERIFY_FEE
+	)
+	.unwrap();
+	let attestation = Attestation {
+		chain_id: DEFAULT_CHAIN_ID,
+		claim_hash,
+		block_hash,
+		depth: 1,
+		verifier: default_accounts.bob,
+		timestamp: ink::env::block_timestamp::<Environment>(),
+	};
+	assert_eq!(bridge.attestations(claim_hash), vec![attestation]);
+	set_next_caller(default_accounts.alice);
+
+	// Failed verifications attest nothing, and only the latest attestations are kept.
+	let forged = StateClaim { key: 1, value: 3 };
+	assert!(ink::env::pay_with_call!(
+		bridge.verify_state(forged, block_hash, 0, proofs[0].clone()),
+		VERIFY_FEE
+	)
+	.is_err());
+	assert!(bridge.attestations(SpvBridge::hash_claim(forged)).is_empty());
+	for _ in 0..MAX_ATTESTATIONS_PER_CLAIM {
+		ink::env::pay_with_call!(
+			bridge.verify_state(claim, block_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		)
+		.unwrap();
+	}
+	let attestations = bridge.attestations(claim_hash);
+	assert_eq!(attestations.len(), MAX_ATTESTATIONS_PER_CLAIM);
+	assert!(attestations.iter().all(|a| a.verifier == default_accounts.alice));
+}
+
+#[ink::test]
+fn test_min_confirmations() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let block_hash = SpvBridge::hash_header(header);
+	let mut tip = header;
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_min_confirmations(2), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_min_confirmations(2), Ok(()));
+	assert_eq!(bridge.min_confirmations(), 2);
+
+	// A 0-conf request is held to the floor.
+	for confirmations in 0..3 {
+		let result = ink::env::pay_with_call!(
+			bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		);
+		if confirmations < 2 {
+			let failure = VerificationFailure::InsufficientDepth { have: confirmations, need: 2 };
+			assert_eq!(result, Err(failure.into()));
+		} else {
+			assert_eq!(result, Ok(()));
+		}
+		tip = make_child(tip);
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+	let next = make_child(tip);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(next, tx_hashes[1], proofs[1].clone(), 0),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Err(Error::DepthUnreachable)
+	);
+}
+
+#[ink::test]
+fn test_staleness_freeze() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let block_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	assert_eq!(bridge.set_staleness_window(Some(10)), Ok(()));
+	assert!(bridge.capabilities().staleness_freeze);
+
+	let verify = |bridge: &mut SpvBridge| {
+		ink::env::pay_with_call!(
+			bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone()),
+			VERIFY_FEE
+		)
+	};
+	bridge.fast_forward(0, 10);
+	assert_eq!(verify(&mut bridge), Ok(()));
+	bridge.fast_forward(0, 1);
+	assert_eq!(verify(&mut bridge), Err(Error::BridgeStale.into()));
+
+	// Relaying resumes, and so do verifications.
+	ink::env::pay_with_call!(bridge.submit_new_header(make_child(header)), RELAY_FEE).unwrap();
+	assert_eq!(verify(&mut bridge), Ok(()));
+	bridge.fast_forward(0, 11);
+	assert_eq!(bridge.set_staleness_window(None), Ok(()));
+	assert_eq!(verify(&mut bridge), Ok(()));
+}
+
+#[ink::test]
+fn test_max_reorg_depth() {
+	// G---A1---A2---A3
+	//  \
+	//   --B1---B2---B3---B4
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let mut a_tip = genesis_header;
+	for _ in 0..3 {
+		a_tip = make_child(a_tip);
+		ink::env::pay_with_call!(bridge.submit_new_header(a_tip), RELAY_FEE).unwrap();
+	}
+	let a3_hash = SpvBridge::hash_header(a_tip);
+	assert_eq!(bridge.set_max_reorg_depth(Some(2)), Ok(()));
+	assert!(bridge.capabilities().reorg_circuit_breaker);
+
+	let b1_header = make_child_with_transactions_root(genesis_header, [1; 32]);
+	let b2_header = make_child(b1_header);
+	let b3_header = make_child(b2_header);
+	let b4_header = make_child(b3_header);
+	let b4_hash = SpvBridge::hash_header(b4_header);
+	for header in [b1_header, b2_header, b3_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.halt(), None);
+
+	// B4 would retract all 3 A blocks.
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(b4_header), RELAY_FEE), Ok(()));
+	assert_eq!(
+		bridge.halt(),
+		Some(Halt { chain_id: DEFAULT_CHAIN_ID, block_hash: b4_hash, depth: 3 })
+	);
+	assert_eq!(bridge.best_header_hash(), a3_hash);
+	let halted = decode_events()
+		.into_iter()
+		.filter(|event| matches!(event, Event::BridgeHalted(e) if e.depth == 3))
+		.count();
+	assert_eq!(halted, 1);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(b4_header)), RELAY_FEE),
+		Err(Error::BridgeHalted)
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.verify_transaction([0; 32], a3_hash, 0, Default::default()),
+			VERIFY_FEE
+		),
+		Err(Error::BridgeHalted.into())
+	);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.resolve_halt(true), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.resolve_halt(true), Ok(()));
+	assert_eq!(bridge.halt(), None);
+	assert_eq!(bridge.best_header_hash(), b4_hash);
+	assert_eq!(bridge.resolve_halt(true), Err(Error::NotHalted));
+}
+
+#[ink::test]
+fn test_header_blacklist() {
+	// G---A1---A2---A3
+	//  \
+	//   --B1---B2
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let a1_header = make_child_with_transactions_root(genesis_header, root);
+	let a1_hash = SpvBridge::hash_header(a1_header);
+	let a2_header = make_child(a1_header);
+	let a2_hash = SpvBridge::hash_header(a2_header);
+	let b1_header = make_child_with_transactions_root(genesis_header, [9; 32]);
+	let b1_hash = SpvBridge::hash_header(b1_header);
+	let b2_header = make_child(b1_header);
+	for header in [a1_header, a2_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.blacklist_header(a1_hash), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.blacklist_header(a1_hash), Ok(()));
+	assert_eq!(bridge.blacklist_header(b1_hash), Ok(()));
+	assert_eq!(bridge.blacklisted_headers(), vec![a1_hash, b1_hash]);
+
+	// Neither the blacklisted headers nor their descendants can be built on or verified.
+	for header in [make_child(a2_header), b1_header] {
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE),
+			Err(Error::BlacklistedHeader)
+		);
+	}
+	for block_hash in [a1_hash, a2_hash] {
+		assert_eq!(
+			ink::env::pay_with_call!(
+				bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone()),
+				VERIFY_FEE
+			),
+			Err(Error::BlacklistedHeader.into())
+		);
+	}
+
+	assert_eq!(bridge.unblacklist_header(b1_hash), Ok(()));
+	assert_eq!(bridge.unblacklist_header(b1_hash), Err(Error::NotBlacklisted));
+	for header in [b1_header, b2_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	let events = decode_events();
+	assert!(events.iter().any(|e| matches!(e, Event::HeaderBlacklisted(_))));
+	assert!(events.iter().any(|e| matches!(e, Event::HeaderUnblacklisted(_))));
+
+	// Other chains have their own blacklists.
+	let checkpoint = Header { height: 500, pow_nonce: 2, ..source_genesis_header() };
+	let params = ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		retarget: None,
+		prune_depth: None,
+		finality_depth: None,
+	};
+	assert_eq!(bridge.blacklist_chain_header(7, a1_hash), Err(Error::UnknownChain));
+	assert_eq!(bridge.register_chain(7, checkpoint, params), Ok(()));
+	let c1_header = make_child(checkpoint);
+	let c1_hash = SpvBridge::hash_header(c1_header);
+	assert_eq!(bridge.blacklist_chain_header(7, c1_hash), Ok(()));
+	assert_eq!(bridge.chain_blacklisted_headers(7), vec![c1_hash]);
+	assert_eq!(bridge.blacklisted_headers(), vec![a1_hash]);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![c1_header]), RELAY_FEE),
+		Err(Error::BlacklistedHeader)
+	);
+	assert_eq!(bridge.unblacklist_header(c1_hash), Err(Error::NotBlacklisted));
+	assert_eq!(bridge.unblacklist_chain_header(7, c1_hash), Ok(()));
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![c1_header]), RELAY_FEE),
+		Ok(())
+	);
+}
+
+#[ink::test]
+fn test_relayer_stats() {
+	// G---A1---A2
+	//  \
+	//   --B1---B2---B3
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a1_header = make_child(genesis_header);
+	let a2_header = make_child(a1_header);
+	for header in [a1_header, a2_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	let alice_stats = RelayerStats { headers_submitted: 2, canon_headers: 2, rewards_earned: 0 };
+	assert_eq!(bridge.relayer_stats(default_accounts.alice), alice_stats);
+
+	set_next_caller(default_accounts.bob);
+	set_balance(default_accounts.bob, 1_000_000);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let b1_header = make_child_with_transactions_root(genesis_header, root);
+	let b2_header = make_child(b1_header);
+	let b3_header = make_child(b2_header);
+	for header in [b1_header, b2_header, b3_header] {
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	}
+	ink::env::pay_with_call!(
+		bridge.verify_transaction(
+			tx_hashes[0],
+			SpvBridge::hash_header(b1_header),
+			0,
+			proofs[0].clone()
+		),
+		VERIFY_FEE
+	)
+	.unwrap();
+
+	let bob_stats =
+		RelayerStats { headers_submitted: 3, canon_headers: 3, rewards_earned: VERIFY_FEE };
+	assert_eq!(bridge.relayer_stats(default_accounts.bob), bob_stats);
+	assert_eq!(bridge.relayer_stats(default_accounts.alice).canon_headers, 0);
+	assert_eq!(
+		bridge.top_relayers(5),
+		vec![
+			(default_accounts.bob, bob_stats),
+			(default_accounts.alice, RelayerStats { canon_headers: 0, ..alice_stats }),
+		]
+	);
+	assert_eq!(bridge.top_relayers(1).len(), 1);
+}
+
+#[ink::test]
+fn test_fee_split() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let block_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	let treasury = bridge.treasury_balance();
+
+	let invalid = FeeSplit { treasury_bps: 6_000, burn_bps: 4_001 };
+	assert_eq!(bridge.set_fee_split(invalid), Err(Error::InvalidFeeSplit));
+	let split = FeeSplit { treasury_bps: 2_000, burn_bps: 1_000 };
+	assert_eq!(bridge.set_fee_split(split), Ok(()));
+	assert_eq!(bridge.fee_split(), split);
+
+	ink::env::pay_with_call!(
+		bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone()),
+		VERIFY_FEE
+	)
+	.unwrap();
+	assert_eq!(bridge.treasury_balance(), treasury + VERIFY_FEE / 5);
+	assert_eq!(bridge.burned_fees(), VERIFY_FEE / 10);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), VERIFY_FEE * 7 / 10);
+	let paid = decode_events()
+		.into_iter()
+		.any(|event| matches!(event, Event::RelayerPaid(e) if e.amount == VERIFY_FEE * 7 / 10));
+	assert!(paid);
+}
+
+#[ink::test]
+fn test_fee_exemption() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let block_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	let verify_free = |bridge: &mut SpvBridge| {
+		ink::env::test::set_value_transferred::<Environment>(0);
+		bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone())
+	};
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.set_fee_exemption(default_accounts.bob, Some(2)), Err(Error::MissingRole));
+	assert_eq!(verify_free(&mut bridge), Err(Error::InsufficientVerifyFee.into()));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_fee_exemption(default_accounts.bob, Some(2)), Ok(()));
+	assert_eq!(bridge.fee_exemptions_left(default_accounts.bob), Some(2));
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(verify_free(&mut bridge), Ok(()));
+	assert_eq!(verify_free(&mut bridge), Ok(()));
+	assert_eq!(bridge.fee_exemptions_left(default_accounts.bob), Some(0));
+	assert_eq!(verify_free(&mut bridge), Err(Error::InsufficientVerifyFee.into()));
+	// Free verifications pay the relayer nothing.
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), 0);
+
+	// The quota is renewed every period.
+	bridge.fast_forward(0, FEE_EXEMPTION_PERIOD);
+	assert_eq!(bridge.fee_exemptions_left(default_accounts.bob), Some(2));
+	assert_eq!(verify_free(&mut bridge), Ok(()));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_fee_exemption(default_accounts.bob, None), Ok(()));
+	assert_eq!(bridge.fee_exemptions_left(default_accounts.bob), None);
+}
+
+#[ink::test]
+fn test_sponsored_verification() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hashes = [[1; 32], [2; 32]];
+	let (root, proofs) = merkle_tree(&tx_hashes);
+	let header = make_child_with_transactions_root(genesis_header, root);
+	let block_hash = SpvBridge::hash_header(header);
+	ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE).unwrap();
+	let verify_free = |bridge: &mut SpvBridge| {
+		ink::env::test::set_value_transferred::<Environment>(0);
+		bridge.verify_transaction(tx_hashes[0], block_hash, 0, proofs[0].clone())
+	};
+
+	set_next_caller(default_accounts.bob);
+	set_balance(default_accounts.bob, 1_000_000);
+	ink::env::pay_with_call!(bridge.deposit_credits(), 10 * VERIFY_FEE).unwrap();
+	assert_eq!(bridge.sponsor(default_accounts.charlie, 2 * VERIFY_FEE), Ok(()));
+	set_next_caller(default_accounts.django);
+	assert_eq!(bridge.sponsor(default_accounts.charlie, VERIFY_FEE), Err(Error::AlreadySponsored));
+
+	set_next_caller(default_accounts.charlie);
+	assert_eq!(verify_free(&mut bridge), Ok(()));
+	assert_eq!(
+		bridge.sponsorship_of(default_accounts.charlie),
+		Some(Sponsorship { sponsor: default_accounts.bob, allowance: VERIFY_FEE })
+	);
+	assert_eq!(verify_free(&mut bridge), Ok(()));
+	assert_eq!(bridge.credits_of(default_accounts.bob), 8 * VERIFY_FEE);
+	assert_eq!(bridge.pending_rewards(default_accounts.alice), 2 * VERIFY_FEE);
+
+	// The allowance is used up.
+	assert_eq!(bridge.sponsorship_of(default_accounts.charlie), None);
+	assert_eq!(verify_free(&mut bridge), Err(Error::InsufficientVerifyFee.into()));
+}
+
+/// A fork tree of up to 16 blocks, each the child of the genesis block (parent 0) or of an
+/// earlier block (parent `i` for the `i`-th block), and an order to submit them in.
+fn fork_trees() -> impl proptest::strategy::Strategy<Value = (Vec<usize>, Vec<usize>)> {
+	use proptest::prelude::*;
+	(1..=16usize)
+		.prop_flat_map(|len| (0..len).map(|i| 0..=i).collect::<Vec<_>>())
+		.prop_flat_map(|parents| {
+			let order = (0..parents.len()).collect::<Vec<_>>();
+			(Just(parents), Just(order).prop_shuffle())
+		})
+}
+
+proptest::proptest! {
+	// `tests.rs` sits at the crate root, so proptest would otherwise keep its regressions
+	// outside the crate. Tests run from the crate root.
+	#![proptest_config(proptest::test_runner::Config {
+		failure_persistence: Some(Box::new(
+			proptest::test_runner::FileFailurePersistence::Direct(
+				"proptest-regressions/lib.txt",
+			),
+		)),
+		..Default::default()
+	})]
+
+	#[test]
+	fn test_reorgs_keep_the_canon_chain_consistent((parents, order) in fork_trees()) {
+		ink::env::test::run_test::<Environment, _>(|_| {
+			let default_accounts = default_accounts();
+			let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+			// Siblings differ in their transactions roots.
+			let mut headers = vec![genesis_header];
+			for (i, parent) in parents.iter().enumerate() {
+				let root = [(i + 1) as u8; 32];
+				headers.push(make_child_with_transactions_root(headers[*parent], root));
+			}
+
+			// Like a relayer, submit everything out of order, resubmitting known headers,
+			// until the whole tree is accepted. Orphans are pooled, and accepted along
+			// with their parent.
+			let mut accepted = vec![true; 1];
+			accepted.resize(headers.len(), false);
+			let mut pooled = vec![false; headers.len()];
+			while accepted.contains(&false) {
+				for &i in &order {
+					let header = headers[i + 1];
+					let response =
+						ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE);
+					let parent_known = accepted[parents[i]];
+					match (accepted[i + 1] || pooled[i + 1], parent_known) {
+						(true, _) => assert!(response.is_err()),
+						(false, true) => {
+							assert_eq!(response, Ok(()));
+							accepted[i + 1] = true;
+							let mut connected = true;
+							while connected {
+								connected = false;
+								for j in 0..parents.len() {
+									if pooled[j + 1] && !accepted[j + 1] && accepted[parents[j]] {
+										accepted[j + 1] = true;
+										connected = true;
+									}
+								}
+							}
+						},
+						(false, false) => {
+							let held = (0..headers.len())
+								.filter(|&j| pooled[j] && !accepted[j])
+								.count();
+							if held < MAX_ORPHAN_HEADERS_PER_RELAYER {
+								assert_eq!(response, Ok(()));
+								pooled[i + 1] = true;
+							} else {
+								assert_eq!(response, Err(Error::OrphanPoolFull));
+							}
+						},
+					}
+
+					let best_height = bridge.best_height();
+					let heaviest = headers
+						.iter()
+						.zip(&accepted)
+						.filter(|(_, known)| **known)
+						.map(|(header, _)| header.height)
+						.max()
+						.unwrap();
+					// Every header has the same target, so the heaviest branch is the
+					// longest one.
+					assert_eq!(best_height, heaviest);
+
+					for height in genesis_header.height..=genesis_header.height + 17 {
+						let canon = bridge.canon_hash_at(height);
+						if let Some(hash) = canon {
+							assert!(bridge.headers.contains((DEFAULT_CHAIN_ID, hash)));
+						}
+						if height > best_height {
+							continue
+						}
+						let hash = canon.expect("the canon chain has no gaps");
+						let header = bridge.headers.get((DEFAULT_CHAIN_ID, hash)).unwrap();
+						assert_eq!(header.height, height);
+						if height > genesis_header.height {
+							assert_eq!(Some(header.parent), bridge.canon_hash_at(height - 1));
+						}
+					}
+				}
+			}
+			Ok(())
+		})
+		.unwrap();
+	}
+}
+
+#[ink::test]
+fn test_verification_events_carry_the_failure() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let transaction =
+		SourceTransaction { sender: [1; 32], recipient: [2; 32], amount: 5_000, fee: 12, nonce: 0 };
+	let tx_hash = codec::transaction_hash(&transaction);
+	let (tx_root, proofs) = merkle_tree(&[tx_hash, [8; 32]]);
+	let a_header = make_child_with_transactions_root(genesis_header, tx_root);
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+
+	let encoded = codec::encode_transaction(&transaction);
+	for (min_depth, proof) in [(0, &proofs[1]), (3, &proofs[0]), (0, &proofs[0])] {
+		assert!(ink::env::pay_with_call!(
+			bridge.verify_transaction_fee(encoded.clone(), a_hash, min_depth, proof.clone()),
+			VERIFY_FEE
+		)
+		.is_ok());
+	}
+	let failures: Vec<_> = decode_events()
+		.into_iter()
+		.filter_map(|event| match event {
+			Event::TransactionVerified(verified) => Some(verified.failure),
+			_ => None,
+		})
+		.collect();
+	assert_eq!(
+		failures,
+		[
+			Some(VerificationFailure::InvalidProof),
+			Some(VerificationFailure::InsufficientDepth { have: 0, need: 3 }),
+			None
+		]
+	);
+}
+
+#[ink::test]
+fn test_events_are_indexed_by_block() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let tx_hash = [7; 32];
+	let a_header =
+		make_child_with_transactions_root(genesis_header, MerkleProof::hash_leaf(&tx_hash));
+	let a_hash = SpvBridge::hash_header(a_header);
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+	ink::env::pay_with_call!(
+		bridge.verify_transaction(tx_hash, a_hash, 0, MerkleProof::default()),
+		VERIFY_FEE
+	)
+	.unwrap();
+
+	// A topic is the hash of the field's path and the encoded value.
+	fn topic(path: &str, value: &impl scale::Encode) -> Vec<u8> {
+		let prefixed = ink::env::topics::PrefixedValue { prefix: path.as_bytes(), value };
+		let mut hash = [0; 32];
+		ink::env::hash_encoded::<Blake2x256, _>(&prefixed, &mut hash);
+		hash.to_vec()
+	}
+	let indexed_by = |topic: Vec<u8>| -> Vec<Event> {
+		ink::env::test::recorded_events()
+			.filter(|event| event.topics.contains(&topic))
+			.map(|event| scale::Decode::decode(&mut &event.data[..]).unwrap())
+			.collect()
+	};
+
+	let submitted = indexed_by(topic("SpvBridge::HeaderSubmitted::block_hash", &a_hash));
+	assert!(matches!(&submitted[..], [Event::HeaderSubmitted(e)] if e.block_height == 101));
+	let submitted = indexed_by(topic("SpvBridge::HeaderSubmitted::block_height", &101u64));
+	assert!(matches!(&submitted[..], [Event::HeaderSubmitted(e)] if e.block_hash == a_hash));
+	let verified = indexed_by(topic("SpvBridge::TransactionVerified::block_hash", &a_hash));
+	assert!(matches!(&verified[..], [Event::TransactionVerified(e)] if e.tx_hash == tx_hash));
+	let verified = indexed_by(topic("SpvBridge::TransactionVerified::tx_hash", &tx_hash));
+	assert_eq!(verified.len(), 1);
+}
+
+#[ink::test]
+fn test_checkpoint_pow_check() {
+	let default_accounts = default_accounts();
+	let (bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let genesis_hash = SpvBridge::hash_header(genesis_header);
+	assert_eq!(bridge.checkpoint(), (genesis_hash, 100));
+
+	// A mined checkpoint passes the check.
+	let checkpoint = make_child(genesis_header);
+	let bridge =
+		SpvBridge::new_with_checkpoint_check(checkpoint, THRESHOLD, RELAY_FEE, VERIFY_FEE, true);
+	assert_eq!(bridge.checkpoint(), (SpvBridge::hash_header(checkpoint), 101));
+
+	// Without the check, any checkpoint is accepted.
+	let easy = mine(Header { target: [0xff; 32], ..checkpoint });
+	let bridge =
+		SpvBridge::new_with_checkpoint_check(easy, THRESHOLD, RELAY_FEE, VERIFY_FEE, false);
+	assert_eq!(bridge.checkpoint().0, SpvBridge::hash_header(easy));
+}
+
+#[ink::test]
+#[should_panic(expected = "checkpoint must meet its PoW target")]
+fn test_checkpoint_pow_check_rejects_unmined_headers() {
+	let mut unmined = source_genesis_header();
+	while SpvBridge::meets_target(SpvBridge::hash_header(unmined), unmined.target) {
+		unmined.pow_nonce += 1;
+	}
+	SpvBridge::new_with_checkpoint_check(unmined, THRESHOLD, RELAY_FEE, VERIFY_FEE, true);
+}
+
+#[ink::test]
+fn test_parameter_changes_wait_out_the_timelock() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	// Without a timelock, changes apply immediately.
+	assert_eq!(bridge.set_parameter_timelock(10), Ok(()));
+	assert_eq!(bridge.parameter_timelock(), 10);
+
+	assert_eq!(bridge.set_relay_fee(RELAY_FEE * 2), Ok(()));
+	assert_eq!(bridge.pause(), Ok(()));
+	assert_eq!(bridge.relay_fee(), RELAY_FEE);
+	assert!(!bridge.is_paused());
+	assert_eq!(
+		bridge.pending_changes(),
+		vec![
+			PendingChange {
+				id: 0,
+				parameter: BridgeParameter::RelayFee(RELAY_FEE * 2),
+				enactable_at: 10,
+			},
+			PendingChange { id: 1, parameter: BridgeParameter::Paused(true), enactable_at: 10 },
+		]
+	);
+
+	assert_eq!(bridge.enact_parameter_change(0), Err(Error::ParameterChangeLocked));
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.cancel_parameter_change(1), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.cancel_parameter_change(1), Ok(()));
+	assert_eq!(bridge.cancel_parameter_change(1), Err(Error::NoPendingChange));
+
+	// Anyone may enact a change once its timelock has passed.
+	bridge.fast_forward(0, 10);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.enact_parameter_change(0), Ok(()));
+	assert_eq!(bridge.relay_fee(), RELAY_FEE * 2);
+	assert!(!bridge.is_paused());
+	assert_eq!(bridge.pending_changes(), vec![]);
+	assert_eq!(bridge.enact_parameter_change(0), Err(Error::NoPendingChange));
+
+	set_next_caller(default_accounts.alice);
+	for _ in 0..MAX_PENDING_CHANGES {
+		assert_eq!(bridge.set_verify_fee(1), Ok(()));
+	}
+	assert_eq!(bridge.set_verify_fee(1), Err(Error::TooManyPendingChanges));
+}
+
+#[ink::test]
+fn test_ownership_is_transferred_in_two_steps() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.transfer_ownership(default_accounts.bob), Err(Error::MissingRole));
+	assert_eq!(bridge.accept_ownership(), Err(Error::NotPendingOwner));
+
+	// A mistyped offer can be replaced, and leaves the owner in charge meanwhile.
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.transfer_ownership(default_accounts.django), Ok(()));
+	assert_eq!(bridge.transfer_ownership(default_accounts.bob), Ok(()));
+	assert_eq!(bridge.pending_owner(), Some(default_accounts.bob));
+	assert_eq!(bridge.owner(), default_accounts.alice);
+
+	set_next_caller(default_accounts.django);
+	assert_eq!(bridge.accept_ownership(), Err(Error::NotPendingOwner));
+	set_next_caller(default_accounts.bob);
+	assert_eq!(bridge.accept_ownership(), Ok(()));
+	assert_eq!(bridge.owner(), default_accounts.bob);
+	assert_eq!(bridge.pending_owner(), None);
+	assert_eq!(bridge.set_relay_fee(1), Ok(()));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_relay_fee(1), Err(Error::MissingRole));
+}
+
+#[ink::test]
+fn test_roles_separate_governance_duties() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let (bob, charlie) = (default_accounts.bob, default_accounts.charlie);
+	assert!(bridge.has_role(Role::Pauser, default_accounts.alice));
+
+	set_next_caller(bob);
+	assert_eq!(bridge.grant_role(Role::Pauser, bob), Err(Error::MissingRole));
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.grant_role(Role::Pauser, bob), Ok(()));
+	assert_eq!(bridge.grant_role(Role::FeeManager, charlie), Ok(()));
+
+	// Each role only unlocks its own messages.
+	set_next_caller(bob);
+	assert_eq!(bridge.pause(), Ok(()));
+	assert_eq!(bridge.unpause(), Ok(()));
+	assert_eq!(bridge.set_relay_fee(1), Err(Error::MissingRole));
+	assert_eq!(bridge.grant_role(Role::FeeManager, bob), Err(Error::MissingRole));
+	set_next_caller(charlie);
+	assert_eq!(bridge.set_relay_fee(1), Ok(()));
+	assert_eq!(bridge.pause(), Err(Error::MissingRole));
+	assert_eq!(bridge.set_max_batch_size(1), Err(Error::MissingRole));
+	assert_eq!(bridge.cancel_checkpoint_reset(), Err(Error::MissingRole));
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.revoke_role(Role::Pauser, bob), Ok(()));
+	assert!(!bridge.has_role(Role::Pauser, bob));
+	set_next_caller(bob);
+	assert_eq!(bridge.pause(), Err(Error::MissingRole));
+
+	// The bridge is never left without an admin.
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.revoke_role(Role::Admin, default_accounts.alice), Err(Error::LastAdmin));
+	assert_eq!(bridge.grant_role(Role::Admin, charlie), Ok(()));
+	assert_eq!(bridge.revoke_role(Role::Admin, default_accounts.alice), Ok(()));
+	assert_eq!(bridge.set_max_batch_size(1), Err(Error::MissingRole));
+}
+
+#[ink::test]
+fn test_critical_actions_need_signer_approval() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let (bob, charlie, django) =
+		(default_accounts.bob, default_accounts.charlie, default_accounts.django);
+
+	assert_eq!(bridge.set_signers(vec![bob, charlie], 3), Err(Error::InvalidSignerSet));
+	assert_eq!(bridge.set_signers(vec![bob, bob], 2), Err(Error::InvalidSignerSet));
+	assert_eq!(bridge.set_signers(vec![bob, charlie, django], 2), Ok(()));
+	assert_eq!(bridge.pause(), Err(Error::MultisigRequired));
+	assert_eq!(bridge.set_signers(vec![], 0), Err(Error::MultisigRequired));
+	assert_eq!(bridge.propose(CriticalAction::Pause), Err(Error::NotSigner));
+
+	set_next_caller(bob);
+	let id = bridge.propose(CriticalAction::Pause).unwrap();
+	assert_eq!(bridge.approve(id), Err(Error::AlreadyApproved));
+	assert!(!bridge.is_paused());
+	set_next_caller(charlie);
+	assert_eq!(bridge.approve(id), Ok(()));
+	assert!(bridge.is_paused());
+	assert_eq!(bridge.proposal(id), None);
+	assert_eq!(bridge.approve(id), Err(Error::NoProposal));
+
+	// Changing the signer set lapses the approvals of open proposals.
+	let unpause = bridge.propose(CriticalAction::Unpause).unwrap();
+	set_next_caller(django);
+	let shrink = bridge.propose(CriticalAction::SetSigners(vec![charlie, django], 1)).unwrap();
+	set_next_caller(bob);
+	assert_eq!(bridge.approve(shrink), Ok(()));
+	assert_eq!(bridge.signers(), (vec![charlie, django], 1));
+	set_next_caller(django);
+	assert_eq!(bridge.approve(unpause), Err(Error::StaleProposal));
+
+	// With a threshold of one, proposing executes straight away.
+	assert_eq!(bridge.propose(CriticalAction::Unpause), Ok(3));
+	assert!(!bridge.is_paused());
+}
+
+#[ink::test]
+fn test_bridge_config_describes_the_deployment() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	assert_eq!(
+		bridge.bridge_config(),
+		BridgeConfig {
+			consensus: ConsensusBackend::ProofOfWork,
+			header_format: HeaderFormat::Native,
+			hash_algorithm: HashAlgorithm::Sha2x256,
+			retarget: None,
+			difficulty_threshold: THRESHOLD,
+			relay_fee: RELAY_FEE,
+			verify_fee: VERIFY_FEE,
+			prune_depth: None,
+			checkpoint: genesis_header,
+			code_version: STORAGE_VERSION,
+		}
+	);
+
+	// The config follows parameter changes, and keeps the checkpoint once pruned.
+	let prune_depth = MEDIAN_TIME_SPAN as u64;
+	assert_eq!(bridge.set_relay_fee(RELAY_FEE * 2), Ok(()));
+	assert_eq!(bridge.set_prune_depth(Some(prune_depth)), Ok(()));
+	let mut tip = genesis_header;
+	for _ in 0..=prune_depth {
+		tip = make_child(tip);
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE * 2), Ok(()));
+	}
+	assert_eq!(bridge.prune(1), 1);
+	assert_eq!(bridge.header_by_hash(SpvBridge::hash_header(genesis_header)), None);
+	let config = bridge.bridge_config();
+	assert_eq!((config.relay_fee, config.prune_depth), (RELAY_FEE * 2, Some(prune_depth)));
+	assert_eq!(config.checkpoint, genesis_header);
+}
+
+#[ink::test]
+fn test_new_with_config_applies_every_parameter() {
+	set_contract_account();
+	let retarget = RetargetParams { interval: 20, target_timespan: 20 * BLOCK_TIME };
+	let config = DeployConfig {
+		check_checkpoint_pow: false,
+		hash_algorithm: HashAlgorithm::Keccak256,
+		retarget: Some(retarget),
+		prune_depth: Some(20),
+		finality_depth: Some(6),
+		payout_depth: Some(3),
+		escrow_safety_margin: Some(2),
+		salt: Some([9; 32]),
+		..DeployConfig::default_for_tests()
+	};
+	let bridge = SpvBridge::new_with_config(config);
+	assert_eq!(
+		bridge.bridge_config(),
+		BridgeConfig {
+			consensus: ConsensusBackend::ProofOfWork,
+			header_format: HeaderFormat::Native,
+			hash_algorithm: HashAlgorithm::Keccak256,
+			retarget: Some(retarget),
+			difficulty_threshold: config.difficulty_threshold,
+			relay_fee: config.relay_fee,
+			verify_fee: config.verify_fee,
+			prune_depth: Some(20),
+			checkpoint: config.checkpoint,
+			code_version: STORAGE_VERSION,
+		}
+	);
+	assert_eq!(
+		bridge.checkpoint().0,
+		codec::header_hash_with(HashAlgorithm::Keccak256, &config.checkpoint)
+	);
+	assert_eq!(bridge.source_chain(DEFAULT_CHAIN_ID).unwrap().finality_depth, Some(6));
+	assert_eq!((bridge.payout_depth, bridge.escrow_safety_margin), (Some(3), Some(2)));
+	assert_eq!(bridge.deployment_salt(), [9; 32]);
+
+	// The shorthand constructors are the config's defaults plus their own parameter.
+	let checkpoint = config.checkpoint;
+	let (threshold, relay_fee, verify_fee) =
+		(config.difficulty_threshold, config.relay_fee, config.verify_fee);
+	let defaults =
+		SpvBridge::new_with_config(DeployConfig::new(checkpoint, threshold, relay_fee, verify_fee));
+	let bridge = SpvBridge::new(checkpoint, threshold, relay_fee, verify_fee);
+	assert_eq!(bridge.bridge_config(), defaults.bridge_config());
+	assert_eq!(bridge.deployment_salt(), [0xff; 32]);
+	let bridge =
+		SpvBridge::new_with_fee_escrow(checkpoint, threshold, relay_fee, verify_fee, Some(3), None);
+	assert_eq!((bridge.payout_depth, bridge.escrow_safety_margin), (Some(3), None));
+}
+
+#[ink::test]
+#[should_panic(expected = "prune depth must keep the ancestors needed to validate new headers")]
+fn test_new_with_config_rejects_shallow_prune_depths() {
+	SpvBridge::new_with_config(DeployConfig {
+		prune_depth: Some(1),
+		..DeployConfig::default_for_tests()
+	});
+}
+
+#[ink::test]
+fn test_headers_below_the_prune_horizon_are_rejected() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let prune_depth = MEDIAN_TIME_SPAN as u64;
+	assert_eq!(bridge.set_prune_depth(Some(prune_depth)), Ok(()));
+
+	let a_header = make_child(genesis_header);
+	let mut tip = a_header;
+	ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE).unwrap();
+	for _ in 0..prune_depth + 1 {
+		tip = make_child(tip);
+		ink::env::pay_with_call!(bridge.submit_new_header(tip), RELAY_FEE).unwrap();
+	}
+	assert_eq!(bridge.best_height(), 113);
+
+	// A fork at height 101 is 12 blocks behind the tip, even though its parent is not
+	// pruned yet. One at height 102 is within the prune depth.
+	let too_deep = make_child_with_transactions_root(genesis_header, [1; 32]);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(too_deep), RELAY_FEE),
+		Err(Error::BelowPruneHorizon)
+	);
+	let shallow = make_child_with_transactions_root(a_header, [1; 32]);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(shallow), RELAY_FEE), Ok(()));
+}
+
+#[ink::test]
+fn test_orphans_are_connected_when_their_parent_arrives() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	let c_hash = SpvBridge::hash_header(c_header);
+
+	// Bob relays C and B before Alice relays their parent A.
+	set_balance(default_accounts.bob, 1_000_000);
+	set_next_caller(default_accounts.bob);
+	for header in [c_header, b_header] {
+		assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE), Ok(()));
+	}
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE),
+		Err(Error::HeaderAlreadySubmitted)
+	);
+	assert_eq!(bridge.orphan_headers().len(), 2);
+	assert_eq!(bridge.best_height(), 100);
+
+	set_next_caller(default_accounts.alice);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.orphan_headers(), vec![]);
+	assert_eq!(bridge.best_header_hash(), c_hash);
+	assert_eq!(bridge.fee_recipient.get((DEFAULT_CHAIN_ID, c_hash)), Some(default_accounts.bob));
+
+	// Orphans that could never become canon, or that don't meet their target, are not
+	// held, and held ones expire.
+	let beside_checkpoint = Header { parent: [9; 32], ..make_child(genesis_header) };
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(beside_checkpoint), RELAY_FEE),
+		Err(Error::UnknownParent)
+	);
+	let e_header = make_child(make_child(c_header));
+	let mut unmined = e_header;
+	while SpvBridge::meets_target(SpvBridge::hash_header(unmined), unmined.target) {
+		unmined.pow_nonce += 1;
+	}
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(unmined), RELAY_FEE),
+		Err(Error::PoWThresholdNotMet)
+	);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(e_header), RELAY_FEE), Ok(()));
+	bridge.fast_forward(0, ORPHAN_HEADER_TTL + 1);
+	let d_header = make_child(c_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(d_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.best_height(), 104);
+	assert_eq!(bridge.orphan_headers().len(), 0);
+}
+
+#[ink::test]
+fn test_every_submission_path_pools_and_connects_orphans() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let b_header = make_child(a_header);
+	let c_header = make_child(b_header);
+	let d_header = make_child(c_header);
+	let d_hash = SpvBridge::hash_header(d_header);
+
+	// A batch and a combined verification whose parents are unknown are pooled.
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_headers(vec![b_header, c_header]), RELAY_FEE * 2),
+		Ok(())
+	);
+	assert_eq!(
+		ink::env::pay_with_call!(
+			bridge.submit_and_verify(d_header, [7; 32], MerkleProof::default(), 0),
+			RELAY_FEE + VERIFY_FEE
+		),
+		Ok(false)
+	);
+	assert_eq!(bridge.orphan_headers().len(), 3);
+
+	// A parent accepted through any path connects its pooled descendants.
+	set_next_caller(default_accounts.alice);
+	let versioned = VersionedHeader::V1(HeaderV1 { header: a_header, extension: vec![] });
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_versioned_header(versioned), RELAY_FEE),
+		Ok(())
+	);
+	assert_eq!(bridge.orphan_headers(), vec![]);
+	assert_eq!(bridge.best_header_hash(), d_hash);
+}
+
+#[ink::test]
+fn test_orphan_pool_limits() {
+	let default_accounts = default_accounts();
+	let (mut bridge, genesis_header) = deploy_bridge(default_accounts.alice);
+	let a_header = make_child(genesis_header);
+	let orphans: Vec<_> = (0..=MAX_ORPHAN_HEADERS as u64)
+		.map(|i| mine(Header { timestamp: a_header.timestamp + 1 + i, ..make_child(a_header) }))
+		.collect();
+	let relayers = [
+		default_accounts.alice,
+		default_accounts.bob,
+		default_accounts.charlie,
+		default_accounts.django,
+		default_accounts.eve,
+	];
+	let mut pool = |relayer, header| {
+		set_balance(relayer, 1_000_000);
+		set_next_caller(relayer);
+		ink::env::pay_with_call!(bridge.submit_new_header(header), RELAY_FEE)
+	};
+
+	// A relayer can only take their share of the pool, and the same header only once.
+	for header in &orphans[..MAX_ORPHAN_HEADERS_PER_RELAYER] {
+		assert_eq!(pool(relayers[0], *header), Ok(()));
+	}
+	assert_eq!(pool(relayers[1], orphans[0]), Err(Error::HeaderAlreadySubmitted));
+	assert_eq!(
+		pool(relayers[0], orphans[MAX_ORPHAN_HEADERS_PER_RELAYER]),
+		Err(Error::OrphanPoolFull)
+	);
+	for (i, header) in
+		orphans[MAX_ORPHAN_HEADERS_PER_RELAYER..MAX_ORPHAN_HEADERS].iter().enumerate()
+	{
+		let relayer = relayers[1 + i / MAX_ORPHAN_HEADERS_PER_RELAYER];
+		assert_eq!(pool(relayer, *header), Ok(()));
+	}
+	assert_eq!(pool(relayers[4], orphans[MAX_ORPHAN_HEADERS]), Err(Error::OrphanPoolFull));
+
+	// Pooled headers count towards the rate limit, and not again once accepted.
+	set_next_caller(default_accounts.alice);
+	assert_eq!(bridge.set_rate_limit(Some(RateLimit { per_relayer: 1, per_block: 100 })), Ok(()));
+	bridge.fast_forward(0, ORPHAN_HEADER_TTL + 1);
+	let b_header = make_child(a_header);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(b_header), RELAY_FEE), Ok(()));
+	let c_header = make_child(b_header);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(c_header), RELAY_FEE),
+		Err(Error::RateLimited)
+	);
+	set_next_caller(default_accounts.bob);
+	assert_eq!(ink::env::pay_with_call!(bridge.submit_new_header(a_header), RELAY_FEE), Ok(()));
+	assert_eq!(bridge.best_header_hash(), SpvBridge::hash_header(b_header));
+
+	// Blacklisted headers, or children of one, are not held.
+	set_next_caller(default_accounts.alice);
+	let e_header = make_child(make_child(c_header));
+	let e_hash = SpvBridge::hash_header(e_header);
+	assert_eq!(bridge.blacklist_header(e_hash), Ok(()));
+	set_next_caller(default_accounts.charlie);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(e_header), RELAY_FEE),
+		Err(Error::BlacklistedHeader)
+	);
+	set_next_caller(default_accounts.django);
+	assert_eq!(
+		ink::env::pay_with_call!(bridge.submit_new_header(make_child(e_header)), RELAY_FEE),
+		Err(Error::BlacklistedHeader)
+	);
+	assert_eq!(bridge.orphan_headers(), vec![]);
+}
+
+#[ink::test]
+fn test_merkle_proofs_bind_leaves_and_directions() {
+	let leaves = [[1; 32], [2; 32], [3; 32], [4; 32]];
+	let (root, proofs) = merkle_tree(&leaves);
+	for (leaf, proof) in leaves.iter().zip(&proofs) {
+		assert!(MerkleProof::check_merkle_proof(*leaf, proof.clone(), root));
+	}
+
+	// An interior node is not the leaf of any claim, so it can't be proven with the rest
+	// of a leaf's path.
+	let left = MerkleProof::hash_pair(
+		&MerkleProof::hash_leaf(&leaves[0]),
+		&MerkleProof::hash_leaf(&leaves[1]),
+	);
+	let upper_path = MerkleProof { siblings: proofs[0].siblings[1..].to_vec(), directions: 0 };
+	assert!(!MerkleProof::check_merkle_proof(left, upper_path, root));
+
+	// Direction bits beyond the path would give the same leaf several indices.
+	let mut unused_bits = proofs[1].clone();
+	unused_bits.directions |= 1 << 2;
+	assert!(!MerkleProof::check_merkle_proof(leaves[1], unused_bits, root));
+}
+
+#[ink::test]
+fn test_merkle_proof_lengths() {
+	// Direction bits describe paths of at most 64 levels. A longer path is refused even
+	// though it hashes up to the root.
+	let leaf = [1; 32];
+	let path_root = |siblings: &[HashValue]| {
+		siblings.iter().fold(MerkleProof::hash_leaf(&leaf), |node, sibling| {
+			MerkleProof::hash_pair(&node, sibling)
+		})
+	};
+	let siblings = vec![[0; 32]; 65];
+	let root = path_root(&siblings);
+	assert!(!MerkleProof::check_merkle_proof(leaf, MerkleProof { siblings, directions: 0 }, root));
+	let siblings = vec![[0; 32]; 64];
+	let root = path_root(&siblings);
+	assert!(MerkleProof::check_merkle_proof(leaf, MerkleProof { siblings, directions: 0 }, root));
+
+	// A multiproof proves exactly as many leaves as it has indices.
+	let leaves = [[1; 32], [2; 32], [3; 32], [4; 32]];
+	let (root, proofs) = merkle_tree(&leaves);
+	let proof =
+		MerkleMultiproof { indices: vec![0, 1], hashes: vec![proofs[0].siblings[1]], depth: 2 };
+	assert!(MerkleMultiproof::check_merkle_multiproof(&leaves[..2], &proof, root));
+	assert!(!MerkleMultiproof::check_merkle_multiproof(&leaves[..1], &proof, root));
+	assert!(!MerkleMultiproof::check_merkle_multiproof(&leaves[..3], &proof, root));
+	assert!(!MerkleMultiproof::check_merkle_multiproof(&[], &proof, root));
+	let deep = MerkleMultiproof { depth: u64::BITS + 1, ..proof.clone() };
+	assert!(!MerkleMultiproof::check_merkle_multiproof(&leaves[..2], &deep, root));
+	let outside = MerkleMultiproof { indices: vec![0, 4], ..proof };
+	assert!(!MerkleMultiproof::check_merkle_multiproof(&leaves[..2], &outside, root));
+}
+
+#[ink::test]
+fn test_registered_chains_prune_and_finalize() {
+	let default_accounts = default_accounts();
+	let (mut bridge, _) = deploy_bridge(default_accounts.alice);
+	let checkpoint = Header { height: 500, pow_nonce: 2, ..source_genesis_header() };
+	let prune_depth = MEDIAN_TIME_SPAN as u64;
+	let params = ChainParams {
+		difficulty_threshold: THRESHOLD,
+		relay_fee: RELAY_FEE,
+		verify_fee: VERIFY_FEE,
+		retarget: None,
+		prune_depth: Some(1),
+		finality_depth: Some(2),
+	};
+	assert_eq!(bridge.register_chain(7, checkpoint, params), Err(Error::InvalidPruneDepth));
+	let params = ChainParams { prune_depth: Some(prune_depth), ..params };
+	assert_eq!(bridge.register_chain(7, checkpoint, params), Ok(()));
+	assert_eq!(bridge.set_chain_prune_depth(8, None), Err(Error::UnknownChain));
+	assert_eq!(bridge.set_chain_prune_depth(7, Some(1)), Err(Error::InvalidPruneDepth));
+
+	let mut tip = checkpoint;
+	for _ in 0..=prune_depth {
+		tip = make_child(tip);
+		assert_eq!(
+			ink::env::pay_with_call!(bridge.submit_chain_headers(7, vec![tip]), RELAY_FEE),
+			Ok(())
+		);
+	}
+	let chain = bridge.source_chain(7).unwrap();
+	assert_eq!(chain.finalized_height, tip.height - 2);
+	assert_eq!(bridge.finalized_height(), source_genesis_header().height);
+
+	// Each chain is pruned by its own depth.
+	assert_eq!(bridge.prune(10), 0);
+	assert_eq!(bridge.prune_chain(7, 10), 1);
+	assert_eq!(bridge.source_chain(7).unwrap().pruned_height, checkpoint.height + 1);
+	assert_eq!(
+		bridge.chain_canon_hash_at(7, checkpoint.height),
+		Some(bridge.header_hash(checkpoint))
+	);
+
+	assert_eq!(bridge.set_chain_prune_depth(7, None), Ok(()));
+	assert_eq!(bridge.set_chain_finality_depth(7, None), Ok(()));
+	let chain = bridge.source_chain(7).unwrap();
+	assert_eq!((chain.prune_depth, chain.finality_depth), (None, None));
+	assert_eq!(bridge.prune_depth(), None);
+}
@@ -0,0 +1,372 @@
+//! Unsigned 256 bit integers for PoW target arithmetic.
+//!
+//! Targets and block hashes are 256 bit numbers, and cumulative chainwork quickly outgrows any
+//! native integer once targets are realistic. This module does the little arithmetic the bridge
+//! needs on them: expanding and compacting "nBits" targets, comparing hashes against targets,
+//! scaling targets when difficulty retargets, and summing the work each block represents.
+
+use core::{
+	cmp::Ordering,
+	ops::{Add, Div, Not, Shl, Shr, Sub},
+};
+
+/// An unsigned 256 bit integer, stored as four 64 bit limbs, least significant first.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, scale::Decode, scale::Encode)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout, scale_info::TypeInfo))]
+pub struct U256([u64; 4]);
+
+impl U256 {
+	pub const ZERO: Self = Self([0; 4]);
+	pub const ONE: Self = Self([1, 0, 0, 0]);
+	pub const MAX: Self = Self([u64::MAX; 4]);
+
+	/// Read a number from its big-endian bytes, which is how the bridge orders hashes and
+	/// targets.
+	pub fn from_be_bytes(bytes: [u8; 32]) -> Self {
+		let mut limbs = [0; 4];
+		for (i, chunk) in bytes.chunks_exact(8).enumerate() {
+			let mut limb = [0; 8];
+			limb.copy_from_slice(chunk);
+			limbs[3 - i] = u64::from_be_bytes(limb);
+		}
+		Self(limbs)
+	}
+
+	/// Read a number from its little-endian bytes, which is how Bitcoin orders hashes
+	/// internally.
+	pub fn from_le_bytes(mut bytes: [u8; 32]) -> Self {
+		bytes.reverse();
+		Self::from_be_bytes(bytes)
+	}
+
+	/// The number's big-endian bytes.
+	pub fn to_be_bytes(self) -> [u8; 32] {
+		let mut bytes = [0; 32];
+		for (i, limb) in self.0.iter().rev().enumerate() {
+			bytes[i * 8..i * 8 + 8].copy_from_slice(&limb.to_be_bytes());
+		}
+		bytes
+	}
+
+	/// The number's little-endian bytes.
+	pub fn to_le_bytes(self) -> [u8; 32] {
+		let mut bytes = self.to_be_bytes();
+		bytes.reverse();
+		bytes
+	}
+
+	/// The number of bits needed to represent the number.
+	pub fn bits(&self) -> u32 {
+		for (i, limb) in self.0.iter().enumerate().rev() {
+			if *limb != 0 {
+				return i as u32 * 64 + 64 - limb.leading_zeros()
+			}
+		}
+		0
+	}
+
+	/// The least significant 64 bits of the number.
+	pub fn low_u64(&self) -> u64 {
+		self.0[0]
+	}
+
+	/// Expand the compact "nBits" encoding of a PoW target.
+	///
+	/// The top byte is the target's length in bytes and the low 23 bits are its leading digits.
+	/// Returns `None` for negative targets and targets that do not fit in 256 bits.
+	pub fn from_compact(bits: u32) -> Option<Self> {
+		let length = bits >> 24;
+		let mantissa = bits & 0x007f_ffff;
+		if bits & 0x0080_0000 != 0 && mantissa != 0 {
+			return None
+		}
+		if length <= 3 {
+			return Some(Self::from(u64::from(mantissa >> (8 * (3 - length)))))
+		}
+
+		let shift = 8 * (length - 3);
+		let target = Self::from(u64::from(mantissa)) << shift;
+		// Reject the target if shifting it lost any of the mantissa's digits.
+		if target >> shift != Self::from(u64::from(mantissa)) {
+			return None
+		}
+		Some(target)
+	}
+
+	/// The compact "nBits" encoding of the number, truncated to its three leading digits.
+	pub fn to_compact(&self) -> u32 {
+		let mut length = self.bits().div_ceil(8);
+		let mut mantissa = if length <= 3 {
+			(self.low_u64() << (8 * (3 - length))) as u32
+		} else {
+			(*self >> (8 * (length - 3))).low_u64() as u32
+		};
+		// The mantissa's top bit is a sign bit, so shift a digit out rather than set it.
+		if mantissa & 0x0080_0000 != 0 {
+			mantissa >>= 8;
+			length += 1;
+		}
+		(length << 24) | mantissa
+	}
+
+	/// Add two numbers, or `None` if the sum does not fit.
+	pub fn checked_add(self, other: Self) -> Option<Self> {
+		let mut sum = [0; 4];
+		let mut carry = false;
+		for (i, limb) in sum.iter_mut().enumerate() {
+			let (value, overflow) = self.0[i].overflowing_add(other.0[i]);
+			let (value, carry_overflow) = value.overflowing_add(u64::from(carry));
+			*limb = value;
+			carry = overflow || carry_overflow;
+		}
+		(!carry).then_some(Self(sum))
+	}
+
+	/// Add two numbers, saturating at `U256::MAX`.
+	pub fn saturating_add(self, other: Self) -> Self {
+		self.checked_add(other).unwrap_or(Self::MAX)
+	}
+
+	/// Subtract a number, or `None` if it is larger than this one.
+	pub fn checked_sub(self, other: Self) -> Option<Self> {
+		let mut difference = [0; 4];
+		let mut borrow = false;
+		for (i, limb) in difference.iter_mut().enumerate() {
+			let (value, overflow) = self.0[i].overflowing_sub(other.0[i]);
+			let (value, borrow_overflow) = value.overflowing_sub(u64::from(borrow));
+			*limb = value;
+			borrow = overflow || borrow_overflow;
+		}
+		(!borrow).then_some(Self(difference))
+	}
+
+	/// Divide by another number, or `None` if it is zero.
+	pub fn checked_div(self, divisor: Self) -> Option<Self> {
+		if divisor == Self::ZERO {
+			return None
+		}
+
+		// Binary long division, most significant bit first.
+		let mut quotient = Self::ZERO;
+		let mut remainder = Self::ZERO;
+		for bit in (0..self.bits()).rev() {
+			remainder = remainder << 1;
+			remainder.0[0] |= (self.0[bit as usize / 64] >> (bit % 64)) & 1;
+			if remainder >= divisor {
+				remainder = remainder - divisor;
+				quotient.0[bit as usize / 64] |= 1 << (bit % 64);
+			}
+		}
+		Some(quotient)
+	}
+
+	/// Compute `self * numerator / denominator`, saturating if the result does not fit.
+	///
+	/// A zero denominator is treated as one.
+	pub fn saturating_mul_div(self, numerator: u64, denominator: u64) -> Self {
+		let numerator = u128::from(numerator);
+		let denominator = u128::from(denominator.max(1));
+
+		let mut product = [0u64; 4];
+		let mut carry = 0u128;
+		for (i, limb) in product.iter_mut().enumerate() {
+			let value = u128::from(self.0[i]) * numerator + carry;
+			*limb = value as u64;
+			carry = value >> 64;
+		}
+
+		// Long division, most significant limb first. The carry is the fifth limb.
+		if carry >= denominator {
+			return Self::MAX
+		}
+		let mut remainder = carry;
+		let mut quotient = [0; 4];
+		for i in (0..4).rev() {
+			let value = (remainder << 64) | u128::from(product[i]);
+			quotient[i] = (value / denominator) as u64;
+			remainder = value % denominator;
+		}
+		Self(quotient)
+	}
+
+	/// The expected number of hashes needed to find one at or below this target, which is
+	/// `2^256 / (target + 1)`.
+	pub fn work(&self) -> Self {
+		match self.checked_add(Self::ONE) {
+			// 2^256 does not fit, so compute (2^256 - target - 1) / (target + 1) + 1 instead.
+			Some(divisor) => (!*self / divisor).saturating_add(Self::ONE),
+			None => Self::ONE,
+		}
+	}
+}
+
+impl From<u64> for U256 {
+	fn from(value: u64) -> Self {
+		Self([value, 0, 0, 0])
+	}
+}
+
+impl From<u128> for U256 {
+	fn from(value: u128) -> Self {
+		Self([value as u64, (value >> 64) as u64, 0, 0])
+	}
+}
+
+impl Ord for U256 {
+	fn cmp(&self, other: &Self) -> Ordering {
+		self.0.iter().rev().cmp(other.0.iter().rev())
+	}
+}
+
+impl PartialOrd for U256 {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl Add for U256 {
+	type Output = Self;
+
+	fn add(self, other: Self) -> Self {
+		self.checked_add(other).expect("U256 addition overflowed")
+	}
+}
+
+impl Sub for U256 {
+	type Output = Self;
+
+	fn sub(self, other: Self) -> Self {
+		self.checked_sub(other).expect("U256 subtraction underflowed")
+	}
+}
+
+impl Div for U256 {
+	type Output = Self;
+
+	fn div(self, divisor: Self) -> Self {
+		self.checked_div(divisor).expect("U256 division by zero")
+	}
+}
+
+impl Not for U256 {
+	type Output = Self;
+
+	fn not(self) -> Self {
+		Self(self.0.map(|limb| !limb))
+	}
+}
+
+impl Shl<u32> for U256 {
+	type Output = Self;
+
+	fn shl(self, shift: u32) -> Self {
+		let mut shifted = [0; 4];
+		let (limbs, bits) = (shift as usize / 64, shift % 64);
+		for (i, limb) in shifted.iter_mut().enumerate().skip(limbs) {
+			*limb = self.0[i - limbs] << bits;
+			if bits > 0 && i > limbs {
+				*limb |= self.0[i - limbs - 1] >> (64 - bits);
+			}
+		}
+		Self(shifted)
+	}
+}
+
+impl Shr<u32> for U256 {
+	type Output = Self;
+
+	fn shr(self, shift: u32) -> Self {
+		let mut shifted = [0; 4];
+		let (limbs, bits) = (shift as usize / 64, shift % 64);
+		for (i, limb) in shifted.iter_mut().take(4usize.saturating_sub(limbs)).enumerate() {
+			*limb = self.0[i + limbs] >> bits;
+			if bits > 0 && i + limbs + 1 < 4 {
+				*limb |= self.0[i + limbs + 1] << (64 - bits);
+			}
+		}
+		Self(shifted)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn bytes_round_trip() {
+		let mut bytes = [0; 32];
+		bytes[0] = 0x80;
+		bytes[31] = 0x01;
+		let number = U256::from_be_bytes(bytes);
+		assert_eq!(number.to_be_bytes(), bytes);
+		assert_eq!(number.bits(), 256);
+		assert_eq!(number.low_u64(), 1);
+
+		bytes.reverse();
+		assert_eq!(U256::from_le_bytes(bytes), number);
+		assert_eq!(number.to_le_bytes(), bytes);
+	}
+
+	#[test]
+	fn compares_as_integers() {
+		// Bitcoin's internal byte order is little-endian, so the last byte is the most
+		// significant one.
+		let mut low = [0xff; 32];
+		low[31] = 0x00;
+		let mut high = [0x00; 32];
+		high[31] = 0x01;
+		assert!(low > high);
+		assert!(U256::from_le_bytes(low) < U256::from_le_bytes(high));
+		assert!(U256::from(u128::MAX) < U256::from(u128::MAX) + U256::ONE);
+	}
+
+	#[test]
+	fn compact_targets_expand() {
+		let mut pow_limit = [0; 32];
+		pow_limit[4..6].copy_from_slice(&[0xff, 0xff]);
+		let pow_limit = U256::from_be_bytes(pow_limit);
+		assert_eq!(U256::from_compact(0x1d00_ffff), Some(pow_limit));
+		assert_eq!(pow_limit.to_compact(), 0x1d00_ffff);
+
+		assert_eq!(U256::from_compact(0x0112_3456), Some(U256::from(0x12u64)));
+		assert_eq!(U256::from(0x12u64).to_compact(), 0x0112_0000);
+		assert_eq!(U256::from(0x80u64).to_compact(), 0x0200_8000);
+		assert_eq!(U256::ZERO.to_compact(), 0);
+
+		// Negative and overflowing targets are rejected.
+		assert_eq!(U256::from_compact(0x0492_3456), None);
+		assert_eq!(U256::from_compact(0x2301_0000), None);
+		assert_eq!(U256::from_compact(0x2200_0001), Some(U256::ONE << 248));
+	}
+
+	#[test]
+	fn arithmetic() {
+		let big = U256::ONE << 200;
+		assert_eq!(big.checked_add(U256::MAX), None);
+		assert_eq!(U256::MAX.saturating_add(U256::ONE), U256::MAX);
+		assert_eq!(U256::ZERO.checked_sub(U256::ONE), None);
+		assert_eq!((big - U256::ONE).bits(), 200);
+		assert_eq!(big / (U256::ONE << 100), U256::ONE << 100);
+		assert_eq!(U256::from(1000u64) / U256::from(7u64), U256::from(142u64));
+		assert_eq!(U256::ONE.checked_div(U256::ZERO), None);
+		assert_eq!(big >> 136, U256::ONE << 64);
+
+		assert_eq!(big.saturating_mul_div(3, 2), (big >> 1) + big);
+		assert_eq!(U256::MAX.saturating_mul_div(2, 1), U256::MAX);
+		assert_eq!(U256::from(10u64).saturating_mul_div(1, 0), U256::from(10u64));
+	}
+
+	#[test]
+	fn work_matches_bitcoin() {
+		// The genesis block's target is worth 0x100010001 hashes.
+		let pow_limit = U256::from_compact(0x1d00_ffff).unwrap();
+		assert_eq!(pow_limit.work(), U256::from(0x1_0001_0001u64));
+		assert_eq!(U256::MAX.work(), U256::ONE);
+		assert_eq!(U256::ZERO.work(), U256::MAX);
+		assert_eq!((U256::MAX >> 1).work(), U256::from(2u64));
+
+		// Work keeps growing for targets far below 2^128.
+		let hard = U256::ONE << 64;
+		assert!(hard.work() > U256::from(u128::MAX));
+	}
+}
@@ -0,0 +1,40 @@
+//! The interface other contracts use to verify claims against a bridge.
+//!
+//! A consumer contract, such as an escrow or a token bridge, can hold a reference to any
+//! deployment implementing `SpvVerifier` without depending on this crate's contract type:
+//!
+//! ```ignore
+//! let mut bridge: ink::contract_ref!(SpvVerifier) = bridge_account.into();
+//! bridge.verify_transaction(tx_hash, block_hash, 6, proof)?;
+//! ```
+//!
+//! Payments for the verify fee are made by transferring value with the call, as with the
+//! bridge's own messages.
+
+use crate::spv_bridge::{HashValue, MerkleProof, StateClaim, VerificationResult};
+
+/// Verification of source chain claims, see `SpvBridge::verify_transaction`.
+#[ink::trait_definition]
+pub trait SpvVerifier {
+	/// Verify that a transaction is included in a canon block with at least `min_depth`
+	/// confirmations.
+	#[ink(message, payable)]
+	fn verify_transaction(
+		&mut self,
+		tx_hash: HashValue,
+		header_hash: HashValue,
+		min_depth: u64,
+		p: MerkleProof,
+	) -> VerificationResult;
+
+	/// Verify that a state claim holds in a canon block with at least `min_depth`
+	/// confirmations.
+	#[ink(message, payable)]
+	fn verify_state(
+		&mut self,
+		claim: StateClaim,
+		block_hash: HashValue,
+		min_depth: u64,
+		p: MerkleProof,
+	) -> VerificationResult;
+}
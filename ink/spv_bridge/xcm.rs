@@ -0,0 +1,66 @@
+//! Forwarding of verified claims to parachains over XCM.
+//!
+//! ink! 4 has no API for sending XCM, so with the `xcm-extension` feature the contract asks the
+//! runtime's XCM chain extension to send the message instead. The contract must then only be
+//! deployed on a runtime that provides the extension, because calling a missing chain extension
+//! traps. Without the feature nothing can be forwarded.
+
+use ink::env::chain_extension::ChainExtensionMethod;
+
+/// Send a `Transact` to a parachain, taking the destination's parachain id and the SCALE-encoded
+/// call to dispatch there, and returning whether the message was sent. The runtime sends it from
+/// the contract's sovereign account, which pays the delivery and execution fees.
+pub const XCM_TRANSACT: u32 = 0x5843_0000;
+
+/// Whether the contract was built to call the XCM chain extension.
+pub const ENABLED: bool = cfg!(feature = "xcm-extension");
+
+/// Dispatch `call` on parachain `para_id` with an XCM `Transact`. Returns whether the message was
+/// sent, or `None` if the contract was built without the XCM chain extension.
+pub fn send_transact(para_id: u32, call: &[u8]) -> Option<bool> {
+	ENABLED.then(|| {
+		ChainExtensionMethod::build(XCM_TRANSACT)
+			.input::<(u32, &[u8])>()
+			.output::<bool, false>()
+			.ignore_error_code()
+			.call(&(para_id, call))
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	#[cfg(not(feature = "xcm-extension"))]
+	fn sends_nothing_without_the_extension() {
+		assert_eq!(send_transact(2000, b"call"), None);
+	}
+
+	#[test]
+	#[cfg(feature = "xcm-extension")]
+	fn calls_the_chain_extension() {
+		use ink::prelude::vec::Vec;
+		use scale::{Decode, Encode};
+
+		/// Stands in for the runtime, which only has a channel to parachain 2000.
+		struct MockExtension;
+
+		impl ink::env::test::ChainExtension for MockExtension {
+			fn func_id(&self) -> u32 {
+				XCM_TRANSACT
+			}
+
+			fn call(&mut self, input: &[u8], output: &mut Vec<u8>) -> u32 {
+				let input = Vec::<u8>::decode(&mut &input[..]).unwrap();
+				let (para_id, _) = <(u32, Vec<u8>)>::decode(&mut &input[..]).unwrap();
+				(para_id == 2000).encode_to(output);
+				0
+			}
+		}
+
+		ink::env::test::register_chain_extension(MockExtension);
+		assert_eq!(send_transact(2000, b"call"), Some(true));
+		assert_eq!(send_transact(2001, b"call"), Some(false));
+	}
+}